@@ -1,13 +1,23 @@
 extern crate clap;
+#[macro_use]
 extern crate serde_json;
 extern crate json_schema;
 
-use std::fs::File;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process;
+use std::sync::Arc;
 use std::time::{Instant, Duration};
 
-use clap::{App, Arg};
-use json_schema::{Schema, SchemaBase};
-use json_schema::errors::Result;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use json_schema::{capabilities, check_duplicate_keys, diagnostics, generate_markdown, instance_diff,
+                  is_subschema, BatchReport, BehaviorDiff, CoverageSession, DocOptions,
+                  Satisfiability, Schema, SchemaStore, SourceMap, StatsCollector,
+                  SubsetResult, UnknownPolicy, ValidateOptions};
+use json_schema::compare::Verdict;
+use json_schema::diagnostics::EnforcementReport;
+use json_schema::errors::{ErrorKind, Result, ValidationErrors};
+use json_schema::files;
 
 pub trait DurationExt {
     fn millis(&self) -> f64;
@@ -20,18 +30,279 @@ impl DurationExt for Duration {
     }
 }
 
+/// Parses durations like `500ms`, `5s` or `2m`.
+fn parse_duration(text: &str) -> Result<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid timeout `{}`", text))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "s" | "" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        _ => Err(format!("invalid timeout `{}`, use a unit of ms, s or m", text).into()),
+    }
+}
+
+/// Compares the verdicts of two schemas on the input files, returns whether no
+/// file became invalid.
+fn compare(matches: &ArgMatches) -> Result<bool> {
+    let old = files::read_schema(matches.value_of("old").unwrap())?;
+    let new = files::read_schema(matches.value_of("new").unwrap())?;
+    let mut diff = BehaviorDiff::new();
+    for path in matches.values_of("inputs").unwrap() {
+        diff.add(&old, &new, Some(path.into()), &files::read_json(path)?);
+    }
+    match matches.value_of("output") {
+        Some("json") => println!("{}", serde_json::to_string_pretty(&diff)?),
+        _ => print!("{}", diff),
+    }
+    Ok(!diff.has_newly_invalid())
+}
+
+/// Diffs two instances of a schema, returns whether no change makes a value
+/// invalid.
+fn idiff(matches: &ArgMatches) -> Result<bool> {
+    let schema = files::read_schema(matches.value_of("schema").unwrap())?;
+    let old = files::read_json(matches.value_of("old").unwrap())?;
+    let new = files::read_json(matches.value_of("new").unwrap())?;
+    let changes = instance_diff(&schema, &old, &new);
+    match matches.value_of("output") {
+        Some("json") => println!("{}", serde_json::to_string_pretty(&changes)?),
+        _ => {
+            for change in &changes {
+                println!("{}", change);
+            }
+        }
+    }
+    Ok(!changes.iter().any(|c| c.verdict == Verdict::NewlyInvalid))
+}
+
+/// Checks whether the second schema accepts every value the first accepts,
+/// returns the exit code.
+fn subset(matches: &ArgMatches) -> Result<i32> {
+    let a = files::read_schema(matches.value_of("a").unwrap())?;
+    let b = files::read_schema(matches.value_of("b").unwrap())?;
+    let result = is_subschema(&a, &b);
+    println!("{}", result);
+    Ok(match result {
+        SubsetResult::Subset => 0,
+        SubsetResult::NotSubset(..) => 1,
+        SubsetResult::Unknown(..) => EXIT_UNKNOWN,
+    })
+}
+
+/// Validates the document on stdin, writing it unchanged to stdout if it's
+/// valid or `--passthrough-invalid` is given, and the errors as JSON to stderr.
+/// Returns whether it's valid.
+fn gate(matches: &ArgMatches) -> Result<bool> {
+    let schema = files::read_schema(matches.value_of("schema").unwrap())?;
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| format!("can't read stdin: {}", e))?;
+    let mut value = files::parse_json("<stdin>", &text)?;
+    let options = ValidateOptions::default();
+    let sanitize = matches.is_present("sanitize");
+    let (removed, output) = if sanitize {
+        let (report, output) =
+            schema.validate_and_sanitize(&mut value, UnknownPolicy::Strip, &options)?;
+        (report.removed, output)
+    } else {
+        (vec![], schema.validate_with(&value, &options))
+    };
+    let valid = output.is_valid();
+    if !valid {
+        let report = json!({"valid": false, "errors": output.errors, "removed": removed});
+        eprintln!("{}", serde_json::to_string(&report)?);
+    }
+    if valid || matches.is_present("passthrough-invalid") {
+        let forwarded = if sanitize {
+            serde_json::to_string(&value)? + "\n"
+        } else {
+            text
+        };
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout
+            .write_all(forwarded.as_bytes())
+            .and_then(|()| stdout.flush())
+            .map_err(|e| format!("can't write to stdout: {}", e))?;
+    }
+    Ok(valid)
+}
+
+/// Writes the Markdown documentation of a schema to `--output` or stdout.
+fn docs(matches: &ArgMatches) -> Result<()> {
+    let schema = files::read_schema(matches.value_of("schema").unwrap())?;
+    let mut options = DocOptions {
+        title: matches.value_of("title").map(String::from),
+        ..DocOptions::default()
+    };
+    if let Some(depth) = matches.value_of("max-depth") {
+        options.max_depth = depth
+            .parse()
+            .map_err(|_| format!("invalid depth `{}`", depth))?;
+    }
+    let markdown = generate_markdown(&schema, &options);
+    match matches.value_of("output") {
+        Some(path) => files::write(path, markdown)?,
+        None => print!("{}", markdown),
+    }
+    Ok(())
+}
+
+/// `path:line:column` of the value at `pointer` in the schema file at `path`,
+/// or `path` without locations.
+fn located(path: &str, locations: Option<&SourceMap>, pointer: &str) -> String {
+    match locations.and_then(|l| l.locate(pointer)) {
+        Some(location) => format!("{}:{}", path, location),
+        None => path.into(),
+    }
+}
+
+/// Loads the schema file at `path` with the locations of its keywords,
+/// pointing out unenforced keywords unless `--quiet` is given.
+fn load_schema(path: &str, matches: &ArgMatches) -> Result<(Schema, SourceMap)> {
+    let text = files::read_text(path)?;
+    let schema_value = files::parse_json(path, &text)?;
+    let locations = SourceMap::new(&text);
+    if !matches.is_present("quiet") {
+        let report = EnforcementReport::from_value(&schema_value);
+        if let Some(summary) = report.summary() {
+            eprintln!("{}", summary);
+        }
+        for keyword in report.not_enforced_uses() {
+            let pointer = format!("{}/{}", keyword.schema_path, keyword.keyword);
+            eprintln!(
+                "{}: `{}` is not enforced",
+                located(path, Some(&locations), &pointer),
+                keyword.keyword
+            );
+        }
+        // schemas that don't parse are reported below
+        for duplicate in diagnostics::check_duplicate_declarations(&text).unwrap_or_default() {
+            let at = located(path, Some(&locations), &duplicate.pointer);
+            eprintln!("{}: {}", at, duplicate);
+        }
+    }
+    match files::parse_schema(path, schema_value.clone()) {
+        Ok(schema) => Ok((schema, locations)),
+        Err(e) => {
+            // point out the likely mix-up instead of the parse error
+            for json_path in matches.values_of("input").into_iter().flatten() {
+                if let Ok(json) = files::read_json(json_path) {
+                    diagnostics::check_swapped(&schema_value, &json)?;
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Registers the schemas of the files given with `--schemas` and of the JSON
+/// files in `--schema-dir`.
+fn load_store(matches: &ArgMatches) -> Result<SchemaStore> {
+    let mut paths: Vec<_> = matches
+        .values_of("schemas")
+        .into_iter()
+        .flatten()
+        .map(Into::into)
+        .collect();
+    if let Some(dir) = matches.value_of("schema-dir") {
+        let mut files = vec![];
+        let io_error = |source| ErrorKind::Io {
+            path: dir.into(),
+            source,
+        };
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        paths.extend(files);
+    }
+    let mut store = SchemaStore::new();
+    for path in paths {
+        store
+            .load_document(files::read_json(&path)?)
+            .map_err(|e| format!("can't load the schemas of {}: {}", path.display(), e))?;
+    }
+    Ok(store)
+}
+
+/// The number of similar errors shown before the rest are folded, unless
+/// `--fold` says otherwise.
+const DEFAULT_FOLD: usize = 3;
+
+/// The exit code of `--check-schema` if the schema rejects one of its examples
+/// or accepts one of its counterexamples.
+const EXIT_SELF_TEST: i32 = 2;
+
+/// The exit code of `subset` if it can't tell whether one schema is a subset of
+/// the other.
+const EXIT_UNKNOWN: i32 = 2;
+
+/// The exit code of validation if the schema couldn't be applied to an input,
+/// like when a reference doesn't resolve or validation timed out.
+const EXIT_INDETERMINATE: i32 = 2;
 
-fn run() -> Result<()> {
+fn exit_code(success: bool) -> i32 {
+    if success {
+        0
+    } else {
+        1
+    }
+}
+
+/// Returns the exit code: 1 if an input file is invalid.
+fn run() -> Result<i32> {
     let matches = App::new("json_schema")
         .about("JSON schema validator")
         .author("Martin Tomasi <martin.tomasi@gmail.com>")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("schema")
                 .short("s")
                 .long("schema")
-                .help("Path to the schema file")
+                .help("Path to the schema file, give it more than once to validate against several")
                 .takes_value(true)
-                .required(true),
+                .multiple(true)
+                .number_of_values(1)
+                .required_unless_one(&["schema-id", "capabilities", "self-described"]),
+        )
+        .arg(
+            Arg::with_name("schemas")
+                .long("schemas")
+                .takes_value(true)
+                .value_name("FILE")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Register the schemas of FILE, a schema or an array of schemas with `$id`s"),
+        )
+        .arg(
+            Arg::with_name("schema-dir")
+                .long("schema-dir")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Register the schemas of the JSON files in DIR like --schemas"),
+        )
+        .arg(
+            Arg::with_name("schema-id")
+                .long("schema-id")
+                .takes_value(true)
+                .value_name("URI")
+                .conflicts_with("schema")
+                .help("Validate against the registered schema with this `$id` instead of --schema"),
+        )
+        .arg(
+            Arg::with_name("self-described")
+                .long("self-described")
+                .conflicts_with_all(&["schema", "schema-id", "coverage"])
+                .help("Validate each input against the registered schema its `$schema` member names"),
         )
         .arg(
             Arg::with_name("input")
@@ -42,32 +313,481 @@ fn run() -> Result<()> {
                 .multiple(true)
                 .min_values(1),
         )
+        .arg(
+            Arg::with_name("multiple-documents")
+                .long("multiple-documents")
+                .help("Read each input file as a stream of JSON texts, like JSON lines, and validate each"),
+        )
+        .arg(
+            Arg::with_name("strict-parse")
+                .long("strict-parse")
+                .help("Reject input files that contain duplicate object keys"),
+        )
+        .arg(
+            Arg::with_name("deny-warnings")
+                .long("deny-warnings")
+                .help("Exit with an error if validation reports warnings"),
+        )
+        .arg(
+            Arg::with_name("deny-expired")
+                .long("deny-expired")
+                .help("Reject values of deprecated schemas whose x-removed-in date has passed"),
+        )
+        .arg(
+            Arg::with_name("fold")
+                .long("fold")
+                .takes_value(true)
+                .value_name("N")
+                .help("Show only N errors of a kind that differ just in array indices and count the \
+                       rest, 0 shows all (default 3 for text output, all for SARIF)"),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .takes_value(true)
+                .value_name("DURATION")
+                .help("Give up validating a file after this long, e.g. 500ms, 5s or 2m"),
+        )
+        .arg(
+            Arg::with_name("coverage")
+                .long("coverage")
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with_all(&["schemas", "schema-dir", "schema-id"])
+                .help("Write which parts of the schema the input files exercised to FILE"),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Write how often each subschema was evaluated and failed and how long it took to FILE"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .help("Don't point out the keywords of the schema that aren't enforced"),
+        )
+        .arg(
+            Arg::with_name("check-schema")
+                .long("check-schema")
+                .help("Only check whether any value can be valid against the schema and whether it \
+                       accepts its examples and rejects its x-counterexamples, exit with 2 if not"),
+        )
+        .arg(
+            Arg::with_name("explain-refs")
+                .long("explain-refs")
+                .help("Only resolve the references of the schema and print how each resolved and \
+                       which documents reference each other, exit with 1 if any is unresolved"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["text", "sarif"])
+                .default_value("text")
+                .help("Print the results as text or as a SARIF log for CI annotations"),
+        )
+        .arg(
+            Arg::with_name("capabilities")
+                .long("capabilities")
+                .help("Print the drafts, keywords, formats and features this build supports as JSON"),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Shows which input files change verdict between two versions of a schema, fails if one becomes invalid")
+                .arg(
+                    Arg::with_name("old")
+                        .long("old")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to the current schema"),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .long("new")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to the changed schema"),
+                )
+                .arg(
+                    Arg::with_name("inputs")
+                        .multiple(true)
+                        .required(true)
+                        .help("The files to validate against both"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .takes_value(true)
+                        .value_name("FORMAT")
+                        .possible_values(&["text", "json"])
+                        .default_value("text")
+                        .help("Print the changes as text or the whole comparison as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("idiff")
+                .about("Lists the values that differ between two instances of a schema, fails if a change makes one invalid")
+                .arg(
+                    Arg::with_name("schema")
+                        .short("s")
+                        .long("schema")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to the schema of both instances"),
+                )
+                .arg(
+                    Arg::with_name("old")
+                        .required(true)
+                        .help("The original instance"),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .required(true)
+                        .help("The changed instance"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .takes_value(true)
+                        .value_name("FORMAT")
+                        .possible_values(&["text", "json"])
+                        .default_value("text")
+                        .help("Print the changes as text or as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("docs")
+                .about("Writes Markdown documentation of a schema")
+                .arg(
+                    Arg::with_name("schema")
+                        .required(true)
+                        .help("Path to the schema"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("Write the documentation to FILE instead of stdout"),
+                )
+                .arg(
+                    Arg::with_name("title")
+                        .long("title")
+                        .takes_value(true)
+                        .help("The heading of the documentation, the title of the schema by default"),
+                )
+                .arg(
+                    Arg::with_name("max-depth")
+                        .long("max-depth")
+                        .takes_value(true)
+                        .value_name("LEVELS")
+                        .help("How many levels of nested schemas get a section of their own"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("gate")
+                .about("Passes the JSON document on stdin through to stdout if it's valid, reports the errors as JSON on stderr and fails otherwise")
+                .arg(
+                    Arg::with_name("schema")
+                        .short("s")
+                        .long("schema")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to the schema"),
+                )
+                .arg(
+                    Arg::with_name("passthrough-invalid")
+                        .long("passthrough-invalid")
+                        .help("Write invalid documents to stdout as well, still reporting their errors and failing"),
+                )
+                .arg(
+                    Arg::with_name("sanitize")
+                        .long("sanitize")
+                        .help("Remove the object properties the schema doesn't declare before validating and forwarding"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("subset")
+                .about("Checks whether the second schema accepts every value the first accepts, exits with 2 if that can't be told")
+                .arg(
+                    Arg::with_name("a")
+                        .required(true)
+                        .help("Path to the narrower schema"),
+                )
+                .arg(
+                    Arg::with_name("b")
+                        .required(true)
+                        .help("Path to the wider schema"),
+                ),
+        )
         .get_matches();
-    let schema_path = matches.value_of("schema").unwrap();
-    let schema: Schema = serde_json::from_reader(File::open(schema_path)?)?;
+    if let Some(matches) = matches.subcommand_matches("compare") {
+        return compare(matches).map(exit_code);
+    }
+    if let Some(matches) = matches.subcommand_matches("idiff") {
+        return idiff(matches).map(exit_code);
+    }
+    if let Some(matches) = matches.subcommand_matches("gate") {
+        return gate(matches).map(exit_code);
+    }
+    if let Some(matches) = matches.subcommand_matches("subset") {
+        return subset(matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("docs") {
+        return docs(matches).map(|()| 0);
+    }
+    if matches.is_present("capabilities") {
+        println!("{}", serde_json::to_string_pretty(&capabilities())?);
+        return Ok(0);
+    }
+    let store = load_store(&matches)?;
+    let mut loaded = vec![];
+    for path in matches.values_of("schema").into_iter().flatten() {
+        let (schema, locations) = load_schema(path, &matches)?;
+        loaded.push((path, schema, locations));
+    }
+    let schemas: Vec<(&str, &Schema)> = match matches.value_of("schema-id") {
+        Some(id) => match store.get(id) {
+            Some(schema) => vec![(id, schema)],
+            None => return Err(format!("no schema with `$id` {} is registered", id).into()),
+        },
+        None => loaded.iter().map(|(path, schema, _)| (*path, schema)).collect(),
+    };
+    let locations = |schema_path: &str| {
+        loaded
+            .iter()
+            .find(|&&(path, _, _)| path == schema_path)
+            .map(|(_, _, locations)| locations)
+    };
+
+    if matches.is_present("explain-refs") {
+        let mut resolved = true;
+        for &(schema_path, schema) in &schemas {
+            let report = store.resolve_references(schema);
+            println!("{}:", schema_path);
+            print!("{}", report);
+            resolved &= report.is_resolved();
+        }
+        return Ok(exit_code(resolved));
+    }
+    if matches.is_present("check-schema") {
+        let mut satisfiable = true;
+        let mut self_tested = true;
+        for &(schema_path, schema) in &schemas {
+            satisfiable &= match schema.is_satisfiable() {
+                Satisfiability::Yes => {
+                    println!("{} can accept values", schema_path);
+                    true
+                }
+                Satisfiability::No(reason, path) => {
+                    let at = located(schema_path, locations(schema_path), &path);
+                    println!("{}: accepts no value: {} at \"{}\"", at, reason, path);
+                    false
+                }
+                Satisfiability::Unknown => {
+                    println!("{} may accept no value, parts of it are beyond the checks", schema_path);
+                    true
+                }
+            };
+            let report = schema.self_test();
+            for mismatch in &report.mismatches {
+                let at = located(schema_path, locations(schema_path), &mismatch.value_path());
+                print!("{}: {}", at, mismatch);
+            }
+            if report.examples + report.counterexamples > 0 {
+                println!("{}", report.summary());
+            }
+            self_tested &= report.is_ok();
+        }
+        if !self_tested {
+            return Ok(EXIT_SELF_TEST);
+        }
+        return Ok(exit_code(satisfiable));
+    }
+    if matches.is_present("coverage") && schemas.len() > 1 {
+        return Err("--coverage works with a single --schema".into());
+    }
+
+    let self_described = matches.is_present("self-described");
+    let strict_parse = matches.is_present("strict-parse");
+    let deny_warnings = matches.is_present("deny-warnings");
+    // colour codes would end up in files and pipes
+    let color = io::stdout().is_terminal();
+    let mut sarif = match matches.value_of("output") {
+        Some("sarif") => Some(BatchReport::new()),
+        _ => None,
+    };
+    let timeout = match matches.value_of("timeout") {
+        Some(timeout) => Some(parse_duration(timeout)?),
+        None => None,
+    };
+    let fold_similar = match matches.value_of("fold") {
+        Some(fold) => match fold.parse() {
+            Ok(0) => None,
+            Ok(n) => Some(n),
+            Err(_) => return Err(format!("invalid number `{}` for --fold", fold).into()),
+        },
+        None if sarif.is_none() => Some(DEFAULT_FOLD),
+        None => None,
+    };
+    let stats = matches
+        .value_of("stats")
+        .map(|_| Arc::new(StatsCollector::new()));
+    let options = ValidateOptions {
+        collect_timing: sarif.is_some(),
+        collect_warnings: true,
+        deny_expired: matches.is_present("deny-expired"),
+        fold_similar,
+        multiple_documents: matches.is_present("multiple-documents"),
+        timeout,
+        stats: stats.clone(),
+        ..ValidateOptions::default()
+    };
+    let mut coverage = matches
+        .value_of("coverage")
+        .map(|_| CoverageSession::with_options(schemas[0].1, options.clone()));
+    let mut all_valid = true;
+    let mut indeterminate = false;
 
     for json_path in matches.values_of("input").unwrap() {
-        let start = Instant::now();
-        let json = serde_json::from_reader(File::open(json_path)?)?;
-        let result = schema.validate(&json);
-        let duration = start.elapsed();
-        match result {
-            Ok(_) => {
-                println!(
-                    "{} validated successfully in {} ms",
-                    json_path,
-                    duration.millis()
-                )
+        let text = files::read_text(json_path)?;
+        if strict_parse {
+            let duplicates = check_duplicate_keys(&text)?;
+            if !duplicates.is_empty() {
+                all_valid = false;
+                if let Some(ref mut report) = sarif {
+                    report.add_duplicate_keys(json_path, duplicates);
+                    continue;
+                }
+                println!("{} has duplicate keys:", json_path);
+                for duplicate in duplicates {
+                    println!("{}", duplicate);
+                }
+                continue;
+            }
+        }
+        let documents = if options.multiple_documents {
+            files::parse_json_documents(json_path, &text)?
+        } else {
+            vec![files::parse_json(json_path, &text)?]
+        };
+        for (index, json) in documents.iter().enumerate() {
+            let document_path = if options.multiple_documents {
+                format!("{} (document {})", json_path, index + 1)
+            } else {
+                json_path.to_string()
+            };
+            let mut outputs = vec![];
+            if self_described {
+                let start = Instant::now();
+                match store.validate_self_described_with(json, &options) {
+                    Ok(output) => {
+                        let schema_path = json["$schema"].as_str().unwrap_or_default();
+                        outputs.push((schema_path, output, start.elapsed()));
+                    }
+                    Err(e) => {
+                        all_valid = false;
+                        println!("{} can't be validated: {}", document_path, e);
+                    }
+                }
+            }
+            for &(schema_path, schema) in &schemas {
+                let start = Instant::now();
+                let output = match coverage {
+                    Some(ref mut coverage) => coverage.validate(json),
+                    None => store.validate_with(schema, json, &options),
+                };
+                outputs.push((schema_path, output, start.elapsed()));
+            }
+            for (schema_path, output, duration) in outputs {
+                if let Some(ref mut report) = sarif {
+                    all_valid &= output.is_valid();
+                    report.add_output(json_path, output);
+                    continue;
+                }
+                let label = if schemas.len() > 1 || self_described {
+                    format!("{} against {}", document_path, schema_path)
+                } else {
+                    document_path.clone()
+                };
+                for warning in &output.warnings {
+                    if color {
+                        println!("\x1b[33m{}: {}\x1b[0m", label, warning);
+                    } else {
+                        println!("{}: {}", label, warning);
+                    }
+                }
+                all_valid &= output.is_valid() && (!deny_warnings || output.warnings.is_empty());
+                let problems = output.problems();
+                indeterminate |= !problems.is_empty();
+                if output.incomplete {
+                    println!(
+                        "{} timed out after {} ms, showing the errors found so far:\n{}",
+                        label,
+                        duration.millis(),
+                        ValidationErrors(output.errors)
+                    );
+                } else if !problems.is_empty() {
+                    println!("{} couldn't be validated, the schema has problems:", label);
+                    for problem in problems {
+                        println!("{}", problem);
+                    }
+                } else if output.is_valid() {
+                    println!(
+                        "{} validated successfully in {} ms",
+                        label,
+                        duration.millis()
+                    )
+                } else {
+                    println!(
+                        "{} has errors:\n{}",
+                        label,
+                        ValidationErrors(output.errors)
+                    );
+                }
             }
-            Err(e) => println!("{} has errors:\n{}", json_path, e),
         }
     }
 
-    Ok(())
+    if let Some(report) = sarif {
+        println!("{}", serde_json::to_string_pretty(&report.to_sarif())?);
+    }
+
+    if let (Some(path), Some(stats)) = (matches.value_of("stats"), stats) {
+        let report = stats.report();
+        serde_json::to_writer_pretty(files::create(path)?, &report)?;
+        eprint!("{}", report);
+    }
+
+    if let (Some(path), Some(coverage)) = (matches.value_of("coverage"), coverage) {
+        let report = coverage.report();
+        serde_json::to_writer_pretty(files::create(path)?, &report)?;
+        println!(
+            "{:.1}% of the schema covered, {} subschemas never matched, see {}",
+            report.percent_covered,
+            report.uncovered().len(),
+            path
+        );
+    }
+
+    if indeterminate {
+        return Ok(EXIT_INDETERMINATE);
+    }
+    Ok(exit_code(all_valid))
 }
 
 fn main() {
-    if let Err(e) = run() {
-        println!("Error: {}", e);
+    match run() {
+        Ok(0) => {}
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
     }
 }
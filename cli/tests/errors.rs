@@ -0,0 +1,169 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A directory of its own for the files of the test `name`.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("json_schema_cli_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs the CLI with `args`, expecting it to fail, and returns its stderr.
+fn stderr(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?} succeeded", args);
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn missing_files() {
+    let dir = test_dir("missing");
+    let schema = dir.join("schema.json");
+    fs::write(&schema, r#"{"type": "string"}"#).unwrap();
+    let schema = schema.to_str().unwrap();
+    let missing = dir.join("missing.json");
+    let missing = missing.to_str().unwrap();
+
+    let error = stderr(&["-s", missing, "-i", schema]);
+    assert!(error.contains(missing), "{}", error);
+    let error = stderr(&["-s", schema, "-i", missing]);
+    assert!(error.contains(missing), "{}", error);
+    let error = stderr(&["-s", schema, "--schemas", missing, "-i", schema]);
+    assert!(error.contains(missing), "{}", error);
+    let error = stderr(&["-s", schema, "--schema-dir", missing, "-i", schema]);
+    assert!(error.contains(missing), "{}", error);
+}
+
+#[test]
+fn invalid_files() {
+    let dir = test_dir("invalid");
+    let schema = dir.join("schema.json");
+    fs::write(&schema, r#"{"type": "string"}"#).unwrap();
+    let schema = schema.to_str().unwrap();
+    let broken = dir.join("broken.json");
+    fs::write(&broken, "{\"type\": \"string\",\n}").unwrap();
+    let broken = broken.to_str().unwrap();
+    let not_a_schema = dir.join("not_a_schema.json");
+    fs::write(&not_a_schema, r#"{"type": "strin"}"#).unwrap();
+    let not_a_schema = not_a_schema.to_str().unwrap();
+
+    let error = stderr(&["-s", broken, "-i", schema]);
+    assert!(error.contains(broken), "{}", error);
+    assert!(error.contains("line 2 column 1"), "{}", error);
+    let error = stderr(&["-s", not_a_schema, "-i", schema]);
+    assert!(
+        error.contains(&format!("{} isn't a valid schema", not_a_schema)),
+        "{}",
+        error
+    );
+    let error = stderr(&["-s", schema, "-i", schema, broken]);
+    assert!(
+        error.contains(&format!("{} isn't valid JSON", broken)),
+        "{}",
+        error
+    );
+    assert!(error.contains("line 2 column 1"), "{}", error);
+}
+
+#[test]
+fn validation_outcomes() {
+    let dir = test_dir("outcomes");
+    let schema = dir.join("schema.json");
+    fs::write(
+        &schema,
+        r##"{"type": "object", "properties": {"id": {"$ref": "#/definitions/id"}},
+            "definitions": {"id": {"type": "integer"}}}"##,
+    )
+    .unwrap();
+    let schema = schema.to_str().unwrap();
+    let broken = dir.join("broken.json");
+    fs::write(&broken, r##"{"type": "array", "items": {"$ref": "#/definitions/id"}}"##).unwrap();
+    let broken = broken.to_str().unwrap();
+    let valid = dir.join("valid.json");
+    fs::write(&valid, r#"{"id": 1}"#).unwrap();
+    let valid = valid.to_str().unwrap();
+    let invalid = dir.join("invalid.json");
+    fs::write(&invalid, r#"{"id": "1"}"#).unwrap();
+    let invalid = invalid.to_str().unwrap();
+    let items = dir.join("items.json");
+    fs::write(&items, "[1]").unwrap();
+    let items = items.to_str().unwrap();
+
+    let code = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cli"))
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+    assert_eq!(code(&["-s", schema, "-i", valid]), Some(0));
+    assert_eq!(code(&["-s", schema, "-i", invalid]), Some(1));
+    assert_eq!(code(&["-s", broken, "-i", items]), Some(2));
+    // an input the schema rejects doesn't hide the problem of another
+    assert_eq!(code(&["-s", broken, "-i", items, "-i", valid]), Some(2));
+}
+
+#[test]
+fn self_described_documents() {
+    let dir = test_dir("self_described");
+    let schemas = dir.join("schemas.json");
+    fs::write(
+        &schemas,
+        r#"[{"$id": "https://example.com/event.json", "type": "object",
+             "additionalProperties": true, "properties": {"at": {"type": "integer"}}},
+            {"$id": "https://example.com/user.json", "type": "object",
+             "additionalProperties": true, "properties": {"name": {"type": "string"}}}]"#,
+    )
+    .unwrap();
+    let schemas = schemas.to_str().unwrap();
+    let event = dir.join("event.json");
+    fs::write(&event, r#"{"$schema": "https://example.com/event.json", "at": 1}"#).unwrap();
+    let event = event.to_str().unwrap();
+    let user = dir.join("user.json");
+    fs::write(&user, r#"{"$schema": "https://example.com/user.json", "name": "Ann"}"#).unwrap();
+    let user = user.to_str().unwrap();
+    let unknown = dir.join("unknown.json");
+    fs::write(&unknown, r#"{"$schema": "https://example.com/order.json"}"#).unwrap();
+    let unknown = unknown.to_str().unwrap();
+
+    let run = |inputs: &[&str]| {
+        let mut args = vec!["--schemas", schemas, "--self-described", "-i"];
+        args.extend(inputs);
+        let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+            .args(&args)
+            .output()
+            .unwrap();
+        (output.status.code(), String::from_utf8(output.stdout).unwrap())
+    };
+    let (code, stdout) = run(&[event, user]);
+    assert_eq!(code, Some(0), "{}", stdout);
+    assert!(stdout.contains(&format!("{} against https://example.com/user.json", user)));
+    let (code, stdout) = run(&[event, unknown]);
+    assert_eq!(code, Some(1), "{}", stdout);
+    assert!(stdout.contains("`https://example.com/order.json`, which isn't registered"));
+}
+
+#[test]
+fn uncoloured_warnings_in_pipes() {
+    let dir = test_dir("warnings");
+    let schema = dir.join("schema.json");
+    fs::write(&schema, r#"{"type": "string", "format": "isbn"}"#).unwrap();
+    let schema = schema.to_str().unwrap();
+    let input = dir.join("input.json");
+    fs::write(&input, r#""0-306-40615-2""#).unwrap();
+    let input = input.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["-s", schema, "-i", input])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("isbn"), "{}", stdout);
+    assert!(!stdout.contains('\x1b'), "{:?}", stdout);
+}
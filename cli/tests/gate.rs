@@ -0,0 +1,93 @@
+extern crate serde_json;
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+/// Writes the schema of the test `name` and returns its path.
+fn schema(name: &str) -> String {
+    let dir = env::temp_dir().join(format!("json_schema_cli_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("schema.json");
+    let schema = r#"{
+        "type": "object",
+        "required": ["id"],
+        "properties": {"id": {"type": "integer"}}
+    }"#;
+    fs::write(&path, schema).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Runs `gate` with `args` and `input` on stdin, returns the exit code, stdout
+/// and stderr.
+fn gate(args: &[&str], input: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .arg("gate")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn valid_documents_pass_unchanged() {
+    let schema = schema("valid");
+    let input = "{ \"id\" :1,\n  \"extra\": [1.50, \"\\u00e9\"] }\n";
+    let (code, stdout, stderr) = gate(&["-s", &schema], input);
+    assert_eq!(code, 0, "{}", stderr);
+    assert_eq!(stdout, input);
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn invalid_documents_are_reported() {
+    let schema = schema("invalid");
+    let input = r#"{"id": "one"}"#;
+    let (code, stdout, stderr) = gate(&["-s", &schema], input);
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+    let report: Value = serde_json::from_str(&stderr).unwrap();
+    assert_eq!(report["valid"], Value::Bool(false));
+    assert_eq!(report["errors"][0]["code"], "TypeMismatch");
+    assert_eq!(report["errors"][0]["instancePath"], "/id");
+
+    let (code, stdout, stderr) = gate(&["-s", &schema, "--passthrough-invalid"], input);
+    assert_eq!(code, 1);
+    assert_eq!(stdout, input);
+    let report: Value = serde_json::from_str(&stderr).unwrap();
+    assert_eq!(report["errors"][0]["code"], "TypeMismatch");
+}
+
+#[test]
+fn sanitized_documents() {
+    let schema = schema("sanitized");
+    let input = r#"{"id": 1, "extra": true}"#;
+    let (code, stdout, stderr) = gate(&["-s", &schema, "--sanitize"], input);
+    assert_eq!(code, 0, "{}", stderr);
+    assert_eq!(stdout, "{\"id\":1}\n");
+
+    let input = r#"{"extra": true}"#;
+    let (code, stdout, stderr) = gate(&["-s", &schema, "--sanitize"], input);
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+    let report: Value = serde_json::from_str(&stderr).unwrap();
+    assert_eq!(report["removed"][0], "/extra");
+    assert_eq!(report["errors"][0]["code"], "MissingProperties");
+}
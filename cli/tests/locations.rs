@@ -0,0 +1,80 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn check_schema_locations() {
+    let dir = env::temp_dir().join(format!("json_schema_cli_locations_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let schema = dir.join("schema.json");
+    fs::write(
+        &schema,
+        r#"{
+  "type": "object",
+  "properties": {
+    "code": {
+      "type": "string",
+      "x-counterexamples": ["abc", 1]
+    }
+  },
+  "if": {"required": ["code"]}
+}"#,
+    )
+    .unwrap();
+    let schema = schema.to_str().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["--check-schema", "-s", schema])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stdout.contains(&format!(
+            "{}:6:29: Counterexample 0 of `/properties/code`",
+            schema
+        )),
+        "{}",
+        stdout
+    );
+    assert!(
+        stderr.contains(&format!("{}:9:3: `if` is not enforced", schema)),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn duplicate_declarations() {
+    let dir = env::temp_dir().join(format!("json_schema_cli_duplicates_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let schema = dir.join("schema.json");
+    fs::write(
+        &schema,
+        "{\"type\": \"object\", \"required\": [\"id\", \"id\"],\n \
+         \"properties\": {\"id\": {\"type\": \"string\"}, \"id\": {\"type\": \"integer\"}}}",
+    )
+    .unwrap();
+    let schema = schema.to_str().unwrap();
+    let input = dir.join("input.json");
+    fs::write(&input, r#"{"id": 1}"#).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["-s", schema, "-i", input.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!(
+            "{}:2:43: `id` is declared 2 times in `properties`",
+            schema
+        )),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains(&format!("{}:1:33: `id` is listed 2 times", schema)),
+        "{}",
+        stderr
+    );
+}
@@ -3,7 +3,7 @@ extern crate serde_json;
 
 use std::str::FromStr;
 
-use json_schema::Schema;
+use json_schema::prelude::*;
 
 const SCHEMA: &str = r#"
 {
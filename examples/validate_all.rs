@@ -0,0 +1,92 @@
+//! Compares `validate_all` with validating against each schema on its own, for
+//! a large array of records and three schemas. Run it with
+//! `cargo run --release --example validate_all`.
+
+extern crate json_schema;
+#[macro_use]
+extern crate serde_json;
+
+use std::time::{Duration, Instant};
+
+use json_schema::{validate_all, Schema};
+use serde_json::Value;
+
+const RECORDS: usize = 200_000;
+const RUNS: usize = 10;
+
+fn schema(items: Value) -> Schema {
+    serde_json::from_value(json!({"type": "array", "items": items})).unwrap()
+}
+
+/// The fastest of `RUNS` runs of `f`.
+fn fastest<F: FnMut()>(mut f: F) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let structure = schema(json!({
+        "type": "object",
+        "required": ["id", "name", "tags"],
+        "additionalProperties": true,
+        "properties": {
+            "id": {"type": "integer"},
+            "name": {"type": "string"},
+            "tags": {"type": "array", "items": {"type": "string"}},
+            "price": {"type": "number"}
+        }
+    }));
+    let policy = schema(json!({
+        "type": "object",
+        "additionalProperties": true,
+        "properties": {
+            "id": {"type": "integer", "minimum": 1},
+            "name": {"type": "string", "minLength": 1, "maxLength": 64},
+            "tags": {"type": "array", "maxItems": 8, "uniqueItems": true},
+            "price": {"type": "number", "minimum": 0}
+        }
+    }));
+    let deprecation = schema(json!({
+        "type": "object",
+        "additionalProperties": true,
+        "properties": {
+            "legacy": {"not": {}},
+            "kind": {"enum": ["a", "b", "c"]}
+        }
+    }));
+    let schemas = [&structure, &policy, &deprecation];
+    let records: Vec<_> = (0..RECORDS)
+        .map(|i| {
+            json!({
+                "id": i + 1,
+                "name": format!("record {}", i),
+                "tags": ["x", "y"],
+                "price": i as f64 / 100.0,
+                "kind": "b",
+                "note": "some text that isn't validated by any of the schemas"
+            })
+        })
+        .collect();
+    let document = Value::Array(records);
+
+    let separate = fastest(|| {
+        for schema in &schemas {
+            assert!(schema.validate(&document).is_ok());
+        }
+    });
+    let shared = fastest(|| {
+        assert!(validate_all(&schemas, &document).iter().all(Result::is_ok));
+    });
+    println!("separate: {:?}", separate);
+    println!("validate_all: {:?}", shared);
+    println!(
+        "speedup: {:.2}x",
+        separate.as_secs_f64() / shared.as_secs_f64()
+    );
+}
@@ -1,43 +1,142 @@
+use std::mem::size_of;
+
 use serde_json::Value;
 
-use util::{JsonType, JsonValueExt};
+use types::JsonType;
 use errors::{ErrorKind, ValidationError};
-use schema::{Context, Schema, SchemaBase};
+use object::PropertyMap;
+use schema::{Context, Schema, SchemaBase, Segment};
+use util::{deserialize_some, json_equal};
 
 /// Schema for JSON arrays like `[1, 2, 3]`.
+///
+/// Besides the keywords of JSON schema, array schemas understand the extension
+/// keyword `x-uniqueBy`, which requires the elements to be unique by the value at
+/// a JSON pointer: `{"type": "array", "x-uniqueBy": "/id"}` rejects two objects
+/// with the same `id`. Other validators ignore it.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
 pub struct ArraySchema {
-    description: Option<String>,
-    id: Option<String>,
-    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
+    pub(crate) enum_values: Option<Vec<Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "const",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) const_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+    #[serde(skip)]
+    pub(crate) nullable: bool,
 
-    min_items: Option<usize>,
-    max_items: Option<usize>,
-    unique_items: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) min_items: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_items: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) unique_items: Option<bool>,
 
-    items: Option<Items>,
+    /// The tuple form of draft 2020-12. If it's given, `items` applies to the
+    /// elements after the tuple.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) prefix_items: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) items: Option<Items>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) additional_items: Option<AdditionalItems>,
 
-    additional_items: Option<bool>,
+    /// The extension keyword `x-uniqueBy`: a JSON pointer into the elements,
+    /// whose values have to be unique. `"/id"` requires objects with different
+    /// `id`s. Elements without a value at the pointer are skipped, `required`
+    /// makes sure they have one.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-uniqueBy")]
+    pub(crate) unique_by: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(untagged)]
-enum Items {
+pub(crate) enum Items {
+    /// `true` allows any elements, `false` none.
+    Allowed(bool),
     List(Box<Schema>),
     Tuple(Vec<Schema>),
 }
 
-impl ArraySchema {
-    fn additional_items(&self) -> bool {
-        self.additional_items.unwrap_or(false)
-    }
+/// What `additionalItems` allows for the elements after the ones covered by a tuple.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum AdditionalItems {
+    Allowed(bool),
+    Schema(Box<Schema>),
+}
 
+impl ArraySchema {
     fn unique_items(&self) -> bool {
         self.unique_items.unwrap_or(false)
     }
 
+    /// The schema the element at `index` has to match, if any.
+    pub(crate) fn item_schema(&self, index: usize) -> Option<&Schema> {
+        let (tuple, rest): (&[Schema], Option<&Schema>) = match (&self.prefix_items, &self.items) {
+            (Some(prefix), Some(Items::List(rest))) => (prefix, Some(rest)),
+            (Some(prefix), _) => (prefix, None),
+            (None, Some(Items::List(rest))) => (&[], Some(rest)),
+            (None, Some(Items::Tuple(tuple))) => match self.additional_items {
+                Some(AdditionalItems::Schema(ref rest)) => (tuple, Some(rest)),
+                _ => (tuple, None),
+            },
+            (None, _) => (&[], None),
+        };
+        if index < tuple.len() {
+            tuple.get(index)
+        } else {
+            rest
+        }
+    }
+
+    /// Like `item_schema`, with the pointer from this schema to the item schema,
+    /// like `/items` or `/prefixItems/0`.
+    pub(crate) fn item_schema_with_pointer(&self, index: usize) -> Option<(String, &Schema)> {
+        let schema = self.item_schema(index)?;
+        let tuple = match (&self.prefix_items, &self.items) {
+            (Some(prefix), _) => Some(("prefixItems", prefix.len())),
+            (None, Some(Items::Tuple(tuple))) => Some(("items", tuple.len())),
+            _ => None,
+        };
+        let pointer = match tuple {
+            Some((keyword, len)) if index < len => format!("/{}/{}", keyword, index),
+            Some(("items", _)) => "/additionalItems".into(),
+            _ => "/items".into(),
+        };
+        Some((pointer, schema))
+    }
+
     fn validate_size<'json>(
         &self,
         array: &'json [Value],
@@ -46,24 +145,24 @@ impl ArraySchema {
     ) {
         if let Some(min) = self.min_items {
             if array.len() < min {
-                errors.push(ValidationError {
-                    reason: ErrorKind::MinLength {
+                errors.push(ValidationError::new(
+                    ErrorKind::MinLength {
                         expected: min,
                         found: array.len(),
                     },
-                    node: parent,
-                });
+                    parent,
+                ));
             }
         }
         if let Some(max) = self.max_items {
             if array.len() > max {
-                errors.push(ValidationError {
-                    reason: ErrorKind::MaxLength {
+                errors.push(ValidationError::new(
+                    ErrorKind::MaxLength {
                         expected: max,
                         found: array.len(),
                     },
-                    node: parent,
-                });
+                    parent,
+                ));
             }
         }
     }
@@ -72,57 +171,213 @@ impl ArraySchema {
         &self,
         ctx: &Context,
         array: &'json [Value],
-        parent: &'json Value,
         errors: &mut Vec<ValidationError<'json>>,
     ) {
-        if let Some(ref items) = self.items {
-            match *items {
-                Items::Tuple(ref schemas) => {
-                    if schemas.len() != array.len() && !self.additional_items() {
-                        errors.push(ValidationError {
-                            reason: ErrorKind::TupleLengthMismatch {
-                                schemas: schemas.len(),
-                                tuple: array.len(),
-                            },
-                            node: parent,
-                        });
-                    }
+        if let Some(deferred) = ctx.deferred {
+            deferred.set(Some(errors.len()));
+            return;
+        }
+        if let Some(ref schemas) = self.prefix_items {
+            self.validate_tuple(ctx, "prefixItems", schemas, array, errors);
+            if let Some(ref items) = self.items {
+                self.validate_items_after(ctx, items, schemas.len(), array, errors);
+            }
+            return;
+        }
 
-                    for (schema, value) in schemas.iter().zip(array) {
-                        schema.validate_inner(ctx, value, errors);
+        match self.items {
+            // Arrays shorter than the tuple are fine, `minItems` is what
+            // requires elements. Elements after the tuple are checked against
+            // `additionalItems`, which allows anything if it's missing.
+            Some(Items::Tuple(ref schemas)) => {
+                self.validate_tuple(ctx, "items", schemas, array, errors);
+                if array.len() <= schemas.len() {
+                    return;
+                }
+                match self.additional_items {
+                    Some(AdditionalItems::Allowed(false)) => {
+                        self.reject_extra(ctx, schemas.len(), array, errors)
+                    }
+                    Some(AdditionalItems::Schema(ref schema)) => {
+                        let (keyword, start) = ("additionalItems", schemas.len());
+                        self.validate_rest(ctx, keyword, Some(schema), start, array, errors)
                     }
+                    Some(AdditionalItems::Allowed(true)) | None => {}
                 }
-                Items::List(ref schema) => for value in array {
-                    schema.validate_inner(ctx, value, errors);
-                },
             }
+            Some(ref items) => self.validate_items_after(ctx, items, 0, array, errors),
+            None => {}
+        }
+    }
+
+    /// Reports each element of `array` after the `schemas` ones of a tuple that
+    /// doesn't allow more, up to `ValidateOptions::max_errors`.
+    fn reject_extra<'json>(
+        &self,
+        ctx: &Context,
+        schemas: usize,
+        array: &'json [Value],
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        for (i, value) in array.iter().enumerate().skip(schemas) {
+            if ctx.has_enough_errors(errors) {
+                return;
+            }
+            ctx.descend(Some(Segment::Index(i)), "additionalItems", None, |ctx| {
+                let kind = ErrorKind::TupleLengthMismatch {
+                    schemas,
+                    tuple: array.len(),
+                    first_extra_index: schemas,
+                    index: i,
+                };
+                let mut error = [ValidationError::new(kind, value)];
+                ctx.locate(&mut error);
+                errors.extend(error);
+            });
+        }
+    }
+
+    /// Validates the first elements of `array` against the schemas of a tuple.
+    fn validate_tuple<'json>(
+        &self,
+        ctx: &Context,
+        keyword: &str,
+        schemas: &[Schema],
+        array: &'json [Value],
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        for (i, (schema, value)) in schemas.iter().zip(array).enumerate() {
+            let index = Segment::Index(i);
+            ctx.descend(Some(index), keyword, Some(index), |ctx| {
+                schema.validate_inner(ctx, value, errors)
+            });
+        }
+    }
+
+    /// Validates the elements of `array` from `start` on against the single schema
+    /// or boolean `items`.
+    fn validate_items_after<'json>(
+        &self,
+        ctx: &Context,
+        items: &Items,
+        start: usize,
+        array: &'json [Value],
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        match *items {
+            Items::List(ref schema) => {
+                self.validate_rest(ctx, "items", Some(schema), start, array, errors)
+            }
+            Items::Allowed(false) => self.validate_rest(ctx, "items", None, start, array, errors),
+            // a tuple after `prefixItems` isn't valid, it's ignored like `true`
+            Items::Allowed(true) | Items::Tuple(_) => {}
+        }
+    }
+
+    /// Validates the elements of `array` from `start` on against `schema`, or
+    /// rejects each of them if there's no schema. Only a sample of them is
+    /// validated against a schema if `ValidateOptions::array_sampling` says so.
+    fn validate_rest<'json>(
+        &self,
+        ctx: &Context,
+        keyword: &str,
+        schema: Option<&Schema>,
+        start: usize,
+        array: &'json [Value],
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        let validate = |i: usize| {
+            let value = &array[i];
+            ctx.descend(Some(Segment::Index(i)), keyword, None, |ctx| match schema {
+                Some(schema) => schema.validate_inner(ctx, value, errors),
+                None => {
+                    let mut error = [ValidationError::new(ErrorKind::ItemNotAllowed(i), value)];
+                    ctx.locate(&mut error);
+                    errors.extend(error);
+                }
+            });
+        };
+        match schema.and_then(|_| ctx.sample(start, array.len())) {
+            Some(indices) => indices.into_iter().for_each(validate),
+            None => (start..array.len()).for_each(validate),
         }
     }
 
     fn validate_unique<'json>(
         &self,
+        ctx: &Context,
         array: &'json [Value],
         parent: &'json Value,
         errors: &mut Vec<ValidationError<'json>>,
     ) {
         if self.unique_items() {
+            if ctx.samples(array.len()) {
+                ctx.skip("uniqueItems");
+                return;
+            }
             let mut unique_items = vec![];
             for item in array {
-                for contained in &unique_items {
-                    if *contained == item {
-                        errors.push(ValidationError {
-                            node: parent,
-                            reason: ErrorKind::ArrayItemNotUnique,
-                        });
-                        return;
-                    }
+                if unique_items.contains(&item) {
+                    errors.push(ValidationError::new(ErrorKind::ArrayItemNotUnique, parent));
+                    break;
+                }
+                if !ctx.track(size_of::<&Value>()) {
+                    break;
                 }
                 unique_items.push(item);
             }
+            ctx.untrack(unique_items.len() * size_of::<&Value>());
         }
     }
-}
 
+    /// Reports each element whose value at the `x-uniqueBy` pointer an earlier
+    /// element already has.
+    fn validate_unique_by<'json>(
+        &self,
+        ctx: &Context,
+        array: &'json [Value],
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        let pointer = match self.unique_by {
+            Some(ref pointer) => pointer,
+            None => return,
+        };
+        if !ctx.applies_extension("x-uniqueBy") {
+            return;
+        }
+        if ctx.samples(array.len()) {
+            ctx.skip("x-uniqueBy");
+            return;
+        }
+        let mut seen: Vec<(&Value, usize)> = vec![];
+        for (i, element) in array.iter().enumerate() {
+            let key = match element.pointer(pointer) {
+                Some(key) => key,
+                None => continue,
+            };
+            if !ctx.track(size_of::<(&Value, usize)>()) {
+                break;
+            }
+            match seen.iter().find(|&&(seen, _)| json_equal(seen, key)) {
+                Some(&(_, first)) => {
+                    ctx.descend(Some(Segment::Index(i)), "x-uniqueBy", None, |ctx| {
+                        let kind = ErrorKind::NotUniqueBy {
+                            pointer: pointer.clone(),
+                            key: Box::new(key.clone()),
+                            first,
+                            duplicate: i,
+                        };
+                        let mut error = [ValidationError::new(kind, element)];
+                        ctx.locate(&mut error);
+                        errors.extend(error);
+                    });
+                }
+                None => seen.push((key, i)),
+            }
+        }
+        ctx.untrack(seen.len() * size_of::<(&Value, usize)>());
+    }
+}
 
 impl SchemaBase for ArraySchema {
     #[doc(hidden)]
@@ -135,14 +390,16 @@ impl SchemaBase for ArraySchema {
         match value {
             &Value::Array(ref array) => {
                 self.validate_size(array, value, errors);
-                self.validate_items(ctx, array, value, errors);
-                self.validate_unique(array, value, errors);
+                self.validate_items(ctx, array, errors);
+                ctx.time(
+                    |timing| &mut timing.uniqueness,
+                    || {
+                        self.validate_unique(ctx, array, value, errors);
+                        self.validate_unique_by(ctx, array, errors);
+                    },
+                );
             }
-            val => errors.push(ValidationError::type_mismatch(
-                val,
-                JsonType::Array,
-                val.get_type(),
-            )),
+            val => errors.push(ValidationError::type_mismatch(val, JsonType::Array)),
         }
     }
 }
@@ -153,13 +410,17 @@ pub struct ArraySchemaBuilder {
     description: Option<String>,
     id: Option<String>,
     title: Option<String>,
+    examples: Option<Vec<Value>>,
+    counterexamples: Option<Vec<Value>>,
 
     min_items: Option<usize>,
     max_items: Option<usize>,
     unique_items: bool,
+    unique_by: Option<String>,
 
     items: Option<Items>,
-    additional_items: bool,
+    additional_items: AdditionalItems,
+    exact_tuple: bool,
 }
 
 impl Default for ArraySchemaBuilder {
@@ -168,13 +429,17 @@ impl Default for ArraySchemaBuilder {
             description: None,
             id: None,
             title: None,
+            examples: None,
+            counterexamples: None,
 
             min_items: None,
             max_items: None,
             unique_items: false,
+            unique_by: None,
             items: Default::default(),
 
-            additional_items: true,
+            additional_items: AdditionalItems::Allowed(true),
+            exact_tuple: false,
         }
     }
 }
@@ -196,6 +461,16 @@ impl ArraySchemaBuilder {
         self.title = Some(value.into());
         self
     }
+    /// Adds a value the schema accepts, see `Schema::self_test`.
+    pub fn example(mut self, value: Value) -> Self {
+        self.examples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
+    /// Adds a value the schema rejects, see `Schema::self_test`.
+    pub fn counterexample(mut self, value: Value) -> Self {
+        self.counterexamples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
     /// Set the minimum number of items this array must have.
     pub fn min_items(mut self, value: usize) -> Self {
         self.min_items = Some(value);
@@ -211,6 +486,12 @@ impl ArraySchemaBuilder {
         self.unique_items = true;
         self
     }
+    /// Make it so the values at `pointer` in the items have to be unique, see the
+    /// `x-uniqueBy` extension keyword.
+    pub fn unique_by<V: Into<String>>(mut self, pointer: V) -> Self {
+        self.unique_by = Some(pointer.into());
+        self
+    }
     /// Set a schema that every item must conform to. (list validation)
     pub fn all_items_schema<V: Into<Schema>>(mut self, value: V) -> Self {
         self.items = Some(Items::List(Box::new(value.into())));
@@ -223,22 +504,54 @@ impl ArraySchemaBuilder {
     }
     /// Set whether additional items are allowed (tuple validation).
     pub fn additional_items(mut self, value: bool) -> Self {
-        self.additional_items = value;
+        self.additional_items = AdditionalItems::Allowed(value);
+        self
+    }
+    /// Set a schema that the items after the tuple must conform to (tuple validation).
+    pub fn additional_items_schema<V: Into<Schema>>(mut self, value: V) -> Self {
+        self.additional_items = AdditionalItems::Schema(Box::new(value.into()));
+        self
+    }
+    /// Make it so arrays have to have an element for each schema of the tuple
+    /// set with `item_schemas`, which are otherwise optional. This sets
+    /// `minItems` and `maxItems` to the length of the tuple when building, in
+    /// place of the bounds set with `min_items` and `max_items`, so the schema
+    /// stays standard JSON schema.
+    pub fn exact_tuple(mut self) -> Self {
+        self.exact_tuple = true;
         self
     }
     /// Returns the finished `Schema`.
     pub fn build(self) -> Schema {
+        let (min_items, max_items) = match self.items {
+            Some(Items::Tuple(ref schemas)) if self.exact_tuple => {
+                (Some(schemas.len()), Some(schemas.len()))
+            }
+            _ => (self.min_items, self.max_items),
+        };
         From::from(ArraySchema {
             description: self.description,
             id: self.id,
             title: self.title,
+            nullable: false,
+            default: None,
+            examples: self.examples,
+            counterexamples: self.counterexamples,
+            deprecated: None,
+            removed_in: None,
+            enum_values: None,
+            const_value: None,
+            definitions: None,
+            dollar_defs: false,
 
-            min_items: self.min_items,
-            max_items: self.max_items,
+            min_items,
+            max_items,
             unique_items: Some(self.unique_items),
 
+            prefix_items: None,
             items: self.items,
             additional_items: Some(self.additional_items),
+            unique_by: self.unique_by,
         })
     }
 }
@@ -248,20 +561,99 @@ mod tests {
     use serde_json;
 
     use super::*;
-    use errors::ErrorKind;
+    use errors::{ErrorCode, ErrorKind};
     use number::NumberSchemaBuilder;
+    use options::{SamplingPolicy, ValidateOptions};
+    use output::SkippedKeyword;
+    use string::StringSchemaBuilder;
 
     #[test]
     fn unique_elements() {
         let schema = ArraySchemaBuilder::default().unique_items().build();
-        let input = serde_json::from_str("[1, 1, 2, 3, 4]").unwrap();
-        let errors = schema.validate(&input).unwrap_err().0;
-        assert_eq!(errors.len(), 1);
-        if let ErrorKind::ArrayItemNotUnique = errors[0].reason {
+        assert_invalid!(schema, json!([1, 1, 2, 3, 4]), ErrorKind::ArrayItemNotUnique);
+    }
 
-        } else {
-            assert!(false, "Wrong error reason");
-        }
+    #[test]
+    fn sampling() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "array",
+            "minItems": 1,
+            "uniqueItems": true,
+            "items": {"type": "integer"}
+        }))
+        .unwrap();
+        // every element is invalid, so the errors show which ones were checked
+        let input = Value::Array(vec![json!("x"); 10_000]);
+        let sampled = |seed| {
+            let options = ValidateOptions {
+                array_sampling: Some(SamplingPolicy {
+                    max_per_array: 100,
+                    seed,
+                }),
+                ..ValidateOptions::default()
+            };
+            let output = schema.validate_with(&input, &options);
+            assert!(output.sampled);
+            let paths: Vec<_> = output
+                .errors
+                .iter()
+                .map(|e| e.instance_path.clone())
+                .collect();
+            (paths, output.skipped)
+        };
+        let (paths, skipped) = sampled(7);
+        assert_eq!(paths.len(), 100);
+        assert_eq!(paths[0], "/0");
+        assert_eq!(paths[99], "/9999");
+        assert_eq!(sampled(7).0, paths);
+        assert_ne!(sampled(8).0, paths);
+        assert_eq!(
+            skipped,
+            vec![SkippedKeyword {
+                keyword: "uniqueItems".into(),
+                schema_path: "".into(),
+                instance_path: "".into(),
+            }]
+        );
+
+        // short arrays are checked completely
+        let options = ValidateOptions {
+            array_sampling: Some(SamplingPolicy {
+                max_per_array: 100,
+                seed: 7,
+            }),
+            ..ValidateOptions::default()
+        };
+        let short = json!([1, 1]);
+        let output = schema.validate_with(&short, &options);
+        assert!(!output.sampled && output.skipped.is_empty());
+        assert_eq!(
+            output.errors[0].reason.code(),
+            ErrorCode::ArrayItemNotUnique
+        );
+        let output = schema.validate_with(&input, &ValidateOptions::default());
+        assert!(!output.sampled);
+        assert_eq!(output.errors.len(), 10_001);
+    }
+
+    #[test]
+    fn memory_limit() {
+        let schema = ArraySchemaBuilder::default().unique_items().build();
+        let input = Value::Array((0..5000).map(|i| json!(i)).collect());
+        let output = schema.validate_with(&input, &ValidateOptions::default());
+        assert!(output.is_valid() && !output.incomplete);
+        assert!(output.peak_tracked_bytes >= 5000 * size_of::<&Value>());
+
+        let options = ValidateOptions {
+            max_memory: Some(16 * 1024),
+            ..ValidateOptions::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        assert!(output.incomplete);
+        assert_eq!(output.errors.len(), 1);
+        assert_eq!(output.errors[0].reason.code(), ErrorCode::MemoryLimit);
+        assert!(output.peak_tracked_bytes > 16 * 1024);
+        assert!(output.peak_tracked_bytes < 17 * 1024);
     }
 
     #[test]
@@ -282,14 +674,281 @@ mod tests {
         let schema = ArraySchemaBuilder::default()
             .all_items_schema(item_schema)
             .build();
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::TypeMismatch, ErrorCode::NumberRange]);
+        assert_eq!(*errors.at_path("/0")[0].node, input[0]);
+        assert!(errors.contains_kind(|kind| match *kind {
+            ErrorKind::NumberRange { value, bound } => value == 2.5 && bound == 2.0,
+            _ => false,
+        }));
+    }
+
+    fn tuple_of(items: Value, additional_items: Value) -> Schema {
+        serde_json::from_value(json!({
+            "type": "array",
+            "items": items,
+            "additionalItems": additional_items
+        }))
+        .unwrap()
+    }
+
+    fn tuple(additional_items: Value) -> Schema {
+        tuple_of(json!([{"type": "string"}, {"type": "integer"}]), additional_items)
+    }
+
+    #[test]
+    fn tuple_exact_length() {
+        let schema = tuple(json!(false));
+        schema.validate(&json!(["a", 1])).unwrap();
+        let input = json!([1, "a"]);
         let errors = schema.validate(&input).unwrap_err().0;
-        assert_eq!(errors.len(), 2);
-        assert_eq!(*errors[0].node, input[0]);
-        if let ErrorKind::NumberRange { value, bound } = errors[1].reason {
-            assert_eq!(value, 2.5);
-            assert_eq!(bound, 2.0);
-        } else {
-            assert!(false, "Wrong property");
+        let paths: Vec<_> = errors.iter().map(|e| e.schema_path.as_str()).collect();
+        assert_eq!(paths, vec!["/items/0", "/items/1"]);
+    }
+
+    #[test]
+    fn tuple_shorter() {
+        let schema = tuple(json!(false));
+        schema.validate(&json!(["a"])).unwrap();
+        schema.validate(&json!([])).unwrap();
+        assert!(schema.validate(&json!([2])).is_err());
+    }
+
+    #[test]
+    fn tuple_min_items() {
+        let strings = || StringSchemaBuilder::default().build();
+        let builder = || {
+            ArraySchemaBuilder::default()
+                .item_schemas(vec![strings(), strings(), strings()])
+                .additional_items(false)
+                .min_items(1)
+        };
+        let schema = builder().build();
+        schema.validate(&json!(["a", "b"])).unwrap();
+        assert!(schema.validate(&json!([])).is_err());
+        assert!(schema.validate(&json!(["a", "b", "c", "d"])).is_err());
+
+        let schema = builder().exact_tuple().build();
+        schema.validate(&json!(["a", "b", "c"])).unwrap();
+        let input = json!(["a", "b"]);
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors[0].reason.code(), ErrorCode::MinLength);
+        assert!(schema.validate(&json!(["a", "b", "c", "d"])).is_err());
+
+        let serialized = serde_json::to_value(&schema).unwrap();
+        assert_eq!(serialized["minItems"], 3);
+        assert_eq!(serialized["maxItems"], 3);
+        let parsed: Schema = serde_json::from_value(serialized.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), serialized);
+        assert!(parsed.validate(&input).is_err());
+    }
+
+    #[test]
+    fn tuple_longer() {
+        let schema = tuple(json!({"type": "boolean"}));
+        schema.validate(&json!(["a", 1, true, false])).unwrap();
+        let input = json!(["a", 1, true, "b", 3]);
+        let errors = schema.validate(&input).unwrap_err().0;
+        let paths: Vec<_> = errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.schema_path.as_str()))
+            .collect();
+        assert_eq!(paths, vec![("/3", "/additionalItems"), ("/4", "/additionalItems")]);
+
+        let schema = tuple(json!(false));
+        let input = json!(["a", 1, true]);
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors[0].instance_path, "/2");
+        match errors[0].reason {
+            ErrorKind::TupleLengthMismatch { schemas: 2, tuple: 3, .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "array",
+            "items": [{"type": "string"}]
+        }))
+        .unwrap();
+        schema.validate(&json!(["a", 1, {}])).unwrap();
+    }
+
+    #[test]
+    fn csv_row_extra_columns() {
+        let schema = tuple_of(
+            json!([
+                {"type": "string"},
+                {"type": "string"},
+                {"type": "integer"},
+                {"type": "string", "format": "date"}
+            ]),
+            json!(false),
+        );
+        schema.validate(&json!(["Jane", "Doe", 31, "1990-01-01"])).unwrap();
+        let row = json!(["Jane", "Doe", 31, "1990-01-01", "x", ""]);
+        let errors = schema.validate(&row).unwrap_err().0;
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.schema_path.as_str(), e.reason.to_string()))
+            .collect();
+        let message = |i| format!("Element {} not allowed: tuple schema defines 4 items", i);
+        assert_eq!(
+            found,
+            vec![
+                ("/4", "/additionalItems", message(4)),
+                ("/5", "/additionalItems", message(5)),
+            ]
+        );
+        assert_eq!(errors[1].node, &row[5]);
+        match errors[1].reason {
+            ErrorKind::TupleLengthMismatch {
+                schemas: 4,
+                tuple: 6,
+                first_extra_index: 4,
+                index: 5,
+            } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let options = ValidateOptions {
+            max_errors: Some(1),
+            ..Default::default()
+        };
+        let errors = schema.validate_with(&row, &options).errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/4");
+
+        let schema = tuple_of(json!([{"type": "string"}]), json!(false));
+        let row = json!(["Jane", "Doe"]);
+        let errors = schema.validate(&row).unwrap_err().0;
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "Element 1 not allowed: tuple schema defines 1 item"
+        );
+    }
+
+    #[test]
+    fn unique_by() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "array",
+            "x-uniqueBy": "/id",
+            "items": {"type": "object", "additionalProperties": true}
+        }))
+        .unwrap();
+        schema
+            .validate(&json!([{"id": 1}, {"id": 2, "name": "a"}, {"id": "1"}]))
+            .unwrap();
+
+        let input = json!([{"id": 1}, {"id": 2}, {"id": 1.0, "name": "b"}, {"id": 2}]);
+        let errors = schema.validate(&input).unwrap_err().0;
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.schema_path.as_str(), e.reason.to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("/2", "/x-uniqueBy", "Items 0 and 2 have the same value 1.0 at `/id`".into()),
+                ("/3", "/x-uniqueBy", "Items 1 and 3 have the same value 2 at `/id`".into()),
+            ]
+        );
+
+        // elements without an `id` are left to `required`
+        schema
+            .validate(&json!([{"name": "a"}, {"name": "a"}, {"id": 1}]))
+            .unwrap();
+
+        let both = ArraySchemaBuilder::default()
+            .unique_items()
+            .unique_by("/id")
+            .build();
+        let input = json!([{"id": 1}, {"id": 1}]);
+        let errors = both.validate(&input).unwrap_err();
+        assert_eq!(
+            errors.codes(),
+            vec![ErrorCode::ArrayItemNotUnique, ErrorCode::NotUniqueBy]
+        );
+        both.validate(&json!([{"id": 1, "v": 1}, {"id": 2, "v": 1}])).unwrap();
+        let input = json!([{"id": 1, "v": 1}, {"id": 1, "v": 2}]);
+        let errors = both.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::NotUniqueBy]);
+        assert_eq!(serde_json::to_value(&both).unwrap()["x-uniqueBy"], json!("/id"));
+    }
+
+    #[test]
+    fn items_false() {
+        let schema: Schema =
+            serde_json::from_value(json!({"type": "array", "items": false})).unwrap();
+        schema.validate(&json!([])).unwrap();
+        let input = json!([1, "a"]);
+        let errors = schema.validate(&input).unwrap_err().0;
+        let paths: Vec<_> = errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.schema_path.as_str()))
+            .collect();
+        assert_eq!(paths, vec![("/0", "/items"), ("/1", "/items")]);
+        match errors[1].reason {
+            ErrorKind::ItemNotAllowed(1) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn empty_tuple() {
+        let schema = tuple_of(json!([]), json!({"type": "integer"}));
+        schema.validate(&json!([])).unwrap();
+        schema.validate(&json!([1, 2])).unwrap();
+        let input = json!([1, "a"]);
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors[0].schema_path, "/additionalItems");
+    }
+
+    #[test]
+    fn prefix_items() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "array",
+            "prefixItems": [{"type": "string"}, {"type": "integer"}],
+            "items": false
+        }))
+        .unwrap();
+        schema.validate(&json!(["a", 1])).unwrap();
+        schema.validate(&json!(["a"])).unwrap();
+        let input = json!([1, 1, null]);
+        let errors = schema.validate(&input).unwrap_err().0;
+        let paths: Vec<_> = errors.iter().map(|e| e.schema_path.as_str()).collect();
+        assert_eq!(paths, vec!["/prefixItems/0", "/items"]);
+    }
+
+    #[test]
+    fn items_round_trip() {
+        let forms = vec![
+            json!({"type": "array", "items": false}),
+            json!({"type": "array", "items": true}),
+            json!({"type": "array", "items": []}),
+            json!({"type": "array", "items": [{"type": "string"}], "additionalItems": false}),
+            json!({"type": "array", "prefixItems": [{"type": "string"}], "items": false}),
+        ];
+        for form in forms {
+            let schema: Schema = serde_json::from_value(form.clone()).unwrap();
+            let serialized = serde_json::to_value(&schema).unwrap();
+            for (key, value) in form.as_object().unwrap() {
+                if key != "items" && key != "prefixItems" && key != "additionalItems" {
+                    continue;
+                }
+                let mut actual = serialized[key].clone();
+                strip_nulls(&mut actual);
+                assert_eq!(&actual, value, "{} of {}", key, form);
+            }
+        }
+    }
+
+    fn strip_nulls(value: &mut Value) {
+        match *value {
+            Value::Object(ref mut object) => {
+                object.retain(|_, v| !v.is_null());
+                object.values_mut().for_each(strip_nulls);
+            }
+            Value::Array(ref mut array) => array.iter_mut().for_each(strip_nulls),
+            _ => {}
         }
     }
 }
@@ -0,0 +1,332 @@
+use std::fmt;
+
+use serde_json::{self, Number, Value};
+
+use duplicate_keys::check_duplicate_keys;
+use errors::Result;
+use util::pointer_token;
+
+/// Limits for `audit_instance`. Set them for validation with
+/// `ValidateOptions::audit`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstanceLimits {
+    /// The number of arrays and objects that may be nested in each other.
+    pub max_depth: usize,
+    /// The longest string or object key, in bytes.
+    pub max_string_length: usize,
+    /// Report numbers that don't survive a round trip through `f64`, which
+    /// bounds and `multipleOf` may judge by the rounded value.
+    pub exact_numbers: bool,
+}
+
+impl Default for InstanceLimits {
+    fn default() -> InstanceLimits {
+        InstanceLimits {
+            max_depth: 128,
+            max_string_length: 1 << 20,
+            exact_numbers: true,
+        }
+    }
+}
+
+/// Something about an instance that makes validation results unreliable, or
+/// that services may want to reject before validating, see `audit_instance`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InstanceIssue {
+    /// The arrays and objects are nested deeper than
+    /// `InstanceLimits::max_depth`. What's inside isn't audited.
+    TooDeep {
+        /// JSON pointer to the first array or object beyond the limit.
+        pointer: String,
+    },
+    /// A number that changes when converted to an `f64`, like integers
+    /// beyond 2^53.
+    InexactNumber {
+        /// JSON pointer to the number.
+        pointer: String,
+        /// The number as written.
+        number: String,
+    },
+    /// A string or key longer than `InstanceLimits::max_string_length`.
+    LongString {
+        /// JSON pointer to the string, or to the member with the key.
+        pointer: String,
+        /// The length in bytes.
+        length: usize,
+    },
+    /// A string or key with the replacement character U+FFFD, which is what
+    /// lossy conversions put in place of unpaired surrogates and invalid
+    /// UTF-8.
+    ReplacementCharacter {
+        /// JSON pointer to the string, or to the member with the key.
+        pointer: String,
+    },
+    /// A key that occurs more than once in the same object, of which parsed
+    /// values only keep the last. Only `audit_text` finds these.
+    DuplicateKey {
+        /// JSON pointer to the member.
+        pointer: String,
+    },
+}
+
+impl InstanceIssue {
+    /// JSON pointer to the value the issue is about.
+    pub fn pointer(&self) -> &str {
+        match *self {
+            InstanceIssue::TooDeep { ref pointer }
+            | InstanceIssue::InexactNumber { ref pointer, .. }
+            | InstanceIssue::LongString { ref pointer, .. }
+            | InstanceIssue::ReplacementCharacter { ref pointer }
+            | InstanceIssue::DuplicateKey { ref pointer } => pointer,
+        }
+    }
+}
+
+impl fmt::Display for InstanceIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InstanceIssue::TooDeep { .. } => write!(f, "Value is nested too deeply"),
+            InstanceIssue::InexactNumber { ref number, .. } => {
+                write!(f, "Number {} is rounded as a 64-bit float", number)
+            }
+            InstanceIssue::LongString { length, .. } => {
+                write!(f, "String of {} bytes is too long", length)
+            }
+            InstanceIssue::ReplacementCharacter { .. } => {
+                write!(f, "String contains U+FFFD, likely from a lossy conversion")
+            }
+            InstanceIssue::DuplicateKey { .. } => {
+                write!(f, "Key occurs more than once, only the last value is kept")
+            }
+        }
+    }
+}
+
+/// Checks `value` for data validation can't judge reliably: nesting deeper
+/// than the limit, numbers `f64`s round, overly long strings
+/// and strings damaged by lossy conversions. The issues are in document order.
+pub fn audit_instance(value: &Value, limits: &InstanceLimits) -> Vec<InstanceIssue> {
+    let mut issues = vec![];
+    audit(value, &mut String::new(), 0, limits, &mut issues);
+    issues
+}
+
+/// Like `audit_instance`, for the JSON text `text`, finding repeated keys too.
+/// Fails if `text` isn't JSON.
+pub fn audit_text(text: &str, limits: &InstanceLimits) -> Result<Vec<InstanceIssue>> {
+    let value: Value = serde_json::from_str(text)?;
+    let duplicates = check_duplicate_keys(text)?;
+    let mut issues = audit_instance(&value, limits);
+    issues.extend(
+        duplicates
+            .into_iter()
+            .map(|duplicate| InstanceIssue::DuplicateKey {
+                pointer: duplicate.pointer,
+            }),
+    );
+    Ok(issues)
+}
+
+fn audit(
+    value: &Value,
+    pointer: &mut String,
+    depth: usize,
+    limits: &InstanceLimits,
+    issues: &mut Vec<InstanceIssue>,
+) {
+    match *value {
+        Value::Array(_) | Value::Object(_) if depth >= limits.max_depth => {
+            issues.push(InstanceIssue::TooDeep {
+                pointer: pointer.clone(),
+            });
+        }
+        Value::Array(ref array) => {
+            for (i, item) in array.iter().enumerate() {
+                let len = pointer.len();
+                pointer.push_str(&format!("/{}", i));
+                audit(item, pointer, depth + 1, limits, issues);
+                pointer.truncate(len);
+            }
+        }
+        Value::Object(ref object) => {
+            for (key, item) in object {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&pointer_token(key));
+                audit_string(key, pointer, limits, issues);
+                audit(item, pointer, depth + 1, limits, issues);
+                pointer.truncate(len);
+            }
+        }
+        Value::String(ref string) => audit_string(string, pointer, limits, issues),
+        Value::Number(ref number) if limits.exact_numbers && !is_exact(number) => {
+            issues.push(InstanceIssue::InexactNumber {
+                pointer: pointer.clone(),
+                number: number.to_string(),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn audit_string(
+    string: &str,
+    pointer: &str,
+    limits: &InstanceLimits,
+    issues: &mut Vec<InstanceIssue>,
+) {
+    if string.len() > limits.max_string_length {
+        issues.push(InstanceIssue::LongString {
+            pointer: pointer.into(),
+            length: string.len(),
+        });
+    }
+    if string.contains('\u{fffd}') {
+        issues.push(InstanceIssue::ReplacementCharacter {
+            pointer: pointer.into(),
+        });
+    }
+}
+
+/// Whether `number` converts to an `f64` and back without changing.
+fn is_exact(number: &Number) -> bool {
+    if let Some(integer) = number.as_i64() {
+        return integer as f64 as i128 == i128::from(integer);
+    }
+    if let Some(integer) = number.as_u64() {
+        return integer as f64 as i128 == i128::from(integer);
+    }
+    exact_float(number)
+}
+
+#[cfg(not(feature = "arbitrary-precision"))]
+fn exact_float(_: &Number) -> bool {
+    true
+}
+
+/// Compares the number as written with the `f64` it parses to.
+#[cfg(feature = "arbitrary-precision")]
+fn exact_float(number: &Number) -> bool {
+    use bigdecimal::BigDecimal;
+
+    let written: Option<BigDecimal> = number.to_string().parse().ok();
+    let rounded = number
+        .as_f64()
+        .and_then(Number::from_f64)
+        .and_then(|f| f.to_string().parse().ok());
+    written.is_some() && written == rounded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use options::ValidateOptions;
+    use output::WarningKind;
+    use schema::Schema;
+
+    fn pointers(issues: &[InstanceIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.pointer()).collect()
+    }
+
+    #[test]
+    fn depth() {
+        let limits = InstanceLimits {
+            max_depth: 2,
+            ..InstanceLimits::default()
+        };
+        let value = json!({"a": [1, [2], {"b": []}], "c": {}});
+        let issues = audit_instance(&value, &limits);
+        assert_eq!(pointers(&issues), vec!["/a/1", "/a/2"]);
+        assert_eq!(issues[0].to_string(), "Value is nested too deeply");
+        assert!(audit_instance(&json!([[]]), &limits).is_empty());
+    }
+
+    #[test]
+    fn inexact_numbers() {
+        let value = json!([
+            9007199254740993u64,
+            9007199254740992u64,
+            -9007199254740993i64,
+            1.5
+        ]);
+        let issues = audit_instance(&value, &InstanceLimits::default());
+        assert_eq!(pointers(&issues), vec!["/0", "/2"]);
+        assert_eq!(
+            issues[0],
+            InstanceIssue::InexactNumber {
+                pointer: "/0".into(),
+                number: "9007199254740993".into(),
+            }
+        );
+
+        let limits = InstanceLimits {
+            exact_numbers: false,
+            ..InstanceLimits::default()
+        };
+        assert!(audit_instance(&value, &limits).is_empty());
+    }
+
+    #[test]
+    fn strings() {
+        let limits = InstanceLimits {
+            max_string_length: 3,
+            ..InstanceLimits::default()
+        };
+        let value = json!({"abc": "abcd", "long/key": "\u{fffd}", "ok": "é"});
+        let issues = audit_instance(&value, &limits);
+        assert_eq!(
+            issues,
+            vec![
+                InstanceIssue::LongString {
+                    pointer: "/abc".into(),
+                    length: 4,
+                },
+                InstanceIssue::LongString {
+                    pointer: "/long~1key".into(),
+                    length: 8,
+                },
+                InstanceIssue::ReplacementCharacter {
+                    pointer: "/long~1key".into(),
+                },
+            ]
+        );
+        let lossy = String::from_utf16_lossy(&[0x61, 0xd800]);
+        let issues = audit_instance(&json!([lossy]), &InstanceLimits::default());
+        assert_eq!(pointers(&issues), vec!["/0"]);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let text = r#"{"a": {"b": 1, "b": 2}, "c": 12345678901234567890}"#;
+        let issues = audit_text(text, &InstanceLimits::default()).unwrap();
+        assert_eq!(pointers(&issues), vec!["/c", "/a/b"]);
+        match issues[1] {
+            InstanceIssue::DuplicateKey { .. } => {}
+            ref issue => panic!("unexpected {:?}", issue),
+        }
+        assert!(audit_text("{", &InstanceLimits::default()).is_err());
+    }
+
+    #[test]
+    fn validation_warnings() {
+        let schema: Schema = serde_json::from_value(json!({"type": "array"})).unwrap();
+        let value = json!([9007199254740993u64]);
+        assert!(schema
+            .validate_with(&value, &ValidateOptions::default())
+            .warnings
+            .is_empty());
+
+        let options = ValidateOptions {
+            audit: Some(InstanceLimits::default()),
+            ..ValidateOptions::default()
+        };
+        let output = schema.validate_with(&value, &options);
+        assert!(output.errors.is_empty());
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].instance_path, "/0");
+        match output.warnings[0].kind {
+            WarningKind::UnsupportedInput(InstanceIssue::InexactNumber { .. }) => {}
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+    }
+}
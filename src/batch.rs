@@ -0,0 +1,262 @@
+use serde_json::{self, Value};
+
+use duplicate_keys::DuplicateKey;
+use errors::{ErrorCode, ErrorKind, ValidationError};
+use output::{Timing, ValidationOutput};
+use session::RecordedError;
+
+/// The location SARIF logs point to for the current schema of SARIF.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The problems found in one validated file.
+#[derive(Debug)]
+pub struct FileReport {
+    /// The path of the file, as given.
+    pub path: String,
+    /// The validation errors.
+    pub errors: Vec<RecordedError>,
+    /// The keys the file repeats, if it was checked for them.
+    pub duplicate_keys: Vec<DuplicateKey>,
+    /// Where the time validating the file went, if it was measured.
+    pub timing: Option<Timing>,
+}
+
+impl FileReport {
+    /// Whether nothing was found in the file.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty() && self.duplicate_keys.is_empty()
+    }
+}
+
+/// The results of validating many files, for reporting them all at once, for
+/// example as a SARIF log that CI systems show as annotations.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// The files, in the order they were added.
+    pub files: Vec<FileReport>,
+}
+
+impl BatchReport {
+    /// Creates an empty report.
+    pub fn new() -> BatchReport {
+        BatchReport::default()
+    }
+
+    /// Adds the file at `path` with the errors validating it produced.
+    pub fn add<'json, P: Into<String>>(&mut self, path: P, errors: Vec<ValidationError<'json>>) {
+        self.files.push(FileReport {
+            path: path.into(),
+            errors: errors.into_iter().map(RecordedError::from).collect(),
+            duplicate_keys: vec![],
+            timing: None,
+        });
+    }
+
+    /// Adds the file at `path` with the errors and the timing of `output`.
+    pub fn add_output<'json, P: Into<String>>(&mut self, path: P, output: ValidationOutput<'json>) {
+        self.add(path, output.errors);
+        self.files.last_mut().unwrap().timing = output.timing;
+    }
+
+    /// Adds the file at `path`, which wasn't validated because it repeats keys.
+    pub fn add_duplicate_keys<P: Into<String>>(&mut self, path: P, duplicates: Vec<DuplicateKey>) {
+        self.files.push(FileReport {
+            path: path.into(),
+            errors: vec![],
+            duplicate_keys: duplicates,
+            timing: None,
+        });
+    }
+
+    /// Whether nothing was found in any of the files.
+    pub fn is_valid(&self) -> bool {
+        self.files.iter().all(FileReport::is_valid)
+    }
+
+    /// The report as a SARIF 2.1.0 log with one result per problem. The rule of a
+    /// result is the `ErrorCode` of the error, the JSON pointer to the invalid
+    /// value is its logical location. Duplicate keys also have a line and column,
+    /// values not in an `enum` the suggested value as the `suggestion` property.
+    /// The timing of the files that have one is the `timing` property of the
+    /// run.
+    pub fn to_sarif(&self) -> Value {
+        let mut rules: Vec<ErrorCode> = vec![];
+        let mut results = vec![];
+        for file in &self.files {
+            let problems = file.duplicate_keys.iter().map(|d| {
+                (
+                    ErrorCode::DuplicateKeys,
+                    d.to_string(),
+                    &d.pointer,
+                    Some(d),
+                    None,
+                )
+            });
+            let errors = file.errors.iter().map(|e| {
+                let suggestion = match e.reason {
+                    ErrorKind::NotInEnum { ref suggestion, .. } => suggestion.as_ref(),
+                    _ => None,
+                };
+                let message = match e.folded {
+                    Some(ref folded) => format!("{} ({})", e.reason, folded),
+                    None => e.reason.to_string(),
+                };
+                (e.reason.code(), message, &e.instance_path, None, suggestion)
+            });
+            for (code, message, pointer, duplicate, suggestion) in problems.chain(errors) {
+                let rule_index = match rules.iter().position(|&rule| rule == code) {
+                    Some(index) => index,
+                    None => {
+                        rules.push(code);
+                        rules.len() - 1
+                    }
+                };
+                let mut physical_location = json!({"artifactLocation": {"uri": file.path}});
+                if let Some(duplicate) = duplicate {
+                    physical_location["region"] = json!({
+                        "startLine": duplicate.line,
+                        "startColumn": duplicate.column,
+                    });
+                }
+                let name = pointer.rsplit('/').next().unwrap_or("");
+                let mut result = json!({
+                    "ruleId": format!("{:?}", code),
+                    "ruleIndex": rule_index,
+                    "level": "error",
+                    "message": {"text": message},
+                    "locations": [{
+                        "physicalLocation": physical_location,
+                        "logicalLocations": [{"name": name, "fullyQualifiedName": pointer}],
+                    }],
+                });
+                if let Some(suggestion) = suggestion {
+                    result["properties"] = json!({ "suggestion": suggestion });
+                }
+                results.push(result);
+            }
+        }
+        let rules: Vec<_> = rules
+            .iter()
+            .map(|code| json!({"id": format!("{:?}", code)}))
+            .collect();
+        let mut run = json!({
+            "tool": {
+                "driver": {
+                    "name": "json_schema",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        });
+        let timing: Vec<_> = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let mut timing = serde_json::to_value(file.timing?).ok()?;
+                timing["uri"] = json!(file.path);
+                Some(timing)
+            })
+            .collect();
+        if !timing.is_empty() {
+            run["properties"] = json!({ "timing": timing });
+        }
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [run],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use serde_json;
+
+    use super::*;
+    use duplicate_keys::check_duplicate_keys;
+    use schema::Schema;
+
+    #[test]
+    fn sarif() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "status": {"type": "string", "enum": ["active", "inactive"]}
+            }
+        }))
+        .unwrap();
+        let mut report = BatchReport::new();
+        let files = vec![
+            ("fixtures/valid.json", json!({"name": "a"})),
+            ("fixtures/tags.json", json!({"name": "b", "tags": ["x", 1]})),
+            ("fixtures/empty.json", json!({"tags": [2]})),
+            (
+                "fixtures/status.json",
+                json!({"name": "e", "status": "actve"}),
+            ),
+        ];
+        for &(path, ref json) in &files {
+            let errors = schema.validate(json).err().map_or(vec![], |e| e.0);
+            report.add(path, errors);
+        }
+        let duplicates = check_duplicate_keys(
+            r#"{"name": "c",
+            "name": "d"}"#,
+        )
+        .unwrap();
+        report.add_duplicate_keys("fixtures/twice.json", duplicates);
+        assert!(!report.is_valid());
+
+        let sarif = report.to_sarif();
+        let sarif_schema: Schema =
+            serde_json::from_reader(File::open("data/sarif-2.1.0-subset.json").unwrap()).unwrap();
+        sarif_schema.validate(&sarif).unwrap();
+        let mut broken = sarif.clone();
+        broken["runs"][0]["results"][0]["level"] = json!("fatal");
+        assert!(sarif_schema.validate(&broken).is_err());
+
+        let run = &sarif["runs"][0];
+        let rules: Vec<_> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                "TypeMismatch",
+                "MissingProperties",
+                "NotInEnum",
+                "DuplicateKeys"
+            ]
+        );
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 5);
+        assert_eq!(
+            results[0],
+            json!({
+                "ruleId": "TypeMismatch",
+                "ruleIndex": 0,
+                "level": "error",
+                "message": {"text": "Type mismatch: expected string, found number"},
+                "locations": [{
+                    "physicalLocation": {"artifactLocation": {"uri": "fixtures/tags.json"}},
+                    "logicalLocations": [{"name": "1", "fullyQualifiedName": "/tags/1"}]
+                }]
+            })
+        );
+        assert_eq!(results[3]["properties"], json!({"suggestion": "active"}));
+        assert_eq!(
+            results[4]["locations"][0]["physicalLocation"]["region"],
+            json!({"startLine": 2, "startColumn": 13})
+        );
+    }
+}
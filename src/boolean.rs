@@ -1,17 +1,51 @@
 use serde_json::Value;
 
-use util::{JsonType, JsonValueExt};
-use schema::{Context, SchemaBase};
-use errors::{ErrorKind, ValidationError};
+use types::JsonType;
+use object::PropertyMap;
+use schema::{Context, Schema, SchemaBase};
+use errors::ValidationError;
+use util::deserialize_some;
 
 /// A schema for a JSON boolean value (`true`, `false`).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
 pub struct BooleanSchema {
-    description: Option<String>,
-    id: Option<String>,
-    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
+    pub(crate) enum_values: Option<Vec<Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "const",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) const_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+    #[serde(skip)]
+    pub(crate) nullable: bool,
 }
 
 impl SchemaBase for BooleanSchema {
@@ -23,13 +57,7 @@ impl SchemaBase for BooleanSchema {
         errors: &mut Vec<ValidationError<'json>>,
     ) {
         if !value.is_boolean() {
-            errors.push(ValidationError {
-                reason: ErrorKind::TypeMismatch {
-                    expected: JsonType::Boolean,
-                    found: value.get_type(),
-                },
-                node: value,
-            });
+            errors.push(ValidationError::type_mismatch(value, JsonType::Boolean));
         }
     }
 }
@@ -0,0 +1,291 @@
+use diagnostics::{keyword_enforcement, Enforcement, KEYWORDS};
+use options::Draft;
+use string::Format;
+
+/// What this build of the crate supports, see `capabilities`. Serializes to JSON
+/// for tools that choose between validators at runtime.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Capabilities {
+    /// The version of the crate.
+    pub version: &'static str,
+    /// The drafts `ValidateOptions::draft` can follow.
+    pub drafts: Vec<Draft>,
+    /// The keywords schemas can use. Schemas using other keywords don't parse,
+    /// like those using `x-orderedKeys` without the `preserve_order` feature.
+    pub keywords: Vec<KeywordSupport>,
+    /// The formats of the `format` keyword this crate knows. Other formats are
+    /// accepted and annotate.
+    pub formats: Vec<FormatSupport>,
+    /// The cargo features of the crate and whether this build enables them.
+    pub features: Vec<FeatureSupport>,
+}
+
+/// A keyword schemas can use.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct KeywordSupport {
+    /// The keyword.
+    pub keyword: &'static str,
+    /// What validation does with it, either `Enforced` or `AnnotationOnly`.
+    pub enforcement: Enforcement,
+}
+
+/// A format of the `format` keyword.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FormatSupport {
+    /// The name of the format.
+    pub format: String,
+    /// `Enforced` if strings are checked against the format, `AnnotationOnly` if
+    /// any string is accepted.
+    pub enforcement: Enforcement,
+}
+
+/// A cargo feature of the crate.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct FeatureSupport {
+    /// The name of the feature.
+    pub feature: &'static str,
+    /// Whether this build enables it.
+    pub enabled: bool,
+}
+
+/// Describes the drafts, keywords, formats and features this build supports.
+pub fn capabilities() -> Capabilities {
+    let drafts = vec![
+        Draft::Draft4,
+        Draft::Draft6,
+        Draft::Draft7,
+        Draft::Draft201909,
+        Draft::Draft202012,
+    ];
+    let keywords = KEYWORDS
+        .iter()
+        .map(|&keyword| KeywordSupport {
+            keyword,
+            enforcement: keyword_enforcement(keyword),
+        })
+        .filter(|k| k.enforcement != Enforcement::Dropped)
+        .collect();
+    let formats = [
+        Format::DateTime,
+        Format::Date,
+        Format::Time,
+        Format::Email,
+        Format::Hostname,
+        Format::Ipv4,
+        Format::Ipv6,
+        Format::Uri,
+    ];
+    let formats = formats
+        .iter()
+        .map(|format| FormatSupport {
+            format: format.as_str().to_string(),
+            enforcement: if format.is_checked() {
+                Enforcement::Enforced
+            } else {
+                Enforcement::AnnotationOnly
+            },
+        })
+        .collect();
+    let features = vec![
+        FeatureSupport {
+            feature: "arbitrary-precision",
+            enabled: cfg!(feature = "arbitrary-precision"),
+        },
+        FeatureSupport {
+            feature: "preserve_order",
+            enabled: cfg!(feature = "preserve_order"),
+        },
+        FeatureSupport {
+            feature: "normalize-unicode",
+            enabled: cfg!(feature = "normalize-unicode"),
+        },
+        FeatureSupport {
+            feature: "test-util",
+            enabled: cfg!(feature = "test-util"),
+        },
+    ];
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        drafts,
+        keywords,
+        formats,
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{self, Value};
+
+    use super::*;
+    use schema::Schema;
+
+    /// A schema using the keyword and a value it rejects, or `None` as the value
+    /// if the keyword can't reject anything.
+    fn canary(keyword: &str) -> (Value, Option<Value>) {
+        match keyword {
+            "$ref" => (
+                json!({"definitions": {"a": {"type": "string"}}, "$ref": "#/definitions/a"}),
+                Some(json!(1)),
+            ),
+            "type" => (json!({"type": "string"}), Some(json!(1))),
+            "enum" => (json!({"enum": [1]}), Some(json!(2))),
+            "const" => (json!({"const": 1}), Some(json!(2))),
+            "allOf" => (json!({"allOf": [{"type": "string"}]}), Some(json!(1))),
+            "anyOf" => (json!({"anyOf": [{"type": "string"}]}), Some(json!(1))),
+            "oneOf" => (json!({"oneOf": [{"type": "string"}]}), Some(json!(1))),
+            "not" => (json!({"not": {"type": "integer"}}), Some(json!(1))),
+            "properties" => (
+                json!({"type": "object", "properties": {"a": {"type": "string"}}}),
+                Some(json!({"a": 1})),
+            ),
+            "patternProperties" => (
+                json!({"type": "object", "patternProperties": {"^a": {"type": "string"}}}),
+                Some(json!({"a": 1})),
+            ),
+            "additionalProperties" => (
+                json!({"type": "object", "additionalProperties": {"type": "string"}}),
+                Some(json!({"a": 1})),
+            ),
+            "required" => (
+                json!({"type": "object", "required": ["a"]}),
+                Some(json!({})),
+            ),
+            "minProperties" => (
+                json!({"type": "object", "minProperties": 1}),
+                Some(json!({})),
+            ),
+            "maxProperties" => (
+                json!({"type": "object", "maxProperties": 0}),
+                Some(json!({"a": 1})),
+            ),
+            "items" => (
+                json!({"type": "array", "items": {"type": "string"}}),
+                Some(json!([1])),
+            ),
+            "prefixItems" => (
+                json!({"type": "array", "prefixItems": [{"type": "string"}]}),
+                Some(json!([1])),
+            ),
+            "additionalItems" => (
+                json!({"type": "array", "items": [{}], "additionalItems": false}),
+                Some(json!([1, 2])),
+            ),
+            "minItems" => (json!({"type": "array", "minItems": 1}), Some(json!([]))),
+            "maxItems" => (json!({"type": "array", "maxItems": 0}), Some(json!([1]))),
+            "uniqueItems" => (
+                json!({"type": "array", "uniqueItems": true}),
+                Some(json!([1, 1])),
+            ),
+            "x-orderedKeys" => (
+                json!({"type": "object", "x-orderedKeys": "asc"}),
+                Some(json!({"b": 1, "a": 2})),
+            ),
+            "x-compare" => (
+                json!({"type": "object", "additionalProperties": true,
+                       "x-compare": [{"left": "/min", "op": "<=", "right": "/max"}]}),
+                Some(json!({"min": 2, "max": 1})),
+            ),
+            "x-uniqueBy" => (
+                json!({"type": "array", "x-uniqueBy": "/id"}),
+                Some(json!([{"id": 1}, {"id": 1}])),
+            ),
+            "minimum" => (json!({"type": "number", "minimum": 1}), Some(json!(0))),
+            "maximum" => (json!({"type": "number", "maximum": 0}), Some(json!(1))),
+            "exclusiveMinimum" => (
+                json!({"type": "number", "minimum": 0, "exclusiveMinimum": true}),
+                Some(json!(0)),
+            ),
+            "exclusiveMaximum" => (
+                json!({"type": "number", "maximum": 0, "exclusiveMaximum": true}),
+                Some(json!(0)),
+            ),
+            "multipleOf" => (json!({"type": "number", "multipleOf": 2}), Some(json!(3))),
+            "minLength" => (json!({"type": "string", "minLength": 1}), Some(json!(""))),
+            "maxLength" => (json!({"type": "string", "maxLength": 0}), Some(json!("a"))),
+            "pattern" => (json!({"type": "string", "pattern": "^a"}), Some(json!("b"))),
+            "format" => (
+                json!({"type": "string", "format": "date-time"}),
+                Some(json!("yesterday")),
+            ),
+            "x-formatMinimum" | "x-formatExclusiveMinimum" => (
+                json!({"type": "string", "format": "date", keyword: "2020-01-01"}),
+                Some(json!("2019-12-31")),
+            ),
+            "x-formatMaximum" | "x-formatExclusiveMaximum" => (
+                json!({"type": "string", "format": "date", keyword: "2020-01-01"}),
+                Some(json!("2020-01-02")),
+            ),
+            "$schema" => (
+                json!({"$schema": "http://json-schema.org/draft-07/schema#"}),
+                None,
+            ),
+            "$id" => (json!({"$id": "http://example.com/a.json"}), None),
+            "id" => (json!({"id": "http://example.com/a.json"}), None),
+            "title" => (json!({"title": "A"}), None),
+            "description" => (json!({"description": "A"}), None),
+            "default" => (json!({"default": 2}), None),
+            "examples" => (json!({"examples": [2]}), None),
+            "x-counterexamples" => (json!({"x-counterexamples": [1]}), None),
+            "deprecated" => (json!({"deprecated": true}), None),
+            "x-removed-in" => (
+                json!({"deprecated": true, "x-removed-in": "2000-01-01"}),
+                None,
+            ),
+            "definitions" => (json!({"definitions": {"a": {"type": "string"}}}), None),
+            "$defs" => (json!({"$defs": {"a": {"type": "string"}}}), None),
+            other => panic!("no canary schema for `{}`", other),
+        }
+    }
+
+    #[test]
+    fn keywords_match_validation() {
+        let capabilities = capabilities();
+        for support in &capabilities.keywords {
+            let (schema, rejected) = canary(support.keyword);
+            let parsed: Schema = serde_json::from_value(schema.clone())
+                .unwrap_or_else(|e| panic!("{} doesn't parse: {}", schema, e));
+            match rejected {
+                Some(value) => {
+                    assert_eq!(support.enforcement, Enforcement::Enforced, "{}", schema);
+                    assert!(
+                        parsed.validate(&value).is_err(),
+                        "{} accepts {}",
+                        schema,
+                        value
+                    );
+                }
+                None => {
+                    assert_eq!(
+                        support.enforcement,
+                        Enforcement::AnnotationOnly,
+                        "{}",
+                        schema
+                    );
+                    for value in &[json!("a"), json!(1), json!({}), json!([1, 1])] {
+                        assert!(
+                            parsed.validate(value).is_ok(),
+                            "{} rejects {}",
+                            schema,
+                            value
+                        );
+                    }
+                }
+            }
+        }
+
+        let unknown = json!({"type": "string", "contentEncoding": "base64"});
+        assert!(serde_json::from_value::<Schema>(unknown).is_err());
+        assert!(!capabilities
+            .keywords
+            .iter()
+            .any(|k| k.keyword == "contentEncoding"));
+
+        let serialized = serde_json::to_value(&capabilities).unwrap();
+        assert_eq!(
+            serialized["formats"][0],
+            json!({"format": "date-time", "enforcement": "Enforced"})
+        );
+        assert_eq!(serialized["drafts"][2], json!("Draft7"));
+    }
+}
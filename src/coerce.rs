@@ -0,0 +1,149 @@
+use serde_json::{self, Value};
+
+use array::ArraySchema;
+use limits::SchemaLimits;
+use object::{ObjectSchema, PropertyResolver};
+use schema::Schema;
+
+impl Schema {
+    /// Converts the strings in `value` to the type the schema expects where the
+    /// conversion is unambiguous: `"8080"` becomes `8080` for an `integer` or
+    /// `number` schema, `"1.5"` becomes `1.5` for a `number` schema and `"true"`
+    /// and `"false"` become booleans for a `boolean` schema. Values that can't be
+    /// converted are left alone, so validating them afterwards reports the type
+    /// mismatch. This is meant for data from query strings, CSV files or
+    /// environment variables, where every value is a string.
+    ///
+    /// `anyOf` and `oneOf` are left out, as the branch to convert for isn't known.
+    /// Returns the number of converted values.
+    pub fn coerce(&self, value: &mut Value) -> usize {
+        let mut count = 0;
+        coerce(self, self, value, &mut count);
+        count
+    }
+}
+
+fn coerce(schema: &Schema, root: &Schema, value: &mut Value, count: &mut usize) {
+    let coerced = match (schema, &*value) {
+        (Schema::Integer(_), Value::String(s)) => {
+            parse_number(s).filter(|n| n.is_i64() || n.is_u64())
+        }
+        (Schema::Number(_), Value::String(s)) => parse_number(s),
+        (Schema::Boolean(_), Value::String(s)) => match s.as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(coerced) = coerced {
+        *value = coerced;
+        *count += 1;
+        return;
+    }
+
+    match *schema {
+        Schema::Object(ref s) => coerce_object(s, root, value, count),
+        Schema::Array(ref s) => coerce_array(s, root, value, count),
+        Schema::Combinator(ref s) => {
+            for schema in s.all_of.iter().flatten() {
+                coerce(schema, root, value, count);
+            }
+        }
+        Schema::Reference(ref s) => {
+            if let Some(target) = s.resolve(root) {
+                coerce(target, root, value, count);
+            }
+        }
+        Schema::Shared(ref s) => coerce(s, root, value, count),
+        _ => {}
+    }
+}
+
+/// Parses `s` if it's exactly a JSON number.
+fn parse_number(s: &str) -> Option<Value> {
+    if s.trim() != s {
+        return None;
+    }
+    serde_json::from_str(s).ok().filter(Value::is_number)
+}
+
+fn coerce_object(schema: &ObjectSchema, root: &Schema, value: &mut Value, count: &mut usize) {
+    let object = match *value {
+        Value::Object(ref mut o) => o,
+        _ => return,
+    };
+    let resolver = PropertyResolver::new(schema, &SchemaLimits::default());
+    for (key, value) in object.iter_mut() {
+        for schema in resolver.resolve(key).schemas() {
+            coerce(schema, root, value, count);
+        }
+    }
+}
+
+fn coerce_array(schema: &ArraySchema, root: &Schema, value: &mut Value, count: &mut usize) {
+    let array = match *value {
+        Value::Array(ref mut a) => a,
+        _ => return,
+    };
+    for (i, value) in array.iter_mut().enumerate() {
+        if let Some(schema) = schema.item_schema(i) {
+            coerce(schema, root, value, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        serde_json::from_value(json!({
+            "type": "object",
+            "definitions": {"port": {"type": "integer", "minimum": 1}},
+            "properties": {
+                "port": {"$ref": "#/definitions/port"},
+                "debug": {"type": "boolean"},
+                "ratio": {"type": "number"},
+                "name": {"type": "string"},
+                "ids": {"type": "array", "items": {"type": "integer"}}
+            },
+            "additionalProperties": true
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn coerce_strings() {
+        let schema = schema();
+        let mut input = json!({
+            "port": "8080",
+            "debug": "true",
+            "ratio": "0.5",
+            "name": "1",
+            "ids": ["1", "2"]
+        });
+        assert_eq!(schema.coerce(&mut input), 5);
+        assert_eq!(
+            input,
+            json!({"port": 8080, "debug": true, "ratio": 0.5, "name": "1", "ids": [1, 2]})
+        );
+        schema.validate(&input).unwrap();
+    }
+
+    #[test]
+    fn uncoercible_values() {
+        let schema = schema();
+        let mut input = json!({"port": "80.5", "debug": "yes", "ratio": " 1", "ids": ["0x1"]});
+        assert_eq!(schema.coerce(&mut input), 0);
+        assert_eq!(input["port"], "80.5");
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors.len(), 4);
+    }
+
+    #[test]
+    fn off_by_default() {
+        let input = json!({"port": "8080", "debug": "false"});
+        assert!(schema().validate(&input).is_err());
+    }
+}
@@ -0,0 +1,504 @@
+use serde_json::Value;
+
+use errors::{ErrorKind, ValidationError};
+use object::PropertyMap;
+use output::WarningKind;
+use schema::{Context, Schema, SchemaBase, Segment};
+use types::JsonType;
+use util::deserialize_some;
+
+/// A schema that combines other schemas: the value has to match all schemas of
+/// `allOf`, at least one of `anyOf`, exactly one of `oneOf` and must not match `not`.
+/// Schemas without a `type` that only give `enum` or `const` are parsed as this too.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CombinatorSchema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
+    pub(crate) enum_values: Option<Vec<Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "const",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) const_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) all_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) any_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) one_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) not: Option<Box<Schema>>,
+}
+
+/// Collects schemas for a combinator keyword.
+fn schemas<I, S>(schemas: I) -> Option<Vec<Schema>>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<Schema>,
+{
+    Some(schemas.into_iter().map(Into::into).collect())
+}
+
+/// A schema that values have to match all `schemas` of, serialized as `allOf`.
+pub fn all_of<I, S>(schemas: I) -> Schema
+where
+    I: IntoIterator<Item = S>,
+    S: Into<Schema>,
+{
+    Schema::from(CombinatorSchema {
+        all_of: self::schemas(schemas),
+        ..CombinatorSchema::default()
+    })
+}
+
+/// A schema that values have to match at least one of `schemas` of, serialized
+/// as `anyOf`.
+pub fn any_of<I, S>(schemas: I) -> Schema
+where
+    I: IntoIterator<Item = S>,
+    S: Into<Schema>,
+{
+    Schema::from(CombinatorSchema {
+        any_of: self::schemas(schemas),
+        ..CombinatorSchema::default()
+    })
+}
+
+/// A schema that values have to match exactly one of `schemas` of, serialized
+/// as `oneOf`.
+pub fn one_of<I, S>(schemas: I) -> Schema
+where
+    I: IntoIterator<Item = S>,
+    S: Into<Schema>,
+{
+    Schema::from(CombinatorSchema {
+        one_of: self::schemas(schemas),
+        ..CombinatorSchema::default()
+    })
+}
+
+/// A schema that values must not match `schema`, serialized as `not`.
+pub fn not<S: Into<Schema>>(schema: S) -> Schema {
+    Schema::from(CombinatorSchema {
+        not: Some(Box::new(schema.into())),
+        ..CombinatorSchema::default()
+    })
+}
+
+/// The branch of `oneOf` or `anyOf` a value matches, see `Schema::match_branch`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchMatch {
+    /// `oneOf` or `anyOf`.
+    pub keyword: &'static str,
+    /// The index of the branch.
+    pub index: usize,
+    /// JSON pointer to the branch in the schema, like `/definitions/shape/oneOf/2`.
+    pub schema_path: String,
+}
+
+impl CombinatorSchema {
+    /// Whether this schema has `definitions` but no keyword that constrains values.
+    pub(crate) fn is_definitions_only(&self) -> bool {
+        self.definitions.is_some()
+            && self.enum_values.is_none()
+            && self.const_value.is_none()
+            && self.all_of.is_none()
+            && self.any_of.is_none()
+            && self.one_of.is_none()
+            && self.not.is_none()
+    }
+
+    /// Returns the indices of the schemas in `schemas` that `value` matches. The
+    /// vector comes from the scratch buffers of `ctx`.
+    ///
+    /// If `types` is given, it collects the types the failing schemas expected as
+    /// long as all of them failed only because of the type of `value`, otherwise
+    /// it's set to `None`.
+    fn matching(
+        &self,
+        ctx: &Context,
+        keyword: &str,
+        schemas: &[Schema],
+        value: &Value,
+        mut types: Option<&mut Option<Vec<JsonType>>>,
+    ) -> Vec<usize> {
+        let mut matched = ctx.scratch.take_indices();
+        let mut errors = ctx.scratch.take_errors();
+        for (i, schema) in schemas.iter().enumerate() {
+            ctx.untrack_errors(&errors);
+            errors.clear();
+            ctx.descend(None, keyword, Some(Segment::Index(i)), |ctx| {
+                ctx.trial(|ctx| {
+                    let mark = ctx.mark();
+                    schema.validate_inner(ctx, value, &mut errors);
+                    if !errors.is_empty() {
+                        ctx.discard(mark);
+                    }
+                })
+            });
+            if errors.is_empty() {
+                matched.push(i);
+            } else if let Some(types) = types.as_mut() {
+                **types = match (types.take(), &errors[..]) {
+                    (Some(mut types), [error]) => error.expected_types(value).map(|expected| {
+                        for ty in expected {
+                            if !types.contains(ty) {
+                                types.push(*ty);
+                            }
+                        }
+                        types
+                    }),
+                    _ => None,
+                };
+            }
+        }
+        ctx.untrack_errors(&errors);
+        ctx.scratch.give_errors(errors);
+        matched
+    }
+}
+
+impl SchemaBase for CombinatorSchema {
+    #[doc(hidden)]
+    fn validate_inner<'json>(
+        &self,
+        ctx: &Context,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        if ctx.focus.is_some()
+            && (self.any_of.is_some() || self.one_of.is_some() || self.not.is_some())
+        {
+            // whether a branch matches depends on the values off the focus path too
+            return ctx.unfocused(|ctx| self.validate_inner(ctx, value, errors));
+        }
+        if self.is_definitions_only() {
+            ctx.warn(WarningKind::DefinitionsOnly);
+        }
+
+        if let Some(ref schemas) = self.all_of {
+            for (i, schema) in schemas.iter().enumerate() {
+                ctx.descend(None, "allOf", Some(Segment::Index(i)), |ctx| {
+                    schema.validate_inner(ctx, value, errors)
+                });
+            }
+        }
+
+        if let Some(ref schemas) = self.any_of {
+            // if every branch only rejects the type, report a single type mismatch
+            let mut types = Some(vec![]);
+            let matched = self.matching(ctx, "anyOf", schemas, value, Some(&mut types));
+            if matched.is_empty() {
+                let reason = match types {
+                    Some(expected) if !expected.is_empty() => ErrorKind::MultiTypeMismatch {
+                        expected,
+                        found: JsonType::reported(value).0,
+                    },
+                    _ => ErrorKind::AnyOfNoMatch,
+                };
+                errors.push(ValidationError::new(reason, value));
+            } else {
+                ctx.annotate("anyOf", json!(matched));
+            }
+            ctx.scratch.give_indices(matched);
+        }
+
+        if let Some(ref schemas) = self.one_of {
+            let matched = self.matching(ctx, "oneOf", schemas, value, None);
+            if matched.len() == 1 {
+                ctx.annotate("oneOf", json!(matched[0]));
+                ctx.scratch.give_indices(matched);
+            } else {
+                errors.push(ValidationError::new(
+                    ErrorKind::OneOfMatchCount { matched },
+                    value,
+                ));
+            }
+        }
+
+        if let Some(ref schema) = self.not {
+            let mut not_errors = ctx.scratch.take_errors();
+            ctx.descend(None, "not", None, |ctx| {
+                ctx.trial(|ctx| {
+                    // a matching `not` schema fails, so its annotations and warnings
+                    // never apply
+                    let mark = ctx.mark();
+                    schema.validate_inner(ctx, value, &mut not_errors);
+                    ctx.discard(mark);
+                })
+            });
+            if not_errors.is_empty() {
+                errors.push(ValidationError::new(ErrorKind::NotMatched, value));
+            }
+            ctx.untrack_errors(&not_errors);
+            ctx.scratch.give_errors(not_errors);
+        }
+    }
+}
+
+impl Schema {
+    /// The branch of the `oneOf`, or else the `anyOf`, of this schema that
+    /// `value` matches, to pick the variant of a union without validating again.
+    /// References at the root are followed within the document. For `oneOf`
+    /// it's `None` if no branch or several branches match, for `anyOf` the first
+    /// matching branch is returned. Each branch is only checked up to its first
+    /// error, and other keywords next to the branches aren't checked.
+    pub fn match_branch(&self, value: &Value) -> Option<BranchMatch> {
+        let mut schema = self;
+        let mut pointer = String::new();
+        let mut followed = vec![];
+        loop {
+            match *schema {
+                Schema::Shared(ref shared) => schema = shared,
+                Schema::Reference(ref reference) if reference.siblings.is_none() => {
+                    let (document, fragment) = reference.target();
+                    if !document.is_empty() || followed.contains(&fragment) {
+                        return None;
+                    }
+                    schema = self.pointer(&fragment)?;
+                    pointer = fragment.to_string();
+                    followed.push(fragment);
+                }
+                _ => break,
+            }
+        }
+        let combinator = match *schema {
+            Schema::Combinator(ref combinator) => combinator,
+            _ => return None,
+        };
+        let (keyword, branches) = match (&combinator.one_of, &combinator.any_of) {
+            (Some(branches), _) => ("oneOf", branches),
+            (None, Some(branches)) => ("anyOf", branches),
+            (None, None) => return None,
+        };
+        let mut matching = branches
+            .iter()
+            .enumerate()
+            .filter(|&(_, branch)| branch.accepts_within(self, value))
+            .map(|(i, _)| i);
+        let index = matching.next()?;
+        if keyword == "oneOf" && matching.next().is_some() {
+            return None;
+        }
+        Some(BranchMatch {
+            keyword,
+            index,
+            schema_path: format!("{}/{}/{}", pointer, keyword, index),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    fn schema(value: Value) -> Schema {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn all_of() {
+        let schema = schema(json!({
+            "allOf": [
+                {"type": "number", "minimum": 1.0},
+                {"type": "number", "maximum": 2.0}
+            ]
+        }));
+        schema.validate(&json!(1.5)).unwrap();
+        let input = json!(3);
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].schema_path, "/allOf/1");
+    }
+
+    #[test]
+    fn any_of() {
+        let schema = schema(json!({"anyOf": [{"type": "string"}, {"type": "integer"}]}));
+        schema.validate(&json!("a")).unwrap();
+        schema.validate(&json!(1)).unwrap();
+        let input = json!(1.5);
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "Type mismatch: expected string or integer, found number"
+        );
+
+        let schema = self::schema(json!({
+            "anyOf": [{"type": "string"}, {"type": "integer", "minimum": 2}]
+        }));
+        assert_invalid!(schema, json!(1), ErrorKind::AnyOfNoMatch);
+    }
+
+    #[test]
+    fn one_of() {
+        let schema = schema(json!({
+            "oneOf": [
+                {"type": "integer"},
+                {"type": "number", "minimum": 2.0}
+            ]
+        }));
+        schema.validate(&json!(1)).unwrap();
+        schema.validate(&json!(2.5)).unwrap();
+        let input = json!(3);
+        let errors = schema.validate(&input).unwrap_err().0;
+        match errors[0].reason {
+            ErrorKind::OneOfMatchCount { ref matched } => assert_eq!(matched, &[0, 1]),
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(schema.validate(&json!("a")).is_err());
+    }
+
+    #[test]
+    fn not() {
+        let schema = schema(json!({"not": {"type": "string"}}));
+        schema.validate(&json!(1)).unwrap();
+        assert!(schema.validate(&json!("a")).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = json!({"oneOf": [{}, {"type": "string"}], "not": {"type": "integer"}});
+        let schema = schema(value);
+        let serialized = serde_json::to_value(&schema).unwrap();
+        assert_eq!(serialized["oneOf"][1]["type"], "string");
+        assert_eq!(serialized["not"]["type"], "integer");
+    }
+
+    #[test]
+    fn builders() {
+        use integer::IntegerSchemaBuilder;
+        use object::ObjectSchemaBuilder;
+        use string::StringSchema;
+
+        let small = IntegerSchemaBuilder::default().maximum(9).build();
+        let even = IntegerSchemaBuilder::default().multiple_of(2).build();
+        let schema = super::any_of(vec![
+            super::all_of(vec![small, even]),
+            super::one_of(vec![StringSchema::default(), StringSchema::default()]).nullable(),
+        ]);
+        let serialized = serde_json::to_value(&schema).unwrap();
+        assert_eq!(serialized["anyOf"][0]["allOf"][1]["multipleOf"], json!(2));
+        assert_eq!(serialized["anyOf"][1]["anyOf"][1]["enum"], json!([null]));
+        assert_eq!(
+            serialized["anyOf"][1]["anyOf"][0]["oneOf"][0]["type"],
+            "string"
+        );
+        schema.validate(&json!(4)).unwrap();
+        schema.validate(&json!(null)).unwrap();
+        assert!(schema.validate(&json!(5)).is_err());
+        assert!(schema.validate(&json!(12)).is_err());
+        // both branches of `oneOf` match strings
+        assert!(schema.validate(&json!("a")).is_err());
+
+        let schema = ObjectSchemaBuilder::default()
+            .add_property("id", IntegerSchemaBuilder::default().build())
+            .and(super::not(
+                ObjectSchemaBuilder::default()
+                    .add_property("deleted", StringSchema::default())
+                    .required(vec!["deleted".into()])
+                    .build(),
+            ));
+        let serialized = serde_json::to_value(&schema).unwrap();
+        assert_eq!(serialized["allOf"][1]["not"]["required"], json!(["deleted"]));
+        schema.validate(&json!({"id": 1})).unwrap();
+        assert!(schema.validate(&json!({"id": 1, "deleted": "yes"})).is_err());
+        let parsed: Schema = serde_json::from_value(serialized).unwrap();
+        assert!(parsed.validate(&json!({"id": 1, "deleted": "yes"})).is_err());
+    }
+
+    #[test]
+    fn match_branch() {
+        let shapes = schema(json!({
+            "definitions": {
+                "shape": {
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "additionalProperties": true,
+                            "properties": {
+                                "kind": {"const": "circle"},
+                                "radius": {"type": "number"}
+                            }
+                        },
+                        {
+                            "type": "object",
+                            "additionalProperties": true,
+                            "properties": {
+                                "kind": {"const": "square"},
+                                "side": {"type": "number"}
+                            }
+                        },
+                        {
+                            "type": "object",
+                            "additionalProperties": true,
+                            "required": ["kind"],
+                            "properties": {"kind": {"const": "point"}}
+                        }
+                    ]
+                }
+            },
+            "$ref": "#/definitions/shape"
+        }));
+        let square = json!({"kind": "square", "side": 2});
+        assert_eq!(
+            shapes.match_branch(&square),
+            Some(BranchMatch {
+                keyword: "oneOf",
+                index: 1,
+                schema_path: "/definitions/shape/oneOf/1".into(),
+            })
+        );
+        let point = shapes.match_branch(&json!({"kind": "point"})).unwrap();
+        assert_eq!(point.index, 2);
+        let big = json!({"kind": "circle", "radius": "big"});
+        assert_eq!(shapes.match_branch(&big), None);
+        assert_eq!(shapes.match_branch(&json!({"kind": "triangle"})), None);
+
+        // ambiguous for `oneOf`, the first match for `anyOf`
+        let branches = json!([{"type": "integer"}, {"type": "number"}, {"type": "string"}]);
+        let one_of = schema(json!({"oneOf": branches.clone()}));
+        assert_eq!(one_of.match_branch(&json!(1)), None);
+        assert_eq!(one_of.match_branch(&json!(1.5)).unwrap().index, 1);
+        assert_eq!(one_of.match_branch(&json!(null)), None);
+        let any_of = schema(json!({"anyOf": branches}));
+        let first = any_of.match_branch(&json!(1)).unwrap();
+        assert_eq!((first.keyword, first.index), ("anyOf", 0));
+        assert_eq!(first.schema_path, "/anyOf/0");
+
+        assert_eq!(
+            schema(json!({"type": "string"})).match_branch(&json!("a")),
+            None
+        );
+    }
+}
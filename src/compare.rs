@@ -0,0 +1,285 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde_json::Value;
+
+use errors::ValidationError;
+use schema::Schema;
+
+/// How the verdict on a document changed between two schemas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Verdict {
+    /// Both schemas accept the document.
+    ValidInBoth,
+    /// Both schemas reject the document.
+    InvalidInBoth,
+    /// The old schema accepts the document, the new one rejects it.
+    NewlyInvalid,
+    /// The old schema rejects the document, the new one accepts it.
+    NewlyValid,
+}
+
+/// An error of one of the compared schemas, see `DocumentDiff`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorSummary {
+    /// JSON pointer to the invalid value.
+    pub instance_path: String,
+    /// The name of the `ErrorCode` of the error.
+    pub code: String,
+    /// The error message.
+    pub message: String,
+}
+
+impl<'a, 'json> From<&'a ValidationError<'json>> for ErrorSummary {
+    fn from(error: &'a ValidationError<'json>) -> ErrorSummary {
+        ErrorSummary {
+            instance_path: error.instance_path.clone(),
+            code: format!("{:?}", error.reason.code()),
+            message: error.reason.to_string(),
+        }
+    }
+}
+
+/// How two schemas judged one document.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiff {
+    /// The position of the document in the compared documents.
+    pub index: usize,
+    /// The name of the document, like its path, if it was given one.
+    pub name: Option<String>,
+    /// Whether the verdict changed.
+    pub verdict: Verdict,
+    /// For documents both schemas reject, whether they report different errors:
+    /// errors of another kind or at other places. Differing messages alone,
+    /// like those of a changed bound, don't count.
+    pub errors_changed: bool,
+    /// The errors of the old schema.
+    pub old_errors: Vec<ErrorSummary>,
+    /// The errors of the new schema.
+    pub new_errors: Vec<ErrorSummary>,
+}
+
+impl DocumentDiff {
+    /// The name of the document, or its index if it has none.
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => format!("#{}", self.index),
+        }
+    }
+}
+
+/// The number of documents per verdict, see `BehaviorDiff`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerdictCounts {
+    /// Documents both schemas accept.
+    pub valid_in_both: usize,
+    /// Documents both schemas reject.
+    pub invalid_in_both: usize,
+    /// Documents both schemas reject with different errors, a subset of
+    /// `invalid_in_both`.
+    pub errors_changed: usize,
+    /// Documents only the old schema accepts.
+    pub newly_invalid: usize,
+    /// Documents only the new schema accepts.
+    pub newly_valid: usize,
+}
+
+/// How the verdicts of two versions of a schema differ over a set of
+/// documents, see `compare_behavior`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BehaviorDiff {
+    /// Every document, in the order they were given.
+    pub documents: Vec<DocumentDiff>,
+    /// The number of documents per verdict.
+    pub counts: VerdictCounts,
+}
+
+impl BehaviorDiff {
+    /// Creates a diff without documents.
+    pub fn new() -> BehaviorDiff {
+        BehaviorDiff::default()
+    }
+
+    /// Validates `document` against both schemas and adds the outcome.
+    pub fn add(&mut self, old: &Schema, new: &Schema, name: Option<String>, document: &Value) {
+        let summarize = |schema: &Schema| -> Vec<ErrorSummary> {
+            match schema.validate(document) {
+                Ok(()) => vec![],
+                Err(errors) => errors.0.iter().map(ErrorSummary::from).collect(),
+            }
+        };
+        let old_errors = summarize(old);
+        let new_errors = summarize(new);
+        let verdict = match (old_errors.is_empty(), new_errors.is_empty()) {
+            (true, true) => Verdict::ValidInBoth,
+            (false, false) => Verdict::InvalidInBoth,
+            (true, false) => Verdict::NewlyInvalid,
+            (false, true) => Verdict::NewlyValid,
+        };
+        let errors_changed =
+            verdict == Verdict::InvalidInBoth && error_set(&old_errors) != error_set(&new_errors);
+        match verdict {
+            Verdict::ValidInBoth => self.counts.valid_in_both += 1,
+            Verdict::InvalidInBoth => self.counts.invalid_in_both += 1,
+            Verdict::NewlyInvalid => self.counts.newly_invalid += 1,
+            Verdict::NewlyValid => self.counts.newly_valid += 1,
+        }
+        if errors_changed {
+            self.counts.errors_changed += 1;
+        }
+        self.documents.push(DocumentDiff {
+            index: self.documents.len(),
+            name,
+            verdict,
+            errors_changed,
+            old_errors,
+            new_errors,
+        });
+    }
+
+    /// The documents whose verdict changed.
+    pub fn changed(&self) -> Vec<&DocumentDiff> {
+        self.documents
+            .iter()
+            .filter(|d| d.verdict == Verdict::NewlyInvalid || d.verdict == Verdict::NewlyValid)
+            .collect()
+    }
+
+    /// Whether the new schema rejects a document the old one accepts, the
+    /// change that breaks existing producers.
+    pub fn has_newly_invalid(&self) -> bool {
+        self.counts.newly_invalid > 0
+    }
+}
+
+fn error_set(errors: &[ErrorSummary]) -> BTreeSet<(&str, &str)> {
+    errors
+        .iter()
+        .map(|e| (e.instance_path.as_str(), e.code.as_str()))
+        .collect()
+}
+
+impl fmt::Display for BehaviorDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for document in &self.documents {
+            match document.verdict {
+                Verdict::NewlyInvalid => {
+                    writeln!(f, "{} is newly invalid:", document.label())?;
+                    for error in &document.new_errors {
+                        writeln!(f, "  at `{}`: {}", error.instance_path, error.message)?;
+                    }
+                }
+                Verdict::NewlyValid => writeln!(f, "{} is newly valid", document.label())?,
+                Verdict::InvalidInBoth if document.errors_changed => writeln!(
+                    f,
+                    "{} is invalid in both with other errors",
+                    document.label()
+                )?,
+                _ => {}
+            }
+        }
+        let c = &self.counts;
+        writeln!(
+            f,
+            "{} valid in both, {} invalid in both ({} with other errors), \
+             {} newly invalid, {} newly valid",
+            c.valid_in_both, c.invalid_in_both, c.errors_changed, c.newly_invalid, c.newly_valid
+        )
+    }
+}
+
+/// Validates each document against an old and a new version of a schema and
+/// reports which documents change verdict, for checking a schema change against
+/// real documents before rolling it out. Documents are identified by their
+/// index; use `BehaviorDiff::add` to name them.
+pub fn compare_behavior<I>(old: &Schema, new: &Schema, documents: I) -> BehaviorDiff
+where
+    I: IntoIterator<Item = Value>,
+{
+    let mut diff = BehaviorDiff::new();
+    for document in documents {
+        diff.add(old, new, None, &document);
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    #[test]
+    fn verdicts() {
+        let old: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["id"],
+            "properties": {
+                "id": {"type": "integer"},
+                "name": {"type": "string", "maxLength": 10}
+            }
+        }))
+        .unwrap();
+        let new: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["id", "name"],
+            "properties": {
+                "id": {"anyOf": [{"type": "integer"}, {"type": "string"}]},
+                "name": {"type": "string", "maxLength": 5}
+            }
+        }))
+        .unwrap();
+        let documents = vec![
+            json!({"id": 1, "name": "ada"}),
+            json!({"id": 2}),
+            json!({"id": "x", "name": "bob"}),
+            json!({"id": 1.5, "name": "a very long name"}),
+            json!({"id": 1, "name": 7}),
+        ];
+        let diff = compare_behavior(&old, &new, documents);
+        let verdicts: Vec<_> = diff
+            .documents
+            .iter()
+            .map(|d| (d.verdict, d.errors_changed))
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                (Verdict::ValidInBoth, false),
+                (Verdict::NewlyInvalid, false),
+                (Verdict::NewlyValid, false),
+                (Verdict::InvalidInBoth, true),
+                (Verdict::InvalidInBoth, false),
+            ]
+        );
+        assert_eq!(
+            diff.counts,
+            VerdictCounts {
+                valid_in_both: 1,
+                invalid_in_both: 2,
+                errors_changed: 1,
+                newly_invalid: 1,
+                newly_valid: 1,
+            }
+        );
+        assert!(diff.has_newly_invalid());
+        let changed: Vec<_> = diff.changed().iter().map(|d| d.label()).collect();
+        assert_eq!(changed, vec!["#1", "#2"]);
+        assert_eq!(diff.documents[1].new_errors[0].code, "MissingProperties");
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["documents"][1]["verdict"], json!("newlyInvalid"));
+        assert_eq!(json["counts"]["newlyValid"], json!(1));
+        assert!(diff
+            .to_string()
+            .ends_with("1 valid in both, 2 invalid in both (1 with other errors), 1 newly invalid, 1 newly valid\n"));
+    }
+}
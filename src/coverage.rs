@@ -0,0 +1,232 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use serde_json::Value;
+
+use options::ValidateOptions;
+use output::{Collected, Timing, ValidationOutput};
+use schema::{Context, Deadline, Schema, SchemaBase};
+use session::Scratch;
+
+/// Numbers the subschemas of a schema in document order, so hits can be
+/// recorded by index. Subschemas are told apart by their address, which doesn't
+/// change while the schema is borrowed.
+#[derive(Debug)]
+pub(crate) struct SchemaIndex {
+    indices: HashMap<*const Schema, usize>,
+    pointers: Vec<String>,
+}
+
+impl SchemaIndex {
+    fn new(schema: &Schema) -> SchemaIndex {
+        let mut index = SchemaIndex {
+            indices: HashMap::new(),
+            pointers: vec![],
+        };
+        index.add(schema, String::new());
+        index
+    }
+
+    fn add(&mut self, schema: &Schema, pointer: String) {
+        // subschemas shared with `Schema::dedup` are counted where they first appear
+        let key: *const Schema = schema;
+        if self.indices.contains_key(&key) {
+            return;
+        }
+        self.indices.insert(key, self.pointers.len());
+        self.pointers.push(pointer.clone());
+        for (step, child) in schema.children_with_pointers() {
+            self.add(child, format!("{}{}", pointer, step));
+        }
+    }
+
+    /// The index of `schema`, if it's part of the indexed schema.
+    pub(crate) fn get(&self, schema: &Schema) -> Option<usize> {
+        let key: *const Schema = schema;
+        self.indices.get(&key).cloned()
+    }
+}
+
+/// The number of times a subschema accepted a value.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubschemaCoverage {
+    /// JSON pointer to the subschema.
+    pub schema_path: String,
+    /// The identifier of the subschema, see `Schema::assign_ids`.
+    pub id: String,
+    /// How many values the subschema accepted.
+    pub hits: u64,
+}
+
+/// Which subschemas the validated documents exercised, see `CoverageSession`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CoverageReport {
+    /// The number of validated documents.
+    pub documents: usize,
+    /// Every subschema, in document order.
+    pub subschemas: Vec<SubschemaCoverage>,
+    /// The percentage of subschemas that accepted at least one value.
+    pub percent_covered: f64,
+}
+
+impl CoverageReport {
+    /// The subschemas that never accepted a value.
+    pub fn uncovered(&self) -> Vec<&SubschemaCoverage> {
+        self.subschemas.iter().filter(|s| s.hits == 0).collect()
+    }
+}
+
+/// Validates many documents against the same schema and counts how often each
+/// subschema accepted a value, to find the parts of a schema a set of fixtures
+/// never exercises: definitions nothing references, `oneOf` branches nothing
+/// matched or properties no document had.
+///
+/// A subschema only counts as hit if it accepted the value. The subschemas of
+/// failed `anyOf`/`oneOf` branches aren't counted, so what's left is what
+/// actually made the documents valid.
+#[derive(Debug)]
+pub struct CoverageSession<'s> {
+    schema: &'s Schema,
+    options: ValidateOptions,
+    index: SchemaIndex,
+    hits: Vec<u64>,
+    documents: usize,
+}
+
+impl<'s> CoverageSession<'s> {
+    /// Creates a session for validating documents against `schema`.
+    pub fn new(schema: &'s Schema) -> CoverageSession<'s> {
+        CoverageSession::with_options(schema, ValidateOptions::default())
+    }
+
+    /// Creates a session that validates with the given options.
+    pub fn with_options(schema: &'s Schema, options: ValidateOptions) -> CoverageSession<'s> {
+        let index = SchemaIndex::new(schema);
+        CoverageSession {
+            schema,
+            options,
+            hits: vec![0; index.pointers.len()],
+            index,
+            documents: 0,
+        }
+    }
+
+    /// Validates `value`, counting the subschemas that accepted it.
+    pub fn validate<'json>(&mut self, value: &'json Value) -> ValidationOutput<'json> {
+        let collected = RefCell::default();
+        let mut errors = vec![];
+        let started = Some(Instant::now()).filter(|_| self.options.collect_timing);
+        let deadline = Deadline::new(self.options.timeout);
+        {
+            let scratch = Scratch::default();
+            let mut context =
+                Context::new(self.schema, &self.options, &collected, &scratch, &deadline);
+            context.coverage = Some(&self.index);
+            self.schema.validate_inner(&context, value, &mut errors);
+            context.finish(value, &mut errors);
+        }
+        let Collected {
+            annotations,
+            warnings,
+            hits,
+            sampled,
+            skipped,
+            timing,
+            ..
+        } = collected.into_inner();
+        for index in hits {
+            self.hits[index] += 1;
+        }
+        self.documents += 1;
+        ValidationOutput {
+            errors,
+            annotations,
+            warnings,
+            incomplete: deadline.aborted(),
+            peak_tracked_bytes: deadline.peak_memory(),
+            sampled,
+            skipped,
+            timing: started.map(|started| Timing {
+                total: started.elapsed(),
+                ..timing
+            }),
+        }
+    }
+
+    /// The coverage of the documents validated so far.
+    pub fn report(&self) -> CoverageReport {
+        let ids = self.schema.assign_ids();
+        let subschemas: Vec<_> = self
+            .index
+            .pointers
+            .iter()
+            .zip(&self.hits)
+            .map(|(pointer, &hits)| SubschemaCoverage {
+                schema_path: pointer.clone(),
+                id: ids[pointer].clone(),
+                hits,
+            })
+            .collect();
+        let covered = subschemas.iter().filter(|s| s.hits > 0).count();
+        CoverageReport {
+            documents: self.documents,
+            percent_covered: covered as f64 * 100.0 / subschemas.len() as f64,
+            subschemas,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    #[test]
+    fn one_of_branches() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {"unused": {"type": "string"}},
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "id": {"oneOf": [{"type": "integer"}, {"type": "string", "minLength": 1}]},
+                "note": {"type": "string"}
+            }
+        }))
+        .unwrap();
+        let mut session = CoverageSession::new(&schema);
+        for fixture in &[json!({"id": 1}), json!({"id": 2}), json!({"id": true})] {
+            session.validate(fixture);
+        }
+
+        let report = session.report();
+        assert_eq!(report.documents, 3);
+        let hits: Vec<_> = report
+            .subschemas
+            .iter()
+            .map(|s| (s.schema_path.as_str(), s.hits))
+            .collect();
+        assert_eq!(
+            hits,
+            vec![
+                ("", 2),
+                ("/definitions/unused", 0),
+                ("/properties/id", 2),
+                ("/properties/id/oneOf/0", 2),
+                ("/properties/id/oneOf/1", 0),
+                ("/properties/note", 0),
+            ]
+        );
+        assert_eq!(report.uncovered().len(), 3);
+        assert_eq!(report.percent_covered, 50.0);
+
+        let json = serde_json::to_value(&report).unwrap();
+        let id = &schema.assign_ids()["/properties/id/oneOf/0"];
+        assert_eq!(
+            json["subschemas"][3],
+            json!({"schemaPath": "/properties/id/oneOf/0", "id": id, "hits": 2})
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{de, Deserialize, Deserializer};
+use serde_json::Value;
+
+use errors::{ErrorKind, ValidationError};
+use schema::{Context, Segment};
+
+/// A rule of the extension keyword `x-compare`: the value at `left` has to
+/// stand in the relation `op` to the value at `right`. Both are JSON pointers
+/// into the object the schema applies to, so
+/// `{"left": "/startDate", "op": "<=", "right": "/endDate", "as": "date-time"}`
+/// requires an object to end no earlier than it starts.
+///
+/// `x-compare` isn't part of JSON Schema, other validators ignore it, and so
+/// does this crate with `ValidateOptions::strict_spec`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Comparison {
+    /// The pointer to the left operand.
+    #[serde(deserialize_with = "deserialize_pointer")]
+    pub left: String,
+    /// The relation the operands have to stand in.
+    pub op: CompareOp,
+    /// The pointer to the right operand.
+    #[serde(deserialize_with = "deserialize_pointer")]
+    pub right: String,
+    /// What the operands are compared as. Without it, two numbers are compared
+    /// as numbers and two strings as strings.
+    #[serde(rename = "as", default, skip_serializing_if = "Option::is_none")]
+    pub operands: Option<OperandType>,
+    /// What happens when an operand is missing.
+    #[serde(default, skip_serializing_if = "MissingOperand::is_skip")]
+    pub missing: MissingOperand,
+}
+
+impl Comparison {
+    /// A rule comparing the values at `left` and `right`, inferring their type
+    /// and skipping objects that lack either.
+    pub fn new<L: Into<String>, R: Into<String>>(left: L, op: CompareOp, right: R) -> Comparison {
+        Comparison {
+            left: left.into(),
+            op,
+            right: right.into(),
+            operands: None,
+            missing: MissingOperand::Skip,
+        }
+    }
+
+    /// Compares the operands as `operands`.
+    pub fn operands(mut self, operands: OperandType) -> Comparison {
+        self.operands = Some(operands);
+        self
+    }
+
+    /// Sets what happens when an operand is missing.
+    pub fn missing(mut self, missing: MissingOperand) -> Comparison {
+        self.missing = missing;
+        self
+    }
+}
+
+/// Deserializes a pointer of a `Comparison`, which can't be relative.
+fn deserialize_pointer<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pointer = String::deserialize(deserializer)?;
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(de::Error::custom(format!(
+            "x-compare operands are JSON pointers, `{}` doesn't start with `/`",
+            pointer
+        )));
+    }
+    Ok(pointer)
+}
+
+/// A relation of `x-compare`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    /// `<`
+    #[serde(rename = "<")]
+    Less,
+    /// `<=`
+    #[serde(rename = "<=")]
+    LessOrEqual,
+    /// `==`
+    #[serde(rename = "==")]
+    Equal,
+    /// `!=`
+    #[serde(rename = "!=")]
+    NotEqual,
+    /// `>=`
+    #[serde(rename = ">=")]
+    GreaterOrEqual,
+    /// `>`
+    #[serde(rename = ">")]
+    Greater,
+}
+
+impl CompareOp {
+    /// Whether operands ordered as `ordering` stand in this relation.
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Less => ordering == Ordering::Less,
+            CompareOp::LessOrEqual => ordering != Ordering::Greater,
+            CompareOp::Equal => ordering == Ordering::Equal,
+            CompareOp::NotEqual => ordering != Ordering::Equal,
+            CompareOp::GreaterOrEqual => ordering != Ordering::Less,
+            CompareOp::Greater => ordering == Ordering::Greater,
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            CompareOp::Less => "<",
+            CompareOp::LessOrEqual => "<=",
+            CompareOp::Equal => "==",
+            CompareOp::NotEqual => "!=",
+            CompareOp::GreaterOrEqual => ">=",
+            CompareOp::Greater => ">",
+        })
+    }
+}
+
+/// What the operands of an `x-compare` rule are compared as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperandType {
+    /// Numbers, or strings holding them, like `"1.5"`.
+    Number,
+    /// Strings, by their code points.
+    String,
+    /// RFC 3339 timestamps, by the instant they stand for, so
+    /// `2020-01-01T01:00:00+01:00` equals `2020-01-01T00:00:00Z`.
+    DateTime,
+}
+
+impl fmt::Display for OperandType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            OperandType::Number => "number",
+            OperandType::String => "string",
+            OperandType::DateTime => "date-time",
+        })
+    }
+}
+
+/// What an `x-compare` rule does when the object lacks an operand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingOperand {
+    /// The rule doesn't apply, `required` is the keyword for operands that
+    /// have to be there.
+    #[default]
+    Skip,
+    /// The object is invalid.
+    Fail,
+}
+
+impl MissingOperand {
+    fn is_skip(&self) -> bool {
+        *self == MissingOperand::Skip
+    }
+}
+
+/// Two values of an object that don't stand in the relation an `x-compare`
+/// rule requires, see `ErrorKind::CrossFieldMismatch`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// The pointer to the left operand.
+    pub left: String,
+    /// The left operand, as JSON.
+    pub left_value: String,
+    /// The relation the rule requires.
+    pub op: CompareOp,
+    /// The pointer to the right operand.
+    pub right: String,
+    /// The right operand, as JSON.
+    pub right_value: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` ({}) must be {} `{}` ({})",
+            self.left, self.left_value, self.op, self.right, self.right_value
+        )
+    }
+}
+
+/// An operand coerced to the type it's compared as.
+#[derive(Clone, Copy)]
+enum Operand<'json> {
+    Number(f64),
+    String(&'json str),
+    DateTime(DateTime<FixedOffset>),
+}
+
+impl<'json> Operand<'json> {
+    /// Coerces `value` to `operands`, `None` if it can't be.
+    fn coerce(value: &'json Value, operands: OperandType) -> Option<Operand<'json>> {
+        let text = value.as_str();
+        match operands {
+            OperandType::Number => match text {
+                Some(text) => text.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+                None => value.as_f64(),
+            }
+            .map(Operand::Number),
+            OperandType::String => text.map(Operand::String),
+            OperandType::DateTime => text
+                .and_then(|text| DateTime::parse_from_rfc3339(text).ok())
+                .map(Operand::DateTime),
+        }
+    }
+
+    fn cmp(self, other: Operand) -> Option<Ordering> {
+        match (self, other) {
+            (Operand::Number(a), Operand::Number(b)) => a.partial_cmp(&b),
+            (Operand::String(a), Operand::String(b)) => Some(a.cmp(b)),
+            (Operand::DateTime(a), Operand::DateTime(b)) => Some(a.cmp(&b)),
+            _ => None,
+        }
+    }
+}
+
+/// The type operands are compared as without an `as` hint, from the left one.
+fn inferred(value: &Value) -> Option<OperandType> {
+    match *value {
+        Value::Number(_) => Some(OperandType::Number),
+        Value::String(_) => Some(OperandType::String),
+        _ => None,
+    }
+}
+
+/// Checks `object` against the rules of `x-compare`.
+pub(crate) fn validate_comparisons<'json>(
+    ctx: &Context,
+    rules: &[Comparison],
+    object: &'json Value,
+    errors: &mut Vec<ValidationError<'json>>,
+) {
+    for (i, rule) in rules.iter().enumerate() {
+        ctx.descend(None, "x-compare", Some(Segment::Index(i)), |ctx| {
+            if let Some(kind) = check(ctx, rule, object) {
+                let mut error = [ValidationError::new(kind, object)];
+                ctx.locate(&mut error);
+                errors.extend(error);
+            }
+        });
+    }
+}
+
+/// The error of `object` under `rule`, if any.
+fn check(ctx: &Context, rule: &Comparison, object: &Value) -> Option<ErrorKind> {
+    let (left, right) = match (object.pointer(&rule.left), object.pointer(&rule.right)) {
+        (Some(left), Some(right)) => (left, right),
+        (left, _) => {
+            // partial objects leave out what they don't change
+            if rule.missing == MissingOperand::Skip || ctx.partial {
+                return None;
+            }
+            let pointer = if left.is_none() {
+                &rule.left
+            } else {
+                &rule.right
+            };
+            let expected = rule
+                .operands
+                .map_or("number or string".to_string(), |t| t.to_string());
+            return Some(ErrorKind::InvalidOperand {
+                pointer: pointer.clone(),
+                expected,
+                found: None,
+            });
+        }
+    };
+    let operands = match rule.operands.or_else(|| inferred(left)) {
+        Some(operands) => operands,
+        None => {
+            return Some(ErrorKind::InvalidOperand {
+                pointer: rule.left.clone(),
+                expected: "number or string".to_string(),
+                found: Some(left.to_string()),
+            })
+        }
+    };
+    // without a hint, the right operand has to have the type of the left one
+    let exact = rule.operands.is_some() || inferred(right) == Some(operands);
+    let coerced = (
+        Operand::coerce(left, operands),
+        Operand::coerce(right, operands).filter(|_| exact),
+    );
+    let ordering = match coerced {
+        (Some(a), Some(b)) => a.cmp(b),
+        (a, _) => {
+            let (pointer, value) = if a.is_none() {
+                (&rule.left, left)
+            } else {
+                (&rule.right, right)
+            };
+            return Some(ErrorKind::InvalidOperand {
+                pointer: pointer.clone(),
+                expected: operands.to_string(),
+                found: Some(value.to_string()),
+            });
+        }
+    };
+    // NaN can't come from JSON, so every pair of operands is ordered
+    if ordering.is_some_and(|ordering| rule.op.holds(ordering)) {
+        return None;
+    }
+    Some(ErrorKind::CrossFieldMismatch(Box::new(Mismatch {
+        left: rule.left.clone(),
+        left_value: left.to_string(),
+        op: rule.op,
+        right: rule.right.clone(),
+        right_value: right.to_string(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::ErrorCode;
+    use options::ValidateOptions;
+    use output::WarningKind;
+    use schema::Schema;
+
+    fn compare_schema(rule: Value) -> Schema {
+        serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "x-compare": [rule]
+        }))
+        .unwrap()
+    }
+
+    fn codes(schema: &Schema, instance: Value) -> Vec<ErrorCode> {
+        match schema.validate(&instance) {
+            Ok(()) => vec![],
+            Err(errors) => errors.codes(),
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        let schema = compare_schema(json!({"left": "/min", "op": "<=", "right": "/max"}));
+        assert_eq!(codes(&schema, json!({"min": 1, "max": 2.5})), vec![]);
+        assert_eq!(codes(&schema, json!({"min": 2, "max": 2})), vec![]);
+        assert_eq!(
+            codes(&schema, json!({"min": 3, "max": 2})),
+            vec![ErrorCode::CrossFieldMismatch]
+        );
+        assert_eq!(
+            codes(&schema, json!({"min": 1, "max": "2"})),
+            vec![ErrorCode::InvalidOperand]
+        );
+
+        let schema =
+            compare_schema(json!({"left": "/min", "op": "<", "right": "/max", "as": "number"}));
+        assert_eq!(codes(&schema, json!({"min": "1.5", "max": 2})), vec![]);
+        assert_eq!(
+            codes(&schema, json!({"min": "2", "max": 2})),
+            vec![ErrorCode::CrossFieldMismatch]
+        );
+
+        let instance = json!({"range": {"min": 3, "max": 2}});
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {"range": {
+                "type": "object",
+                "additionalProperties": true,
+                "x-compare": [{"left": "/min", "op": "<=", "right": "/max"}]
+            }}
+        }))
+        .unwrap();
+        let errors = schema.validate(&instance).unwrap_err().0;
+        assert_eq!(errors[0].instance_path, "/range");
+        assert_eq!(errors[0].schema_path, "/properties/range/x-compare/0");
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "`/min` (3) must be <= `/max` (2)"
+        );
+    }
+
+    #[test]
+    fn date_times() {
+        let schema = compare_schema(json!({
+            "left": "/startDate",
+            "op": "<=",
+            "right": "/endDate",
+            "as": "date-time"
+        }));
+        let valid = json!({"startDate": "2020-01-01T10:00:00Z", "endDate": "2020-01-02T09:00:00Z"});
+        assert_eq!(codes(&schema, valid), vec![]);
+        // the same instant, in another offset
+        let valid =
+            json!({"startDate": "2020-01-01T10:00:00Z", "endDate": "2020-01-01T11:00:00+01:00"});
+        assert_eq!(codes(&schema, valid), vec![]);
+        // later as a string, earlier as an instant
+        let invalid =
+            json!({"startDate": "2020-01-01T10:00:00Z", "endDate": "2020-01-01T10:30:00+01:00"});
+        assert_eq!(codes(&schema, invalid), vec![ErrorCode::CrossFieldMismatch]);
+        let invalid = json!({"startDate": "2020-01-01T10:00:00Z", "endDate": "tomorrow"});
+        let errors = schema.validate(&invalid).unwrap_err().0;
+        assert_eq!(errors[0].reason.code(), ErrorCode::InvalidOperand);
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "`/endDate` is \"tomorrow\", which isn't a date-time"
+        );
+    }
+
+    #[test]
+    fn missing_operands() {
+        let rule = json!({"left": "/start", "op": "<", "right": "/end"});
+        let skip = compare_schema(rule.clone());
+        assert_eq!(codes(&skip, json!({"start": 1})), vec![]);
+        assert_eq!(codes(&skip, json!({})), vec![]);
+
+        let mut rule = rule;
+        rule["missing"] = json!("fail");
+        let fail = compare_schema(rule);
+        let instance = json!({"start": 1});
+        let errors = fail.validate(&instance).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].reason.code(), ErrorCode::InvalidOperand);
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "`/end` is missing, it has to be a number or string"
+        );
+        let options = ValidateOptions {
+            partial: true,
+            ..Default::default()
+        };
+        assert!(fail
+            .validate_with(&json!({"start": 1}), &options)
+            .is_valid());
+    }
+
+    #[test]
+    fn strict_spec() {
+        let schema = compare_schema(json!({"left": "/min", "op": "<=", "right": "/max"}));
+        let instance = json!({"min": 3, "max": 2});
+        let options = ValidateOptions {
+            strict_spec: true,
+            collect_warnings: true,
+            ..Default::default()
+        };
+        let output = schema.validate_with(&instance, &options);
+        assert!(output.is_valid());
+        let ignored = WarningKind::IgnoredExtension("x-compare".into());
+        assert!(output.warnings.iter().any(|w| w.kind == ignored));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let invalid = vec![
+            json!({"left": "min", "op": "<=", "right": "/max"}),
+            json!({"left": "/min", "op": "=<", "right": "/max"}),
+            json!({"left": "/min", "op": "<=", "right": "/max", "as": "date"}),
+            json!({"left": "/min", "op": "<=", "right": "/max", "missing": "error"}),
+        ];
+        for rule in invalid {
+            let schema = json!({"type": "object", "x-compare": [rule]});
+            assert!(serde_json::from_value::<Schema>(schema).is_err());
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+
+use serde_json;
+
+use schema::{EmptySchema, Schema};
+
+/// The result of `Schema::dedup`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DedupReport {
+    /// The number of subschemas that were replaced by a shared copy.
+    pub merged: usize,
+    /// The number of distinct subschemas that are now shared.
+    pub shared: usize,
+}
+
+impl Schema {
+    /// Replaces structurally identical subschemas with a single `Schema::Shared`
+    /// copy, which saves memory for generated schemas that repeat the same
+    /// subschemas many times. Validation isn't affected.
+    ///
+    /// Subschemas are compared by their serialized form, so with the
+    /// `preserve_order` feature subschemas that only differ in the order of their
+    /// properties aren't merged. Subschemas containing a `Schema::Custom` can't be
+    /// serialized and are never merged, only their other subschemas are.
+    pub fn dedup(&mut self) -> DedupReport {
+        let mut counts = HashMap::new();
+        for child in self.children_mut() {
+            count(child, &mut counts);
+        }
+        let mut shared = HashMap::new();
+        let mut report = DedupReport::default();
+        for child in self.children_mut() {
+            share(child, &counts, &mut shared, &mut report);
+        }
+        report
+    }
+}
+
+/// The key that identifies structurally identical schemas, `None` for schemas
+/// containing custom schemas.
+fn key(schema: &Schema) -> Option<String> {
+    serde_json::to_string(schema).ok()
+}
+
+fn count(schema: &mut Schema, counts: &mut HashMap<String, usize>) {
+    if let Some(key) = key(schema) {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    for child in schema.children_mut() {
+        count(child, counts);
+    }
+}
+
+fn share(
+    schema: &mut Schema,
+    counts: &HashMap<String, usize>,
+    shared: &mut HashMap<String, Arc<Schema>>,
+    report: &mut DedupReport,
+) {
+    let key = key(schema);
+    if let Some(existing) = key.as_ref().and_then(|k| shared.get(k)) {
+        *schema = Schema::Shared(existing.clone());
+        report.merged += 1;
+        return;
+    }
+
+    for child in schema.children_mut() {
+        share(child, counts, shared, report);
+    }
+    let key = match key {
+        Some(key) => key,
+        None => return,
+    };
+    if counts.get(&key).is_some_and(|&count| count > 1) {
+        let owned = mem::replace(schema, Schema::Empty(EmptySchema::default()));
+        let owned = Arc::new(owned);
+        shared.insert(key, owned.clone());
+        *schema = Schema::Shared(owned);
+        report.shared += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{self, Map, Value};
+
+    use super::*;
+
+    #[test]
+    fn identical_leaves() {
+        let mut properties = Map::new();
+        for i in 0..10_000 {
+            properties.insert(format!("p{}", i), json!({"type": "string", "minLength": 1}));
+        }
+        let mut schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": true
+        }))
+        .unwrap();
+        let before = serde_json::to_value(&schema).unwrap();
+
+        let report = schema.dedup();
+        assert_eq!(
+            report,
+            DedupReport {
+                merged: 9_999,
+                shared: 1
+            }
+        );
+        assert_eq!(serde_json::to_value(&schema).unwrap(), before);
+
+        schema.validate(&json!({"p1": "a", "p9999": "b"})).unwrap();
+        let input = json!({"p1": "", "p2": 1});
+        assert_eq!(schema.validate(&input).unwrap_err().0.len(), 2);
+    }
+
+    #[test]
+    fn nested_duplicates() {
+        let address = json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {"street": {"type": "string"}, "city": {"type": "string"}}
+        });
+        let mut schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "billing": address,
+                "shipping": address,
+                "name": {"type": "string"}
+            }
+        }))
+        .unwrap();
+        let report = schema.dedup();
+        // the street of the first address, the name and the second address are
+        // replaced by the shared string and address schemas
+        assert_eq!(
+            report,
+            DedupReport {
+                merged: 3,
+                shared: 2
+            }
+        );
+
+        let input = json!({"billing": {"city": 1}, "shipping": {"city": "x"}, "name": 2});
+        let errors: Vec<Value> = schema
+            .validate(&input)
+            .unwrap_err()
+            .0
+            .iter()
+            .map(|e| json!(e.instance_path))
+            .collect();
+        assert_eq!(errors, vec![json!("/billing/city"), json!("/name")]);
+    }
+}
@@ -0,0 +1,660 @@
+use std::fmt;
+
+use serde_json::{self, Value};
+
+use duplicate_keys::repeated_members;
+use errors::{ErrorKind, Result};
+use schema::Schema;
+use string::Format;
+use util::{pointer_token, strip_bom};
+
+/// The keywords of JSON schema, including the ones this crate doesn't enforce.
+pub(crate) const KEYWORDS: &[&str] = &[
+    "$schema",
+    "$id",
+    "id",
+    "$ref",
+    "type",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "x-counterexamples",
+    "deprecated",
+    "x-removed-in",
+    "definitions",
+    "$defs",
+    "enum",
+    "const",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+    "properties",
+    "patternProperties",
+    "additionalProperties",
+    "required",
+    "minProperties",
+    "maxProperties",
+    "x-orderedKeys",
+    "x-compare",
+    "items",
+    "prefixItems",
+    "additionalItems",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "x-uniqueBy",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "format",
+    "x-formatMinimum",
+    "x-formatExclusiveMinimum",
+    "x-formatMaximum",
+    "x-formatExclusiveMaximum",
+];
+
+/// The keywords validation enforces. `format` is only enforced for the formats
+/// `Format::is_checked` accepts.
+const ENFORCED: &[&str] = &[
+    "$ref",
+    "type",
+    "enum",
+    "const",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+    "properties",
+    "patternProperties",
+    "additionalProperties",
+    "required",
+    "minProperties",
+    "maxProperties",
+    "x-orderedKeys",
+    "x-compare",
+    "items",
+    "prefixItems",
+    "additionalItems",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "x-uniqueBy",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "x-formatMinimum",
+    "x-formatExclusiveMinimum",
+    "x-formatMaximum",
+    "x-formatExclusiveMaximum",
+];
+
+/// The keywords that are read but only describe values or hold subschemas.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "id",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "x-counterexamples",
+    "deprecated",
+    "x-removed-in",
+    "definitions",
+    "$defs",
+];
+
+const TYPE_NAMES: &[&str] = &[
+    "null", "boolean", "object", "array", "number", "string", "integer",
+];
+
+/// Whether `value` uses at least one schema keyword. The empty object is the
+/// empty schema, so it counts as using keywords too.
+pub fn has_schema_keywords(value: &Value) -> bool {
+    match *value {
+        Value::Object(ref object) => {
+            object.is_empty() || object.keys().any(|k| KEYWORDS.contains(&k.as_str()))
+        }
+        Value::Bool(_) => true,
+        _ => false,
+    }
+}
+
+/// Whether `value` is very likely a schema: it only uses schema keywords and
+/// either names a `$schema` or has a `type` made of type names or `properties`
+/// holding only objects. Data that happens to have a `type` field doesn't count.
+pub fn looks_like_schema(value: &Value) -> bool {
+    let object = match *value {
+        Value::Object(ref object) => object,
+        _ => return false,
+    };
+    if !object.keys().all(|k| KEYWORDS.contains(&k.as_str())) {
+        return false;
+    }
+    let is_type_name = |t: &Value| t.as_str().is_some_and(|t| TYPE_NAMES.contains(&t));
+    let has_schema = object.get("$schema").is_some_and(Value::is_string);
+    let has_type = match object.get("type") {
+        Some(Value::Array(types)) => !types.is_empty() && types.iter().all(is_type_name),
+        Some(t) => is_type_name(t),
+        None => false,
+    };
+    let has_properties = match object.get("properties") {
+        Some(Value::Object(properties)) => {
+            !properties.is_empty() && properties.values().all(Value::is_object)
+        }
+        _ => false,
+    };
+    has_schema || has_type || has_properties
+}
+
+/// Fails with `ErrorKind::SwappedSchemaAndInstance` if `schema` doesn't use any
+/// schema keyword while `instance` looks like a schema, which usually means the
+/// two were passed the other way round.
+pub fn check_swapped(schema: &Value, instance: &Value) -> Result<()> {
+    if !has_schema_keywords(schema) && looks_like_schema(instance) {
+        bail!(ErrorKind::SwappedSchemaAndInstance);
+    }
+    Ok(())
+}
+
+/// The keys of `value` if it's an object that uses no schema keyword, for the
+/// error about a document that isn't a schema.
+pub(crate) fn non_schema_keys(value: &Value) -> Option<Vec<String>> {
+    if has_schema_keywords(value) {
+        return None;
+    }
+    Some(value.as_object()?.keys().cloned().collect())
+}
+
+/// What validation does with `keyword`. `format` counts as enforced, though only
+/// the formats `Format::is_checked` accepts are. `x-orderedKeys` is dropped
+/// without the `preserve_order` feature.
+pub(crate) fn keyword_enforcement(keyword: &str) -> Enforcement {
+    match keyword {
+        "format" => Enforcement::Enforced,
+        "x-orderedKeys" if cfg!(not(feature = "preserve_order")) => Enforcement::Dropped,
+        k if ENFORCED.contains(&k) => Enforcement::Enforced,
+        k if ANNOTATIONS.contains(&k) => Enforcement::AnnotationOnly,
+        _ => Enforcement::Dropped,
+    }
+}
+
+/// What validation does with a keyword.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Enforcement {
+    /// Values that violate the keyword are rejected.
+    Enforced,
+    /// The keyword is read but never rejects a value, like `title`, `examples`
+    /// or a `format` that isn't checked.
+    AnnotationOnly,
+    /// The keyword isn't supported.
+    Dropped,
+}
+
+/// A keyword used by a schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeywordUse {
+    /// JSON pointer to the subschema using the keyword.
+    pub schema_path: String,
+    /// The keyword.
+    pub keyword: String,
+    /// What validation does with it.
+    pub enforcement: Enforcement,
+}
+
+/// Which keywords of a schema validation enforces, see
+/// `Schema::enforcement_report`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnforcementReport {
+    /// Every use of a keyword, in document order.
+    pub keywords: Vec<KeywordUse>,
+}
+
+impl EnforcementReport {
+    /// Inspects the schema document `schema`, which doesn't have to be a schema
+    /// this crate can parse.
+    pub fn from_value(schema: &Value) -> EnforcementReport {
+        let mut report = EnforcementReport::default();
+        report.add(schema, String::new(), false);
+        report
+    }
+
+    /// Adds the keywords of `schema`. Parsed schemas serialize missing keywords
+    /// as `null`, they're skipped if `skip_nulls` is set.
+    fn add(&mut self, schema: &Value, pointer: String, skip_nulls: bool) {
+        let object = match *schema {
+            Value::Object(ref object) => object,
+            _ => return,
+        };
+        for (keyword, value) in object {
+            if skip_nulls && value.is_null() {
+                continue;
+            }
+            let enforcement = match keyword.as_str() {
+                "format" if !value.as_str().is_some_and(|f| Format::from(f).is_checked()) => {
+                    Enforcement::AnnotationOnly
+                }
+                k => keyword_enforcement(k),
+            };
+            self.keywords.push(KeywordUse {
+                schema_path: pointer.clone(),
+                keyword: keyword.clone(),
+                enforcement,
+            });
+            if enforcement == Enforcement::Dropped {
+                continue;
+            }
+            let pointer = format!("{}/{}", pointer, pointer_token(keyword));
+            match (keyword.as_str(), value) {
+                ("properties", &Value::Object(ref map))
+                | ("patternProperties", &Value::Object(ref map))
+                | ("definitions", &Value::Object(ref map))
+                | ("$defs", &Value::Object(ref map)) => {
+                    for (key, schema) in map {
+                        let pointer = format!("{}/{}", pointer, pointer_token(key));
+                        self.add(schema, pointer, skip_nulls);
+                    }
+                }
+                ("items", &Value::Array(ref list))
+                | ("prefixItems", &Value::Array(ref list))
+                | ("allOf", &Value::Array(ref list))
+                | ("anyOf", &Value::Array(ref list))
+                | ("oneOf", &Value::Array(ref list)) => {
+                    for (i, schema) in list.iter().enumerate() {
+                        self.add(schema, format!("{}/{}", pointer, i), skip_nulls);
+                    }
+                }
+                ("items", _) | ("additionalItems", _) | ("not", _) => {
+                    self.add(value, pointer, skip_nulls)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The uses of keywords with the given enforcement.
+    pub fn with(&self, enforcement: Enforcement) -> Vec<&KeywordUse> {
+        self.keywords
+            .iter()
+            .filter(|k| k.enforcement == enforcement)
+            .collect()
+    }
+
+    /// The uses of keywords that look like constraints but reject nothing,
+    /// dropped ones and unchecked `format`s.
+    pub fn not_enforced_uses(&self) -> Vec<&KeywordUse> {
+        self.keywords
+            .iter()
+            .filter(|k| {
+                k.enforcement == Enforcement::Dropped
+                    || k.enforcement == Enforcement::AnnotationOnly && k.keyword == "format"
+            })
+            .collect()
+    }
+
+    /// The keywords of `not_enforced_uses` with their number of uses, in order
+    /// of first use.
+    pub fn not_enforced(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = vec![];
+        for k in self.not_enforced_uses() {
+            match counts
+                .iter_mut()
+                .find(|&&mut (keyword, _)| keyword == k.keyword)
+            {
+                Some(&mut (_, ref mut count)) => *count += 1,
+                None => counts.push((&k.keyword, 1)),
+            }
+        }
+        counts
+    }
+
+    /// A line like `2 keywords in this schema are not enforced: if (1 use), format
+    /// (2 uses)`, if any keyword isn't enforced.
+    pub fn summary(&self) -> Option<String> {
+        let counts = self.not_enforced();
+        if counts.is_empty() {
+            return None;
+        }
+        let uses: Vec<_> = counts
+            .iter()
+            .map(|&(keyword, n)| {
+                format!("{} ({} use{})", keyword, n, if n == 1 { "" } else { "s" })
+            })
+            .collect();
+        Some(format!(
+            "{} keyword{} in this schema {} not enforced: {}",
+            counts.len(),
+            if counts.len() == 1 { "" } else { "s" },
+            if counts.len() == 1 { "is" } else { "are" },
+            uses.join(", ")
+        ))
+    }
+}
+
+impl Schema {
+    /// Which keywords of this schema validation enforces. Parsing rejects
+    /// keywords this crate doesn't know, so a parsed schema has no dropped
+    /// keywords, use `EnforcementReport::from_value` on the document to find
+    /// them. Custom schemas can't be inspected, the report for a schema using
+    /// them is empty.
+    pub fn enforcement_report(&self) -> EnforcementReport {
+        let mut report = EnforcementReport::default();
+        if let Ok(value) = serde_json::to_value(self) {
+            report.add(&value, String::new(), true);
+        }
+        report
+    }
+}
+
+/// A name a schema declares more than once, which parsing resolves without
+/// an error, see `check_duplicate_declarations`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateDeclaration {
+    /// JSON pointer to the declaration in effect: the first entry of the name
+    /// in `required`, like `/required/0`, or the last of the repeated keys,
+    /// like `/properties/id`.
+    pub pointer: String,
+    /// The keyword: `required`, `properties`, `patternProperties`,
+    /// `definitions` or `$defs`.
+    pub keyword: String,
+    /// The repeated name.
+    pub name: String,
+    /// How often the name is declared.
+    pub occurrences: usize,
+    /// Whether the declarations differ, so that one of them is lost. Entries of
+    /// `required` never conflict.
+    pub conflicting: bool,
+}
+
+impl fmt::Display for DuplicateDeclaration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.keyword == "required" {
+            return write!(
+                f,
+                "`{}` is listed {} times in `required`, the repeats are ignored",
+                self.name, self.occurrences
+            );
+        }
+        write!(
+            f,
+            "`{}` is declared {} times in `{}`, the last declaration is used",
+            self.name, self.occurrences, self.keyword
+        )?;
+        if self.conflicting {
+            write!(f, " and the others, which differ from it, are ignored")?;
+        }
+        Ok(())
+    }
+}
+
+/// The keywords whose maps of subschemas may repeat a key.
+const SCHEMA_MAPS: &[&str] = &["properties", "patternProperties", "definitions", "$defs"];
+
+/// Finds the names the schema in the JSON text `text` declares more than
+/// once: repeated entries of `required` and keys repeated within the same
+/// `properties`, `patternProperties`, `definitions` or `$defs`. Parsing keeps
+/// the last of repeated keys, which machine-generated schemas may not expect.
+/// Fails if `text` isn't a schema.
+pub fn check_duplicate_declarations(text: &str) -> Result<Vec<DuplicateDeclaration>> {
+    let text = strip_bom(text);
+    let schema: Schema = text.parse()?;
+    let value: Value = serde_json::from_str(text)?;
+    let mut found = vec![];
+    repeated_required(&schema, &value, String::new(), &mut found);
+    for member in repeated_members(text)? {
+        let parent = &member.pointer[..member.pointer.rfind('/').unwrap_or(0)];
+        let (schema_path, keyword) = match parent.rfind('/') {
+            Some(i) => (&parent[..i], &parent[i + 1..]),
+            None => continue,
+        };
+        if SCHEMA_MAPS.contains(&keyword) && schema.pointer(schema_path).is_some() {
+            let last = &member.values[member.values.len() - 1];
+            found.push(DuplicateDeclaration {
+                pointer: member.pointer.clone(),
+                keyword: keyword.into(),
+                name: member.key.clone(),
+                occurrences: member.values.len(),
+                conflicting: member.values.iter().any(|v| v != last),
+            });
+        }
+    }
+    found.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+    Ok(found)
+}
+
+/// Adds the names the `required` of `schema` and of its subschemas repeat.
+/// `value` is the whole document, `pointer` the location of `schema` in it.
+fn repeated_required(
+    schema: &Schema,
+    value: &Value,
+    pointer: String,
+    found: &mut Vec<DuplicateDeclaration>,
+) {
+    let required = value
+        .pointer(&format!("{}/required", pointer))
+        .and_then(Value::as_array);
+    if let Some(names) = required {
+        for (i, name) in names.iter().enumerate() {
+            let occurrences = names.iter().filter(|n| *n == name).count();
+            if occurrences > 1 && !names[..i].contains(name) {
+                found.push(DuplicateDeclaration {
+                    pointer: format!("{}/required/{}", pointer, i),
+                    keyword: "required".into(),
+                    name: name.as_str().unwrap_or_default().into(),
+                    occurrences,
+                    conflicting: false,
+                });
+            }
+        }
+    }
+    for (step, child) in schema.children_with_pointers() {
+        repeated_required(child, value, format!("{}{}", pointer, step), found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use serde_json;
+
+    use super::*;
+    use schema::Schema;
+
+    #[test]
+    fn swapped() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {"name": {"type": "string"}}
+        });
+        let instance = json!({"name": "Jane", "age": 31});
+        assert!(check_swapped(&schema, &instance).is_ok());
+        match *check_swapped(&instance, &schema).unwrap_err().kind() {
+            ErrorKind::SwappedSchemaAndInstance => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let error = instance.to_string().parse::<Schema>().unwrap_err();
+        match *error.kind() {
+            ErrorKind::NotASchema(ref keys) => assert_eq!(keys.len(), 2),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn data_like_schemas() {
+        let schemas = vec![
+            json!({}),
+            json!({"type": "object", "properties": {"type": {"type": "string"}}}),
+            json!({"enum": ["object", "array"]}),
+            json!({"const": {"type": "string", "name": "x"}}),
+            serde_json::from_reader(File::open("data/schema-with-refs.json").unwrap()).unwrap(),
+        ];
+        // values that look a bit like schemas, but have other fields too
+        let instances = vec![
+            json!({"type": "string", "name": "title"}),
+            json!({"type": "user", "properties": {"admin": true}}),
+            json!({"properties": {}}),
+            json!(["object"]),
+        ];
+        for schema in &schemas {
+            assert!(has_schema_keywords(schema));
+            for instance in schemas.iter().chain(&instances) {
+                check_swapped(schema, instance).unwrap();
+            }
+        }
+        for instance in &instances {
+            assert!(!looks_like_schema(instance), "{}", instance);
+            check_swapped(instance, instance).unwrap();
+        }
+    }
+
+    #[test]
+    fn duplicate_declarations() {
+        let text = r#"{
+            "type": "object",
+            "required": ["id", "name", "id", "id"],
+            "properties": {
+                "id": {"type": "string"},
+                "name": {"type": "string"},
+                "id": {"type": "integer"},
+                "tags": {
+                    "type": "object",
+                    "required": ["a", "a"],
+                    "properties": {"a": {}},
+                    "additionalProperties": true
+                }
+            },
+            "definitions": {"code": {"type": "string"}, "code": {"type": "string"}},
+            "examples": [{"id": 1, "id": 2}]
+        }"#;
+        let duplicates = check_duplicate_declarations(text).unwrap();
+        let found: Vec<_> = duplicates
+            .iter()
+            .map(|d| (d.pointer.as_str(), d.name.as_str(), d.occurrences, d.conflicting))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("/definitions/code", "code", 2, false),
+                ("/properties/id", "id", 2, true),
+                ("/properties/tags/required/0", "a", 2, false),
+                ("/required/0", "id", 3, false),
+            ]
+        );
+        assert_eq!(
+            duplicates[1].to_string(),
+            "`id` is declared 2 times in `properties`, the last declaration is used and the \
+             others, which differ from it, are ignored"
+        );
+        assert_eq!(
+            duplicates[3].to_string(),
+            "`id` is listed 3 times in `required`, the repeats are ignored"
+        );
+
+        // the last `id` wins and `required` lists each name once
+        let schema: Schema = text.parse().unwrap();
+        schema
+            .validate(&json!({"id": 1, "name": "a", "tags": {"a": 1}}))
+            .unwrap();
+        assert!(schema
+            .validate(&json!({"id": "1", "name": "a", "tags": {"a": 1}}))
+            .is_err());
+        let serialized = serde_json::to_value(&schema).unwrap();
+        assert_eq!(serialized["required"], json!(["id", "name"]));
+
+        assert!(check_duplicate_declarations(r#"{"type": "object", "required": ["a"]}"#)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn enforcement() {
+        let document = json!({
+            "title": "Order",
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "id": {"type": "string", "format": "uuid", "examples": ["a"]},
+                "placed": {"type": "string", "format": "date-time"},
+                "lines": {
+                    "type": "array",
+                    "items": {"type": "integer", "minimum": 1},
+                    "unevaluatedItems": false
+                },
+                "tags": {"type": "array", "unevaluatedItems": {"type": "string"}}
+            },
+            "if": {"required": ["id"]}
+        });
+        let report = EnforcementReport::from_value(&document);
+        let mut uses: Vec<_> = report
+            .keywords
+            .iter()
+            .map(|k| (k.schema_path.as_str(), k.keyword.as_str(), k.enforcement))
+            .collect();
+        uses.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        use self::Enforcement::*;
+        assert_eq!(
+            uses,
+            vec![
+                ("", "additionalProperties", Enforced),
+                ("", "if", Dropped),
+                ("", "properties", Enforced),
+                ("", "title", AnnotationOnly),
+                ("", "type", Enforced),
+                ("/properties/id", "examples", AnnotationOnly),
+                ("/properties/id", "format", AnnotationOnly),
+                ("/properties/id", "type", Enforced),
+                ("/properties/lines", "items", Enforced),
+                ("/properties/lines", "type", Enforced),
+                ("/properties/lines", "unevaluatedItems", Dropped),
+                ("/properties/lines/items", "minimum", Enforced),
+                ("/properties/lines/items", "type", Enforced),
+                ("/properties/placed", "format", Enforced),
+                ("/properties/placed", "type", Enforced),
+                ("/properties/tags", "type", Enforced),
+                ("/properties/tags", "unevaluatedItems", Dropped),
+            ]
+        );
+        let mut not_enforced = report.not_enforced();
+        not_enforced.sort();
+        assert_eq!(
+            not_enforced,
+            vec![("format", 1), ("if", 1), ("unevaluatedItems", 2)]
+        );
+        assert!(report
+            .summary()
+            .unwrap()
+            .starts_with("3 keywords in this schema are not enforced: "));
+
+        // without the unsupported keywords the document parses
+        let mut supported = document.clone();
+        supported.as_object_mut().unwrap().remove("if");
+        for property in &["lines", "tags"] {
+            let property = supported["properties"][property].as_object_mut().unwrap();
+            property.remove("unevaluatedItems");
+        }
+        let schema: Schema = serde_json::from_value(supported.clone()).unwrap();
+        let report = schema.enforcement_report();
+        assert!(report.with(Dropped).is_empty());
+        assert_eq!(report.with(Enforced).len(), 11);
+        assert_eq!(report.with(AnnotationOnly).len(), 3);
+        assert_eq!(
+            report.summary().unwrap(),
+            "1 keyword in this schema is not enforced: format (1 use)"
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use std::fmt;
+
+use serde_json::{self, Map, Value};
+
+use errors::{ErrorKind, Result};
+use schema::Schema;
+use string::Format;
+
+/// The flavour of JSON schema a document is written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// Plain JSON schema, following the rules of `ValidateOptions::draft`, draft 7
+    /// by default.
+    #[default]
+    JsonSchema,
+    /// The schema object of OpenAPI 3.0. Accepts `nullable`, `example`,
+    /// `readOnly`/`writeOnly` and the OpenAPI number formats. `exclusiveMinimum`
+    /// and `exclusiveMaximum` are booleans in both dialects and need no translation.
+    OpenApi30,
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Dialect::JsonSchema => write!(f, "JSON schema"),
+            Dialect::OpenApi30 => write!(f, "OpenAPI 3.0"),
+        }
+    }
+}
+
+/// A keyword that was accepted while parsing, but that will not be enforced during validation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DialectWarning {
+    /// JSON pointer to the schema containing the keyword.
+    pub pointer: String,
+    /// The keyword.
+    pub keyword: String,
+    /// Why the keyword is not enforced.
+    pub message: String,
+}
+
+impl fmt::Display for DialectWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` at `{}`: {}",
+            self.keyword, self.pointer, self.message
+        )
+    }
+}
+
+/// The extension keywords of this crate, kept when converting from OpenAPI.
+const CRATE_EXTENSIONS: &[&str] = &[
+    "x-uniqueBy",
+    "x-orderedKeys",
+    "x-compare",
+    "x-formatMinimum",
+    "x-formatExclusiveMinimum",
+    "x-formatMaximum",
+    "x-formatExclusiveMaximum",
+    "x-counterexamples",
+    "x-removed-in",
+];
+
+/// OpenAPI keywords that are pure annotations and are dropped without a warning.
+const OPENAPI_ANNOTATIONS: &[&str] = &["readOnly", "writeOnly", "externalDocs"];
+
+/// OpenAPI keywords that carry meaning this crate can't enforce.
+const OPENAPI_UNENFORCED: &[&str] = &["discriminator", "xml"];
+
+impl Schema {
+    /// Parses a schema written in the given dialect. Returns the schema together
+    /// with warnings about keywords that were accepted but won't be enforced.
+    pub fn from_value_with_dialect(
+        value: Value,
+        dialect: Dialect,
+    ) -> Result<(Schema, Vec<DialectWarning>)> {
+        let mut warnings = vec![];
+        let value = match dialect {
+            Dialect::JsonSchema => value,
+            Dialect::OpenApi30 => openapi_to_internal(value, "", &mut warnings),
+        };
+        let schema = serde_json::from_value(value)?;
+        Ok((schema, warnings))
+    }
+
+    /// Converts an OpenAPI 3.0 schema object into a schema. Unlike
+    /// `from_value_with_dialect`, constructs that aren't part of OpenAPI 3.0
+    /// (`patternProperties`, tuple `items`, lists of types) are rejected.
+    pub fn from_openapi30(value: Value) -> Result<Schema> {
+        reject_non_openapi(&value, "")?;
+        Schema::from_value_with_dialect(value, Dialect::OpenApi30).map(|(schema, _)| schema)
+    }
+
+    /// Converts this schema into an OpenAPI 3.0 schema object. Nullable
+    /// schemas are written with `nullable: true`, and unset keywords are omitted.
+    /// Fails if the schema uses keywords OpenAPI 3.0 can't express.
+    pub fn to_openapi30(&self) -> Result<Value> {
+        internal_to_openapi(serde_json::to_value(self)?, "")
+    }
+}
+
+fn unsupported(keyword: &str, pointer: &str) -> ::errors::Error {
+    ErrorKind::UnsupportedInDialect {
+        keyword: keyword.into(),
+        pointer: if pointer.is_empty() {
+            "/".into()
+        } else {
+            pointer.into()
+        },
+        dialect: Dialect::OpenApi30,
+    }
+    .into()
+}
+
+fn reject_non_openapi(value: &Value, pointer: &str) -> Result<()> {
+    let object = match *value {
+        Value::Object(ref object) => object,
+        _ => return Ok(()),
+    };
+    for (key, value) in object {
+        let child = format!("{}/{}", pointer, key);
+        match (key.as_str(), value) {
+            ("patternProperties", _)
+            | ("prefixItems", _)
+            | ("type", Value::Array(_))
+            | ("items", Value::Array(_))
+            | ("items", Value::Bool(_)) => return Err(unsupported(key, pointer)),
+            ("properties", Value::Object(properties)) => {
+                for (name, value) in properties {
+                    reject_non_openapi(value, &format!("{}/{}", child, name))?;
+                }
+            }
+            ("allOf", Value::Array(elements))
+            | ("anyOf", Value::Array(elements))
+            | ("oneOf", Value::Array(elements)) => {
+                for (i, value) in elements.iter().enumerate() {
+                    reject_non_openapi(value, &format!("{}/{}", child, i))?;
+                }
+            }
+            ("items", _) | ("additionalProperties", _) | ("not", _) => {
+                reject_non_openapi(value, &child)?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites the serialized form of a `Schema` into an OpenAPI 3.0 schema object.
+fn internal_to_openapi(value: Value, pointer: &str) -> Result<Value> {
+    let object = match value {
+        Value::Object(object) => object,
+        other => return Ok(other),
+    };
+
+    let mut converted = Map::new();
+    for (key, value) in object {
+        let child = format!("{}/{}", pointer, key);
+        let value = match (key.as_str(), value) {
+            (_, Value::Null) => continue,
+            ("patternProperties", _)
+            | ("prefixItems", _)
+            | ("items", Value::Array(_))
+            | ("items", Value::Bool(_)) => return Err(unsupported(&key, pointer)),
+            ("type", Value::Array(types)) => {
+                let name = types.into_iter().find(|t| t != "null");
+                converted.insert("nullable".into(), Value::Bool(true));
+                name.unwrap_or(Value::Null)
+            }
+            ("properties", Value::Object(properties)) => {
+                let mut map = Map::new();
+                for (name, value) in properties {
+                    let schema = internal_to_openapi(value, &format!("{}/{}", child, name))?;
+                    map.insert(name, schema);
+                }
+                Value::Object(map)
+            }
+            ("items", value) | ("additionalProperties", value) | ("not", value) => {
+                internal_to_openapi(value, &child)?
+            }
+            ("allOf", Value::Array(elements))
+            | ("anyOf", Value::Array(elements))
+            | ("oneOf", Value::Array(elements)) => {
+                let mut converted = vec![];
+                for (i, value) in elements.into_iter().enumerate() {
+                    converted.push(internal_to_openapi(value, &format!("{}/{}", child, i))?);
+                }
+                Value::Array(converted)
+            }
+            // OpenAPI 3.0 only has a single `example`
+            ("examples", Value::Array(mut examples)) => {
+                if examples.len() != 1 {
+                    return Err(unsupported(&key, pointer));
+                }
+                converted.insert("example".into(), examples.remove(0));
+                continue;
+            }
+            (_, value) => value,
+        };
+        converted.insert(key, value);
+    }
+    Ok(Value::Object(converted))
+}
+
+fn warn(warnings: &mut Vec<DialectWarning>, pointer: &str, keyword: &str, message: String) {
+    warnings.push(DialectWarning {
+        pointer: if pointer.is_empty() {
+            "/".into()
+        } else {
+            pointer.into()
+        },
+        keyword: keyword.into(),
+        message,
+    });
+}
+
+/// Rewrites an OpenAPI 3.0 schema object into the spelling the `Schema`
+/// deserializer understands.
+fn openapi_to_internal(value: Value, pointer: &str, warnings: &mut Vec<DialectWarning>) -> Value {
+    let mut object = match value {
+        Value::Object(object) => object,
+        other => return other,
+    };
+
+    let mut converted = Map::new();
+    let nullable = object.remove("nullable");
+    for (key, value) in object {
+        let child = format!("{}/{}", pointer, key);
+        // other extensions are dropped
+        if key.starts_with("x-") && !CRATE_EXTENSIONS.contains(&key.as_str())
+            || OPENAPI_ANNOTATIONS.contains(&key.as_str())
+        {
+            continue;
+        }
+        if OPENAPI_UNENFORCED.contains(&key.as_str()) {
+            warn(
+                warnings,
+                pointer,
+                &key,
+                "not enforced by this validator".into(),
+            );
+            continue;
+        }
+        let value = match key.as_str() {
+            "properties" | "patternProperties" => map_values(value, &child, warnings),
+            "items" | "additionalProperties" | "not" => {
+                openapi_to_internal(value, &child, warnings)
+            }
+            "allOf" | "anyOf" | "oneOf" => map_elements(value, &child, warnings),
+            "example" => {
+                converted.insert("examples".into(), Value::Array(vec![value]));
+                continue;
+            }
+            _ => value,
+        };
+        converted.insert(key, value);
+    }
+
+    convert_format(&mut converted, pointer, warnings);
+
+    if let Some(Value::Bool(true)) = nullable {
+        match converted.remove("type") {
+            Some(Value::String(name)) => {
+                converted.insert("type".into(), json!([name, "null"]));
+            }
+            other => {
+                if let Some(other) = other {
+                    converted.insert("type".into(), other);
+                }
+                warn(
+                    warnings,
+                    pointer,
+                    "nullable",
+                    "has no effect without a single `type`".into(),
+                );
+            }
+        }
+    }
+
+    Value::Object(converted)
+}
+
+/// OpenAPI formats on numbers (`int32`, `double`, ...) are annotations as far as
+/// validation goes. String formats this crate doesn't know are kept but not checked.
+fn convert_format(
+    object: &mut Map<String, Value>,
+    pointer: &str,
+    warnings: &mut Vec<DialectWarning>,
+) {
+    let is_string = object.get("type").and_then(Value::as_str) == Some("string");
+    let known = match object.get("format") {
+        None => return,
+        Some(Value::String(format)) => !matches!(Format::from(format.as_str()), Format::Other(_)),
+        Some(_) => false,
+    };
+    if !is_string {
+        object.remove("format");
+    } else if !known {
+        warn(
+            warnings,
+            pointer,
+            "format",
+            format!("format {} is not enforced by this validator", object["format"]),
+        );
+    }
+}
+
+fn map_values(value: Value, pointer: &str, warnings: &mut Vec<DialectWarning>) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(k, v)| {
+                    let child = format!("{}/{}", pointer, k);
+                    (k, openapi_to_internal(v, &child, warnings))
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn map_elements(value: Value, pointer: &str, warnings: &mut Vec<DialectWarning>) -> Value {
+    match value {
+        Value::Array(elements) => Value::Array(
+            elements
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| openapi_to_internal(v, &format!("{}/{}", pointer, i), warnings))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use super::*;
+    use errors::Error;
+
+    /// The `Pet` component of the OpenAPI 3.0 petstore example.
+    fn pet_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": {"type": "integer", "format": "int64"},
+                "name": {"type": "string", "example": "doggie"},
+                "tag": {"type": "string", "nullable": true},
+                "status": {
+                    "type": "string",
+                    "format": "pet-status",
+                    "description": "pet status in the store"
+                }
+            },
+            "discriminator": {"propertyName": "petType"},
+            "xml": {"name": "Pet"}
+        })
+    }
+
+    #[test]
+    fn openapi_pet() {
+        let (schema, warnings) =
+            Schema::from_value_with_dialect(pet_schema(), Dialect::OpenApi30).unwrap();
+        let mut keywords: Vec<_> = warnings
+            .iter()
+            .map(|w| (w.keyword.as_str(), w.pointer.as_str()))
+            .collect();
+        keywords.sort();
+        assert_eq!(
+            keywords,
+            vec![
+                ("discriminator", "/"),
+                ("format", "/properties/status"),
+                ("xml", "/"),
+            ]
+        );
+
+        let valid = json!({"id": 1, "name": "doggie", "tag": null, "status": "sold"});
+        schema.validate(&valid).unwrap();
+        let valid = json!({"id": 1, "name": "doggie", "tag": "good", "status": "sold"});
+        schema.validate(&valid).unwrap();
+
+        let invalid = json!({"id": "1", "name": null, "tag": 5, "status": "sold"});
+        let errors = schema.validate(&invalid).unwrap_err().0;
+        assert_eq!(errors.len(), 3);
+    }
+
+    #[test]
+    fn openapi_round_trip() {
+        let openapi = json!({
+            "type": "object",
+            "required": ["id"],
+            "additionalProperties": true,
+            "properties": {
+                "id": {"type": "integer", "minimum": 1.0, "example": 7, "deprecated": true},
+                "price": {
+                    "type": "number",
+                    "minimum": 0.0,
+                    "exclusiveMinimum": true,
+                    "nullable": true
+                },
+                "tags": {
+                    "type": "array",
+                    "items": {"type": "string", "maxLength": 10},
+                    "uniqueItems": true
+                }
+            }
+        });
+        let schema = Schema::from_openapi30(openapi.clone()).unwrap();
+        assert_eq!(schema.to_openapi30().unwrap(), openapi);
+
+        let json_schema = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            json_schema["properties"]["price"]["type"],
+            json!(["number", "null"])
+        );
+        let schema: Schema = serde_json::from_value(json_schema.clone()).unwrap();
+        let back = Schema::from_openapi30(schema.to_openapi30().unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json_schema);
+    }
+
+    #[test]
+    fn openapi_unrepresentable() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "row": {"type": "array", "items": [{"type": "string"}]}
+            }
+        }))
+        .unwrap();
+        match *schema.to_openapi30().unwrap_err().kind() {
+            ErrorKind::UnsupportedInDialect {
+                ref keyword,
+                ref pointer,
+                ..
+            } => {
+                assert_eq!(keyword, "items");
+                assert_eq!(pointer, "/properties/row");
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let openapi = json!({"type": "object", "patternProperties": {"^x-": {}}});
+        assert!(Schema::from_openapi30(openapi).is_err());
+    }
+
+    #[test]
+    fn openapi_keywords_rejected_in_json_schema() {
+        assert!(Schema::from_value_with_dialect(
+            pet_schema()["properties"]["tag"].clone(),
+            Dialect::JsonSchema
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn small_errors() {
+        // clippy's `result_large_err` rejects functions like
+        // `from_value_with_dialect` once `Error` grows past 128 bytes, and the
+        // `preserve_order` feature makes the `Value`s in errors larger
+        assert!(size_of::<Error>() <= 128);
+    }
+}
@@ -0,0 +1,688 @@
+use serde_json::Value;
+use url::percent_encoding::percent_decode;
+
+use array::{AdditionalItems, ArraySchema, Items};
+use combinator::CombinatorSchema;
+use ids::SubschemaIds;
+use number::Range;
+use object::{AdditionalProperties, KeyOrder, ObjectSchema};
+use schema::Schema;
+use util::pointer_token;
+
+/// Options for `generate_markdown`.
+#[derive(Clone, Debug)]
+pub struct DocOptions {
+    /// The heading of the document. Without one, the `title` of the schema is
+    /// used, or `Schema` if it has none.
+    pub title: Option<String>,
+    /// How many levels of properties, items and options get a section of their
+    /// own. Deeper schemas are only listed in the table of their parent.
+    pub max_depth: usize,
+    /// Whether to show the values of `examples`.
+    pub examples: bool,
+    /// Whether to show the identifiers of the sections' schemas from
+    /// `Schema::assign_ids`, for linking to them from other tools.
+    pub ids: bool,
+}
+
+impl Default for DocOptions {
+    fn default() -> DocOptions {
+        DocOptions {
+            title: None,
+            max_depth: 4,
+            examples: true,
+            ids: false,
+        }
+    }
+}
+
+/// A row of the table listing the properties, items or options of a schema.
+struct Entry<'s> {
+    /// The first column, like the name of a property.
+    label: String,
+    schema: &'s Schema,
+    pointer: String,
+    /// The heading of the section of the entry.
+    heading: String,
+    /// The name of the value in the headings of nested sections, like
+    /// `address.street`.
+    path: String,
+    /// Whether the property is required, `None` for items and options.
+    required: Option<bool>,
+}
+
+struct Writer<'a> {
+    root: &'a Schema,
+    options: &'a DocOptions,
+    /// The identifiers to show, empty unless `DocOptions::ids` is set.
+    ids: SubschemaIds,
+    out: String,
+}
+
+impl<'a> Writer<'a> {
+    /// Renders the schema of `entry` as a section with a heading of `level`,
+    /// followed by the sections of its properties, items and options.
+    fn section(&mut self, entry: &Entry, level: usize, depth: usize) {
+        let schema = match *entry.schema {
+            Schema::Shared(ref s) => &**s,
+            ref schema => schema,
+        };
+        let pointer = entry.pointer.as_str();
+        self.out.push_str(&format!(
+            "{} <a id=\"{}\"></a>{}\n\n",
+            "#".repeat(level.min(6)),
+            anchor(pointer),
+            entry.heading
+        ));
+        if let Some(description) = schema.description() {
+            self.out.push_str(&format!("{}\n\n", description.trim()));
+        }
+
+        let mut facts = vec![format!("**Type:** {}", self.type_label(schema))];
+        if let Some(id) = self.ids.get(pointer) {
+            facts.push(format!("**Id:** `{}`", id));
+        }
+        if let Some(required) = entry.required {
+            facts.push(format!("**Required:** {}", yes_no(required)));
+        }
+        if let Schema::String(ref s) = *schema {
+            if let Some(ref format) = s.format {
+                facts.push(format!("**Format:** `{}`", format.as_str()));
+            }
+        }
+        let constraints = constraints(schema);
+        if !constraints.is_empty() {
+            facts.push(format!("**Constraints:** {}", constraints.join(", ")));
+        }
+        match schema.allowed_values() {
+            Some(("const", values)) => facts.push(format!("**Value:** {}", codes(&values))),
+            Some((_, values)) => facts.push(format!("**Allowed values:** {}", codes(&values))),
+            None => {}
+        }
+        if let Some(default) = schema.default_value(self.root) {
+            facts.push(format!("**Default:** `{}`", default));
+        }
+        let examples: Vec<_> = schema.test_vectors().0.iter().collect();
+        if self.options.examples && !examples.is_empty() {
+            facts.push(format!("**Examples:** {}", codes(&examples)));
+        }
+        if schema.is_deprecated() {
+            facts.push("**Deprecated:** yes".into());
+        }
+        match *schema {
+            Schema::Object(ref s) => match s.additional_properties {
+                Some(AdditionalProperties::Allowed(allowed)) => facts.push(format!(
+                    "**Additional properties:** {}",
+                    allowed_label(allowed)
+                )),
+                // listed with the properties
+                Some(AdditionalProperties::Schema(_)) | None => {}
+            },
+            Schema::Array(ref s) => match s.additional_items {
+                Some(AdditionalItems::Allowed(allowed)) => {
+                    facts.push(format!("**Additional items:** {}", allowed_label(allowed)))
+                }
+                Some(AdditionalItems::Schema(ref additional)) => facts.push(format!(
+                    "**Additional items:** {}",
+                    self.type_label(additional)
+                )),
+                None => {}
+            },
+            Schema::Combinator(ref s) => {
+                if let Some(ref not) = s.not {
+                    facts.push(format!("**Must not be:** {}", self.type_label(not)));
+                }
+            }
+            _ => {}
+        }
+        for fact in facts {
+            self.out.push_str(&format!("- {}\n", fact));
+        }
+        self.out.push('\n');
+
+        let path = entry.path.as_str();
+        match *schema {
+            Schema::Object(ref s) => self.object(s, pointer, path, level, depth),
+            Schema::Array(ref s) => self.array(s, pointer, path, level, depth),
+            Schema::Combinator(ref s) => self.combinator(s, pointer, path, level, depth),
+            _ => {}
+        }
+    }
+
+    fn object(&mut self, s: &ObjectSchema, pointer: &str, path: &str, level: usize, depth: usize) {
+        let required: Vec<_> = s.required_properties().collect();
+        let mut entries = vec![];
+        for (key, schema) in s.properties.iter().flatten() {
+            let path = join(path, key);
+            entries.push(Entry {
+                label: format!("`{}`", key),
+                schema,
+                pointer: format!("{}/properties/{}", pointer, pointer_token(key)),
+                heading: format!("`{}`", path),
+                path,
+                required: Some(required.contains(&key)),
+            });
+        }
+        for (pattern, schema) in s.pattern_properties.iter().flatten() {
+            let path = join(path, &format!("/{}/", pattern));
+            entries.push(Entry {
+                label: format!("`/{}/`", pattern),
+                schema,
+                pointer: format!("{}/patternProperties/{}", pointer, pointer_token(pattern)),
+                heading: format!("`{}`", path),
+                path,
+                required: Some(false),
+            });
+        }
+        if let Some(schema) = s.additional_schema() {
+            let path = join(path, "*");
+            entries.push(Entry {
+                label: "other properties".into(),
+                schema,
+                pointer: format!("{}/additionalProperties", pointer),
+                heading: format!("`{}`", path),
+                path,
+                required: Some(false),
+            });
+        }
+        self.table("Property", entries, level, depth);
+    }
+
+    fn array(&mut self, s: &ArraySchema, pointer: &str, path: &str, level: usize, depth: usize) {
+        let mut entries = vec![];
+        for (i, schema) in s.prefix_items.iter().flatten().enumerate() {
+            entries.push(item_entry(schema, pointer, "prefixItems", path, i));
+        }
+        if let Some(Items::Tuple(ref schemas)) = s.items {
+            for (i, schema) in schemas.iter().enumerate() {
+                entries.push(item_entry(schema, pointer, "items", path, i));
+            }
+        }
+        self.table("Item", entries, level, depth);
+        // the type of the items is part of the type of the array, so they only
+        // get a section
+        if let Some(Items::List(ref schema)) = s.items {
+            let path = format!("{}[]", path);
+            let entry = Entry {
+                label: String::new(),
+                schema,
+                pointer: format!("{}/items", pointer),
+                heading: format!("`{}`", path),
+                path,
+                required: None,
+            };
+            self.nested(vec![entry], level, depth);
+        }
+    }
+
+    fn combinator(
+        &mut self,
+        s: &CombinatorSchema,
+        pointer: &str,
+        path: &str,
+        level: usize,
+        depth: usize,
+    ) {
+        let lists = [
+            ("All of", "allOf", &s.all_of),
+            ("Any of", "anyOf", &s.any_of),
+            ("One of", "oneOf", &s.one_of),
+        ];
+        for &(title, keyword, schemas) in &lists {
+            let schemas = match *schemas {
+                Some(ref schemas) => schemas,
+                None => continue,
+            };
+            self.out.push_str(&format!("{}:\n\n", title));
+            let entries = schemas
+                .iter()
+                .enumerate()
+                .map(|(i, schema)| {
+                    let heading = if path.is_empty() {
+                        format!("Option {}", i + 1)
+                    } else {
+                        format!("`{}` option {}", path, i + 1)
+                    };
+                    Entry {
+                        label: (i + 1).to_string(),
+                        schema,
+                        pointer: format!("{}/{}/{}", pointer, keyword, i),
+                        heading,
+                        path: path.into(),
+                        required: None,
+                    }
+                })
+                .collect();
+            self.table("Option", entries, level, depth);
+        }
+    }
+
+    /// Renders a table of `entries`, followed by the sections of the entries
+    /// with more to show than the table does.
+    fn table(&mut self, column: &str, entries: Vec<Entry>, level: usize, depth: usize) {
+        if entries.is_empty() {
+            return;
+        }
+        let with_required = entries.iter().any(|e| e.required.is_some());
+        if with_required {
+            self.out.push_str(&format!(
+                "| {} | Type | Required | Description |\n| --- | --- | --- | --- |\n",
+                column
+            ));
+        } else {
+            self.out.push_str(&format!(
+                "| {} | Type | Description |\n| --- | --- | --- |\n",
+                column
+            ));
+        }
+        let nests = depth < self.options.max_depth;
+        for entry in &entries {
+            let label = if nests && has_details(entry.schema) {
+                format!("[{}](#{})", entry.label, anchor(&entry.pointer))
+            } else {
+                entry.label.clone()
+            };
+            let description = entry
+                .schema
+                .description()
+                .or_else(|| self.resolve(entry.schema).and_then(Schema::description))
+                .unwrap_or("");
+            let required = match entry.required {
+                Some(required) => format!(" {} |", yes_no(required)),
+                None => String::new(),
+            };
+            self.out.push_str(&format!(
+                "| {} | {} |{} {} |\n",
+                label,
+                cell(&self.type_label(entry.schema)),
+                required,
+                cell(description)
+            ));
+        }
+        self.out.push('\n');
+        self.nested(entries, level, depth);
+    }
+
+    /// Renders the sections of the `entries` with more to show than their type,
+    /// unless they're nested too deep.
+    fn nested(&mut self, entries: Vec<Entry>, level: usize, depth: usize) {
+        let detailed: Vec<_> = entries
+            .into_iter()
+            .filter(|e| has_details(e.schema))
+            .collect();
+        if detailed.is_empty() {
+            return;
+        }
+        if depth >= self.options.max_depth {
+            self.out.push_str(&format!(
+                "Schemas nested deeper than {} are not shown.\n\n",
+                count(self.options.max_depth, "level")
+            ));
+            return;
+        }
+        for entry in detailed {
+            self.section(&entry, level + 1, depth + 1);
+        }
+    }
+
+    /// The type of values `schema` accepts, linking to the section of the
+    /// schema a reference points to.
+    fn type_label(&self, schema: &Schema) -> String {
+        let label = match *schema {
+            Schema::Reference(ref s) => return self.link(&s.reference),
+            Schema::Shared(ref s) => return self.type_label(s),
+            Schema::Array(ref s) => match s.items {
+                Some(Items::List(ref items)) => format!("`array` of {}", self.type_label(items)),
+                _ => "`array`".into(),
+            },
+            Schema::Combinator(ref s) => {
+                let keyword = if s.one_of.is_some() {
+                    "one of"
+                } else if s.any_of.is_some() {
+                    "any of"
+                } else if s.all_of.is_some() {
+                    "all of"
+                } else {
+                    match schema.allowed_values() {
+                        Some((keyword, _)) => keyword,
+                        None => "any",
+                    }
+                };
+                keyword.into()
+            }
+            _ => match schema.type_name() {
+                Some(name) => format!("`{}`", name),
+                None => "any".into(),
+            },
+        };
+        if schema.is_nullable() {
+            format!("{} or `null`", label)
+        } else {
+            label
+        }
+    }
+
+    /// A link to the section of the subschema `reference` points to, or the
+    /// reference itself if it points to another document.
+    fn link(&self, reference: &str) -> String {
+        let pointer = match reference.find('#') {
+            Some(0) => percent_decode(&reference.as_bytes()[1..]).decode_utf8_lossy(),
+            _ => return format!("`{}`", reference),
+        };
+        if self.root.pointer(&pointer).is_none() {
+            return format!("`{}`", reference);
+        }
+        let name = match pointer.rsplit('/').next() {
+            Some(token) if !token.is_empty() => token.replace("~1", "/").replace("~0", "~"),
+            _ => self.title(),
+        };
+        format!("[`{}`](#{})", name, anchor(&pointer))
+    }
+
+    /// The schema `schema` points to, if it's a reference.
+    fn resolve(&self, schema: &Schema) -> Option<&'a Schema> {
+        match *schema {
+            Schema::Reference(ref s) => s.resolve(self.root),
+            Schema::Shared(ref s) => self.resolve(s),
+            _ => None,
+        }
+    }
+
+    fn title(&self) -> String {
+        self.options
+            .title
+            .clone()
+            .or_else(|| self.root.title().map(String::from))
+            .unwrap_or_else(|| "Schema".into())
+    }
+}
+
+fn item_entry<'s>(
+    schema: &'s Schema,
+    pointer: &str,
+    keyword: &str,
+    path: &str,
+    index: usize,
+) -> Entry<'s> {
+    let path = format!("{}[{}]", path, index);
+    Entry {
+        label: index.to_string(),
+        schema,
+        pointer: format!("{}/{}/{}", pointer, keyword, index),
+        heading: format!("`{}`", path),
+        path,
+        required: None,
+    }
+}
+
+/// Whether `schema` has more to show than its type and description, so it
+/// gets a section. References never do, they link to the section of the
+/// schema they point to.
+fn has_details(schema: &Schema) -> bool {
+    match *schema {
+        Schema::Reference(_) | Schema::Empty(_) | Schema::Custom(_) => return false,
+        Schema::Shared(ref s) => return has_details(s),
+        Schema::Object(ref s) if s.properties.is_some() || s.pattern_properties.is_some() => {
+            return true
+        }
+        Schema::Array(ref s) => match s.items {
+            Some(Items::List(ref items)) if has_details(items) => return true,
+            Some(Items::Tuple(_)) => return true,
+            _ if s.prefix_items.is_some() => return true,
+            _ => {}
+        },
+        Schema::Combinator(_) => return true,
+        _ => {}
+    }
+    let format = match *schema {
+        Schema::String(ref s) => s.format.is_some(),
+        _ => false,
+    };
+    format
+        || !constraints(schema).is_empty()
+        || schema.allowed_values().is_some()
+        || !schema.examples().is_empty()
+        || schema.is_deprecated()
+}
+
+/// The constraints of the type specific keywords, like `at least 1 character`.
+fn constraints(schema: &Schema) -> Vec<String> {
+    let mut constraints = vec![];
+    match *schema {
+        Schema::String(ref s) => {
+            if let Some(min) = s.min_length {
+                constraints.push(format!("at least {}", count(min, "character")));
+            }
+            if let Some(max) = s.max_length {
+                constraints.push(format!("at most {}", count(max, "character")));
+            }
+            if let Some(ref pattern) = s.pattern {
+                constraints.push(format!("matches `{}`", pattern));
+            }
+            let bounds = [
+                ("from", &s.format_minimum),
+                ("after", &s.format_exclusive_minimum),
+                ("up to", &s.format_maximum),
+                ("before", &s.format_exclusive_maximum),
+            ];
+            for &(relation, bound) in &bounds {
+                if let Some(ref bound) = *bound {
+                    constraints.push(format!("{} `{}`", relation, bound));
+                }
+            }
+        }
+        Schema::Number(ref s) => constraints = range_constraints(&s.range()),
+        Schema::Integer(ref s) => constraints = range_constraints(&s.range()),
+        Schema::Array(ref s) => {
+            if let Some(min) = s.min_items {
+                constraints.push(format!("at least {}", count(min, "item")));
+            }
+            if let Some(max) = s.max_items {
+                constraints.push(format!("at most {}", count(max, "item")));
+            }
+            if s.unique_items == Some(true) {
+                constraints.push("unique items".into());
+            }
+            if let Some(ref pointer) = s.unique_by {
+                constraints.push(format!("items unique by `{}`", pointer));
+            }
+        }
+        Schema::Object(ref s) => {
+            if let Some(min) = s.min_properties {
+                constraints.push(format!("at least {}", count(min, "property")));
+            }
+            if let Some(max) = s.max_properties {
+                constraints.push(format!("at most {}", count(max, "property")));
+            }
+            match s.ordered_keys {
+                Some(KeyOrder::Asc) => constraints.push("sorted keys".into()),
+                Some(KeyOrder::Schema) => constraints.push("keys in declared order".into()),
+                None => {}
+            }
+        }
+        Schema::Shared(ref s) => return self::constraints(s),
+        _ => {}
+    }
+    constraints
+}
+
+fn range_constraints(range: &Range) -> Vec<String> {
+    let mut constraints = vec![];
+    match range.minimum {
+        Some(min) if range.exclusive_minimum => constraints.push(format!("greater than {}", min)),
+        Some(min) => constraints.push(format!("at least {}", min)),
+        None => {}
+    }
+    match range.maximum {
+        Some(max) if range.exclusive_maximum => constraints.push(format!("less than {}", max)),
+        Some(max) => constraints.push(format!("at most {}", max)),
+        None => {}
+    }
+    if let Some(multiple_of) = range.multiple_of {
+        constraints.push(format!("a multiple of {}", multiple_of));
+    }
+    constraints
+}
+
+fn count(n: usize, noun: &str) -> String {
+    match (n, noun) {
+        (1, _) => format!("1 {}", noun),
+        (_, "property") => format!("{} properties", n),
+        _ => format!("{} {}s", n, noun),
+    }
+}
+
+fn codes(values: &[&Value]) -> String {
+    values
+        .iter()
+        .map(|v| format!("`{}`", v))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn yes_no(yes: bool) -> &'static str {
+    if yes {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn allowed_label(allowed: bool) -> &'static str {
+    if allowed {
+        "allowed"
+    } else {
+        "not allowed"
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.into()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Escapes `text` for a cell of a table.
+fn cell(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', " ")
+}
+
+/// The id of the section of the subschema at `pointer`. `$defs` and
+/// `definitions` share ids, so references link to the same section whichever
+/// keyword they use.
+fn anchor(pointer: &str) -> String {
+    let pointer = pointer.replace("/$defs/", "/definitions/");
+    let mut anchor = String::from("schema");
+    for c in pointer.chars() {
+        anchor.push(if c.is_alphanumeric() || c == '_' {
+            c
+        } else {
+            '-'
+        });
+    }
+    anchor
+}
+
+/// Renders `schema` as Markdown documentation, like the sections of a
+/// reference manual: one section for the schema with a table of its
+/// properties, items or options, followed by sections for the entries with
+/// constraints, formats or nested schemas of their own, up to
+/// `DocOptions::max_depth` levels deep. The `definitions` of the schema get a
+/// section each, which references link to instead of repeating them, so
+/// recursive schemas are rendered once.
+pub fn generate_markdown(schema: &Schema, options: &DocOptions) -> String {
+    let mut writer = Writer {
+        root: schema,
+        options,
+        ids: if options.ids {
+            schema.assign_ids()
+        } else {
+            SubschemaIds::new()
+        },
+        out: String::new(),
+    };
+    let root = Entry {
+        label: String::new(),
+        schema,
+        pointer: String::new(),
+        heading: writer.title(),
+        path: String::new(),
+        required: None,
+    };
+    writer.section(&root, 1, 0);
+    let definitions = schema.definitions().into_iter().flatten();
+    for (i, (name, definition)) in definitions.enumerate() {
+        if i == 0 {
+            writer.out.push_str("## Definitions\n\n");
+        }
+        let entry = Entry {
+            label: String::new(),
+            schema: definition,
+            pointer: format!("/definitions/{}", pointer_token(name)),
+            heading: format!("`{}`", name),
+            path: name.clone(),
+            required: None,
+        };
+        writer.section(&entry, 3, 1);
+    }
+    let trimmed = writer.out.trim_end().len();
+    writer.out.truncate(trimmed);
+    writer.out.push('\n');
+    writer.out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Read;
+
+    use serde_json;
+
+    use super::*;
+
+    #[test]
+    fn catalog() {
+        let schema: Schema =
+            serde_json::from_reader(File::open("data/catalog.json").unwrap()).unwrap();
+        let markdown = generate_markdown(&schema, &DocOptions::default());
+        let mut expected = String::new();
+        File::open("data/catalog.md")
+            .unwrap()
+            .read_to_string(&mut expected)
+            .unwrap();
+        // `catalog.md` lists the properties sorted, with `preserve_order` they're
+        // in the order of `catalog.json`
+        if cfg!(feature = "preserve_order") {
+            let mut lines: Vec<_> = markdown.lines().collect();
+            let mut expected: Vec<_> = expected.lines().collect();
+            lines.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(lines, expected);
+        } else {
+            assert_eq!(markdown, expected);
+        }
+
+        let options = DocOptions {
+            title: Some("Shop".into()),
+            max_depth: 1,
+            examples: false,
+            ids: false,
+        };
+        let markdown = generate_markdown(&schema, &options);
+        assert!(markdown.starts_with("# <a id=\"schema\"></a>Shop\n"));
+        assert!(markdown.contains("Schemas nested deeper than 1 level are not shown."));
+        assert!(!markdown.contains("#### "));
+        assert!(!markdown.contains("**Examples:**"));
+        assert!(!markdown.contains("**Id:**"));
+
+        let options = DocOptions {
+            ids: true,
+            ..DocOptions::default()
+        };
+        let markdown = generate_markdown(&schema, &options);
+        let id = format!("- **Type:** `object`\n- **Id:** `{}`\n", schema.assign_ids()[""]);
+        assert!(markdown.contains(&id), "{}", markdown);
+    }
+}
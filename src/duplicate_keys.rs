@@ -0,0 +1,286 @@
+use std::fmt;
+
+use serde_json::{self, Value};
+
+use errors::{ErrorKind, Result};
+use schema::Schema;
+use util::{pointer_token, strip_bom};
+
+/// An object key that occurs more than once in the same JSON object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// JSON pointer to the repeated member.
+    pub pointer: String,
+    /// The (unescaped) key.
+    pub key: String,
+    /// Byte offset of the repeated key in the input.
+    pub offset: usize,
+    /// 1-based line of the repeated key.
+    pub line: usize,
+    /// 1-based column of the repeated key.
+    pub column: usize,
+}
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "duplicate key `{}` at `{}` (line {}, column {})",
+            self.key, self.pointer, self.line, self.column
+        )
+    }
+}
+
+/// Finds keys that occur more than once within the same object. `serde_json`
+/// keeps the last occurrence, so such documents may be read differently by
+/// other parsers. Fails if `json` isn't well-formed JSON.
+pub fn check_duplicate_keys(json: &str) -> Result<Vec<DuplicateKey>> {
+    Ok(scan(json)?.duplicates)
+}
+
+/// A key that occurs more than once in the same object, with the values of all
+/// its occurrences.
+#[derive(Debug)]
+pub(crate) struct RepeatedMember {
+    /// JSON pointer to the member.
+    pub pointer: String,
+    /// The (unescaped) key.
+    pub key: String,
+    /// The values in document order, `serde_json` keeps the last.
+    pub values: Vec<Value>,
+}
+
+/// The keys of `json` that occur more than once in the same object, in
+/// document order of their objects.
+pub(crate) fn repeated_members(json: &str) -> Result<Vec<RepeatedMember>> {
+    Ok(scan(json)?.repeated)
+}
+
+fn scan(json: &str) -> Result<Scanner<'_>> {
+    serde_json::from_str::<Value>(json)?;
+    let mut scanner = Scanner {
+        input: json.as_bytes(),
+        text: json,
+        pos: 0,
+        duplicates: vec![],
+        repeated: vec![],
+    };
+    scanner.value(&mut String::new());
+    Ok(scanner)
+}
+
+impl Schema {
+    /// Parses `json` and validates it, rejecting documents that contain
+    /// duplicate object keys before any validation happens. A leading
+    /// byte-order mark is skipped, like by `Schema::validate_str`.
+    pub fn validate_str_strict(&self, json: &str) -> Result<()> {
+        let json = strip_bom(json);
+        let duplicates = check_duplicate_keys(json)?;
+        if !duplicates.is_empty() {
+            bail!(ErrorKind::DuplicateKeys(duplicates));
+        }
+        let value: Value = serde_json::from_str(json)?;
+        self.validate(&value)?;
+        Ok(())
+    }
+}
+
+/// Walks a document that is known to be well-formed, keeping track of the
+/// keys seen in each object.
+struct Scanner<'a> {
+    input: &'a [u8],
+    text: &'a str,
+    pos: usize,
+    duplicates: Vec<DuplicateKey>,
+    repeated: Vec<RepeatedMember>,
+}
+
+impl<'a> Scanner<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && (self.input[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self, pointer: &mut String) {
+        self.skip_whitespace();
+        match self.input[self.pos] {
+            b'{' => self.object(pointer),
+            b'[' => self.array(pointer),
+            b'"' => {
+                self.string();
+            }
+            _ => {
+                while self.pos < self.input.len() && !b",]} \t\r\n".contains(&self.input[self.pos])
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Skips over a string literal and returns its raw text including quotes.
+    fn string(&mut self) -> &'a str {
+        let start = self.pos;
+        self.pos += 1;
+        while self.input[self.pos] != b'"' {
+            if self.input[self.pos] == b'\\' {
+                self.pos += 1;
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        &self.text[start..self.pos]
+    }
+
+    fn object(&mut self, pointer: &mut String) {
+        // the keys with the raw text of their values
+        let mut members: Vec<(String, &'a str)> = vec![];
+        self.pos += 1;
+        loop {
+            self.skip_whitespace();
+            match self.input[self.pos] {
+                b'}' => break,
+                b',' => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let offset = self.pos;
+            let key: String = serde_json::from_str(self.string()).unwrap_or_default();
+            self.skip_whitespace();
+            // the colon
+            self.pos += 1;
+
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(&pointer_token(&key));
+            if members.iter().any(|(k, _)| *k == key) {
+                self.duplicate(pointer, &key, offset);
+            }
+            let start = self.pos;
+            self.value(pointer);
+            members.push((key, &self.text[start..self.pos]));
+            pointer.truncate(len);
+        }
+        self.pos += 1;
+
+        for (i, (key, _)) in members.iter().enumerate() {
+            let first = members[..i].iter().all(|(k, _)| k != key);
+            let values: Vec<Value> = members[i..]
+                .iter()
+                .filter(|(k, _)| k == key)
+                .filter_map(|(_, raw)| serde_json::from_str(raw).ok())
+                .collect();
+            if first && values.len() > 1 {
+                self.repeated.push(RepeatedMember {
+                    pointer: format!("{}/{}", pointer, pointer_token(key)),
+                    key: key.clone(),
+                    values,
+                });
+            }
+        }
+    }
+
+    fn array(&mut self, pointer: &mut String) {
+        self.pos += 1;
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.input[self.pos] {
+                b']' => break,
+                b',' => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(&index.to_string());
+            self.value(pointer);
+            pointer.truncate(len);
+            index += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn duplicate(&mut self, pointer: &str, key: &str, offset: usize) {
+        let before = &self.text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+        self.duplicates.push(DuplicateKey {
+            pointer: pointer.into(),
+            key: key.into(),
+            offset,
+            line,
+            column,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object::ObjectSchemaBuilder;
+
+    #[test]
+    fn root_duplicate() {
+        let duplicates = check_duplicate_keys(r#"{"amount": 1, "amount": 9999}"#).unwrap();
+        assert_eq!(
+            duplicates,
+            vec![DuplicateKey {
+                pointer: "/amount".into(),
+                key: "amount".into(),
+                offset: 14,
+                line: 1,
+                column: 15,
+            }]
+        );
+    }
+
+    #[test]
+    fn nested_duplicates() {
+        let json = "{\"a\": {\"b~/\": 1,\n  \"b~\\/\": [1, {\"c\": 2}],\n  \"\\u0062~/\": 3}}";
+        let duplicates = check_duplicate_keys(json).unwrap();
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].pointer, "/a/b~0~1");
+        assert_eq!((duplicates[0].line, duplicates[0].column), (2, 3));
+        assert_eq!((duplicates[1].line, duplicates[1].column), (3, 3));
+    }
+
+    #[test]
+    fn duplicates_in_array() {
+        let json = r#"[{"id": 1}, {"id": 2, "name": "x", "id": 3}, {"id": 4, "id": 5}]"#;
+        let pointers: Vec<_> = check_duplicate_keys(json)
+            .unwrap()
+            .into_iter()
+            .map(|d| d.pointer)
+            .collect();
+        assert_eq!(pointers, vec!["/1/id", "/2/id"]);
+    }
+
+    #[test]
+    fn no_duplicates() {
+        let json = r#"{"a": {"a": "{\"a\": 1, \"a\": 2}"}, "b": [{"a": 1}, {"a": 2}]}"#;
+        assert!(check_duplicate_keys(json).unwrap().is_empty());
+        assert!(check_duplicate_keys(r#"{"a": 1,"#).is_err());
+    }
+
+    #[test]
+    fn strict_validation() {
+        let schema = ObjectSchemaBuilder::default()
+            .required(vec!["amount".into()])
+            .build();
+        schema.validate_str_strict(r#"{"amount": 1}"#).unwrap();
+        match *schema
+            .validate_str_strict(r#"{"amount": 1, "amount": 9999}"#)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::DuplicateKeys(ref keys) => assert_eq!(keys[0].key, "amount"),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
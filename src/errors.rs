@@ -1,8 +1,10 @@
-use std::{error, fmt};
+use std::{error, fmt, ptr, slice};
 
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use serde_json::Value;
 
-use util::JsonType;
+use render::{EnglishRenderer, ErrorPaths, MessageRenderer};
+use types::JsonType;
 
 pub use self::generated::*;
 
@@ -13,24 +15,236 @@ pub struct ValidationError<'json> {
     pub reason: ErrorKind,
     /// Pointer to the relevant JSON node.
     pub node: &'json Value,
+    /// JSON pointer to `node` within the validated document.
+    pub instance_path: String,
+    /// JSON pointer to the subschema that reported the error, relative to the root schema.
+    pub schema_path: String,
+    /// A short excerpt of `node`, see `render::preview`. Left out if
+    /// `ValidateOptions::value_preview` is `None`.
+    pub value_preview: Option<String>,
+    /// The similar errors this error stands for, see
+    /// `ValidateOptions::fold_similar`.
+    pub folded: Option<FoldedErrors>,
+    located: bool,
+}
+
+/// Errors left out of a report because another error stands for them: errors
+/// reported by the same subschema, with the same code, about values that only
+/// differ in their array indices. See `ValidateOptions::fold_similar`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldedErrors {
+    /// The number of errors left out.
+    pub count: usize,
+    /// The instance paths of the first few of them.
+    pub sample_paths: Vec<String>,
+}
+
+/// Writes `n` with commas between groups of three digits, like `9,997`.
+fn with_separators(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Lists where the folded errors are, like ``also at `/items/1`, `/items/2`
+/// and 9,997 more``.
+impl fmt::Display for FoldedErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let paths: Vec<_> = self
+            .sample_paths
+            .iter()
+            .map(|path| format!("`{}`", path))
+            .collect();
+        let rest = self.count - self.sample_paths.len();
+        match (paths.is_empty(), rest) {
+            (true, _) => write!(f, "and {} more like it", with_separators(rest)),
+            (false, 0) => write!(f, "also at {}", paths.join(", ")),
+            (false, _) => write!(
+                f,
+                "also at {} and {} more",
+                paths.join(", "),
+                with_separators(rest)
+            ),
+        }
+    }
 }
 
 impl<'json> ValidationError<'json> {
-    pub(crate) fn type_mismatch(
-        node: &'json Value,
-        expected: JsonType,
-        found: JsonType,
-    ) -> ValidationError<'json> {
+    /// Creates an error about `node`, for custom validators implementing
+    /// `SchemaBase::validate_inner`.
+    ///
+    /// `node` should be the value passed to `validate_inner`: the paths are left
+    /// empty here and filled in by the validator once `validate_inner` returns,
+    /// with the path to that value and to the schema that was validating it. An
+    /// error about a value deeper down, like an element of an array, is reported
+    /// at the path of the array. Errors that already have paths are kept as
+    /// they are.
+    pub fn new(reason: ErrorKind, node: &'json Value) -> ValidationError<'json> {
         ValidationError {
-            reason: ErrorKind::TypeMismatch { expected, found },
-            node: node,
+            reason,
+            node,
+            instance_path: String::new(),
+            schema_path: String::new(),
+            value_preview: None,
+            folded: None,
+            located: false,
+        }
+    }
+
+    /// Creates an `ErrorKind::Custom` error with `message` about `node`, see
+    /// `ValidationError::new`.
+    pub fn custom<M: Into<String>>(message: M, node: &'json Value) -> ValidationError<'json> {
+        ValidationError::new(ErrorKind::Custom(message.into()), node)
+    }
+
+    pub(crate) fn type_mismatch(node: &'json Value, expected: JsonType) -> ValidationError<'json> {
+        let (found, integral) = JsonType::reported(node);
+        let reason = ErrorKind::TypeMismatch {
+            expected,
+            found,
+            integral,
+        };
+        ValidationError::new(reason, node)
+    }
+
+    /// The types `node` was expected to have if this is a type mismatch about
+    /// `value` itself.
+    pub(crate) fn expected_types(&self, value: &Value) -> Option<&[JsonType]> {
+        if !ptr::eq(self.node, value) {
+            return None;
+        }
+        match self.reason {
+            ErrorKind::TypeMismatch { ref expected, .. } => Some(slice::from_ref(expected)),
+            ErrorKind::MultiTypeMismatch { ref expected, .. } => Some(expected),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_located(&self) -> bool {
+        self.located
+    }
+
+    pub(crate) fn locate(&mut self, instance_path: &str, schema_path: &str) {
+        self.instance_path = instance_path.into();
+        self.schema_path = schema_path.into();
+        self.located = true;
+    }
+}
+
+/// A property of an object schema, named in errors about it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Property {
+    /// The name of the property.
+    pub name: String,
+    /// The `title` of the property's schema, if it has one.
+    pub title: Option<String>,
+    /// The value the property has if it's only counted as missing because of
+    /// `ValidateOptions::required_rejects`.
+    pub blank: Option<Blank>,
+    /// JSON pointer to the `required` keyword that requires the property, like
+    /// `/allOf/1/required`.
+    pub required_by: String,
+}
+
+/// Lists `properties` for `ErrorKind::MissingProperties`, each run of
+/// properties followed by the keyword that requires them.
+fn missing_properties(properties: &[Property]) -> String {
+    let mut text = String::new();
+    for (i, property) in properties.iter().enumerate() {
+        if i > 0 {
+            text.push_str(", ");
+        }
+        text.push_str(&property.to_string());
+        let next = properties.get(i + 1);
+        if next.map_or(true, |next| next.required_by != property.required_by) {
+            text.push_str(&format!(" (required by #{})", property.required_by));
         }
     }
+    text
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}`", self.name)?;
+        if let Some(ref title) = self.title {
+            write!(f, " ({})", title)?;
+        }
+        if let Some(blank) = self.blank {
+            write!(f, " (is {}, which counts as missing)", blank)?;
+        }
+        Ok(())
+    }
+}
+
+/// A value of a required property that `ValidateOptions::required_rejects`
+/// counts as missing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blank {
+    /// `null`.
+    Null,
+    /// `""`.
+    EmptyString,
+    /// `[]`.
+    EmptyArray,
+}
+
+impl fmt::Display for Blank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Blank::Null => write!(f, "null"),
+            Blank::EmptyString => write!(f, "an empty string"),
+            Blank::EmptyArray => write!(f, "an empty array"),
+        }
+    }
+}
+
+impl<'json> ValidationError<'json> {
+    /// The message for this error, as `renderer` writes it.
+    pub fn render(&self, renderer: &dyn MessageRenderer) -> String {
+        let paths = ErrorPaths {
+            instance_path: Some(&self.instance_path[..]).filter(|_| self.located),
+            schema_path: Some(&self.schema_path[..]).filter(|_| self.located),
+            node: self.node,
+        };
+        renderer.render(&self.reason, &paths)
+    }
+}
+
+/// Errors are serialized as their code, message, paths, value preview and folded
+/// errors, as in `{"code": "MinLength", "message": "...", "instancePath": "/name", ...}`.
+impl<'json> Serialize for ValidationError<'json> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("ValidationError", 6)?;
+        error.serialize_field("code", &format!("{:?}", self.reason.code()))?;
+        error.serialize_field("message", &self.reason.to_string())?;
+        error.serialize_field("instancePath", &self.instance_path)?;
+        error.serialize_field("schemaPath", &self.schema_path)?;
+        match self.value_preview {
+            Some(ref preview) => error.serialize_field("valuePreview", preview)?,
+            None => error.skip_field("valuePreview")?,
+        }
+        match self.folded {
+            Some(ref folded) => error.serialize_field("folded", folded)?,
+            None => error.skip_field("folded")?,
+        }
+        error.end()
+    }
 }
 
 impl<'json> fmt::Display for ValidationError<'json> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Error at JSON value `{}`: {}", self.node, self.reason)
+        f.write_str(&self.render(&EnglishRenderer))?;
+        if let Some(ref folded) = self.folded {
+            write!(f, " ({})", folded)?;
+        }
+        Ok(())
     }
 }
 
@@ -38,10 +252,45 @@ impl<'json> fmt::Display for ValidationError<'json> {
 #[derive(Debug)]
 pub struct ValidationErrors<'json>(pub Vec<ValidationError<'json>>);
 
+impl<'json> ValidationErrors<'json> {
+    /// Whether any of the errors is of a kind for which `f` returns true.
+    pub fn contains_kind<F: Fn(&ErrorKind) -> bool>(&self, f: F) -> bool {
+        self.0.iter().any(|e| f(&e.reason))
+    }
+
+    /// Whether the required property `name` is reported missing.
+    pub fn contains_missing_property(&self, name: &str) -> bool {
+        self.contains_kind(|kind| match *kind {
+            ErrorKind::MissingProperties(ref properties) => {
+                properties.iter().any(|p| p.name == name)
+            }
+            _ => false,
+        })
+    }
+
+    /// The errors about the value at `instance_path`.
+    pub fn at_path(&self, instance_path: &str) -> Vec<&ValidationError<'json>> {
+        self.0
+            .iter()
+            .filter(|e| e.instance_path == instance_path)
+            .collect()
+    }
+
+    /// The messages for the errors, as `renderer` writes them.
+    pub fn render(&self, renderer: &dyn MessageRenderer) -> Vec<String> {
+        self.0.iter().map(|e| e.render(renderer)).collect()
+    }
+
+    /// The kinds of the errors, in order.
+    pub fn codes(&self) -> Vec<ErrorCode> {
+        self.0.iter().map(|e| e.reason.code()).collect()
+    }
+}
+
 impl<'json> fmt::Display for ValidationErrors<'json> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for error in &self.0 {
-            write!(f, "Error at {}: {}\n", error.node, error.reason)?;
+            writeln!(f, "{}", error)?;
         }
         Ok(())
     }
@@ -59,28 +308,180 @@ impl<'json> From<ValidationErrors<'json>> for Error {
     }
 }
 
+/// The kind of an error without its details, for comparing errors in tests or
+/// counting them by kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ErrorCode {
+    TypeMismatch,
+    MultiTypeMismatch,
+    TupleLengthMismatch,
+    ItemNotAllowed,
+    MaxLength,
+    MinLength,
+    MissingProperties,
+    ArrayItemNotUnique,
+    NotUniqueBy,
+    NumberRange,
+    FormatRange,
+    NotInEnum,
+    ConstMismatch,
+    AnyOfNoMatch,
+    OneOfMatchCount,
+    NotMatched,
+    NotMultipleOf,
+    NonFiniteNumber,
+    PropertyCount,
+    InvalidRegex,
+    InvalidFormat,
+    RegexMismatch,
+    UnknownProperties,
+    UnorderedKeys,
+    CrossFieldMismatch,
+    InvalidOperand,
+    DuplicateKeys,
+    UnknownType,
+    UnresolvedPointer,
+    NotASchema,
+    SwappedSchemaAndInstance,
+    InvalidPointer,
+    MissingId,
+    NoVersions,
+    NotSelfDescribed,
+    UnknownSchema,
+    ReferenceCycle,
+    DepthLimitExceeded,
+    Timeout,
+    MemoryLimit,
+    UnsupportedInDialect,
+    Invalid,
+    Custom,
+    LimitExceeded,
+    Removed,
+    /// Errors that aren't about the schema or the validated value, like I/O
+    /// errors.
+    Other,
+}
+
+impl ErrorKind {
+    /// The kind of this error without its details.
+    pub fn code(&self) -> ErrorCode {
+        match *self {
+            ErrorKind::TypeMismatch { .. } => ErrorCode::TypeMismatch,
+            ErrorKind::MultiTypeMismatch { .. } => ErrorCode::MultiTypeMismatch,
+            ErrorKind::TupleLengthMismatch { .. } => ErrorCode::TupleLengthMismatch,
+            ErrorKind::ItemNotAllowed(..) => ErrorCode::ItemNotAllowed,
+            ErrorKind::MaxLength { .. } => ErrorCode::MaxLength,
+            ErrorKind::MinLength { .. } => ErrorCode::MinLength,
+            ErrorKind::MissingProperties(..) => ErrorCode::MissingProperties,
+            ErrorKind::ArrayItemNotUnique => ErrorCode::ArrayItemNotUnique,
+            ErrorKind::NotUniqueBy { .. } => ErrorCode::NotUniqueBy,
+            ErrorKind::NumberRange { .. } => ErrorCode::NumberRange,
+            ErrorKind::FormatRange { .. } => ErrorCode::FormatRange,
+            ErrorKind::NotInEnum { .. } => ErrorCode::NotInEnum,
+            ErrorKind::ConstMismatch { .. } => ErrorCode::ConstMismatch,
+            ErrorKind::AnyOfNoMatch => ErrorCode::AnyOfNoMatch,
+            ErrorKind::OneOfMatchCount { .. } => ErrorCode::OneOfMatchCount,
+            ErrorKind::NotMatched => ErrorCode::NotMatched,
+            ErrorKind::NotMultipleOf { .. } => ErrorCode::NotMultipleOf,
+            ErrorKind::NonFiniteNumber(..) => ErrorCode::NonFiniteNumber,
+            ErrorKind::PropertyCount { .. } => ErrorCode::PropertyCount,
+            ErrorKind::InvalidRegex(..) => ErrorCode::InvalidRegex,
+            ErrorKind::InvalidFormat(..) => ErrorCode::InvalidFormat,
+            ErrorKind::RegexMismatch { .. } => ErrorCode::RegexMismatch,
+            ErrorKind::UnknownProperties(..) => ErrorCode::UnknownProperties,
+            ErrorKind::UnorderedKeys { .. } => ErrorCode::UnorderedKeys,
+            ErrorKind::CrossFieldMismatch(..) => ErrorCode::CrossFieldMismatch,
+            ErrorKind::InvalidOperand { .. } => ErrorCode::InvalidOperand,
+            ErrorKind::DuplicateKeys(..) => ErrorCode::DuplicateKeys,
+            ErrorKind::UnknownType(..) => ErrorCode::UnknownType,
+            ErrorKind::UnresolvedPointer(..) => ErrorCode::UnresolvedPointer,
+            ErrorKind::NotASchema(..) => ErrorCode::NotASchema,
+            ErrorKind::SwappedSchemaAndInstance => ErrorCode::SwappedSchemaAndInstance,
+            ErrorKind::InvalidPointer(..) => ErrorCode::InvalidPointer,
+            ErrorKind::MissingId => ErrorCode::MissingId,
+            ErrorKind::MissingIdInDocument(..) => ErrorCode::MissingId,
+            ErrorKind::NoVersions(..) => ErrorCode::NoVersions,
+            ErrorKind::NotSelfDescribed => ErrorCode::NotSelfDescribed,
+            ErrorKind::UnknownSchema(..) => ErrorCode::UnknownSchema,
+            ErrorKind::ReferenceCycle(..) => ErrorCode::ReferenceCycle,
+            ErrorKind::DepthLimitExceeded => ErrorCode::DepthLimitExceeded,
+            ErrorKind::Timeout { .. } => ErrorCode::Timeout,
+            ErrorKind::MemoryLimit { .. } => ErrorCode::MemoryLimit,
+            ErrorKind::UnsupportedInDialect { .. } => ErrorCode::UnsupportedInDialect,
+            ErrorKind::Invalid(..) => ErrorCode::Invalid,
+            ErrorKind::Custom(..) => ErrorCode::Custom,
+            ErrorKind::LimitExceeded { .. } => ErrorCode::LimitExceeded,
+            ErrorKind::Removed { .. } => ErrorCode::Removed,
+            _ => ErrorCode::Other,
+        }
+    }
+}
+
 #[allow(missing_docs)]
 mod generated {
-    use util::JsonType;
+    use std::io;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use serde_json::Value;
+
+    use types::JsonType;
     use string::Format;
-    use regex::Regex;
+    use super::Property;
+    use dialect::Dialect;
+    use duplicate_keys::DuplicateKey;
+    use object::KeyOrder;
+    use cross_field::Mismatch;
+    use session::RecordedError;
 
     error_chain! {
             foreign_links {
-                Io(::std::io::Error);
                 Serde(::serde_json::Error);
             }
 
             errors {
-                TypeMismatch { expected: JsonType, found: JsonType } {
+                Io { path: PathBuf, source: io::Error } {
+                    description("Error reading or writing a file")
+                    display("{}: {}", path.display(), source)
+                }
+                InvalidJson { path: PathBuf, source: ::serde_json::Error } {
+                    description("File isn't valid JSON")
+                    display("{} isn't valid JSON: {}", path.display(), source)
+                }
+                InvalidSchemaFile { path: PathBuf, source: Box<Error> } {
+                    description("File isn't a valid schema")
+                    display("{} isn't a valid schema: {}", path.display(), source)
+                }
+                TypeMismatch { expected: JsonType, found: JsonType, integral: Option<bool> } {
                     description("Type mismatch")
-                    display("Type mismatch: expected {}, found {}", expected, found)
+                    display("Type mismatch: expected {}, found {}{}", expected, found,
+                        match (expected, integral) {
+                            (&JsonType::Integer, &Some(false)) => " with a fractional part",
+                            _ => "",
+                        })
                 }
-                TupleLengthMismatch { schemas: usize, tuple: usize } {
-                    description("Tuple length mismatch")
+                MultiTypeMismatch { expected: Vec<JsonType>, found: JsonType } {
+                    description("Type mismatch")
+                    display(
+                        "Type mismatch: expected {}, found {}",
+                        expected.iter().map(ToString::to_string).collect::<Vec<_>>().join(" or "),
+                        found)
+                }
+                TupleLengthMismatch {
+                    schemas: usize,
+                    tuple: usize,
+                    first_extra_index: usize,
+                    index: usize
+                } {
+                    description("Array has more items than the tuple allows")
                     display(
-                        "Tuple length mismatch: expected {}, found {}",
-                        schemas, tuple)
+                        "Element {} not allowed: tuple schema defines {} {}",
+                        index, schemas, if *schemas == 1 { "item" } else { "items" })
+                }
+                ItemNotAllowed(index: usize) {
+                    description("Array item is not allowed")
+                    display("Array item {} is not allowed", index)
                 }
                 MaxLength { expected: usize, found: usize } {
                     description("Maximum length exceeded")
@@ -94,23 +495,71 @@ mod generated {
                         "Length mismatch: Expected a minimum of {}, found {}",
                         expected, found)
                 }
-                MissingProperty(prop: String) {
-                    description("Missing object property")
-                    display("Missing object property: `{}`", prop)
+                MissingProperties(properties: Vec<Property>) {
+                    description("Missing object properties")
+                    display("Missing object {}: {}",
+                        if properties.len() == 1 { "property" } else { "properties" },
+                        super::missing_properties(properties))
                 }
                 ArrayItemNotUnique {
                     description("Array items are not unique")
                     display("Array items are not unique")
                 }
+                NotUniqueBy { pointer: String, key: Box<Value>, first: usize, duplicate: usize } {
+                    description("Array items have the same value at the `x-uniqueBy` pointer")
+                    display(
+                        "Items {} and {} have the same value {} at `{}`",
+                        first, duplicate, key, pointer)
+                }
                 NumberRange { bound: f64, value: f64 } {
                     description("Number out of range")
                     display("Number out of range: bound is {}, value is {}", bound, value)
                 }
+                FormatRange { keyword: String, bound: String, value: String } {
+                    description("Date or time out of range")
+                    display(
+                        "Date or time out of range: `{}` is {}, value is {}",
+                        keyword, bound, value)
+                }
+                NotInEnum { allowed: Vec<Value>, suggestion: Option<String> } {
+                    description("Value is not one of the allowed values")
+                    display(
+                        "Value is not one of the allowed values: {}{}",
+                        Value::from(allowed.clone()),
+                        suggestion.as_ref().map_or(String::new(), |s| {
+                            format!(", did you mean {}?", Value::from(s.as_str()))
+                        }))
+                }
+                ConstMismatch { expected: Value } {
+                    description("Value is not equal to the constant")
+                    display("Value is not equal to the constant {}", expected)
+                }
+                AnyOfNoMatch {
+                    description("Value matches none of the anyOf schemas")
+                    display("Value matches none of the anyOf schemas")
+                }
+                OneOfMatchCount { matched: Vec<usize> } {
+                    description("Value doesn't match exactly one of the oneOf schemas")
+                    display("Value must match exactly one of the oneOf schemas, matched {:?}", matched)
+                }
+                NotMatched {
+                    description("Value matches the not schema")
+                    display("Value matches the not schema")
+                }
+                NotMultipleOf { multiple_of: f64, value: f64 } {
+                    description("Number is not a multiple of the given number")
+                    display("Number {} is not a multiple of {}", value, multiple_of)
+                }
+                NonFiniteNumber(number: String) {
+                    description("Number can't be represented as a finite float")
+                    display("Number {} can't be represented as a finite float", number)
+                }
                 PropertyCount { bound: usize, found: usize } {
                     description("Property count out of range")
                     display(
-                        "Property count out of range: bound is {}, value is {}",
-                            bound, found)
+                        "Property count out of range: bound is {}, the object has {} \
+                        (all keys count, including those of patternProperties)",
+                        bound, found)
                 }
                 InvalidRegex(regex: String) {
                     description("Invalid regex")
@@ -120,9 +569,115 @@ mod generated {
                     description("Error parsing with format")
                     display("Error parsing with format: {:?}", format)
                 }
-                RegexMismatch { regex: Regex } {
+                RegexMismatch { pattern: String } {
                     description("Regex did not match")
-                    display("Regex did not match: {}", regex)
+                    display("Regex did not match: {}", pattern)
+                }
+                UnknownProperties(pointers: Vec<String>) {
+                    description("Properties the schema doesn't declare")
+                    display("Properties the schema doesn't declare: {}",
+                        pointers.iter().map(|p| format!("`{}`", p)).collect::<Vec<_>>().join(", "))
+                }
+                UnorderedKeys { key: String, previous: String, order: KeyOrder } {
+                    description("Object keys are out of order")
+                    display("Key `{}` comes after `{}`, {}", key, previous, order)
+                }
+                CrossFieldMismatch(mismatch: Box<Mismatch>) {
+                    description("Values of an object don't compare as required")
+                    display("{}", mismatch)
+                }
+                InvalidOperand { pointer: String, expected: String, found: Option<String> } {
+                    description("Value can't be compared")
+                    display("{}", match *found {
+                        Some(ref found) => format!("`{}` is {}, which isn't a {}", pointer, found, expected),
+                        None => format!("`{}` is missing, it has to be a {}", pointer, expected),
+                    })
+                }
+                DuplicateKeys(keys: Vec<DuplicateKey>) {
+                    description("Duplicate object keys")
+                    display("Duplicate object keys: {}",
+                        keys.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(", "))
+                }
+                UnknownType(name: String) {
+                    description("Unknown JSON type")
+                    display("Unknown JSON type `{}`", name)
+                }
+                UnresolvedPointer(pointer: String) {
+                    description("Pointer doesn't point to a schema")
+                    display("`{}` doesn't point to a schema", pointer)
+                }
+                NotASchema(keys: Vec<String>) {
+                    description("Document doesn't use any schema keyword")
+                    display("Document doesn't use any schema keyword (it has {}), is it an instance?",
+                        keys.iter().map(|k| format!("`{}`", k)).collect::<Vec<_>>().join(", "))
+                }
+                SwappedSchemaAndInstance {
+                    description("Schema and instance seem to be swapped")
+                    display("The schema looks like an instance and the instance looks like a \
+                        schema, were they passed the other way round?")
+                }
+                InvalidPointer(pointer: String) {
+                    description("Pointer doesn't point to a value")
+                    display("`{}` doesn't point to a value", pointer)
+                }
+                MissingId {
+                    description("Schema has no `$id`")
+                    display("Schema has no `$id` to register it under")
+                }
+                MissingIdInDocument(index: usize) {
+                    description("Schema in a document of several schemas has no `$id`")
+                    display("Schema {} of the document has no `$id` to register it under", index)
+                }
+                NoVersions(base_id: String) {
+                    description("No version of the schema is registered")
+                    display("No version of the schema `{}` is registered", base_id)
+                }
+                NotSelfDescribed {
+                    description("Document doesn't name its schema")
+                    display("Document has no `$schema` member naming its schema")
+                }
+                UnknownSchema(id: String) {
+                    description("Schema the document names is unknown")
+                    display("The document names the schema `{}`, which isn't registered", id)
+                }
+                ReferenceCycle(reference: String) {
+                    description("Reference leads back to itself")
+                    display("`{}` leads back to itself without reaching a value", reference)
+                }
+                Timeout { elapsed: Duration } {
+                    description("Validation ran out of time")
+                    display("Validation was aborted after {:?}, the value may have more errors",
+                        elapsed)
+                }
+                MemoryLimit { limit: usize } {
+                    description("Validation ran out of memory")
+                    display("Validation was aborted after holding more than {} bytes, the value \
+                             may have more errors", limit)
+                }
+                DepthLimitExceeded {
+                    description("Schema nesting exceeds the depth limit")
+                    display("Schema nesting exceeds the depth limit")
+                }
+                Invalid(errors: Vec<RecordedError>) {
+                    description("Value doesn't match the schema")
+                    display("Value doesn't match the schema:\n{}",
+                        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"))
+                }
+                UnsupportedInDialect { keyword: String, pointer: String, dialect: Dialect } {
+                    description("Keyword can't be represented in the target dialect")
+                    display("`{}` at `{}` can't be represented in {}", keyword, pointer, dialect)
+                }
+                LimitExceeded { limit: String, pointer: String, reason: String } {
+                    description("Schema exceeds a limit")
+                    display("Schema exceeds `{}` at `{}`: {}", limit, pointer, reason)
+                }
+                Removed { removed_in: String } {
+                    description("Value uses a deprecated schema past its removal date")
+                    display("Value uses a deprecated schema that was removed in {}", removed_in)
+                }
+                Custom(message: String) {
+                    description("Custom validation error")
+                    display("{}", message)
                 }
             }
         }
@@ -0,0 +1,135 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// State of an integrator carried into validation, one value per type, see
+/// `ValidateOptions::extensions`. Custom schemas read it with
+/// `Context::extension`.
+///
+/// The values belong to the options: every validation run with the options
+/// sees the same values, and clones of the options share them. State for a
+/// single run, like a tenant's limits, goes into options made for that run.
+/// Validators only get shared references, so state they change needs a
+/// `Mutex` or atomics.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Creates an empty map.
+    pub fn new() -> Extensions {
+        Extensions::default()
+    }
+
+    /// Stores `value`, returning the value of the same type it replaces.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<Arc<T>> {
+        self.values
+            .insert(TypeId::of::<T>(), Arc::new(value))
+            .and_then(|old| old.downcast().ok())
+    }
+
+    /// The value of type `T`, if one was stored.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Removes the value of type `T`, returning it.
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<Arc<T>> {
+        self.values
+            .remove(&TypeId::of::<T>())
+            .and_then(|old| old.downcast().ok())
+    }
+
+    /// The number of values stored.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether no value is stored.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use errors::ValidationError;
+    use object::ObjectSchemaBuilder;
+    use options::ValidateOptions;
+    use schema::{Context, Schema, SchemaBase};
+
+    /// The largest score a tenant accepts.
+    #[derive(Debug)]
+    struct Threshold(f64);
+
+    /// Rejects numbers above the `Threshold` of the options, if they have one.
+    #[derive(Debug)]
+    struct AtMostThreshold;
+
+    impl SchemaBase for AtMostThreshold {
+        fn validate_inner<'json>(
+            &self,
+            ctx: &Context,
+            value: &'json Value,
+            errors: &mut Vec<ValidationError<'json>>,
+        ) {
+            let threshold = match ctx.extension::<Threshold>() {
+                Some(threshold) => threshold.0,
+                None => return,
+            };
+            if value.as_f64().is_some_and(|score| score > threshold) {
+                let message = format!("the score is above {}", threshold);
+                errors.push(ValidationError::custom(message, value));
+            }
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn custom_keyword_reads_extension() {
+        let schema = ObjectSchemaBuilder::default()
+            .add_property("score", Schema::Custom(Arc::new(AtMostThreshold)))
+            .build();
+        let input = json!({"score": 0.7});
+
+        let mut strict = ValidateOptions::default();
+        assert!(strict.extensions.insert(Threshold(0.5)).is_none());
+        let mut lenient = strict.clone();
+        let replaced = lenient.extensions.insert(Threshold(0.9)).unwrap();
+        assert_eq!(replaced.0, 0.5);
+        assert_send_sync(&strict);
+
+        assert!(schema
+            .validate_with(&input, &ValidateOptions::default())
+            .is_valid());
+        let output = schema.validate_with(&input, &strict);
+        assert_eq!(output.errors.len(), 1);
+        assert_eq!(output.errors[0].instance_path, "/score");
+        assert_eq!(
+            output.errors[0].reason.to_string(),
+            "the score is above 0.5"
+        );
+        assert!(schema.validate_with(&input, &lenient).is_valid());
+
+        assert_eq!(strict.extensions.get::<Threshold>().unwrap().0, 0.5);
+        assert!(strict.extensions.get::<String>().is_none());
+        assert!(strict.extensions.remove::<Threshold>().is_some());
+        assert!(strict.extensions.is_empty());
+    }
+}
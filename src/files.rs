@@ -0,0 +1,154 @@
+use std::fs::{self, File};
+use std::path::Path;
+
+use serde_json::{self, Value};
+
+use diagnostics::non_schema_keys;
+use errors::{Error, ErrorKind, Result};
+use schema::Schema;
+use util::strip_bom;
+
+/// Reads the file at `path` as text, without a leading byte-order mark. Fails
+/// with `ErrorKind::Io` naming the file.
+pub fn read_text<P: AsRef<Path>>(path: P) -> Result<String> {
+    let path = path.as_ref();
+    let mut text = fs::read_to_string(path).map_err(|source| ErrorKind::Io {
+        path: path.into(),
+        source,
+    })?;
+    let bom = text.len() - strip_bom(&text).len();
+    text.drain(..bom);
+    Ok(text)
+}
+
+/// Parses `text`, the contents of the file at `path`, as JSON. A leading
+/// byte-order mark and trailing whitespace are accepted. Fails with
+/// `ErrorKind::InvalidJson` naming the file and the line and column of the
+/// mistake.
+pub fn parse_json<P: AsRef<Path>>(path: P, text: &str) -> Result<Value> {
+    serde_json::from_str(strip_bom(text)).map_err(|source| invalid_json(path, source))
+}
+
+/// Parses `text`, the contents of the file at `path`, as a stream of
+/// concatenated JSON texts, see `ValidateOptions::multiple_documents`. Fails
+/// like `parse_json` if one of them doesn't parse.
+pub fn parse_json_documents<P: AsRef<Path>>(path: P, text: &str) -> Result<Vec<Value>> {
+    serde_json::Deserializer::from_str(strip_bom(text))
+        .into_iter()
+        .collect::<serde_json::Result<_>>()
+        .map_err(|source| invalid_json(path, source))
+}
+
+fn invalid_json<P: AsRef<Path>>(path: P, source: serde_json::Error) -> Error {
+    ErrorKind::InvalidJson {
+        path: path.as_ref().into(),
+        source,
+    }
+    .into()
+}
+
+/// Reads the JSON document at `path`, see `read_text` and `parse_json`.
+pub fn read_json<P: AsRef<Path>>(path: P) -> Result<Value> {
+    let path = path.as_ref();
+    parse_json(path, &read_text(path)?)
+}
+
+/// Parses `value`, read from the file at `path`, as a schema. Fails with
+/// `ErrorKind::InvalidSchemaFile` naming the file.
+pub fn parse_schema<P: AsRef<Path>>(path: P, value: Value) -> Result<Schema> {
+    let parsed = match non_schema_keys(&value) {
+        Some(keys) => Err(ErrorKind::NotASchema(keys).into()),
+        None => serde_json::from_value(value).map_err(Error::from),
+    };
+    parsed.map_err(|source| {
+        ErrorKind::InvalidSchemaFile {
+            path: path.as_ref().into(),
+            source: Box::new(source),
+        }
+        .into()
+    })
+}
+
+/// Reads the schema at `path`, see `read_json` and `parse_schema`.
+pub fn read_schema<P: AsRef<Path>>(path: P) -> Result<Schema> {
+    let path = path.as_ref();
+    parse_schema(path, read_json(path)?)
+}
+
+/// Creates the file at `path`, replacing it if it exists. Fails with
+/// `ErrorKind::Io` naming the file.
+pub fn create<P: AsRef<Path>>(path: P) -> Result<File> {
+    let path = path.as_ref();
+    File::create(path).map_err(|source| {
+        ErrorKind::Io {
+            path: path.into(),
+            source,
+        }
+        .into()
+    })
+}
+
+/// Writes `contents` to the file at `path`, replacing it if it exists. Fails
+/// with `ErrorKind::Io` naming the file.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
+    let path = path.as_ref();
+    fs::write(path, contents).map_err(|source| {
+        ErrorKind::Io {
+            path: path.into(),
+            source,
+        }
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use errors::ErrorCode;
+
+    #[test]
+    fn errors_name_the_file() {
+        let error = read_schema("data/missing.json").unwrap_err();
+        assert_eq!(error.kind().code(), ErrorCode::Other);
+        assert!(error.to_string().starts_with("data/missing.json: "));
+
+        let error = parse_json("input.json", "{\"id\": 1,\n}").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "input.json isn't valid JSON: trailing comma at line 2 column 1"
+        );
+
+        let error = parse_schema("schema.json", json!({"type": "strin"})).unwrap_err();
+        match *error.kind() {
+            ErrorKind::InvalidSchemaFile { ref path, .. } => {
+                assert_eq!(path.to_str(), Some("schema.json"))
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+        let error = parse_schema("schema.json", json!({"name": "x"})).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("schema.json isn't a valid schema: "));
+
+        read_schema("data/catalog.json").unwrap();
+    }
+
+    #[test]
+    fn byte_order_marks_and_documents() {
+        assert_eq!(
+            read_text("data/with-bom.json").unwrap(),
+            "{\"id\": 7}\r\n\r\n"
+        );
+        assert_eq!(read_json("data/with-bom.json").unwrap(), json!({"id": 7}));
+        assert_eq!(parse_json("a.json", "\u{feff}[1]").unwrap(), json!([1]));
+
+        let documents = parse_json_documents("a.jsonl", "{\"id\": 1}\n{\"id\": 2}\n3").unwrap();
+        assert_eq!(
+            documents,
+            vec![json!({"id": 1}), json!({"id": 2}), json!(3)]
+        );
+        let error = parse_json_documents("a.jsonl", "{\"id\": 1}\n{\"id\": }").unwrap_err();
+        assert!(error.to_string().starts_with("a.jsonl isn't valid JSON: "));
+        assert!(parse_json("a.json", "{\"id\": 1}\n{\"id\": 2}").is_err());
+    }
+}
@@ -0,0 +1,497 @@
+use serde_json::Value;
+
+use array::{AdditionalItems, Items};
+use number::Range;
+use schema::Schema;
+use types::JsonType;
+use util::pointer_token;
+
+/// What a schema says about the values at one place in its instances, see
+/// `Schema::flatten_constraints`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyConstraint {
+    /// JSON pointer to the values, where `*` stands for every array index, like
+    /// `/tags/*`.
+    pub pointer: String,
+    /// The same place as a dotted path, like `address.street` or `tags[]`.
+    pub path: String,
+    /// JSON pointer to the subschema the constraints are from. For subschemas
+    /// reached through a reference, it's the pointer the reference points to.
+    pub schema_path: String,
+    /// The types the values may have. Empty if any type is allowed, or if that
+    /// depends on parts that aren't flattened.
+    pub types: Vec<JsonType>,
+    /// Whether instances may lack the value, because it's an array element, or
+    /// a property that isn't required itself or in an object that isn't.
+    pub optional: bool,
+    /// The least length of strings, or the least number of items of arrays.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// The greatest length of strings, or the greatest number of items of
+    /// arrays.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// The lower bound of numbers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    /// Whether `minimum` itself is excluded.
+    #[serde(skip_serializing_if = "is_false")]
+    pub exclusive_minimum: bool,
+    /// The upper bound of numbers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+    /// Whether `maximum` itself is excluded.
+    #[serde(skip_serializing_if = "is_false")]
+    pub exclusive_maximum: bool,
+    /// What numbers have to be a multiple of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiple_of: Option<f64>,
+    /// The values of `enum`, or the value of `const`.
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<Value>>,
+    /// The `format` of strings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// The keywords of the subschema whose constraints aren't part of this
+    /// view, like `pattern`, `patternProperties` or `oneOf`. Values meeting
+    /// the other constraints may still be invalid because of them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unflattened: Vec<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Where a subschema applies.
+struct Location {
+    pointer: String,
+    path: String,
+    schema_path: String,
+    optional: bool,
+}
+
+impl Location {
+    fn property(&self, name: &str, schema_path: String, optional: bool) -> Location {
+        let separator = if self.path.is_empty() { "" } else { "." };
+        Location {
+            pointer: format!("{}/{}", self.pointer, pointer_token(name)),
+            path: format!("{}{}{}", self.path, separator, name),
+            schema_path,
+            optional: self.optional || optional,
+        }
+    }
+
+    fn item(&self, index: Option<usize>, schema_path: String) -> Location {
+        let (token, path) = match index {
+            Some(i) => (i.to_string(), format!("[{}]", i)),
+            None => ("*".into(), "[]".into()),
+        };
+        Location {
+            pointer: format!("{}/{}", self.pointer, token),
+            path: format!("{}{}", self.path, path),
+            schema_path,
+            optional: true,
+        }
+    }
+}
+
+struct Flattener<'s> {
+    root: &'s Schema,
+    constraints: Vec<PropertyConstraint>,
+    /// The references followed to get to the current subschema.
+    references: Vec<&'s str>,
+}
+
+impl<'s> Flattener<'s> {
+    fn visit(&mut self, schema: &'s Schema, location: Location) {
+        let mut entry = PropertyConstraint {
+            pointer: location.pointer.clone(),
+            path: location.path.clone(),
+            schema_path: location.schema_path.clone(),
+            types: types(schema),
+            optional: location.optional,
+            min_length: None,
+            max_length: None,
+            minimum: None,
+            exclusive_minimum: false,
+            maximum: None,
+            exclusive_maximum: false,
+            multiple_of: None,
+            enum_values: None,
+            format: None,
+            unflattened: vec![],
+        };
+        if let Some((_, values)) = schema.allowed_values() {
+            if entry.types.is_empty() {
+                for value in &values {
+                    let json_type = JsonType::of(value);
+                    if !entry.types.contains(&json_type) {
+                        entry.types.push(json_type);
+                    }
+                }
+            }
+            entry.enum_values = Some(values.into_iter().cloned().collect());
+        }
+        let mut skipped = vec![];
+        let mut unflattened = |keyword: &str, present: bool| {
+            if present {
+                skipped.push(keyword.to_string());
+            }
+        };
+        let mut children = vec![];
+        match *schema {
+            Schema::Shared(ref s) => return self.visit(s, location),
+            Schema::Reference(ref s) => match s.resolve(self.root) {
+                Some(target) if !self.references.contains(&s.reference.as_str()) => {
+                    self.references.push(&s.reference);
+                    let location = Location {
+                        schema_path: s.target().1.into_owned(),
+                        ..location
+                    };
+                    self.visit(target, location);
+                    self.references.pop();
+                    return;
+                }
+                _ => unflattened("$ref", true),
+            },
+            Schema::String(ref s) => {
+                entry.min_length = s.min_length;
+                entry.max_length = s.max_length;
+                entry.format = s.format.as_ref().map(|f| f.as_str().into());
+                unflattened("pattern", s.pattern.is_some());
+                unflattened("x-formatMinimum", s.format_minimum.is_some());
+                unflattened(
+                    "x-formatExclusiveMinimum",
+                    s.format_exclusive_minimum.is_some(),
+                );
+                unflattened("x-formatMaximum", s.format_maximum.is_some());
+                unflattened(
+                    "x-formatExclusiveMaximum",
+                    s.format_exclusive_maximum.is_some(),
+                );
+            }
+            Schema::Number(ref s) => set_range(&mut entry, &s.range()),
+            Schema::Integer(ref s) => set_range(&mut entry, &s.range()),
+            Schema::Array(ref s) => {
+                entry.min_length = s.min_items;
+                entry.max_length = s.max_items;
+                unflattened("uniqueItems", s.unique_items == Some(true));
+                unflattened("x-uniqueBy", s.unique_by.is_some());
+                let (tuple, keyword) = match (&s.prefix_items, &s.items) {
+                    (Some(prefix), items) => {
+                        let rest = matches!(*items, Some(Items::List(_)));
+                        unflattened("items", rest);
+                        (Some(prefix), "prefixItems")
+                    }
+                    (None, Some(Items::Tuple(tuple))) => (Some(tuple), "items"),
+                    (None, Some(Items::List(items))) => {
+                        let schema_path = format!("{}/items", location.schema_path);
+                        children.push((&**items, location.item(None, schema_path)));
+                        (None, "items")
+                    }
+                    (None, _) => (None, "items"),
+                };
+                for (i, item) in tuple.into_iter().flatten().enumerate() {
+                    let schema_path = format!("{}/{}/{}", location.schema_path, keyword, i);
+                    children.push((item, location.item(Some(i), schema_path)));
+                }
+                let rest = matches!(s.additional_items, Some(AdditionalItems::Schema(_)));
+                unflattened("additionalItems", rest && s.prefix_items.is_none());
+            }
+            Schema::Object(ref s) => {
+                unflattened("minProperties", s.min_properties.is_some());
+                unflattened("maxProperties", s.max_properties.is_some());
+                unflattened("patternProperties", s.pattern_properties.is_some());
+                unflattened("additionalProperties", s.additional_schema().is_some());
+                unflattened("x-orderedKeys", s.ordered_keys.is_some());
+                unflattened("x-compare", s.comparisons.is_some());
+                let required: Vec<_> = s.required_properties().collect();
+                for (name, property) in s.properties.iter().flatten() {
+                    let schema_path = format!(
+                        "{}/properties/{}",
+                        location.schema_path,
+                        pointer_token(name)
+                    );
+                    let optional = !required.contains(&name);
+                    children.push((property, location.property(name, schema_path, optional)));
+                }
+            }
+            Schema::Combinator(ref s) => {
+                unflattened("allOf", s.all_of.is_some());
+                unflattened("anyOf", s.any_of.is_some());
+                unflattened("oneOf", s.one_of.is_some());
+                unflattened("not", s.not.is_some());
+            }
+            Schema::Custom(_) => unflattened("custom", true),
+            Schema::Boolean(_) | Schema::Empty(_) => {}
+        }
+        entry.unflattened = skipped;
+        self.constraints.push(entry);
+        for (child, location) in children {
+            self.visit(child, location);
+        }
+    }
+}
+
+fn set_range(entry: &mut PropertyConstraint, range: &Range) {
+    entry.minimum = range.minimum;
+    entry.exclusive_minimum = range.minimum.is_some() && range.exclusive_minimum;
+    entry.maximum = range.maximum;
+    entry.exclusive_maximum = range.maximum.is_some() && range.exclusive_maximum;
+    entry.multiple_of = range.multiple_of;
+}
+
+/// The types the `type` of `schema` allows.
+fn types(schema: &Schema) -> Vec<JsonType> {
+    let json_type = match *schema {
+        Schema::Boolean(_) => JsonType::Boolean,
+        Schema::Object(_) => JsonType::Object,
+        Schema::Array(_) => JsonType::Array,
+        Schema::Number(_) => JsonType::Number,
+        Schema::String(_) => JsonType::String,
+        Schema::Integer(_) => JsonType::Integer,
+        _ => return vec![],
+    };
+    if schema.is_nullable() {
+        vec![json_type, JsonType::Null]
+    } else {
+        vec![json_type]
+    }
+}
+
+impl Schema {
+    /// A flat view of the constraints of the schema, for deriving things like
+    /// database columns and indices from it: an entry for the schema itself
+    /// and for each property and array element it describes, parents first.
+    /// Local references are followed, unless they lead back to a schema they
+    /// are in.
+    ///
+    /// Only constraints every value has to meet are represented: types, the
+    /// bounds of lengths and numbers, allowed values and formats. The keywords
+    /// of each subschema that aren't are listed in
+    /// `PropertyConstraint::unflattened`, and the values they apply to, like
+    /// the properties of `patternProperties` and the branches of `oneOf`, get
+    /// no entries.
+    pub fn flatten_constraints(&self) -> Vec<PropertyConstraint> {
+        let mut flattener = Flattener {
+            root: self,
+            constraints: vec![],
+            references: vec![],
+        };
+        let location = Location {
+            pointer: String::new(),
+            path: String::new(),
+            schema_path: String::new(),
+            optional: false,
+        };
+        flattener.visit(self, location);
+        flattener.constraints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    #[test]
+    fn medium_schema() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "additionalProperties": true,
+            "patternProperties": {"^x-": {"type": "string"}},
+            "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "name": {"type": "string", "minLength": 1, "maxLength": 80, "pattern": "^\\S"},
+                "email": {"type": ["string", "null"], "format": "email"},
+                "price": {
+                    "type": "number",
+                    "minimum": 0,
+                    "maximum": 1000,
+                    "exclusiveMaximum": true,
+                    "multipleOf": 0.01
+                },
+                "status": {"enum": ["draft", "live"]},
+                "address": {"$ref": "#/definitions/address"},
+                "tags": {
+                    "type": "array",
+                    "maxItems": 10,
+                    "uniqueItems": true,
+                    "items": {"type": "string", "maxLength": 20}
+                },
+                "point": {
+                    "type": "array",
+                    "items": [{"type": "number"}, {"type": "number"}]
+                },
+                "contact": {"oneOf": [{"type": "string"}, {"type": "integer"}]}
+            },
+            "definitions": {
+                "address": {
+                    "type": "object",
+                    "required": ["city"],
+                    "additionalProperties": true,
+                    "properties": {
+                        "city": {"type": "string"},
+                        "zip": {"type": "string", "minLength": 4, "maxLength": 10}
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let expected = json!([
+            {
+                "pointer": "",
+                "path": "",
+                "schemaPath": "",
+                "types": ["object"],
+                "optional": false,
+                "unflattened": ["patternProperties"]
+            },
+            {
+                "pointer": "/address",
+                "path": "address",
+                "schemaPath": "/definitions/address",
+                "types": ["object"],
+                "optional": true
+            },
+            {
+                "pointer": "/address/city",
+                "path": "address.city",
+                "schemaPath": "/definitions/address/properties/city",
+                "types": ["string"],
+                "optional": true
+            },
+            {
+                "pointer": "/address/zip",
+                "path": "address.zip",
+                "schemaPath": "/definitions/address/properties/zip",
+                "types": ["string"],
+                "optional": true,
+                "minLength": 4,
+                "maxLength": 10
+            },
+            {
+                "pointer": "/contact",
+                "path": "contact",
+                "schemaPath": "/properties/contact",
+                "types": [],
+                "optional": true,
+                "unflattened": ["oneOf"]
+            },
+            {
+                "pointer": "/email",
+                "path": "email",
+                "schemaPath": "/properties/email",
+                "types": ["string", "null"],
+                "optional": true,
+                "format": "email"
+            },
+            {
+                "pointer": "/id",
+                "path": "id",
+                "schemaPath": "/properties/id",
+                "types": ["integer"],
+                "optional": false,
+                "minimum": 1.0
+            },
+            {
+                "pointer": "/name",
+                "path": "name",
+                "schemaPath": "/properties/name",
+                "types": ["string"],
+                "optional": false,
+                "minLength": 1,
+                "maxLength": 80,
+                "unflattened": ["pattern"]
+            },
+            {
+                "pointer": "/point",
+                "path": "point",
+                "schemaPath": "/properties/point",
+                "types": ["array"],
+                "optional": true
+            },
+            {
+                "pointer": "/point/0",
+                "path": "point[0]",
+                "schemaPath": "/properties/point/items/0",
+                "types": ["number"],
+                "optional": true
+            },
+            {
+                "pointer": "/point/1",
+                "path": "point[1]",
+                "schemaPath": "/properties/point/items/1",
+                "types": ["number"],
+                "optional": true
+            },
+            {
+                "pointer": "/price",
+                "path": "price",
+                "schemaPath": "/properties/price",
+                "types": ["number"],
+                "optional": true,
+                "minimum": 0.0,
+                "maximum": 1000.0,
+                "exclusiveMaximum": true,
+                "multipleOf": 0.01
+            },
+            {
+                "pointer": "/status",
+                "path": "status",
+                "schemaPath": "/properties/status",
+                "types": ["string"],
+                "optional": true,
+                "enum": ["draft", "live"]
+            },
+            {
+                "pointer": "/tags",
+                "path": "tags",
+                "schemaPath": "/properties/tags",
+                "types": ["array"],
+                "optional": true,
+                "maxLength": 10,
+                "unflattened": ["uniqueItems"]
+            },
+            {
+                "pointer": "/tags/*",
+                "path": "tags[]",
+                "schemaPath": "/properties/tags/items",
+                "types": ["string"],
+                "optional": true,
+                "maxLength": 20
+            }
+        ]);
+        // the constraints follow the order of the properties, compare them by pointer
+        let mut flattened = schema.flatten_constraints();
+        flattened.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+        assert_eq!(serde_json::to_value(flattened).unwrap(), expected);
+    }
+
+    #[test]
+    fn recursive_references() {
+        let schema: Schema = serde_json::from_value(json!({
+            "$ref": "#/definitions/node",
+            "definitions": {
+                "node": {
+                    "type": "object",
+                    "required": ["children"],
+                    "properties": {
+                        "children": {"type": "array", "items": {"$ref": "#/definitions/node"}}
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let constraints = schema.flatten_constraints();
+        let pointers: Vec<_> = constraints.iter().map(|c| c.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["", "/children", "/children/*"]);
+        assert_eq!(constraints[2].unflattened, vec!["$ref"]);
+        assert!(!constraints[1].optional);
+        assert!(constraints[2].optional);
+    }
+}
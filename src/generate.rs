@@ -0,0 +1,540 @@
+use serde_json::{Map, Value};
+
+use array::ArraySchema;
+use errors::ErrorCode;
+use number::Range;
+use object::{AdditionalProperties, ObjectSchema};
+use schema::Schema;
+use string::{Format, StringSchema};
+use util::pointer_token;
+
+/// How deep references are followed when generating values, to stop at
+/// recursive schemas.
+const MAX_DEPTH: usize = 32;
+
+/// Values of each type, tried when nothing better is known.
+fn fallbacks() -> Vec<Value> {
+    vec![
+        Value::Null,
+        json!(true),
+        json!(0),
+        json!(""),
+        json!([]),
+        json!({}),
+    ]
+}
+
+/// The error a counterexample is expected to produce, see
+/// `Schema::generate_counterexamples`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpectedViolation {
+    /// The kind of the error.
+    pub code: ErrorCode,
+    /// JSON pointer to the subschema reporting the error.
+    pub schema_path: String,
+    /// JSON pointer to the value that violates the subschema.
+    pub instance_path: String,
+}
+
+/// A number that satisfies `range`, an integer if `integer` is set.
+fn number_example(range: &Range, integer: bool) -> Value {
+    let step = match range.multiple_of {
+        Some(step) => Some(step),
+        None if integer => Some(1.0),
+        None => None,
+    };
+    let number = match (range.minimum, range.maximum, step) {
+        (Some(min), _, Some(step)) => {
+            let first = (min / step).ceil() * step;
+            if range.exclusive_minimum && first == min {
+                first + step
+            } else {
+                first
+            }
+        }
+        (None, Some(max), Some(step)) => {
+            let last = (max / step).floor() * step;
+            if range.exclusive_maximum && last == max {
+                last - step
+            } else {
+                last
+            }
+        }
+        (Some(min), Some(max), None) if range.exclusive_minimum => (min + max) / 2.0,
+        (Some(min), None, None) if range.exclusive_minimum => min + 1.0,
+        (Some(min), _, None) => min,
+        (None, Some(max), None) if range.exclusive_maximum => max - 1.0,
+        (None, Some(max), None) => max,
+        (None, None, _) => 0.0,
+    };
+    number_value(number)
+}
+
+/// `number` as an integer if it's a whole number.
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < 9_007_199_254_740_992.0 {
+        json!(number as i64)
+    } else {
+        json!(number)
+    }
+}
+
+fn format_example(format: &Format) -> &'static str {
+    match *format {
+        Format::DateTime => "2018-01-01T00:00:00Z",
+        Format::Date => "2018-01-01",
+        Format::Time => "00:00:00Z",
+        Format::Email => "user@example.com",
+        Format::Hostname => "example.com",
+        Format::Ipv4 => "192.0.2.1",
+        Format::Ipv6 => "2001:db8::1",
+        Format::Uri => "https://example.com/",
+        Format::Other(_) => "",
+    }
+}
+
+fn string_example(s: &StringSchema) -> Value {
+    match s.format {
+        Some(ref format) if format.is_checked() => json!(format_example(format)),
+        _ => json!("a".repeat(s.min_length.unwrap_or(0))),
+    }
+}
+
+fn array_example(root: &Schema, s: &ArraySchema, depth: usize) -> Option<Value> {
+    let mut array = vec![];
+    for i in 0..s.min_items.unwrap_or(0) {
+        array.push(match s.item_schema(i) {
+            Some(item) => example(root, item, depth + 1)?,
+            None => Value::Null,
+        });
+    }
+    Some(Value::Array(array))
+}
+
+fn object_example(root: &Schema, s: &ObjectSchema, depth: usize) -> Option<Value> {
+    let mut object = Map::new();
+    let properties = s.properties.as_ref();
+    for name in s.required_properties() {
+        let value = match properties.and_then(|p| p.get(name)) {
+            Some(property) => example(root, property, depth + 1)?,
+            None => Value::Null,
+        };
+        object.insert(name.clone(), value);
+    }
+    // the declared properties make up for a `minProperties` beyond `required`
+    let min = s.min_properties.unwrap_or(0);
+    for (name, property) in properties.into_iter().flatten() {
+        if object.len() >= min {
+            break;
+        }
+        if !object.contains_key(name) {
+            object.insert(name.clone(), example(root, property, depth + 1)?);
+        }
+    }
+    Some(Value::Object(object))
+}
+
+/// A value `schema` accepts, with references resolved against `root`. Prefers
+/// the values of `const`, `enum`, `default` and `examples`, then builds the
+/// smallest value satisfying the keywords.
+fn example(root: &Schema, schema: &Schema, depth: usize) -> Option<Value> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let built = match *schema {
+        Schema::Shared(ref s) => return example(root, s, depth),
+        Schema::Reference(ref s) => {
+            return s
+                .resolve(root)
+                .and_then(|target| example(root, target, depth + 1))
+        }
+        Schema::Custom(_) => return None,
+        Schema::Empty(_) => Some(Value::Null),
+        Schema::Boolean(_) => Some(json!(true)),
+        Schema::Integer(ref s) => Some(number_example(&s.range(), true)),
+        Schema::Number(ref s) => Some(number_example(&s.range(), false)),
+        Schema::String(ref s) => Some(string_example(s)),
+        Schema::Array(ref s) => array_example(root, s, depth),
+        Schema::Object(ref s) => object_example(root, s, depth),
+        Schema::Combinator(ref s) => {
+            let members = s.all_of.iter().chain(&s.any_of).chain(&s.one_of);
+            members
+                .flatten()
+                .filter_map(|member| example(root, member, depth + 1))
+                .find(|value| schema.accepts_within(root, value))
+        }
+    };
+    // only the allowed values are worth trying if there are any
+    let (allowed, built, fallbacks) = match schema.allowed_values() {
+        Some((_, values)) => (values, None, vec![]),
+        None => (vec![], built, fallbacks()),
+    };
+    let candidates = allowed.into_iter().chain(schema.examples()).cloned();
+    candidates
+        .chain(built)
+        .chain(fallbacks)
+        .find(|value| schema.accepts_within(root, value))
+}
+
+/// A value of another type than `type_name`.
+fn wrong_type(type_name: &str) -> Value {
+    match type_name {
+        "string" => json!(1),
+        "boolean" => json!("true"),
+        "object" => json!([]),
+        "array" => json!({}),
+        _ => json!("1"),
+    }
+}
+
+/// A value similar to `value` that's different from all of `values`.
+fn other_value(value: &Value, values: &[&Value]) -> Option<Value> {
+    let candidates = match *value {
+        Value::String(ref s) => vec![json!(format!("{}_", s)), json!("")],
+        Value::Number(ref n) => vec![number_value(n.as_f64().unwrap_or(0.0) + 1.0), json!(0)],
+        Value::Bool(b) => vec![json!(!b)],
+        Value::Null => vec![json!(false)],
+        Value::Array(_) | Value::Object(_) => vec![],
+    };
+    candidates
+        .into_iter()
+        .chain(fallbacks())
+        .find(|candidate| !values.contains(&candidate))
+}
+
+/// Collects counterexamples by changing one value of a valid document at a time.
+struct Generator<'s> {
+    root: &'s Schema,
+    document: Value,
+    limit: usize,
+    found: Vec<(Value, ExpectedViolation)>,
+}
+
+impl<'s> Generator<'s> {
+    /// Keeps the document with the value at `instance_path` replaced, if the
+    /// change only causes the expected error.
+    fn try_replace(
+        &mut self,
+        instance_path: &str,
+        schema_path: &str,
+        value: Value,
+        code: ErrorCode,
+    ) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+        let mut document = self.document.clone();
+        match document.pointer_mut(instance_path) {
+            Some(slot) => *slot = value,
+            None => return,
+        }
+        let expected = ExpectedViolation {
+            code,
+            schema_path: schema_path.into(),
+            instance_path: instance_path.into(),
+        };
+        let matches = match self.root.validate(&document) {
+            Ok(()) => false,
+            Err(errors) => {
+                errors.0.len() == 1 && {
+                    let error = &errors.0[0];
+                    error.reason.code() == code
+                        && error.schema_path == expected.schema_path
+                        && error.instance_path == expected.instance_path
+                }
+            }
+        };
+        if matches && self.found.iter().all(|(found, _)| *found != document) {
+            self.found.push((document, expected));
+        }
+    }
+
+    fn walk(
+        &mut self,
+        schema: &'s Schema,
+        schema_path: String,
+        instance_path: String,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH || self.found.len() >= self.limit {
+            return;
+        }
+        let value = match self.document.pointer(&instance_path) {
+            Some(value) => value.clone(),
+            None => return,
+        };
+        let (sp, ip) = (&schema_path[..], &instance_path[..]);
+        match *schema {
+            Schema::Shared(ref s) => return self.walk(s, schema_path, instance_path, depth),
+            Schema::Reference(ref s) => {
+                if let Some(target) = s.resolve(self.root) {
+                    let target_path = format!("{}/$ref", schema_path);
+                    self.walk(target, target_path, instance_path, depth + 1);
+                }
+                return;
+            }
+            Schema::Empty(_) | Schema::Custom(_) => return,
+            _ => {}
+        }
+
+        if let Some(type_name) = schema.type_name() {
+            let code = if schema.is_nullable() {
+                ErrorCode::MultiTypeMismatch
+            } else {
+                ErrorCode::TypeMismatch
+            };
+            self.try_replace(ip, sp, wrong_type(type_name), code);
+        }
+        if let Some((keyword, values)) = schema.allowed_values() {
+            let code = match keyword {
+                "const" => ErrorCode::ConstMismatch,
+                _ => ErrorCode::NotInEnum,
+            };
+            if let Some(other) = other_value(&value, &values) {
+                self.try_replace(ip, sp, other, code);
+            }
+        }
+
+        match *schema {
+            Schema::String(ref s) => {
+                let text = value.as_str().unwrap_or("");
+                if let Some(min) = s.min_length.filter(|&min| min > 0) {
+                    let shorter: String = text.chars().take(min - 1).collect();
+                    self.try_replace(ip, sp, json!(shorter), ErrorCode::MinLength);
+                }
+                if let Some(max) = s.max_length {
+                    let longer = text.chars().chain(::std::iter::repeat('a'));
+                    let longer: String = longer.take(max + 1).collect();
+                    self.try_replace(ip, sp, json!(longer), ErrorCode::MaxLength);
+                }
+                if s.pattern.is_some() {
+                    for candidate in &["", " ", "-"] {
+                        self.try_replace(ip, sp, json!(candidate), ErrorCode::RegexMismatch);
+                    }
+                }
+                if let Some(format) = s.format.as_ref().filter(|f| f.is_checked()) {
+                    let invalid = format!("not a valid {}", format.as_str());
+                    self.try_replace(ip, sp, json!(invalid), ErrorCode::InvalidFormat);
+                }
+            }
+            Schema::Number(ref s) => self.walk_range(&s.range(), &value, sp, ip),
+            Schema::Integer(ref s) => self.walk_range(&s.range(), &value, sp, ip),
+            Schema::Array(ref s) => self.walk_array(s, &value, sp, ip, depth),
+            Schema::Object(ref s) => self.walk_object(s, &value, sp, ip, depth),
+            Schema::Combinator(ref s) => {
+                for (i, member) in s.all_of.iter().flatten().enumerate() {
+                    let member_path = format!("{}/allOf/{}", sp, i);
+                    self.walk(member, member_path, instance_path.clone(), depth + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_range(&mut self, range: &Range, value: &Value, sp: &str, ip: &str) {
+        if let Some(min) = range.minimum {
+            let below = if range.exclusive_minimum {
+                min
+            } else {
+                min - 1.0
+            };
+            self.try_replace(ip, sp, number_value(below), ErrorCode::NumberRange);
+        }
+        if let Some(max) = range.maximum {
+            let above = if range.exclusive_maximum {
+                max
+            } else {
+                max + 1.0
+            };
+            self.try_replace(ip, sp, number_value(above), ErrorCode::NumberRange);
+        }
+        if let Some(step) = range.multiple_of {
+            let number = value.as_f64().unwrap_or(0.0);
+            for &offset in &[step / 2.0, 1.0] {
+                let off = number_value(number + offset);
+                self.try_replace(ip, sp, off, ErrorCode::NotMultipleOf);
+            }
+        }
+    }
+
+    fn walk_array(&mut self, s: &'s ArraySchema, value: &Value, sp: &str, ip: &str, depth: usize) {
+        let array = match value.as_array() {
+            Some(array) => array,
+            None => return,
+        };
+        if let Some(min) = s.min_items.filter(|&min| min > 0) {
+            let shorter = array[..min - 1].to_vec();
+            self.try_replace(ip, sp, Value::Array(shorter), ErrorCode::MinLength);
+        }
+        if let Some(max) = s.max_items {
+            let mut longer = array.clone();
+            while longer.len() <= max {
+                let item = s.item_schema(longer.len());
+                match item.map_or(Some(Value::Null), |item| {
+                    example(self.root, item, depth + 1)
+                }) {
+                    Some(item) => longer.push(item),
+                    None => break,
+                }
+            }
+            self.try_replace(ip, sp, Value::Array(longer), ErrorCode::MaxLength);
+        }
+        if s.unique_items == Some(true) && !array.is_empty() {
+            let mut repeated = array.clone();
+            repeated.push(array[0].clone());
+            self.try_replace(
+                ip,
+                sp,
+                Value::Array(repeated),
+                ErrorCode::ArrayItemNotUnique,
+            );
+        }
+        for i in 0..array.len() {
+            let (pointer, item) = match s.item_schema_with_pointer(i) {
+                Some(item) => item,
+                None => continue,
+            };
+            let item_path = format!("{}{}", sp, pointer);
+            self.walk(item, item_path, format!("{}/{}", ip, i), depth + 1);
+        }
+    }
+
+    fn walk_object(
+        &mut self,
+        s: &'s ObjectSchema,
+        value: &Value,
+        sp: &str,
+        ip: &str,
+        depth: usize,
+    ) {
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+        for name in s.required_properties() {
+            let mut missing = object.clone();
+            if missing.remove(name).is_some() {
+                let missing = Value::Object(missing);
+                self.try_replace(ip, sp, missing, ErrorCode::MissingProperties);
+            }
+        }
+        if let Some(max) = s.max_properties {
+            let mut more = object.clone();
+            for i in 0.. {
+                if more.len() > max {
+                    break;
+                }
+                more.insert(format!("extra{}", i), Value::Null);
+            }
+            self.try_replace(ip, sp, Value::Object(more), ErrorCode::PropertyCount);
+        }
+        if matches!(s.additional_properties, Some(AdditionalProperties::Allowed(false))) {
+            let mut unknown = object.clone();
+            unknown.insert("unexpected".into(), Value::Null);
+            let unknown = Value::Object(unknown);
+            self.try_replace(ip, sp, unknown, ErrorCode::UnknownProperties);
+        }
+        for (name, property) in s.properties.iter().flatten() {
+            if object.contains_key(name) {
+                let token = pointer_token(name);
+                let property_path = format!("{}/properties/{}", sp, token);
+                self.walk(
+                    property,
+                    property_path,
+                    format!("{}/{}", ip, token),
+                    depth + 1,
+                );
+            }
+        }
+    }
+}
+
+impl Schema {
+    /// A value this schema accepts, if one can be found. Prefers the values
+    /// of `const`, `enum`, `default` and `examples`, then builds the smallest
+    /// value satisfying the keywords. Strings with a `pattern` are only found
+    /// if one of the given values matches.
+    pub fn example(&self) -> Option<Value> {
+        example(self, self, 0)
+    }
+
+    /// Up to `limit` invalid values that each violate exactly one keyword: a
+    /// too short string, a missing required property, a number out of range, a
+    /// value of the wrong type. Each comes with the error validating it reports.
+    ///
+    /// They are made from `Schema::example` by changing one value at a time.
+    /// Changes that don't cause exactly the expected error, like a shorter
+    /// string that no longer matches the `pattern`, are left out.
+    pub fn generate_counterexamples(&self, limit: usize) -> Vec<(Value, ExpectedViolation)> {
+        let document = match self.example() {
+            Some(document) => document,
+            None => return vec![],
+        };
+        let mut generator = Generator {
+            root: self,
+            document,
+            limit,
+            found: vec![],
+        };
+        generator.walk(self, String::new(), String::new(), 0);
+        generator.found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    #[test]
+    fn counterexamples() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {
+                "port": {"type": "integer", "minimum": 1, "maximum": 65535}
+            },
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["name", "port", "status"],
+            "properties": {
+                "name": {"type": "string", "minLength": 2, "maxLength": 8},
+                "port": {"$ref": "#/definitions/port"},
+                "status": {"type": "string", "enum": ["up", "down"]},
+                "tags": {"type": "array", "maxItems": 2, "items": {"type": "string"}}
+            }
+        }))
+        .unwrap();
+        let example = schema.example().unwrap();
+        assert_eq!(example, json!({"name": "aa", "port": 1, "status": "up"}));
+
+        let counterexamples = schema.generate_counterexamples(100);
+        for (value, expected) in &counterexamples {
+            let errors = schema.validate(value).unwrap_err().0;
+            assert_eq!(errors.len(), 1, "{}", value);
+            assert_eq!(errors[0].reason.code(), expected.code);
+            assert_eq!(errors[0].schema_path, expected.schema_path);
+            assert_eq!(errors[0].instance_path, expected.instance_path);
+        }
+        let violations: Vec<_> = counterexamples
+            .iter()
+            .map(|(_, e)| (e.code, e.schema_path.as_str()))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (ErrorCode::TypeMismatch, ""),
+                (ErrorCode::MissingProperties, ""),
+                (ErrorCode::MissingProperties, ""),
+                (ErrorCode::MissingProperties, ""),
+                (ErrorCode::TypeMismatch, "/properties/name"),
+                (ErrorCode::MinLength, "/properties/name"),
+                (ErrorCode::MaxLength, "/properties/name"),
+                (ErrorCode::TypeMismatch, "/properties/port/$ref"),
+                (ErrorCode::NumberRange, "/properties/port/$ref"),
+                (ErrorCode::NumberRange, "/properties/port/$ref"),
+                (ErrorCode::NotInEnum, "/properties/status"),
+            ]
+        );
+        assert_eq!(schema.generate_counterexamples(3).len(), 3);
+    }
+}
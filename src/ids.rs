@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use serde_json::{self, Value};
+
+use schema::Schema;
+
+/// The identifiers of the subschemas of a schema by their JSON pointers, see
+/// `Schema::assign_ids`.
+pub type SubschemaIds = BTreeMap<String, String>;
+
+impl Schema {
+    /// Gives every subschema an identifier like `fnv1a:3f2c9b0d41e7a865`, made
+    /// from a hash of its contents, for tools that track subschemas across runs
+    /// and edits of the schema, like coverage viewers and error dashboards.
+    ///
+    /// The contents are normalized first: the keys of objects are sorted and
+    /// members that are `null` are left out. The identifier of a subschema
+    /// stays the same as long as it and its own subschemas do, wherever it is
+    /// in the document, so adding a property only changes the identifiers of
+    /// the schemas holding it. Identical subschemas share their identifier.
+    /// Should different subschemas hash to the same value, all but the first
+    /// of them in normalized order get a suffix like `-2`. Custom schemas,
+    /// which can't be serialized, are identified by their pointer instead.
+    pub fn assign_ids(&self) -> SubschemaIds {
+        let mut contents = vec![];
+        collect(self, String::new(), &mut contents);
+        label(&contents, fnv1a)
+    }
+}
+
+/// Makes the identifiers of the subschemas with the normalized `contents`.
+fn label(contents: &[(String, String)], hash: fn(&str) -> u64) -> SubschemaIds {
+    let mut by_hash: BTreeMap<u64, Vec<&str>> = BTreeMap::new();
+    for (_, text) in contents {
+        let texts = by_hash.entry(hash(text)).or_default();
+        if !texts.contains(&text.as_str()) {
+            texts.push(text);
+        }
+    }
+    for texts in by_hash.values_mut() {
+        texts.sort();
+    }
+    contents
+        .iter()
+        .map(|(pointer, text)| {
+            let hash = hash(text);
+            let mut id = format!("fnv1a:{:016x}", hash);
+            let position = by_hash[&hash].iter().position(|t| t == text);
+            if let Some(n) = position.filter(|&n| n > 0) {
+                id.push_str(&format!("-{}", n + 1));
+            }
+            (pointer.clone(), id)
+        })
+        .collect()
+}
+
+/// Collects the normalized contents of `schema` and its subschemas.
+fn collect(schema: &Schema, pointer: String, contents: &mut Vec<(String, String)>) {
+    for (step, child) in schema.children_with_pointers() {
+        collect(child, format!("{}{}", pointer, step), contents);
+    }
+    let text = match serde_json::to_value(schema) {
+        Ok(value) => {
+            let mut text = String::new();
+            normalize(&value, &mut text);
+            text
+        }
+        Err(_) => format!("custom:{}", pointer),
+    };
+    contents.push((pointer, text));
+}
+
+/// Writes `value` with sorted keys and without `null` members.
+fn normalize(value: &Value, out: &mut String) {
+    match *value {
+        Value::Object(ref object) => {
+            let mut members: Vec<_> = object.iter().filter(|(_, v)| !v.is_null()).collect();
+            members.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                normalize(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(ref items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                normalize(item, out);
+            }
+            out.push(']');
+        }
+        ref scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// The 64-bit FNV-1a hash of `text`, which unlike the hashers of the standard
+/// library is the same in every build.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: Value) -> Schema {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn stable_ids() {
+        let schema = parse(json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        }));
+        let ids = schema.assign_ids();
+        assert_eq!(ids.len(), 4);
+        assert!(ids[""].starts_with("fnv1a:"));
+        assert_eq!(ids[""].len(), "fnv1a:".len() + 16);
+        // the same contents, the same identifier
+        assert_eq!(
+            ids["/properties/tags/items"],
+            parse(json!({"type": "string"})).assign_ids()[""]
+        );
+
+        // an unrelated property only changes the schema holding it
+        let extended = parse(json!({
+            "type": "object",
+            "properties": {
+                "age": {"type": "integer"},
+                "name": {"type": "string", "minLength": 1},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        }));
+        let extended_ids = extended.assign_ids();
+        assert_eq!(extended_ids["/properties/name"], ids["/properties/name"]);
+        assert_eq!(extended_ids["/properties/tags"], ids["/properties/tags"]);
+        assert_ne!(extended_ids[""], ids[""]);
+
+        // changing a subschema changes it and the schemas holding it
+        let changed = parse(json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 2},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        }));
+        let changed_ids = changed.assign_ids();
+        assert_ne!(changed_ids["/properties/name"], ids["/properties/name"]);
+        assert_ne!(changed_ids[""], ids[""]);
+        assert_eq!(changed_ids["/properties/tags"], ids["/properties/tags"]);
+    }
+
+    #[test]
+    fn normalized_contents() {
+        let mut text = String::new();
+        normalize(
+            &json!({"b": [1, {"d": null, "c": "x"}], "a": null}),
+            &mut text,
+        );
+        assert_eq!(text, r#"{"b":[1,{"c":"x"}]}"#);
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn collisions() {
+        let contents: Vec<_> = [("/b", "{}"), ("/a", "[]"), ("", "{}")]
+            .iter()
+            .map(|&(pointer, text)| (pointer.to_string(), text.to_string()))
+            .collect();
+        let ids = label(&contents, |_| 1);
+        assert_eq!(ids["/a"], "fnv1a:0000000000000001");
+        assert_eq!(ids["/b"], "fnv1a:0000000000000001-2");
+        assert_eq!(ids[""], ids["/b"]);
+    }
+}
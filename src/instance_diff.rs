@@ -0,0 +1,420 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use compare::Verdict;
+use limits::SchemaLimits;
+use object::PropertyResolver;
+use render::{preview, DEFAULT_PREVIEW_LENGTH};
+use schema::Schema;
+use util::pointer_token;
+
+/// The number of references followed in a row before giving up on a chain.
+const MAX_REFERENCES: usize = 32;
+
+/// What happened to a value between two documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    /// The value is only in the new document.
+    Added,
+    /// The value is only in the old document.
+    Removed,
+    /// The value is in both documents, with a different value or type.
+    Changed,
+}
+
+/// A value that differs between two documents, with the subschemas that govern
+/// it, see `instance_diff`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceChange {
+    /// JSON pointer to the value.
+    pub instance_path: String,
+    /// Whether the value was added, removed or changed.
+    pub kind: ChangeKind,
+    /// A short excerpt of the old value, if there is one.
+    pub old_preview: Option<String>,
+    /// A short excerpt of the new value, if there is one.
+    pub new_preview: Option<String>,
+    /// JSON pointers to the subschemas that apply to the value, with references
+    /// followed and `allOf` branches included. Empty if none applies or which
+    /// one does depends on an `anyOf`, `oneOf` or `not` above the value.
+    pub schema_paths: Vec<String>,
+    /// Whether the value is a property that an enclosing object schema requires.
+    pub required: bool,
+    /// Whether one of the subschemas is marked `deprecated`.
+    pub deprecated: bool,
+    /// How the subschemas judge the old and the new value. Added and removed
+    /// values are judged by the subschemas of the object or array holding them,
+    /// as their presence is what changed: the old object or array against a copy
+    /// with only this value added or removed.
+    pub verdict: Verdict,
+}
+
+impl fmt::Display for InstanceChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.instance_path.is_empty() {
+            "/"
+        } else {
+            &self.instance_path
+        };
+        let old = self.old_preview.as_ref().map_or("", |p| p.as_str());
+        let new = self.new_preview.as_ref().map_or("", |p| p.as_str());
+        match self.kind {
+            ChangeKind::Added => write!(f, "`{}` added: {}", path, new)?,
+            ChangeKind::Removed => write!(f, "`{}` removed: {}", path, old)?,
+            ChangeKind::Changed => write!(f, "`{}` changed: {} -> {}", path, old, new)?,
+        }
+        let mut notes = vec![];
+        if self.required {
+            notes.push("required");
+        }
+        if self.deprecated {
+            notes.push("deprecated");
+        }
+        match self.verdict {
+            Verdict::NewlyInvalid => notes.push("newly invalid"),
+            Verdict::NewlyValid => notes.push("newly valid"),
+            Verdict::InvalidInBoth => notes.push("invalid in both"),
+            Verdict::ValidInBoth => {}
+        }
+        if !notes.is_empty() {
+            write!(f, " ({})", notes.join(", "))?;
+        }
+        if !self.schema_paths.is_empty() {
+            let paths: Vec<_> = self
+                .schema_paths
+                .iter()
+                .map(|p| if p.is_empty() { "/" } else { p.as_str() })
+                .collect();
+            write!(f, " [{}]", paths.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the reference tokens of the values that differ, and the values.
+fn walk<'v>(
+    tokens: &mut Vec<String>,
+    old: Option<&'v Value>,
+    new: Option<&'v Value>,
+    out: &mut Vec<(Vec<String>, Option<&'v Value>, Option<&'v Value>)>,
+) {
+    match (old, new) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let mut keys: Vec<&String> = a
+                .keys()
+                .chain(b.keys().filter(|k| !a.contains_key(*k)))
+                .collect();
+            keys.sort();
+            for key in keys {
+                tokens.push(key.clone());
+                walk(tokens, a.get(key), b.get(key), out);
+                tokens.pop();
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for i in 0..a.len().max(b.len()) {
+                tokens.push(i.to_string());
+                walk(tokens, a.get(i), b.get(i), out);
+                tokens.pop();
+            }
+        }
+        (Some(a), Some(b)) if a == b => {}
+        _ => out.push((tokens.clone(), old, new)),
+    }
+}
+
+/// Adds `schema` and the subschemas that apply along with it: the targets of
+/// references and the branches of `allOf`.
+fn expand<'s>(
+    root: &'s Schema,
+    pointer: String,
+    schema: &'s Schema,
+    out: &mut Vec<(String, &'s Schema)>,
+) {
+    let mut pointer = pointer;
+    let mut schema = schema;
+    for _ in 0..MAX_REFERENCES {
+        match *schema {
+            Schema::Reference(ref reference) => match reference.resolve(root) {
+                Some(target) => {
+                    pointer = match reference.reference.find('#') {
+                        Some(i) => reference.reference[i + 1..].into(),
+                        None => reference.reference.clone(),
+                    };
+                    schema = target;
+                }
+                None => return,
+            },
+            Schema::Shared(ref shared) => schema = shared,
+            _ => break,
+        }
+    }
+    if let Schema::Combinator(ref combinator) = *schema {
+        for (i, branch) in combinator.all_of.iter().flatten().enumerate() {
+            expand(root, format!("{}/allOf/{}", pointer, i), branch, out);
+        }
+    }
+    out.push((pointer, schema));
+}
+
+/// The subschemas that apply to the value at `tokens`, as far as they can be
+/// told without validating.
+fn governing<'s>(root: &'s Schema, tokens: &[String]) -> Vec<(String, &'s Schema)> {
+    let mut current = vec![];
+    expand(root, String::new(), root, &mut current);
+    for token in tokens {
+        let mut next = vec![];
+        for (pointer, schema) in current {
+            match *schema {
+                Schema::Object(ref object) => {
+                    let resolved =
+                        PropertyResolver::new(object, &SchemaLimits::default()).resolve(token);
+                    if let Some(declared) = resolved.declared {
+                        let child = format!("{}/properties/{}", pointer, pointer_token(token));
+                        expand(root, child, declared, &mut next);
+                    }
+                    for (pattern, subschema) in resolved.patterns {
+                        let child =
+                            format!("{}/patternProperties/{}", pointer, pointer_token(pattern));
+                        expand(root, child, subschema, &mut next);
+                    }
+                    if let Some(additional) = resolved.additional {
+                        expand(
+                            root,
+                            format!("{}/additionalProperties", pointer),
+                            additional,
+                            &mut next,
+                        );
+                    }
+                }
+                Schema::Array(ref array) => {
+                    let item = token
+                        .parse()
+                        .ok()
+                        .and_then(|i| array.item_schema_with_pointer(i));
+                    if let Some((child, item)) = item {
+                        expand(root, format!("{}{}", pointer, child), item, &mut next);
+                    }
+                }
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+/// Whether every schema of `schemas` accepts `value`.
+fn accepted(root: &Schema, schemas: &[(String, &Schema)], value: Option<&Value>) -> bool {
+    match value {
+        Some(value) => schemas
+            .iter()
+            .all(|&(_, schema)| schema.accepts_within(root, value)),
+        None => true,
+    }
+}
+
+/// `parent` with the value at the last of `tokens` added or removed, and no
+/// other change.
+fn with_change(parent: &Value, tokens: &[String], value: Option<&Value>) -> Value {
+    let mut parent = parent.clone();
+    let last = &tokens[tokens.len() - 1];
+    match (&mut parent, value) {
+        (Value::Object(object), Some(value)) => {
+            object.insert(last.clone(), value.clone());
+        }
+        (Value::Object(object), None) => {
+            object.remove(last);
+        }
+        (Value::Array(array), Some(value)) => array.push(value.clone()),
+        (Value::Array(array), None) => {
+            array.pop();
+        }
+        _ => {}
+    }
+    parent
+}
+
+fn pointer(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|t| format!("/{}", pointer_token(t)))
+        .collect()
+}
+
+/// Lists the values that differ between `old` and `new`, two instances of
+/// `schema`, in document order with object keys sorted. Each change names the
+/// subschemas governing the value and whether the change turns them from
+/// accepting to rejecting or the other way around, for reviewing changes to
+/// documents like configuration files. Values whose type changed and values of
+/// arrays that got shorter or longer are reported as a whole.
+pub fn instance_diff(schema: &Schema, old: &Value, new: &Value) -> Vec<InstanceChange> {
+    let mut raw = vec![];
+    walk(&mut vec![], Some(old), Some(new), &mut raw);
+    raw.into_iter()
+        .map(|(tokens, old_value, new_value)| {
+            let schemas = governing(schema, &tokens);
+            let kind = match (old_value, new_value) {
+                (None, _) => ChangeKind::Added,
+                (_, None) => ChangeKind::Removed,
+                _ => ChangeKind::Changed,
+            };
+            let parent = &tokens[..tokens.len().saturating_sub(1)];
+            let parent_schemas = if tokens.is_empty() {
+                vec![]
+            } else {
+                governing(schema, parent)
+            };
+            let required = tokens.last().is_some_and(|key| {
+                parent_schemas.iter().any(|&(_, s)| match *s {
+                    Schema::Object(ref object) => object.required_properties().any(|r| r == key),
+                    _ => false,
+                })
+            });
+            let (before, after) = match kind {
+                ChangeKind::Changed => (
+                    accepted(schema, &schemas, old_value),
+                    accepted(schema, &schemas, new_value),
+                ),
+                _ => {
+                    let before = old.pointer(&pointer(parent));
+                    let after = before.map(|before| with_change(before, &tokens, new_value));
+                    (
+                        accepted(schema, &parent_schemas, before),
+                        accepted(schema, &parent_schemas, after.as_ref()),
+                    )
+                }
+            };
+            let verdict = match (before, after) {
+                (true, true) => Verdict::ValidInBoth,
+                (false, false) => Verdict::InvalidInBoth,
+                (true, false) => Verdict::NewlyInvalid,
+                (false, true) => Verdict::NewlyValid,
+            };
+            InstanceChange {
+                instance_path: pointer(&tokens),
+                kind,
+                old_preview: old_value.map(|v| preview(v, DEFAULT_PREVIEW_LENGTH)),
+                new_preview: new_value.map(|v| preview(v, DEFAULT_PREVIEW_LENGTH)),
+                required,
+                deprecated: schemas.iter().any(|&(_, s)| s.is_deprecated()),
+                schema_paths: schemas.into_iter().map(|(p, _)| p).collect(),
+                verdict,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    #[test]
+    fn changes() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {
+                "port": {"type": "integer", "minimum": 1.0, "maximum": 65535.0}
+            },
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string"},
+                "port": {"$ref": "#/definitions/port"},
+                "verbose": {"type": "boolean", "deprecated": true},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}}
+            }
+        }))
+        .unwrap();
+        let old = json!({"name": "db", "port": 80, "verbose": true, "tags": ["a", "b"]});
+        let new = json!({"port": 0, "verbose": false, "tags": ["a", "long"], "note": "x"});
+        let changes = instance_diff(&schema, &old, &new);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| {
+                (
+                    c.instance_path.as_str(),
+                    c.kind,
+                    c.verdict,
+                    c.required,
+                    c.deprecated,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "/name",
+                    ChangeKind::Removed,
+                    Verdict::NewlyInvalid,
+                    true,
+                    false
+                ),
+                (
+                    "/note",
+                    ChangeKind::Added,
+                    Verdict::ValidInBoth,
+                    false,
+                    false
+                ),
+                (
+                    "/port",
+                    ChangeKind::Changed,
+                    Verdict::NewlyInvalid,
+                    false,
+                    false
+                ),
+                (
+                    "/tags/1",
+                    ChangeKind::Changed,
+                    Verdict::NewlyInvalid,
+                    false,
+                    false
+                ),
+                (
+                    "/verbose",
+                    ChangeKind::Changed,
+                    Verdict::ValidInBoth,
+                    false,
+                    true
+                ),
+            ]
+        );
+        assert_eq!(changes[2].schema_paths, vec!["/definitions/port"]);
+        assert_eq!(changes[3].schema_paths, vec!["/properties/tags/items"]);
+        assert_eq!(changes[1].schema_paths, Vec::<String>::new());
+        assert_eq!(
+            changes[2].to_string(),
+            "`/port` changed: 80 -> 0 (newly invalid) [/definitions/port]"
+        );
+
+        // a change that keeps the value valid
+        let changes = instance_diff(
+            &schema,
+            &old,
+            &json!({"name": "cache", "port": 81, "verbose": true, "tags": ["a", "b"]}),
+        );
+        let verdicts: Vec<_> = changes
+            .iter()
+            .map(|c| (c.instance_path.as_str(), c.verdict))
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                ("/name", Verdict::ValidInBoth),
+                ("/port", Verdict::ValidInBoth)
+            ]
+        );
+        let json = serde_json::to_value(&changes[0]).unwrap();
+        assert_eq!(json["instancePath"], json!("/name"));
+        assert_eq!(json["oldPreview"], json!("\"db\""));
+        assert_eq!(json["verdict"], json!("validInBoth"));
+        assert_eq!(json["kind"], json!("changed"));
+    }
+}
@@ -1,25 +1,81 @@
 use serde_json::Value;
 
-use util::{JsonType, JsonValueExt};
-use schema::{Context, SchemaBase};
+use types::{JsonType, JsonValueExt};
+use object::PropertyMap;
+use schema::{Context, Schema, SchemaBase};
 use errors::ValidationError;
+use number::{deserialize_multiple_of, positive_step, NumericBound, Range};
+use util::deserialize_some;
 
 /// Schema for integer values like `42`.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
 pub struct IntegerSchema {
-    description: Option<String>,
-    id: Option<String>,
-    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
+    pub(crate) enum_values: Option<Vec<Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "const",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) const_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+    #[serde(skip)]
+    pub(crate) nullable: bool,
 
-    multiple_of: Option<f64>,
-    minimum: Option<f64>,
-    maximum: Option<f64>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_multiple_of"
+    )]
+    multiple_of: Option<NumericBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<NumericBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<NumericBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     exclusive_minimum: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     exclusive_maximum: Option<bool>,
 }
 
+impl IntegerSchema {
+    pub(crate) fn range(&self) -> Range {
+        Range::new(
+            &self.multiple_of,
+            &self.minimum,
+            &self.maximum,
+            self.exclusive_minimum,
+            self.exclusive_maximum,
+        )
+    }
+}
+
 impl SchemaBase for IntegerSchema {
     #[doc(hidden)]
     fn validate_inner<'json>(
@@ -29,10 +85,108 @@ impl SchemaBase for IntegerSchema {
         errors: &mut Vec<ValidationError<'json>>,
     ) {
         match value.get_type() {
-            JsonType::Integer => {}
-            ty => errors.push(ValidationError::type_mismatch(value, JsonType::Integer, ty)),
+            JsonType::Integer => self.range().validate(value, errors),
+            _ => errors.push(ValidationError::type_mismatch(value, JsonType::Integer)),
         }
     }
 }
 
-// TODO make builder for schema
+/// Builder for an integer schema.
+#[derive(Default, Debug)]
+pub struct IntegerSchemaBuilder {
+    description: Option<String>,
+    id: Option<String>,
+    title: Option<String>,
+    examples: Option<Vec<Value>>,
+    counterexamples: Option<Vec<Value>>,
+
+    multiple_of: Option<NumericBound>,
+    minimum: Option<NumericBound>,
+    maximum: Option<NumericBound>,
+    exclusive_minimum: bool,
+    exclusive_maximum: bool,
+}
+
+impl IntegerSchemaBuilder {
+    /// Set the description
+    pub fn description<V: Into<String>>(mut self, value: V) -> Self {
+        self.description = Some(value.into());
+        self
+    }
+
+    /// Set the ID
+    pub fn id<V: Into<String>>(mut self, value: V) -> Self {
+        self.id = Some(value.into());
+        self
+    }
+    /// Set the title
+    pub fn title<V: Into<String>>(mut self, value: V) -> Self {
+        self.title = Some(value.into());
+        self
+    }
+    /// Adds a value the schema accepts, see `Schema::self_test`.
+    pub fn example(mut self, value: Value) -> Self {
+        self.examples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
+    /// Adds a value the schema rejects, see `Schema::self_test`.
+    pub fn counterexample(mut self, value: Value) -> Self {
+        self.counterexamples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
+    /// Sets the minimum value, an integer or a float like `1e3`.
+    pub fn minimum<V: Into<NumericBound>>(mut self, value: V) -> Self {
+        self.minimum = Some(value.into());
+        self
+    }
+    /// Sets the maximum value, an integer or a float like `1e3`.
+    pub fn maximum<V: Into<NumericBound>>(mut self, value: V) -> Self {
+        self.maximum = Some(value.into());
+        self
+    }
+    /// Requires the value to be a multiple of `value`.
+    ///
+    /// # Panics
+    ///
+    /// If `value` isn't greater than 0.
+    pub fn multiple_of<V: Into<NumericBound>>(mut self, value: V) -> Self {
+        self.multiple_of = Some(positive_step(value.into()));
+        self
+    }
+    /// Makes the maximum value exclusive.
+    pub fn exclusive_maximum(mut self) -> Self {
+        self.exclusive_maximum = true;
+        self
+    }
+
+    /// Makes the minimum value exclusive.
+    pub fn exclusive_minimum(mut self) -> Self {
+        self.exclusive_minimum = true;
+        self
+    }
+
+    /// Returns the finished `Schema`.
+    pub fn build(self) -> Schema {
+        From::from(IntegerSchema {
+            description: self.description,
+            id: self.id,
+            title: self.title,
+            nullable: false,
+            default: None,
+            examples: self.examples,
+            counterexamples: self.counterexamples,
+            deprecated: None,
+            removed_in: None,
+            enum_values: None,
+            const_value: None,
+            definitions: None,
+            dollar_defs: false,
+
+            multiple_of: self.multiple_of,
+            minimum: self.minimum,
+            maximum: self.maximum,
+            exclusive_minimum: Some(self.exclusive_minimum),
+            exclusive_maximum: Some(self.exclusive_maximum),
+        })
+    }
+}
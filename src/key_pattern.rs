@@ -0,0 +1,278 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+
+use limits::{RegexError, SchemaLimits};
+use util::{compile_regex, parse_regex};
+
+/// A compiled pattern of `patternProperties`. Common patterns, like `^[0-9]+$`
+/// for maps keyed by numbers or `^x-` for extensions, are matched with a
+/// direct string check instead of the regular expression, as objects with many
+/// keys match every key against every pattern. Both ways give the same result.
+#[derive(Clone, Debug)]
+pub(crate) enum KeyPattern {
+    /// `^[0-9]+$` or, if `empty` is set, `^[0-9]*$`.
+    Digits { empty: bool },
+    /// `^literal$`.
+    Exact(String),
+    /// `^literal`.
+    Prefix(String),
+    /// `literal$`.
+    Suffix(String),
+    /// `literal`.
+    Contains(String),
+    /// Any other pattern.
+    Regex(Regex),
+}
+
+impl KeyPattern {
+    /// Compiles `pattern` within `limits`. The recognized patterns are only
+    /// parsed, to reject the invalid ones, as they're checked without the
+    /// regular expression.
+    pub(crate) fn compile(pattern: &str, limits: &SchemaLimits) -> Result<KeyPattern, RegexError> {
+        match KeyPattern::analyze(pattern) {
+            Some(matcher) => parse_regex(pattern, limits).map(|_| matcher),
+            None => compile_regex(pattern, limits).map(KeyPattern::Regex),
+        }
+    }
+
+    /// Recognizes the patterns that need no regular expression.
+    fn analyze(pattern: &str) -> Option<KeyPattern> {
+        match pattern {
+            "^[0-9]+$" => return Some(KeyPattern::Digits { empty: false }),
+            "^[0-9]*$" => return Some(KeyPattern::Digits { empty: true }),
+            _ => {}
+        }
+        let (start, rest) = match pattern.strip_prefix('^') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        // a `$` after an odd number of backslashes is escaped
+        let end_anchor = rest.ends_with('$') && {
+            let before = &rest[..rest.len() - 1];
+            (before.len() - before.trim_end_matches('\\').len()) % 2 == 0
+        };
+        let rest = if end_anchor {
+            &rest[..rest.len() - 1]
+        } else {
+            rest
+        };
+        let literal = literal(rest)?;
+        Some(match (start, end_anchor) {
+            (true, true) => KeyPattern::Exact(literal),
+            (true, false) => KeyPattern::Prefix(literal),
+            (false, true) => KeyPattern::Suffix(literal),
+            (false, false) => KeyPattern::Contains(literal),
+        })
+    }
+
+    /// Whether `key` matches the pattern.
+    pub(crate) fn is_match(&self, key: &str) -> bool {
+        match *self {
+            KeyPattern::Digits { empty } => {
+                (empty || !key.is_empty()) && key.bytes().all(|b| b.is_ascii_digit())
+            }
+            KeyPattern::Exact(ref literal) => key == literal,
+            KeyPattern::Prefix(ref literal) => key.starts_with(literal.as_str()),
+            KeyPattern::Suffix(ref literal) => key.ends_with(literal.as_str()),
+            KeyPattern::Contains(ref literal) => key.contains(literal.as_str()),
+            KeyPattern::Regex(ref regex) => regex.is_match(key),
+        }
+    }
+}
+
+/// The patterns of `patternProperties`, compiled once when the schema is built,
+/// in the order of the map.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct KeyPatterns {
+    limits: SchemaLimits,
+    compiled: Vec<Result<KeyPattern, RegexError>>,
+}
+
+impl KeyPatterns {
+    /// Compiles `patterns` within the default limits.
+    pub(crate) fn compile<'a, I>(patterns: I) -> KeyPatterns
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        let limits = SchemaLimits::default();
+        let compiled = patterns
+            .into_iter()
+            .map(|pattern| KeyPattern::compile(pattern, &limits))
+            .collect();
+        KeyPatterns { limits, compiled }
+    }
+
+    /// The pattern at `index`, which is `pattern`, compiled within `limits`:
+    /// the one compiled before if the limits for regular expressions are the
+    /// same, or else compiled again.
+    pub(crate) fn get<'a>(
+        &'a self,
+        index: usize,
+        pattern: &str,
+        limits: &SchemaLimits,
+    ) -> Cow<'a, Result<KeyPattern, RegexError>> {
+        match self.compiled.get(index) {
+            Some(compiled) if limits.same_for_regexes(&self.limits) => Cow::Borrowed(compiled),
+            _ => Cow::Owned(KeyPattern::compile(pattern, limits)),
+        }
+    }
+}
+
+/// The text a pattern without special characters matches. Punctuation may be
+/// escaped with a backslash.
+fn literal(pattern: &str) -> Option<String> {
+    let mut literal = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped.is_ascii_punctuation() => literal.push(escaped),
+                _ => return None,
+            },
+            '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$' => {
+                return None
+            }
+            c => literal.push(c),
+        }
+    }
+    Some(literal)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{self, Map, Value};
+
+    use super::*;
+    use errors::ErrorCode;
+    use options::ValidateOptions;
+    use schema::Schema;
+
+    /// Keys made of the characters the patterns use, from a fixed seed.
+    fn keys(count: usize) -> Vec<String> {
+        let alphabet: Vec<char> = "0123456789x-_.$^\\abé\n ".chars().collect();
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..count)
+            .map(|_| {
+                let len = (next() % 8) as usize;
+                (0..len)
+                    .map(|_| alphabet[(next() % alphabet.len() as u64) as usize])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recognized_patterns() {
+        let fast = |pattern| match KeyPattern::analyze(pattern) {
+            Some(KeyPattern::Regex(_)) | None => None,
+            Some(matcher) => Some(format!("{:?}", matcher)),
+        };
+        assert_eq!(fast("^[0-9]+$").unwrap(), "Digits { empty: false }");
+        assert_eq!(fast("^x-").unwrap(), "Prefix(\"x-\")");
+        assert_eq!(fast("_id$").unwrap(), "Suffix(\"_id\")");
+        assert_eq!(fast("^a\\.b$").unwrap(), "Exact(\"a.b\")");
+        assert_eq!(fast("^a\\$").unwrap(), "Prefix(\"a$\")");
+        assert_eq!(fast("^a\\\\$").unwrap(), "Exact(\"a\\\\\")");
+        for pattern in &[
+            "^\\d+$",
+            "^[0-9]{3}$",
+            "^a.b",
+            "(?i)^x-",
+            "\\w$",
+            "a|b",
+            "^a^",
+        ] {
+            assert_eq!(fast(pattern), None, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn same_as_regex() {
+        let patterns = [
+            "^[0-9]+$",
+            "^[0-9]*$",
+            "^x-",
+            "^x-$",
+            "_$",
+            "\\.",
+            "^\\$",
+            "\\\\$",
+            "\\$$",
+            "^",
+            "$",
+            "^$",
+            "",
+            "é",
+            "^[0-9]{2}$",
+            "^\\d+$",
+        ];
+        let keys = keys(5_000);
+        for pattern in &patterns {
+            let regex = Regex::new(pattern).unwrap();
+            let matcher = KeyPattern::compile(pattern, &SchemaLimits::default()).unwrap();
+            for key in &keys {
+                assert_eq!(
+                    matcher.is_match(key),
+                    regex.is_match(key),
+                    "{} on {:?}",
+                    pattern,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn many_numeric_keys() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "patternProperties": {"^[0-9]+$": {"type": "array", "maxItems": 1}},
+            "additionalProperties": false
+        }))
+        .unwrap();
+        // compiled with the schema, and without the regular expression
+        match schema {
+            Schema::Object(ref s) => {
+                let matcher = s.key_patterns.get(0, "^[0-9]+$", &SchemaLimits::default());
+                assert!(matches!(*matcher, Ok(KeyPattern::Digits { empty: false })));
+            }
+            _ => panic!("not an object schema"),
+        }
+
+        let object: Map<String, Value> =
+            (0..200_000).map(|i| (i.to_string(), json!([1]))).collect();
+        let mut object = Value::Object(object);
+        schema.validate(&object).unwrap();
+        object["12a"] = json!([1]);
+        object["7"] = json!([1, 2]);
+        let errors = schema.validate(&object).unwrap_err().0;
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn invalid_and_limited_patterns() {
+        // recognized as a prefix, but `<` can't be escaped
+        assert!(KeyPattern::compile("^a\\<", &SchemaLimits::default()).is_err());
+
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "patternProperties": {"^x-": {"type": "integer"}}
+        }))
+        .unwrap();
+        let value = json!({"x-a": 1});
+        let mut options = ValidateOptions::default();
+        assert!(schema.validate_with(&value, &options).is_valid());
+        // the patterns compiled with the schema are compiled again within other limits
+        options.limits.max_pattern_length = 2;
+        let output = schema.validate_with(&value, &options);
+        let codes: Vec<_> = output.errors.iter().map(|e| e.reason.code()).collect();
+        assert_eq!(codes, vec![ErrorCode::InvalidRegex]);
+    }
+}
@@ -1,27 +1,38 @@
 //! A JSON schema validation library.
 //! TODO
 //! [ ] Null schema
-//! [ ] schema references per JSON pointer syntax
-//! [ ] enums
+//! [x] schema references per JSON pointer syntax
+//! [x] enums
 #![deny(missing_debug_implementations, missing_copy_implementations, trivial_casts,
        trivial_numeric_casts, unsafe_code, unstable_features, unused_import_braces,
        unused_qualifications)]
 #![warn(missing_docs)]
 #![cfg_attr(feature = "clippy", feature(plugin))]
 #![cfg_attr(feature = "clippy", plugin(clippy))]
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 
+#[cfg(feature = "arbitrary-precision")]
+extern crate bigdecimal;
 extern crate chrono;
 #[macro_use]
 extern crate error_chain;
+#[cfg(feature = "preserve_order")]
+extern crate indexmap;
 extern crate regex;
+extern crate regex_syntax;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
+#[cfg(feature = "normalize-unicode")]
+extern crate unicode_normalization;
 extern crate url;
 extern crate quote;
 
+#[macro_use]
+mod macros;
+
 /// Error and result types
 pub mod errors;
 /// Basic types
@@ -40,9 +51,121 @@ pub mod boolean;
 pub mod integer;
 /// Implementation of references
 pub mod reference;
+/// Implementation of `allOf`, `anyOf`, `oneOf` and `not`
+pub mod combinator;
+/// Structured validation output
+pub mod output;
+/// Options for a validation run
+pub mod options;
+/// Support for schema dialects other than plain JSON schema
+pub mod dialect;
+/// Detection of duplicate keys in JSON documents
+pub mod duplicate_keys;
+/// Sharing of identical subschemas
+pub mod dedup;
+/// Removal of object properties a schema doesn't declare
+pub mod sanitize;
+/// The types of JSON values
+pub mod types;
+/// Repeated validation with reused buffers
+pub mod session;
+/// Schema documents that reference each other
+pub mod store;
+/// Tracking which subschemas a set of documents exercises
+pub mod coverage;
+/// Detection of common mistakes, like passing the instance as the schema
+pub mod diagnostics;
+/// Rendering of error messages, for example in other languages
+pub mod render;
+/// Reports about many validated files, for example as SARIF
+pub mod batch;
+/// Static checks whether a schema can accept any value
+pub mod satisfiability;
+/// Generates valid and invalid example values from schemas
+pub mod generate;
+/// Transformations applied to documents before validation
+pub mod preprocess;
+/// JSON Patches repairing simple validation errors
+pub mod repair;
+/// Limits on schemas from untrusted sources
+pub mod limits;
+/// Counts of evaluations, failures and time per subschema
+pub mod stats;
+/// Comparison of how two versions of a schema judge the same documents
+pub mod compare;
+/// Checking schemas against the examples and counterexamples they carry
+pub mod self_test;
+/// Differences between two instances, annotated with the subschemas they touch
+pub mod instance_diff;
+/// Markdown documentation generated from schemas
+pub mod docs;
+/// State of integrators carried into validation
+pub mod extensions;
+/// Description of the drafts, keywords, formats and features this build supports
+pub mod capabilities;
+/// Reading schemas and instances from files, with errors naming the file
+pub mod files;
+/// Reports on how the references of a schema resolve
+pub mod resolution;
+/// Checking whether one schema accepts every value another accepts
+pub mod subset;
+/// Line and column of the keywords of schema documents
+pub mod locations;
+/// Validation of partial updates and JSON Merge Patches
+pub mod merge_patch;
+/// Validation of Rust strings, numbers and booleans without building JSON values
+pub mod scalar;
+/// Checks of instances for data validation can't judge reliably
+pub mod audit;
+/// Identifiers of subschemas that survive edits of unrelated parts
+pub mod ids;
+/// A flat view of the constraints of a schema, for deriving database indices
+pub mod flatten;
+/// Non-standard comparisons between the values of an object
+pub mod cross_field;
+/// The commonly used types, for `use json_schema::prelude::*;`
+pub mod prelude;
+/// Telling invalid values from schemas that couldn't be applied
+pub mod outcome;
 
+mod coerce;
+mod key_pattern;
+mod rfc3339;
 mod util;
 
 pub use schema::{Schema, SchemaBase};
+pub use errors::{Error, ErrorKind, Result};
+pub use options::{Draft, PatternMode, RequiredRejects, SamplingPolicy, ValidateOptions};
+pub use sanitize::{SanitizeReport, UnknownPolicy};
+pub use session::{validate_all, Session};
+pub use store::{SchemaStore, Version};
+pub use coverage::{CoverageReport, CoverageSession};
+pub use render::{EnglishRenderer, MessageRenderer};
+pub use batch::BatchReport;
+pub use satisfiability::Satisfiability;
+pub use generate::ExpectedViolation;
+pub use preprocess::Preprocessor;
+pub use repair::{PatchOp, RepairPolicy};
+pub use limits::SchemaLimits;
+pub use stats::{StatsCollector, StatsReport};
+pub use compare::{compare_behavior, BehaviorDiff};
+pub use instance_diff::{instance_diff, InstanceChange};
+pub use docs::{generate_markdown, DocOptions};
+pub use extensions::Extensions;
+pub use capabilities::{capabilities, Capabilities};
+pub use resolution::ResolutionReport;
+pub use subset::{is_subschema, SubsetResult};
+pub use locations::{SourceLocation, SourceMap};
+pub use merge_patch::merge_patch;
+pub use audit::{audit_instance, audit_text, InstanceIssue, InstanceLimits};
+pub use ids::SubschemaIds;
+pub use flatten::PropertyConstraint;
+pub use cross_field::{CompareOp, Comparison, MissingOperand, OperandType};
+pub use outcome::{SchemaProblem, ValidationOutcome};
+pub use self_test::SelfTestReport;
+pub use types::{JsonType, JsonValueExt};
+pub use dialect::Dialect;
+pub use duplicate_keys::{check_duplicate_keys, DuplicateKey};
 pub use array::ArraySchemaBuilder;
+pub use combinator::{all_of, any_of, not, one_of, BranchMatch};
 pub use object::ObjectSchemaBuilder;
@@ -0,0 +1,343 @@
+use std::fmt;
+
+use serde_json::{self, Value};
+
+use errors::{ErrorKind, Result};
+use schema::Schema;
+use util::compile_regex;
+
+/// Limits on the size of schemas and on the regular expressions in them, for
+/// schemas from untrusted sources. Set them for parsing with
+/// `Schema::from_value_with_limits` and for the regular expressions compiled
+/// during validation with `ValidateOptions::limits`. The defaults are generous
+/// enough for handwritten schemas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SchemaLimits {
+    /// The longest pattern of `pattern` or `patternProperties`, in bytes.
+    pub max_pattern_length: usize,
+    /// The size a regular expression may take once compiled, in bytes.
+    pub regex_size_limit: usize,
+    /// The size of the cache of the lazily built DFA of a regular expression,
+    /// in bytes. Matching gets slower, not wrong, if the cache is too small.
+    pub regex_dfa_size_limit: usize,
+    /// Reject patterns that repeat a group containing a repetition, like
+    /// `(a+)+`.
+    pub reject_nested_quantifiers: bool,
+    /// The number of subschemas, counting the schema itself.
+    pub max_subschemas: usize,
+    /// The number of entries of all `definitions` together.
+    pub max_definitions: usize,
+    /// The number of `$ref`s that may lead to another `$ref` in a row.
+    pub max_ref_depth: usize,
+}
+
+impl Default for SchemaLimits {
+    fn default() -> SchemaLimits {
+        SchemaLimits {
+            max_pattern_length: 4096,
+            regex_size_limit: 10 * (1 << 20),
+            regex_dfa_size_limit: 2 * (1 << 20),
+            reject_nested_quantifiers: false,
+            max_subschemas: 100_000,
+            max_definitions: 10_000,
+            max_ref_depth: 32,
+        }
+    }
+}
+
+impl SchemaLimits {
+    /// Whether the limits for regular expressions are the same as in `other`, so
+    /// patterns compiled within either are compiled within both.
+    pub(crate) fn same_for_regexes(&self, other: &SchemaLimits) -> bool {
+        self.max_pattern_length == other.max_pattern_length
+            && self.regex_size_limit == other.regex_size_limit
+            && self.regex_dfa_size_limit == other.regex_dfa_size_limit
+            && self.reject_nested_quantifiers == other.reject_nested_quantifiers
+    }
+}
+
+/// Why a regular expression couldn't be compiled, see `util::compile_regex`.
+#[derive(Clone, Debug)]
+pub(crate) struct RegexError {
+    /// The name of the field of `SchemaLimits` that rejected it, if any.
+    pub limit: Option<&'static str>,
+    pub message: String,
+}
+
+impl RegexError {
+    pub(crate) fn limit(limit: &'static str, message: String) -> RegexError {
+        RegexError {
+            limit: Some(limit),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+fn exceeded(limit: &str, pointer: &str, reason: String) -> ErrorKind {
+    ErrorKind::LimitExceeded {
+        limit: limit.into(),
+        pointer: pointer.into(),
+        reason,
+    }
+}
+
+struct Checker<'a> {
+    root: &'a Schema,
+    limits: &'a SchemaLimits,
+    subschemas: usize,
+    definitions: usize,
+}
+
+impl<'a> Checker<'a> {
+    fn check(&mut self, schema: &Schema, pointer: &str) -> Result<()> {
+        self.subschemas += 1;
+        if self.subschemas > self.limits.max_subschemas {
+            bail!(exceeded(
+                "max_subschemas",
+                pointer,
+                format!(
+                    "the schema has more than {} subschemas",
+                    self.limits.max_subschemas
+                )
+            ));
+        }
+        self.definitions += schema.definitions().map_or(0, |d| d.len());
+        if self.definitions > self.limits.max_definitions {
+            bail!(exceeded(
+                "max_definitions",
+                pointer,
+                format!(
+                    "the schema has more than {} definitions",
+                    self.limits.max_definitions
+                )
+            ));
+        }
+        self.check_patterns(schema, pointer)?;
+        self.check_ref_depth(schema, pointer)?;
+        for (step, child) in schema.children_with_pointers() {
+            self.check(child, &format!("{}{}", pointer, step))?;
+        }
+        Ok(())
+    }
+
+    fn check_patterns(&self, schema: &Schema, pointer: &str) -> Result<()> {
+        let patterns: Vec<&String> = match *schema {
+            Schema::String(ref s) => s.pattern.iter().collect(),
+            Schema::Object(ref s) => s.pattern_properties.iter().flat_map(|p| p.keys()).collect(),
+            _ => vec![],
+        };
+        for pattern in patterns {
+            if let Err(e) = compile_regex(pattern, self.limits) {
+                match e.limit {
+                    Some(limit) => bail!(exceeded(limit, pointer, e.message)),
+                    None => bail!(ErrorKind::InvalidRegex(e.message)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Follows the chain of references starting at `schema`.
+    fn check_ref_depth(&self, schema: &Schema, pointer: &str) -> Result<()> {
+        let mut depth = 0;
+        let mut current = schema;
+        while let Schema::Reference(ref reference) = *current {
+            depth += 1;
+            if depth > self.limits.max_ref_depth {
+                bail!(exceeded(
+                    "max_ref_depth",
+                    pointer,
+                    format!(
+                        "more than {} references lead to each other",
+                        self.limits.max_ref_depth
+                    )
+                ));
+            }
+            current = match reference.resolve(self.root) {
+                Some(target) => target,
+                None => break,
+            };
+        }
+        Ok(())
+    }
+}
+
+impl Schema {
+    /// Checks that the schema stays within `limits`, failing with
+    /// `ErrorKind::LimitExceeded` naming the first limit exceeded or
+    /// `ErrorKind::InvalidRegex` for a pattern that isn't a regular expression.
+    pub fn check_limits(&self, limits: &SchemaLimits) -> Result<()> {
+        let mut checker = Checker {
+            root: self,
+            limits,
+            subschemas: 0,
+            definitions: 0,
+        };
+        checker.check(self, "")
+    }
+
+    /// Parses a schema from an untrusted source, see `Schema::check_limits`.
+    pub fn from_value_with_limits(value: Value, limits: &SchemaLimits) -> Result<Schema> {
+        let schema: Schema = serde_json::from_value(value)?;
+        schema.check_limits(limits)?;
+        Ok(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use errors::ErrorCode;
+    use options::ValidateOptions;
+
+    fn limit_of(value: Value, limits: &SchemaLimits) -> (String, String) {
+        match *Schema::from_value_with_limits(value, limits)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::LimitExceeded {
+                ref limit,
+                ref pointer,
+                ..
+            } => (limit.clone(), pointer.clone()),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn fixtures_within_defaults() {
+        for name in &[
+            "canada-schema",
+            "library",
+            "order",
+            "sarif-2.1.0-subset",
+            "schema-with-refs",
+        ] {
+            let file = File::open(format!("data/{}.json", name)).unwrap();
+            let value: Value = serde_json::from_reader(file).unwrap();
+            Schema::from_value_with_limits(value, &SchemaLimits::default()).unwrap();
+        }
+    }
+
+    #[test]
+    fn pattern_limits() {
+        let schema = |pattern: &str| {
+            json!({
+                "type": "object",
+                "properties": {"name": {"type": "string", "pattern": pattern}}
+            })
+        };
+        let limits = SchemaLimits {
+            max_pattern_length: 8,
+            ..SchemaLimits::default()
+        };
+        assert_eq!(
+            limit_of(schema("^[a-z]{1,20}$"), &limits),
+            ("max_pattern_length".into(), "/properties/name".into())
+        );
+        Schema::from_value_with_limits(schema("^[a-z]+$"), &limits).unwrap();
+
+        let limits = SchemaLimits {
+            reject_nested_quantifiers: true,
+            ..SchemaLimits::default()
+        };
+        for nested in &["(a+)+", "^(\\w*)*$", "((ab)*c)+", "(x+){2,}"] {
+            assert_eq!(
+                limit_of(schema(nested), &limits).0,
+                "reject_nested_quantifiers"
+            );
+        }
+        for flat in &["(ab)+", "a+b*", "[(a+)]+", "\\(a+\\)+", "(a{2})"] {
+            Schema::from_value_with_limits(schema(flat), &limits).unwrap();
+        }
+
+        let limits = SchemaLimits {
+            regex_size_limit: 100,
+            ..SchemaLimits::default()
+        };
+        let huge = json!({
+            "type": "object",
+            "patternProperties": {"^\\w{100}$": {"type": "string"}}
+        });
+        assert_eq!(limit_of(huge, &limits).0, "regex_size_limit");
+
+        // patterns compiled during validation are held to the limits of the options
+        let value = schema("^[a-z]{1,20}$");
+        let schema: Schema = serde_json::from_value(value).unwrap();
+        let options = ValidateOptions {
+            limits: SchemaLimits {
+                max_pattern_length: 8,
+                ..SchemaLimits::default()
+            },
+            ..ValidateOptions::default()
+        };
+        let input = json!({"name": "abc"});
+        let output = schema.validate_with(&input, &options);
+        assert_eq!(output.errors[0].reason.code(), ErrorCode::InvalidRegex);
+        assert!(output.errors[0]
+            .reason
+            .to_string()
+            .contains("at most 8 are allowed"));
+        assert!(schema.validate(&input).is_ok());
+    }
+
+    #[test]
+    fn size_limits() {
+        let schema = json!({
+            "definitions": {
+                "a": {"$ref": "#/definitions/b"},
+                "b": {"$ref": "#/definitions/c"},
+                "c": {"type": "string"}
+            },
+            "type": "object",
+            "properties": {
+                "x": {"$ref": "#/definitions/a"},
+                "y": {"type": "array", "items": {"type": "integer"}}
+            }
+        });
+        Schema::from_value_with_limits(schema.clone(), &SchemaLimits::default()).unwrap();
+
+        let limits = SchemaLimits {
+            max_subschemas: 6,
+            ..SchemaLimits::default()
+        };
+        assert_eq!(limit_of(schema.clone(), &limits).0, "max_subschemas");
+        let limits = SchemaLimits {
+            max_subschemas: 7,
+            ..SchemaLimits::default()
+        };
+        Schema::from_value_with_limits(schema.clone(), &limits).unwrap();
+
+        let limits = SchemaLimits {
+            max_definitions: 2,
+            ..SchemaLimits::default()
+        };
+        assert_eq!(
+            limit_of(schema.clone(), &limits),
+            ("max_definitions".into(), "".into())
+        );
+
+        let limits = SchemaLimits {
+            max_ref_depth: 2,
+            ..SchemaLimits::default()
+        };
+        assert_eq!(
+            limit_of(schema.clone(), &limits),
+            ("max_ref_depth".into(), "/properties/x".into())
+        );
+        let error = Schema::from_value_with_limits(schema, &limits).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Schema exceeds `max_ref_depth` at `/properties/x`: \
+             more than 2 references lead to each other"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_json;
+
+use errors::Result;
+use schema::Schema;
+use util::pointer_token;
+
+/// Where a part of a JSON document starts in its text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The byte offset.
+    pub offset: usize,
+    /// The line, starting at 1.
+    pub line: usize,
+    /// The column in characters, starting at 1.
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The locations of the values of a JSON document by their JSON pointers, for
+/// pointing editors at the keywords diagnostics are about. The location of an
+/// object member is that of its key, so `/properties/name/type` is where
+/// `"type"` starts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceMap {
+    locations: BTreeMap<String, SourceLocation>,
+}
+
+impl SourceMap {
+    /// Scans the JSON text `text`. The scan stops at the first syntax error,
+    /// keeping the locations found before it.
+    pub fn new(text: &str) -> SourceMap {
+        let mut scanner = Scanner {
+            text: text.as_bytes(),
+            position: 0,
+            line: 1,
+            column: 1,
+            locations: BTreeMap::new(),
+        };
+        if text.starts_with('\u{feff}') {
+            scanner.position = '\u{feff}'.len_utf8();
+        }
+        scanner.skip_whitespace();
+        scanner.value(String::new(), true);
+        SourceMap {
+            locations: scanner.locations,
+        }
+    }
+
+    /// The location of the value at `pointer`.
+    pub fn get(&self, pointer: &str) -> Option<SourceLocation> {
+        self.locations.get(pointer).cloned()
+    }
+
+    /// The location of the value at `pointer` or, if the text doesn't have it,
+    /// like a keyword a parsed schema adds, of the closest value holding it.
+    pub fn locate(&self, pointer: &str) -> Option<SourceLocation> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(location) = self.get(pointer) {
+                return Some(location);
+            }
+            match pointer.rfind('/') {
+                Some(i) => pointer = &pointer[..i],
+                None => return None,
+            }
+        }
+    }
+}
+
+impl Schema {
+    /// Parses a schema from JSON text, together with the locations of its
+    /// keywords in the text, to report diagnostics by line and column.
+    pub fn from_str_with_locations(text: &str) -> Result<(Schema, SourceMap)> {
+        let schema = text.parse()?;
+        Ok((schema, SourceMap::new(text)))
+    }
+}
+
+/// Walks JSON text, recording the location of every value.
+struct Scanner<'t> {
+    text: &'t [u8],
+    position: usize,
+    line: usize,
+    column: usize,
+    locations: BTreeMap<String, SourceLocation>,
+}
+
+impl<'t> Scanner<'t> {
+    fn location(&self) -> SourceLocation {
+        SourceLocation {
+            offset: self.position,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).cloned()
+    }
+
+    fn advance(&mut self) {
+        let byte = self.text[self.position];
+        self.position += 1;
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if byte & 0xc0 != 0x80 {
+            // not a continuation byte, so a new character
+            self.column += 1;
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.advance();
+        }
+    }
+
+    /// Scans the value at `pointer`, recording its location if `record` is set.
+    /// Returns `None` at a syntax error.
+    fn value(&mut self, pointer: String, record: bool) -> Option<()> {
+        if record {
+            let location = self.location();
+            self.locations.insert(pointer.clone(), location);
+        }
+        match self.peek()? {
+            b'{' => self.object(&pointer),
+            b'[' => self.array(&pointer),
+            b'"' => self.string().map(|_| ()),
+            _ => {
+                let start = self.position;
+                while let Some(byte) = self.peek() {
+                    match byte {
+                        b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r' => break,
+                        _ => self.advance(),
+                    }
+                }
+                Some(()).filter(|_| self.position > start)
+            }
+        }
+    }
+
+    fn object(&mut self, pointer: &str) -> Option<()> {
+        self.advance();
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.advance();
+            return Some(());
+        }
+        loop {
+            self.skip_whitespace();
+            let location = self.location();
+            let key = self.string()?;
+            let child = format!("{}/{}", pointer, pointer_token(&key));
+            self.locations.insert(child.clone(), location);
+            self.skip_whitespace();
+            if self.peek()? != b':' {
+                return None;
+            }
+            self.advance();
+            self.skip_whitespace();
+            self.value(child, false)?;
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.advance(),
+                b'}' => {
+                    self.advance();
+                    return Some(());
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self, pointer: &str) -> Option<()> {
+        self.advance();
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            self.advance();
+            return Some(());
+        }
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            self.value(format!("{}/{}", pointer, index), true)?;
+            index += 1;
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.advance(),
+                b']' => {
+                    self.advance();
+                    return Some(());
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Scans a string, returning its decoded contents.
+    fn string(&mut self) -> Option<String> {
+        if self.peek()? != b'"' {
+            return None;
+        }
+        let start = self.position;
+        self.advance();
+        loop {
+            match self.peek()? {
+                b'\\' => {
+                    self.advance();
+                    self.peek()?;
+                    self.advance();
+                }
+                b'"' => {
+                    self.advance();
+                    break;
+                }
+                _ => self.advance(),
+            }
+        }
+        serde_json::from_slice(&self.text[start..self.position]).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "tags": {
+            "type": "array",
+            "items": [{"type": "string"}, {"type": "integer", "maximum": 10}]
+        },
+        "näme/€": {"type": "string", "minLength": 1}
+    },
+    "required": ["tags"]
+}"#;
+
+    fn at(line: usize, column: usize) -> (usize, usize) {
+        (line, column)
+    }
+
+    #[test]
+    fn keyword_locations() {
+        let (schema, locations) = Schema::from_str_with_locations(SCHEMA).unwrap();
+        assert!(schema
+            .validate(&json!({"tags": ["a", 3], "näme/€": "x"}))
+            .is_ok());
+        let position = |pointer: &str| {
+            let location = locations.get(pointer).unwrap();
+            assert_eq!(&SCHEMA[location.offset..location.offset + 1], "\"");
+            at(location.line, location.column)
+        };
+        assert_eq!(at(1, 1), {
+            let root = locations.get("").unwrap();
+            (root.line, root.column)
+        });
+        assert_eq!(position("/properties/tags/items/1/maximum"), at(6, 63));
+        assert_eq!(position("/properties/näme~1€/minLength"), at(8, 38));
+        // the last keyword in the file
+        assert_eq!(position("/required"), at(10, 5));
+        let tags = locations.get("/required/0").unwrap();
+        assert_eq!(at(tags.line, tags.column), at(10, 18));
+        assert_eq!(tags.to_string(), "10:18");
+
+        // keywords a parsed schema adds are located at their subschema
+        let exclusive = locations.locate("/properties/tags/items/1/exclusiveMaximum");
+        assert_eq!(exclusive, locations.get("/properties/tags/items/1"));
+        assert_eq!(
+            locations.get("/properties/tags/items/1/exclusiveMaximum"),
+            None
+        );
+    }
+
+    #[test]
+    fn broken_text() {
+        let locations = SourceMap::new("\u{feff}{\"a\": {\"b\": 1,, \"c\": 2}}");
+        assert_eq!(locations.get("/a/b").unwrap().column, 8);
+        assert_eq!(locations.get("/a/c"), None);
+    }
+}
@@ -0,0 +1,22 @@
+/// Asserts that `$value` is invalid according to `$schema` and that one of the
+/// errors matches the `ErrorKind` pattern `$kind`.
+///
+/// ```ignore
+/// assert_invalid!(schema, json!({}), ErrorKind::MissingProperties(_));
+/// ```
+#[cfg(any(test, feature = "test-util"))]
+#[macro_export]
+macro_rules! assert_invalid {
+    ($schema:expr, $value:expr, $kind:pat) => {{
+        let value = $value;
+        match $schema.validate(&value) {
+            Ok(()) => panic!("expected `{}` to be invalid", value),
+            Err(errors) => assert!(
+                errors.contains_kind(|kind| matches!(*kind, $kind)),
+                "no error matches `{}`:\n{}",
+                stringify!($kind),
+                errors
+            ),
+        }
+    }};
+}
@@ -0,0 +1,157 @@
+use serde_json::Value;
+
+use errors::{Result, ValidationErrors};
+use options::ValidateOptions;
+use schema::Schema;
+
+/// Applies the JSON Merge Patch `patch` to `target`, as in RFC 7386: members
+/// of an object patch that are `null` are removed, other members are merged
+/// recursively, and any other patch replaces the target.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let patch = match *patch {
+        Value::Object(ref patch) => patch,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(ref mut target) = *target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+impl Schema {
+    /// Validates a partial update, like the body of a PATCH request, see
+    /// `ValidateOptions::partial`: the properties present have to be valid,
+    /// but missing ones aren't errors.
+    pub fn validate_partial<'json>(
+        &self,
+        value: &'json Value,
+    ) -> ::std::result::Result<(), ValidationErrors<'json>> {
+        let options = ValidateOptions {
+            partial: true,
+            ..ValidateOptions::default()
+        };
+        let output = self.validate_with(value, &options);
+        if output.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(output.errors))
+        }
+    }
+
+    /// Applies the JSON Merge Patch `patch` to a copy of `base` and validates
+    /// the result, which is returned if it's valid. Unlike `validate_partial`,
+    /// this catches patches removing required properties. The paths of the
+    /// errors are those of the merged document.
+    pub fn validate_merge_patch(&self, base: &Value, patch: &Value) -> Result<Value> {
+        let mut merged = base.clone();
+        merge_patch(&mut merged, patch);
+        self.validate_parsed(&merged, &ValidateOptions::default())?;
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::{ErrorCode, ErrorKind};
+
+    fn schema() -> Schema {
+        serde_json::from_value(json!({
+            "type": "object",
+            "required": ["name", "address"],
+            "minProperties": 2,
+            "additionalProperties": true,
+            "properties": {
+                "name": {"type": "string"},
+                "address": {
+                    "type": "object",
+                    "required": ["street", "city"],
+                    "properties": {
+                        "street": {"type": "string"},
+                        "city": {"type": "string"}
+                    }
+                },
+                "contacts": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["email"],
+                        "properties": {"email": {"type": "string"}}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn partial_updates() {
+        let schema = schema();
+        let update = json!({"address": {"city": "Vienna"}});
+        assert!(schema.validate(&update).is_err());
+        assert!(schema.validate_partial(&update).is_ok());
+
+        let update = json!({"address": {"city": 1}});
+        let errors = schema.validate_partial(&update).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::TypeMismatch]);
+        assert_eq!(errors.0[0].instance_path, "/address/city");
+
+        // array elements are replaced as a whole
+        let update = json!({"contacts": [{}]});
+        let errors = schema.validate_partial(&update).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::MissingProperties]);
+    }
+
+    #[test]
+    fn merge_patches() {
+        let schema = schema();
+        let base = json!({
+            "name": "Ann",
+            "address": {"street": "Ring 1", "city": "Graz"}
+        });
+        let merged = schema
+            .validate_merge_patch(&base, &json!({"address": {"city": "Vienna"}}))
+            .unwrap();
+        assert_eq!(merged["address"]["city"], "Vienna");
+        assert_eq!(merged["address"]["street"], "Ring 1");
+
+        // removing a required field
+        let patch = json!({"address": {"street": null}});
+        let error = schema.validate_merge_patch(&base, &patch).unwrap_err();
+        match *error.kind() {
+            ErrorKind::Invalid(ref errors) => {
+                assert_eq!(errors[0].reason.code(), ErrorCode::MissingProperties);
+                assert_eq!(errors[0].instance_path, "/address");
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn rfc_examples() {
+        let mut target = json!({"a": "b", "c": {"d": "e", "f": "g"}});
+        merge_patch(&mut target, &json!({"a": "z", "c": {"f": null}}));
+        assert_eq!(target, json!({"a": "z", "c": {"d": "e"}}));
+
+        let mut target = json!({"a": [{"b": "c"}]});
+        merge_patch(&mut target, &json!({"a": [1]}));
+        assert_eq!(target, json!({"a": [1]}));
+
+        let mut target = json!(["a", "b"]);
+        merge_patch(&mut target, &json!({"a": {"bb": {"ccc": null}}}));
+        assert_eq!(target, json!({"a": {"bb": {}}}));
+    }
+}
@@ -1,8 +1,16 @@
-use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
 
-use util::{JsonType, JsonValueExt};
+#[cfg(feature = "arbitrary-precision")]
+use bigdecimal::{BigDecimal, Zero};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Number, Value};
+
+use types::JsonType;
 use errors::{ErrorKind, ValidationError};
+use object::PropertyMap;
 use schema::{Context, Schema, SchemaBase};
+use util::deserialize_some;
 
 /// A schema for JSON numbers. This (contrary to `IntegerSchema`) allows
 /// for floating point values. Supports validation of a minimum and maximum
@@ -12,67 +20,388 @@ use schema::{Context, Schema, SchemaBase};
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
 pub struct NumberSchema {
-    description: Option<String>,
-    id: Option<String>,
-    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
+    pub(crate) enum_values: Option<Vec<Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "const",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) const_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+    #[serde(skip)]
+    pub(crate) nullable: bool,
 
-    multiple_of: Option<f64>,
-    minimum: Option<f64>,
-    maximum: Option<f64>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_multiple_of"
+    )]
+    multiple_of: Option<NumericBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<NumericBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<NumericBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     exclusive_minimum: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     exclusive_maximum: Option<bool>,
 }
 
 impl NumberSchema {
-    fn exclusive_maximum(&self) -> bool {
-        self.exclusive_maximum.unwrap_or(false)
+    pub(crate) fn range(&self) -> Range {
+        Range::new(
+            &self.multiple_of,
+            &self.minimum,
+            &self.maximum,
+            self.exclusive_minimum,
+            self.exclusive_maximum,
+        )
     }
+}
 
-    fn exclusive_minimum(&self) -> bool {
-        self.exclusive_minimum.unwrap_or(false)
+/// The value of `minimum`, `maximum` or `multipleOf` as the schema gives it, an
+/// integer or a float. Integers keep every digit, so bounds beyond 2^53 are
+/// compared exactly. Made from any primitive number, so builders take
+/// `minimum(5)` as well as `minimum(0.5)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumericBound {
+    /// The number, unless it's a float that JSON can't represent, like NaN.
+    number: Option<Number>,
+    float: f64,
+}
+
+impl NumericBound {
+    /// The bound as the nearest `f64`.
+    pub fn as_f64(&self) -> f64 {
+        self.float
     }
 
-    fn validate_range<'json>(
-        &self,
-        node: &'json Value,
-        value: f64,
-        errors: &mut Vec<ValidationError<'json>>,
-    ) {
-        let mut bound = None;
-        if let Some(min) = self.minimum {
-            let out_of_bounds = if self.exclusive_minimum() {
-                value < min
-            } else {
-                value <= min
-            };
-            if out_of_bounds {
-                bound = Some(min);
+    /// How `value` compares to the bound, `None` if either is NaN.
+    #[cfg(not(feature = "arbitrary-precision"))]
+    fn compare(&self, value: &Number) -> Option<Ordering> {
+        let number = match self.number {
+            Some(ref number) => number,
+            None => return value.as_f64()?.partial_cmp(&self.float),
+        };
+        match (integer(value), integer(number)) {
+            (Some(value), Some(bound)) => Some(value.cmp(&bound)),
+            (Some(value), None) => compare_integer(value, number.as_f64()?),
+            (None, Some(bound)) => compare_integer(bound, value.as_f64()?).map(Ordering::reverse),
+            (None, None) => value.as_f64()?.partial_cmp(&number.as_f64()?),
+        }
+    }
+
+    /// The bound as an exact decimal.
+    #[cfg(feature = "arbitrary-precision")]
+    fn decimal(&self) -> Option<BigDecimal> {
+        decimal(&self.number.as_ref()?.to_string())
+    }
+}
+
+impl From<Number> for NumericBound {
+    fn from(number: Number) -> Self {
+        NumericBound {
+            float: number.as_f64().unwrap_or(f64::NAN),
+            number: Some(number),
+        }
+    }
+}
+
+impl From<f64> for NumericBound {
+    fn from(value: f64) -> Self {
+        NumericBound {
+            number: Number::from_f64(value),
+            float: value,
+        }
+    }
+}
+
+impl From<f32> for NumericBound {
+    fn from(value: f32) -> Self {
+        NumericBound::from(f64::from(value))
+    }
+}
+
+macro_rules! bound_from_integer {
+    ($($t:ty)*) => {
+        $(
+            impl From<$t> for NumericBound {
+                fn from(value: $t) -> Self {
+                    NumericBound::from(Number::from(value))
+                }
             }
+        )*
+    };
+}
+
+bound_from_integer!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+impl fmt::Display for NumericBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.number {
+            Some(ref number) => write!(f, "{}", number),
+            None => write!(f, "{}", self.float),
+        }
+    }
+}
+
+impl Serialize for NumericBound {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.number {
+            Some(ref number) => number.serialize(serializer),
+            None => self.float.serialize(serializer),
         }
+    }
+}
+
+impl<'de> Deserialize<'de> for NumericBound {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Number::deserialize(deserializer).map(NumericBound::from)
+    }
+}
+
+/// The value of an integral `number`.
+#[cfg(not(feature = "arbitrary-precision"))]
+fn integer(number: &Number) -> Option<i128> {
+    number
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| number.as_u64().map(i128::from))
+}
+
+/// Compares an integer with a float without rounding the integer.
+#[cfg(not(feature = "arbitrary-precision"))]
+fn compare_integer(integer: i128, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        return None;
+    }
+    // every i64 and u64 lies between these
+    if float >= 2e19 {
+        return Some(Ordering::Less);
+    }
+    if float <= -1e19 {
+        return Some(Ordering::Greater);
+    }
+    let floor = float.floor();
+    match integer.cmp(&(floor as i128)) {
+        Ordering::Equal if float > floor => Some(Ordering::Less),
+        ordering => Some(ordering),
+    }
+}
+
+/// The numeric keywords shared by `NumberSchema` and `IntegerSchema`. The
+/// bounds are approximated as `f64`s for the static checks, validation uses
+/// the exact values.
+#[derive(Clone, Debug)]
+pub(crate) struct Range {
+    pub multiple_of: Option<f64>,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    pub exclusive_minimum: bool,
+    pub exclusive_maximum: bool,
+    exact_multiple_of: Option<NumericBound>,
+    exact_minimum: Option<NumericBound>,
+    exact_maximum: Option<NumericBound>,
+}
+
+impl Range {
+    pub(crate) fn new(
+        multiple_of: &Option<NumericBound>,
+        minimum: &Option<NumericBound>,
+        maximum: &Option<NumericBound>,
+        exclusive_minimum: Option<bool>,
+        exclusive_maximum: Option<bool>,
+    ) -> Range {
+        Range {
+            multiple_of: multiple_of.as_ref().map(NumericBound::as_f64),
+            minimum: minimum.as_ref().map(NumericBound::as_f64),
+            maximum: maximum.as_ref().map(NumericBound::as_f64),
+            exclusive_minimum: exclusive_minimum.unwrap_or(false),
+            exclusive_maximum: exclusive_maximum.unwrap_or(false),
+            exact_multiple_of: multiple_of.clone(),
+            exact_minimum: minimum.clone(),
+            exact_maximum: maximum.clone(),
+        }
+    }
 
-        if let Some(max) = self.maximum {
-            let out_of_bounds = if self.exclusive_maximum() {
-                value > max
-            } else {
-                value >= max
+    /// Checks `node`, which must be a number, against the keywords. Numbers that
+    /// have no finite `f64` representation (only possible with serde_json's
+    /// `arbitrary_precision` feature, plain serde_json rejects `1e999` while parsing)
+    /// are reported as `NonFiniteNumber` instead of being compared. Integers are
+    /// compared with the bounds without rounding them to `f64`s.
+    #[cfg(not(feature = "arbitrary-precision"))]
+    pub fn validate<'json>(&self, node: &'json Value, errors: &mut Vec<ValidationError<'json>>) {
+        let (number, value) = match (node, node.as_f64()) {
+            (Value::Number(number), Some(value)) if value.is_finite() => (number, value),
+            _ => {
+                return errors.push(ValidationError::new(
+                    ErrorKind::NonFiniteNumber(node.to_string()),
+                    node,
+                ))
+            }
+        };
+        if let Some(bound) = self.violated_bound(|bound| bound.compare(number)) {
+            errors.push(ValidationError::new(
+                ErrorKind::NumberRange { bound, value },
+                node,
+            ))
+        }
+        if let Some(ref multiple_of) = self.exact_multiple_of {
+            let step = multiple_of.number.as_ref().and_then(integer);
+            let is_multiple = match (integer(number), step) {
+                (Some(value), Some(step)) => value % step == 0,
+                _ => is_multiple_of(value, multiple_of.as_f64()),
             };
-            if out_of_bounds {
-                bound = Some(max);
+            if !is_multiple {
+                errors.push(ValidationError::new(
+                    ErrorKind::NotMultipleOf {
+                        multiple_of: multiple_of.as_f64(),
+                        value,
+                    },
+                    node,
+                ))
             }
         }
+    }
+
+    /// Checks `node`, which must be a number, against the keywords using exact
+    /// decimal arithmetic on the number as written in the document. The keyword
+    /// values are the numbers as written in the schema, or for values set with
+    /// a builder, the shortest decimals that round-trip to the given `f64`s.
+    #[cfg(feature = "arbitrary-precision")]
+    pub fn validate<'json>(&self, node: &'json Value, errors: &mut Vec<ValidationError<'json>>) {
+        let value = match decimal(&node.to_string()) {
+            Some(value) => value,
+            None => {
+                return errors.push(ValidationError::new(
+                    ErrorKind::NonFiniteNumber(node.to_string()),
+                    node,
+                ))
+            }
+        };
+        let as_f64 = || node.as_f64().unwrap_or(f64::NAN);
 
-        if let Some(b) = bound {
-            errors.push(ValidationError {
-                reason: ErrorKind::NumberRange {
-                    bound: b,
-                    value: value,
+        if let Some(bound) = self.violated_bound(|bound| value.partial_cmp(&bound.decimal()?)) {
+            errors.push(ValidationError::new(
+                ErrorKind::NumberRange {
+                    bound,
+                    value: as_f64(),
                 },
-                node: node,
-            })
+                node,
+            ))
         }
+        if let Some(ref multiple_of) = self.exact_multiple_of {
+            let is_multiple = multiple_of
+                .decimal()
+                .is_some_and(|d| (&value % d).is_zero());
+            if !is_multiple {
+                errors.push(ValidationError::new(
+                    ErrorKind::NotMultipleOf {
+                        multiple_of: multiple_of.as_f64(),
+                        value: as_f64(),
+                    },
+                    node,
+                ))
+            }
+        }
+    }
+
+    /// Returns the bound a value violates, if any. `compare` tells how the value
+    /// compares to a bound. Bounds it doesn't compare to (NaN) count as violated.
+    fn violated_bound<F>(&self, compare: F) -> Option<f64>
+    where
+        F: Fn(&NumericBound) -> Option<Ordering>,
+    {
+        if let Some(ref min) = self.exact_minimum {
+            let in_bounds = match compare(min) {
+                Some(Ordering::Greater) => true,
+                Some(Ordering::Equal) => !self.exclusive_minimum,
+                _ => false,
+            };
+            if !in_bounds {
+                return Some(min.as_f64());
+            }
+        }
+
+        if let Some(ref max) = self.exact_maximum {
+            let in_bounds = match compare(max) {
+                Some(Ordering::Less) => true,
+                Some(Ordering::Equal) => !self.exclusive_maximum,
+                _ => false,
+            };
+            if !in_bounds {
+                return Some(max.as_f64());
+            }
+        }
+        None
+    }
+}
+
+/// Parses the textual form of a number as an exact decimal.
+#[cfg(feature = "arbitrary-precision")]
+fn decimal(number: &str) -> Option<BigDecimal> {
+    number.parse().ok()
+}
+
+/// Whether `value` is a multiple of `multiple_of`, allowing for the rounding error
+/// of decimal fractions like `0.01` that have no exact binary representation.
+#[cfg(not(feature = "arbitrary-precision"))]
+fn is_multiple_of(value: f64, multiple_of: f64) -> bool {
+    let quotient = value / multiple_of;
+    quotient.is_finite() && (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+}
+
+/// Deserializes `multipleOf`, which must be strictly greater than 0.
+pub(crate) fn deserialize_multiple_of<'de, D>(
+    deserializer: D,
+) -> Result<Option<NumericBound>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<NumericBound>::deserialize(deserializer)? {
+        Some(ref value) if value.as_f64() <= 0.0 => Err(de::Error::custom(format!(
+            "multipleOf must be greater than 0, found {}",
+            value
+        ))),
+        value => Ok(value),
     }
 }
 
+/// Checks the `multipleOf` of a builder.
+pub(crate) fn positive_step(value: NumericBound) -> NumericBound {
+    assert!(
+        value.as_f64() > 0.0,
+        "multipleOf must be greater than 0, found {}",
+        value
+    );
+    value
+}
+
 impl SchemaBase for NumberSchema {
     #[doc(hidden)]
     fn validate_inner<'json>(
@@ -82,15 +411,9 @@ impl SchemaBase for NumberSchema {
         errors: &mut Vec<ValidationError<'json>>,
     ) {
         if let Value::Number(_) = *value {
-            self.validate_range(value, value.as_f64().unwrap(), errors);
+            self.range().validate(value, errors);
         } else {
-            errors.push(ValidationError {
-                reason: ErrorKind::TypeMismatch {
-                    expected: JsonType::Number,
-                    found: value.get_type(),
-                },
-                node: value,
-            })
+            errors.push(ValidationError::type_mismatch(value, JsonType::Number))
         }
     }
 }
@@ -101,10 +424,12 @@ pub struct NumberSchemaBuilder {
     description: Option<String>,
     id: Option<String>,
     title: Option<String>,
+    examples: Option<Vec<Value>>,
+    counterexamples: Option<Vec<Value>>,
 
-    multiple_of: Option<f64>,
-    minimum: Option<f64>,
-    maximum: Option<f64>,
+    multiple_of: Option<NumericBound>,
+    minimum: Option<NumericBound>,
+    maximum: Option<NumericBound>,
     exclusive_minimum: bool,
     exclusive_maximum: bool,
 }
@@ -126,14 +451,33 @@ impl NumberSchemaBuilder {
         self.title = Some(value.into());
         self
     }
-    /// Sets the minimum value.
-    pub fn minimum(mut self, value: f64) -> Self {
-        self.minimum = Some(value);
+    /// Adds a value the schema accepts, see `Schema::self_test`.
+    pub fn example(mut self, value: Value) -> Self {
+        self.examples.get_or_insert_with(Vec::new).push(value);
         self
     }
-    /// Sets the maximum value.
-    pub fn maximum(mut self, value: f64) -> Self {
-        self.maximum = Some(value);
+    /// Adds a value the schema rejects, see `Schema::self_test`.
+    pub fn counterexample(mut self, value: Value) -> Self {
+        self.counterexamples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
+    /// Sets the minimum value, an integer or a float.
+    pub fn minimum<V: Into<NumericBound>>(mut self, value: V) -> Self {
+        self.minimum = Some(value.into());
+        self
+    }
+    /// Sets the maximum value, an integer or a float.
+    pub fn maximum<V: Into<NumericBound>>(mut self, value: V) -> Self {
+        self.maximum = Some(value.into());
+        self
+    }
+    /// Requires the value to be a multiple of `value`.
+    ///
+    /// # Panics
+    ///
+    /// If `value` isn't greater than 0.
+    pub fn multiple_of<V: Into<NumericBound>>(mut self, value: V) -> Self {
+        self.multiple_of = Some(positive_step(value.into()));
         self
     }
     /// Makes the maximum value exclusive.
@@ -154,6 +498,16 @@ impl NumberSchemaBuilder {
             description: self.description,
             id: self.id,
             title: self.title,
+            nullable: false,
+            default: None,
+            examples: self.examples,
+            counterexamples: self.counterexamples,
+            deprecated: None,
+            removed_in: None,
+            enum_values: None,
+            const_value: None,
+            definitions: None,
+            dollar_defs: false,
 
             multiple_of: self.multiple_of,
             minimum: self.minimum,
@@ -166,6 +520,231 @@ impl NumberSchemaBuilder {
 
 #[cfg(test)]
 mod tests {
+    use serde_json;
+
+    use super::*;
+    use integer::IntegerSchemaBuilder;
+
+    #[test]
+    fn range() {
+        let schema = NumberSchemaBuilder::default()
+            .minimum(1.0)
+            .maximum(2.0)
+            .build();
+        for valid in &[json!(1.0), json!(1.5), json!(2)] {
+            schema.validate(valid).unwrap();
+        }
+        for invalid in &[json!(0.99), json!(2.01)] {
+            assert!(schema.validate(invalid).is_err());
+        }
+
+        let schema = NumberSchemaBuilder::default()
+            .minimum(1.0)
+            .maximum(2.0)
+            .exclusive_minimum()
+            .exclusive_maximum()
+            .build();
+        schema.validate(&json!(1.5)).unwrap();
+        assert!(schema.validate(&json!(1.0)).is_err());
+        assert!(schema.validate(&json!(2.0)).is_err());
+    }
+
+    #[test]
+    fn integral_values() {
+        let number: Schema = serde_json::from_value(json!({"type": "number"})).unwrap();
+        let integer: Schema = serde_json::from_value(json!({"type": "integer"})).unwrap();
+        for value in &[json!(2), json!(2.0), json!(2.5), json!(-0.0)] {
+            number.validate(value).unwrap();
+        }
+        for value in &[json!(2), json!(2.0), json!(-0.0)] {
+            integer.validate(value).unwrap();
+        }
+
+        let value = json!(2.5);
+        let errors = integer.validate(&value).unwrap_err().0;
+        match errors[0].reason {
+            ErrorKind::TypeMismatch {
+                expected: JsonType::Integer,
+                found: JsonType::Number,
+                integral: Some(false),
+            } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "Type mismatch: expected integer, found number with a fractional part"
+        );
+
+        let value = json!("2");
+        let errors = number.validate(&value).unwrap_err().0;
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "Type mismatch: expected number, found string"
+        );
+    }
+
+    #[test]
+    fn nan_bound() {
+        let schema = NumberSchemaBuilder::default()
+            .minimum(f64::NAN)
+            .build();
+        assert!(schema.validate(&json!(1.0)).is_err());
+    }
+
+    #[test]
+    fn bounds_of_any_numeric_type() {
+        let schema = NumberSchemaBuilder::default()
+            .minimum(5)
+            .maximum(7u64)
+            .build();
+        for valid in &[json!(5), json!(6.5), json!(7.0)] {
+            schema.validate(valid).unwrap();
+        }
+        for invalid in &[json!(4.999), json!(7.0001), json!(-5i64)] {
+            assert!(schema.validate(invalid).is_err());
+        }
+        assert_eq!(serde_json::to_value(&schema).unwrap()["minimum"], json!(5));
+
+        let schema = IntegerSchemaBuilder::default()
+            .minimum(0.5)
+            .maximum(1e3)
+            .multiple_of(5u8)
+            .build();
+        for valid in &[json!(5), json!(1000)] {
+            schema.validate(valid).unwrap();
+        }
+        for invalid in &[json!(0), json!(1005), json!(7)] {
+            assert!(schema.validate(invalid).is_err());
+        }
+        let schema = NumberSchemaBuilder::default().minimum(0.25f32).build();
+        schema.validate(&json!(0.25)).unwrap();
+        assert!(schema.validate(&json!(0.24)).is_err());
+
+        // integers beyond 2^53 aren't rounded to f64s
+        let schema = IntegerSchemaBuilder::default()
+            .minimum(9_007_199_254_740_993i64)
+            .maximum(u64::MAX)
+            .multiple_of(3)
+            .build();
+        schema.validate(&json!(9_007_199_254_740_993u64)).unwrap();
+        schema.validate(&json!(u64::MAX)).unwrap();
+        assert!(schema.validate(&json!(9_007_199_254_740_992u64)).is_err());
+        assert!(schema.validate(&json!(9_007_199_254_740_994u64)).is_err());
+        let schema = NumberSchemaBuilder::default()
+            .maximum(9_007_199_254_740_992.0)
+            .build();
+        assert!(schema.validate(&json!(9_007_199_254_740_993u64)).is_err());
+    }
+
+    #[test]
+    fn multiple_of() {
+        let schema = NumberSchemaBuilder::default().multiple_of(0.01).build();
+        schema.validate(&json!(10.1)).unwrap();
+        schema.validate(&json!(-3)).unwrap();
+        let input = json!(10.105);
+        let errors = schema.validate(&input).unwrap_err().0;
+        match errors[0].reason {
+            ErrorKind::NotMultipleOf { multiple_of, value } => {
+                assert_eq!((multiple_of, value), (0.01, 10.105))
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
     #[test]
-    fn range() {}
+    fn multiple_of_zero() {
+        for schema in &[
+            r#"{"type": "number", "multipleOf": 0}"#,
+            r#"{"type": "number", "multipleOf": -1.5}"#,
+            r#"{"type": "integer", "multipleOf": 0}"#,
+        ] {
+            let error = serde_json::from_str::<Schema>(schema).unwrap_err();
+            assert!(error.to_string().contains("multipleOf must be greater than 0"));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn builder_multiple_of_zero() {
+        NumberSchemaBuilder::default().multiple_of(0.0);
+    }
+
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn exact_decimals() {
+        let schema = NumberSchemaBuilder::default().multiple_of(0.01).build();
+        for valid in &["10.10", "0.3", "12345678901234567890.01", "1e-2"] {
+            schema.validate(&serde_json::from_str(valid).unwrap()).unwrap();
+        }
+        for invalid in &["10.105", "0.30000000000000004", "12345678901234567890.001"] {
+            assert!(schema.validate(&serde_json::from_str(invalid).unwrap()).is_err());
+        }
+
+        // 0.1 + 0.2 is exactly 0.3, not the f64 sum
+        let schema = NumberSchemaBuilder::default().maximum(0.3).build();
+        schema.validate(&serde_json::from_str("0.3").unwrap()).unwrap();
+        let sum = serde_json::from_str("0.30000000000000004").unwrap();
+        assert!(schema.validate(&sum).is_err());
+
+        // integers beyond 2^53 keep every digit
+        let schema: Schema =
+            serde_json::from_str(r#"{"type": "integer", "maximum": 1e29}"#).unwrap();
+        let equal = serde_json::from_str("100000000000000000000000000000").unwrap();
+        schema.validate(&equal).unwrap();
+        let above = serde_json::from_str("100000000000000000000000000001").unwrap();
+        assert!(schema.validate(&above).is_err());
+        let huge = serde_json::from_str("1e999").unwrap();
+        assert!(schema.validate(&huge).is_err());
+    }
+
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn long_numbers_are_errors() {
+        let hundred_digits = format!("9{}", "0".repeat(99));
+        let schemas = [
+            json!({"type": "number", "maximum": 10, "multipleOf": 3}),
+            json!({"type": "integer", "minimum": 0, "maximum": 5}),
+            json!({"enum": [1, 2]}),
+            json!({"type": "array", "uniqueItems": true, "items": {"type": "number", "maximum": 0}}),
+        ];
+        for text in &[
+            hundred_digits.clone(),
+            format!("-{}.5", hundred_digits),
+            "1e400".into(),
+        ] {
+            let value: Value = serde_json::from_str(text).unwrap();
+            for raw in &schemas {
+                let schema: Schema = serde_json::from_value(raw.clone()).unwrap();
+                assert!(schema.validate(&value).is_err(), "{} accepts {}", raw, text);
+                let array = Value::Array(vec![value.clone(), value.clone()]);
+                assert!(
+                    schema.validate(&array).is_err(),
+                    "{} accepts [{}, {}]",
+                    raw,
+                    text,
+                    text
+                );
+            }
+        }
+
+        let schema = NumberSchemaBuilder::default().maximum(1e99).build();
+        let value = serde_json::from_str(&hundred_digits).unwrap();
+        let errors = schema.validate(&value).unwrap_err();
+        match errors.0[0].reason {
+            ErrorKind::NumberRange { bound, .. } => assert_eq!(bound, 1e99),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[cfg(not(feature = "arbitrary-precision"))]
+    #[test]
+    fn out_of_range_instances() {
+        // serde_json refuses numbers that overflow an f64, so they never reach validation.
+        assert!(serde_json::from_str::<Value>("1e999").is_err());
+        assert!(serde_json::from_str::<Value>("-1e999").is_err());
+        // non-finite floats can't be stored in a `Value` either
+        assert_eq!(Value::from(f64::INFINITY), Value::Null);
+        let schema = NumberSchemaBuilder::default().build();
+        assert!(schema.validate(&Value::from(f64::NAN)).is_err());
+    }
 }
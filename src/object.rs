@@ -1,106 +1,449 @@
-use std::collections::HashMap;
+#[cfg(not(feature = "preserve_order"))]
+use std::collections::BTreeMap;
 
+#[cfg(feature = "preserve_order")]
+use indexmap::IndexMap;
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer};
 use serde_json::Value;
 use serde_json::value::Map;
-use regex::Regex;
 
-use util::{JsonType, JsonValueExt};
-use schema::{Context, Schema, SchemaBase};
-use errors::{ErrorKind, ValidationError};
+use types::JsonType;
+use combinator::all_of;
+use schema::{Context, Deadline, EmptySchema, Schema, SchemaBase, Segment};
+use errors::{ErrorKind, Property, ValidationError, ValidationErrors};
+use options::ValidateOptions;
+use output::WarningKind;
+use session::Scratch;
+use limits::SchemaLimits;
+use key_pattern::{KeyPattern, KeyPatterns};
+use util::{deserialize_some, pointer_token};
+use cross_field::{validate_comparisons, Comparison};
+
+/// The map type of `properties` and `patternProperties`. It's kept sorted by key,
+/// so properties are validated, and their errors reported, in the same order on
+/// every run.
+#[cfg(not(feature = "preserve_order"))]
+pub type PropertyMap<V> = BTreeMap<String, V>;
+
+/// The map type of `properties` and `patternProperties`. With the `preserve_order`
+/// feature it keeps the order in which the properties were written or added, which
+/// is also the order they are validated and serialized in.
+#[cfg(feature = "preserve_order")]
+pub type PropertyMap<V> = IndexMap<String, V>;
+
+/// The order the extension keyword `x-orderedKeys` requires the keys of objects
+/// to be in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyOrder {
+    /// `"asc"`: the keys are sorted, comparing them character by character.
+    Asc,
+    /// `"schema"`: the keys declared by `properties` appear in the order they
+    /// are declared in. Other keys can be anywhere.
+    Schema,
+}
+
+impl fmt::Display for KeyOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyOrder::Asc => write!(f, "keys must be sorted"),
+            KeyOrder::Schema => write!(f, "keys must be in the order of `properties`"),
+        }
+    }
+}
+
+/// Deserializes `x-orderedKeys`, which needs the `preserve_order` feature: without
+/// it, parsed objects don't keep the order of their keys.
+fn deserialize_key_order<'de, D>(deserializer: D) -> Result<Option<KeyOrder>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let order = Option::<KeyOrder>::deserialize(deserializer)?;
+    if order.is_some() && cfg!(not(feature = "preserve_order")) {
+        return Err(de::Error::custom(
+            "x-orderedKeys needs the `preserve_order` feature of json_schema, without it \
+             the order of keys is lost when parsing",
+        ));
+    }
+    Ok(order)
+}
+
+/// Deserializes `required`, dropping repeated names. They require nothing more,
+/// `diagnostics::check_duplicate_declarations` points them out.
+fn deserialize_required<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let names = Option::<Vec<String>>::deserialize(deserializer)?;
+    Ok(names.map(|names| {
+        let mut unique: Vec<String> = Vec::with_capacity(names.len());
+        for name in names {
+            if !unique.contains(&name) {
+                unique.push(name);
+            }
+        }
+        unique
+    }))
+}
 
 /// An object schema.
+///
+/// The keywords of an object are checked in a fixed order: `required`, `properties`,
+/// `patternProperties`, `additionalProperties`, then `minProperties`/`maxProperties`
+/// and the extension keywords `x-orderedKeys` and `x-compare`.
+///
+/// `x-orderedKeys` requires the keys of objects to be in an order, see `KeyOrder`,
+/// for documents in a canonical form: `{"type": "object", "x-orderedKeys": "asc"}`
+/// rejects `{"b": 1, "a": 2}`. It needs the `preserve_order` feature, schemas using
+/// it don't parse without.
+///
+/// `x-compare` relates values within an object, like a start date that can't come
+/// after the end date, see `Comparison`. It isn't standard JSON Schema either.
+///
+/// `minProperties` and `maxProperties` count every key of the object, as the spec
+/// says, whether `properties`, `patternProperties` or none of them declare it. To
+/// count only the keys the schema knows, strip the others first with
+/// `Schema::validate_and_sanitize`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 // #[serde(deny_unknown_fields)]
 pub struct ObjectSchema {
-    description: Option<String>,
-    id: Option<String>,
-    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
+    pub(crate) enum_values: Option<Vec<Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "const",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) const_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+    #[serde(skip)]
+    pub(crate) nullable: bool,
 
-    properties: Option<HashMap<String, Schema>>,
-    // TODO either object or bool
-    additional_properties: Option<bool>,
-    required: Option<Vec<String>>,
-    min_properties: Option<usize>,
-    max_properties: Option<usize>,
-    pattern_properties: Option<HashMap<String, Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) properties: Option<PropertyMap<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) additional_properties: Option<AdditionalProperties>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_required"
+    )]
+    pub(crate) required: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) min_properties: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_properties: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pattern_properties: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) key_patterns: KeyPatterns,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "x-orderedKeys",
+        default,
+        deserialize_with = "deserialize_key_order"
+    )]
+    pub(crate) ordered_keys: Option<KeyOrder>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-compare")]
+    pub(crate) comparisons: Option<Vec<Comparison>>,
+}
+
+/// What `additionalProperties` allows for the properties that neither `properties`
+/// nor `patternProperties` cover.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum AdditionalProperties {
+    Allowed(bool),
+    Schema(Box<Schema>),
 }
 
 impl ObjectSchema {
-    fn additional_properties(&self) -> bool {
-        self.additional_properties.unwrap_or(false)
+    /// Whether `additionalProperties` allows other properties than the declared
+    /// ones, which it does unless it's `false`.
+    pub(crate) fn additional_properties(&self) -> bool {
+        match self.additional_properties {
+            Some(AdditionalProperties::Allowed(allowed)) => allowed,
+            Some(AdditionalProperties::Schema(_)) | None => true,
+        }
+    }
+
+    /// Compiles the patterns of `patternProperties`, once the schema is read.
+    pub(crate) fn compile_key_patterns(&mut self) {
+        let patterns = self.pattern_properties.iter().flat_map(|p| p.keys());
+        self.key_patterns = KeyPatterns::compile(patterns);
+    }
+
+    /// The schema of `additionalProperties`, if it is one.
+    pub(crate) fn additional_schema(&self) -> Option<&Schema> {
+        match self.additional_properties {
+            Some(AdditionalProperties::Schema(ref schema)) => Some(schema),
+            _ => None,
+        }
+    }
+
+    /// Whether `properties` or `patternProperties` declare the property `name`.
+    pub(crate) fn is_declared(&self, name: &str) -> bool {
+        PropertyResolver::new(self, &SchemaLimits::default())
+            .resolve(name)
+            .covered()
+    }
+
+    /// The subschemas that apply to the property `key` of objects of this schema:
+    /// the schema of `properties` for `key` and those of the matching
+    /// `patternProperties`, all of which apply, or else the schema of
+    /// `additionalProperties`.
+    pub fn applicable_schemas(&self, key: &str) -> Vec<&Schema> {
+        PropertyResolver::new(self, &SchemaLimits::default())
+            .resolve(key)
+            .schemas()
+    }
+
+    /// Whether objects of this schema may have the property `name`: it's declared
+    /// by `properties`, matches a pattern of `patternProperties` or
+    /// `additionalProperties` isn't `false`.
+    pub fn is_property_allowed(&self, name: &str) -> bool {
+        self.is_declared(name) || self.additional_properties()
+    }
+
+    /// Validates `value` as the property `name` of an object of this schema,
+    /// without the rest of the object: against the schema of `properties` for
+    /// `name` and those of the matching `patternProperties`, against the schema of
+    /// `additionalProperties` if neither covers it, or rejects it if the property
+    /// isn't allowed. Constraints on the whole object, like `required`
+    /// or `maxProperties`, are skipped.
+    ///
+    /// The object schema is validated on its own, so references to other parts
+    /// of the document it's in can't be resolved.
+    pub fn validate_property<'json>(
+        &self,
+        name: &str,
+        value: &'json Value,
+    ) -> Result<(), ValidationErrors<'json>> {
+        let root = Schema::Empty(EmptySchema::default());
+        let options = ValidateOptions::default();
+        let collected = RefCell::default();
+        let scratch = Scratch::default();
+        let deadline = Deadline::new(None);
+        let ctx = Context::new(&root, &options, &collected, &scratch, &deadline);
+        let mut errors = vec![];
+        let key = Segment::Key(name);
+        let resolved = PropertyResolver::new(self, &ctx.options.limits).resolve(name);
+        if let Some(schema) = resolved.declared {
+            ctx.descend(Some(key), "properties", Some(key), |ctx| {
+                schema.validate_inner(ctx, value, &mut errors)
+            });
+        }
+        for (pattern, schema) in resolved.patterns {
+            ctx.descend(Some(key), "patternProperties", Some(Segment::Key(pattern)), |ctx| {
+                schema.validate_inner(ctx, value, &mut errors)
+            });
+        }
+        if let Some(schema) = resolved.additional {
+            ctx.descend(Some(key), "additionalProperties", None, |ctx| {
+                schema.validate_inner(ctx, value, &mut errors)
+            });
+        } else if !self.is_property_allowed(name) {
+            ctx.descend(Some(key), "additionalProperties", None, |ctx| {
+                let pointer = format!("/{}", pointer_token(name));
+                let mut error = [ValidationError::new(
+                    ErrorKind::UnknownProperties(vec![pointer]),
+                    value,
+                )];
+                ctx.locate(&mut error);
+                errors.extend(error);
+            });
+        }
+        ctx.finish(value, &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
     }
 
     fn validate_properties<'json>(
         &self,
         ctx: &Context,
         object: &'json Map<String, Value>,
-        parent: &'json Value,
         errors: &mut Vec<ValidationError<'json>>,
     ) {
+        if let Some(deferred) = ctx.deferred {
+            deferred.set(Some(errors.len()));
+            return;
+        }
         if let Some(ref schemas) = self.properties {
             for (property, schema) in schemas {
-                match object.get(property) {
-                    Some(value) => {
-                        schema.validate_inner(ctx, value, errors);
-                    }
-                    None => if !self.additional_properties() {
-                        errors.push(ValidationError {
-                            reason: ErrorKind::MissingProperty(property.to_string()),
-                            node: parent,
-                        });
-                    },
+                if let Some(value) = object.get(property) {
+                    let key = Segment::Key(property);
+                    ctx.descend(Some(key), "properties", Some(key), |ctx| {
+                        schema.validate_inner(ctx, value, errors)
+                    });
                 }
             }
         }
     }
 
+    /// The properties of `required`, which objects have to have. Declaring a
+    /// property in `properties` doesn't make it required. Names can repeat.
+    pub(crate) fn required_properties(&self) -> impl Iterator<Item = &String> {
+        self.required.iter().flatten()
+    }
+
+    /// Reports the properties of `required` that `object` lacks in a single
+    /// error. Present properties count as missing if
+    /// `ValidateOptions::required_rejects` rejects their value.
     fn validate_required<'json>(
         &self,
+        ctx: &Context,
         object: &'json Map<String, Value>,
         parent: &'json Value,
         errors: &mut Vec<ValidationError<'json>>,
     ) {
-        if let Some(ref required) = self.required {
-            for property in required {
-                if object.get(property).is_none() {
-                    errors.push(ValidationError {
-                        reason: ErrorKind::MissingProperty(property.to_string()),
-                        node: parent,
-                    })
-                }
+        let mut missing: Vec<Property> = vec![];
+        let mut pointer = None;
+        for name in self.required_properties() {
+            let blank = match object.get(name) {
+                Some(value) => match ctx.options.required_rejects.blank(value) {
+                    Some(blank) => Some(blank),
+                    None => continue,
+                },
+                None => None,
+            };
+            if missing.iter().any(|p| p.name == *name) {
+                continue;
             }
+            let title = self
+                .properties
+                .as_ref()
+                .and_then(|p| p.get(name))
+                .and_then(Schema::title);
+            let pointer = pointer.get_or_insert_with(|| ctx.schema_pointer());
+            missing.push(Property {
+                name: name.clone(),
+                title: title.map(String::from),
+                blank,
+                required_by: format!("{}/required", pointer),
+            });
+        }
+        if !missing.is_empty() {
+            errors.push(ValidationError::new(
+                ErrorKind::MissingProperties(missing),
+                parent,
+            ));
         }
     }
 
     fn validate_count<'json>(
         &self,
+        ctx: &Context,
         object: &'json Map<String, Value>,
         parent: &'json Value,
         errors: &mut Vec<ValidationError<'json>>,
     ) {
-        if let Some(min) = self.min_properties {
+        if let Some(min) = self.min_properties.filter(|_| !ctx.partial) {
             if object.len() < min {
-                errors.push(ValidationError {
-                    reason: ErrorKind::PropertyCount {
+                errors.push(ValidationError::new(
+                    ErrorKind::PropertyCount {
                         bound: min,
                         found: object.len(),
                     },
-                    node: parent,
-                })
+                    parent,
+                ))
             }
         }
 
         if let Some(max) = self.max_properties {
             if object.len() > max {
-                errors.push(ValidationError {
-                    reason: ErrorKind::PropertyCount {
+                errors.push(ValidationError::new(
+                    ErrorKind::PropertyCount {
                         bound: max,
                         found: object.len(),
                     },
-                    node: parent,
-                })
+                    parent,
+                ))
+            }
+        }
+    }
+
+    /// Reports the first key of `object` that is out of the order `x-orderedKeys`
+    /// asks for.
+    fn validate_key_order<'json>(
+        &self,
+        ctx: &Context,
+        object: &'json Map<String, Value>,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        let order = match self.ordered_keys {
+            Some(order) => order,
+            None => return,
+        };
+        if !ctx.applies_extension("x-orderedKeys") {
+            return;
+        }
+        let declared = |key: &str| {
+            self.properties
+                .as_ref()
+                .and_then(|properties| properties.keys().position(|k| k == key))
+        };
+        let mut previous: Option<(&String, usize)> = None;
+        for (key, value) in object {
+            let rank = match order {
+                KeyOrder::Asc => 0,
+                KeyOrder::Schema => match declared(key) {
+                    Some(rank) => rank,
+                    None => continue,
+                },
+            };
+            if let Some((before, before_rank)) = previous {
+                let out_of_order = match order {
+                    KeyOrder::Asc => key < before,
+                    KeyOrder::Schema => rank < before_rank,
+                };
+                if out_of_order {
+                    ctx.descend(Some(Segment::Key(key)), "x-orderedKeys", None, |ctx| {
+                        let kind = ErrorKind::UnorderedKeys {
+                            key: key.clone(),
+                            previous: before.clone(),
+                            order,
+                        };
+                        let mut error = [ValidationError::new(kind, value)];
+                        ctx.locate(&mut error);
+                        errors.extend(error);
+                    });
+                    return;
+                }
             }
+            previous = Some((key, rank));
         }
     }
 
@@ -112,14 +455,19 @@ impl ObjectSchema {
         errors: &mut Vec<ValidationError<'json>>,
     ) {
         if let Some(ref patterns) = self.pattern_properties {
-            for (pattern, schema) in patterns {
-                // TODO(performance) cache compiled regexes
-                match Regex::new(pattern) {
-                    Ok(re) => {
+            let limits = &ctx.options.limits;
+            for (index, (pattern, schema)) in patterns.iter().enumerate() {
+                match *self.key_patterns.get(index, pattern, limits) {
+                    Ok(ref matcher) => {
                         let mut found_match = false;
                         for (prop, value) in object.iter() {
-                            if re.is_match(prop) {
-                                schema.validate_inner(ctx, value, errors);
+                            if ctx.time_pattern(pattern, || matcher.is_match(prop)) {
+                                ctx.descend(
+                                    Some(Segment::Key(prop)),
+                                    "patternProperties",
+                                    Some(Segment::Key(pattern)),
+                                    |ctx| schema.validate_inner(ctx, value, errors),
+                                );
                                 found_match = true;
                             }
                         }
@@ -127,14 +475,163 @@ impl ObjectSchema {
                             // TODO? Error: No matching property found
                         }
                     }
-                    Err(e) => errors.push(ValidationError {
-                        reason: ErrorKind::InvalidRegex(format!("{}", e)),
-                        node: parent,
-                    }),
+                    Err(ref e) => errors.push(ValidationError::new(
+                        ErrorKind::InvalidRegex(format!("{}", e)),
+                        parent,
+                    )),
                 }
             }
         }
     }
+
+    /// Validates the properties that neither `properties` nor `patternProperties`
+    /// declare against the schema of `additionalProperties`, or rejects all of
+    /// them in a single error if it's `false`. Without either, as for maps whose
+    /// values all have the same schema, no property needs to be looked up.
+    fn validate_additional<'json>(
+        &self,
+        ctx: &Context,
+        object: &'json Map<String, Value>,
+        parent: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        let rejected = match self.additional_properties {
+            Some(AdditionalProperties::Allowed(false)) => true,
+            Some(AdditionalProperties::Schema(_)) => false,
+            _ => return,
+        };
+        let resolver = PropertyResolver::new(self, &ctx.options.limits);
+        let mut unknown = vec![];
+        for (property, value) in object {
+            let resolved = resolver.resolve(property);
+            if let Some(schema) = resolved.additional {
+                let key = Some(Segment::Key(property));
+                ctx.descend(key, "additionalProperties", None, |ctx| {
+                    schema.validate_inner(ctx, value, errors)
+                });
+            } else if rejected && !resolved.covered() {
+                unknown.push(property);
+            }
+        }
+        if unknown.is_empty() {
+            return;
+        }
+        ctx.descend(None, "additionalProperties", None, |ctx| {
+            let pointer = ctx.instance_pointer();
+            let pointers = unknown
+                .iter()
+                .map(|property| format!("{}/{}", pointer, pointer_token(property)))
+                .collect();
+            let mut error = [ValidationError::new(
+                ErrorKind::UnknownProperties(pointers),
+                parent,
+            )];
+            ctx.locate(&mut error);
+            errors.extend(error);
+        });
+    }
+
+    /// Warns about properties that neither `properties` nor `patternProperties`
+    /// declare when `additionalProperties` isn't given.
+    fn warn_undeclared(&self, ctx: &Context, object: &Map<String, Value>) {
+        if self.additional_properties.is_some()
+            || (self.properties.is_none() && self.pattern_properties.is_none())
+        {
+            return;
+        }
+        let resolver = PropertyResolver::new(self, &ctx.options.limits);
+        for property in object.keys() {
+            if !resolver.resolve(property).covered() {
+                ctx.descend(
+                    Some(Segment::Key(property)),
+                    "additionalProperties",
+                    None,
+                    |ctx| ctx.warn(WarningKind::UndeclaredProperty(property.clone())),
+                );
+            }
+        }
+    }
+}
+
+/// The subschemas that apply to a property of an object, see
+/// `PropertyResolver::resolve`.
+#[derive(Debug)]
+pub(crate) struct PropertySchemas<'a> {
+    /// The schema of `properties` for the property.
+    pub declared: Option<&'a Schema>,
+    /// The patterns of `patternProperties` the property matches, with their
+    /// schemas.
+    pub patterns: Vec<(&'a str, &'a Schema)>,
+    /// The schema of `additionalProperties`, if neither `properties` nor
+    /// `patternProperties` cover the property.
+    pub additional: Option<&'a Schema>,
+}
+
+impl<'a> PropertySchemas<'a> {
+    /// Whether `properties` or `patternProperties` cover the property, which
+    /// keeps `additionalProperties` from applying to it.
+    pub fn covered(&self) -> bool {
+        self.declared.is_some() || !self.patterns.is_empty()
+    }
+
+    /// All the subschemas, in the order of their keywords.
+    pub fn schemas(&self) -> Vec<&'a Schema> {
+        self.declared
+            .into_iter()
+            .chain(self.patterns.iter().map(|&(_, schema)| schema))
+            .chain(self.additional)
+            .collect()
+    }
+}
+
+/// Decides which subschemas of an object schema apply to each property. All
+/// code that needs to know whether a property is covered by `properties` or
+/// `patternProperties`, or falls to `additionalProperties`, goes through it.
+/// The patterns that don't compile within the limits are left out; validating
+/// `patternProperties` reports them.
+#[derive(Debug)]
+pub(crate) struct PropertyResolver<'a> {
+    schema: &'a ObjectSchema,
+    patterns: Vec<(&'a str, Cow<'a, KeyPattern>, &'a Schema)>,
+}
+
+impl<'a> PropertyResolver<'a> {
+    pub(crate) fn new(schema: &'a ObjectSchema, limits: &SchemaLimits) -> PropertyResolver<'a> {
+        let patterns = schema
+            .pattern_properties
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, (pattern, subschema))| {
+                let matcher = match schema.key_patterns.get(index, pattern, limits) {
+                    Cow::Borrowed(matcher) => Cow::Borrowed(matcher.as_ref().ok()?),
+                    Cow::Owned(matcher) => Cow::Owned(matcher.ok()?),
+                };
+                Some((pattern.as_str(), matcher, subschema))
+            })
+            .collect();
+        PropertyResolver { schema, patterns }
+    }
+
+    /// The subschemas that apply to the property `key`.
+    pub(crate) fn resolve(&self, key: &str) -> PropertySchemas<'a> {
+        let declared = self.schema.properties.as_ref().and_then(|p| p.get(key));
+        let patterns: Vec<_> = self
+            .patterns
+            .iter()
+            .filter(|(_, matcher, _)| matcher.is_match(key))
+            .map(|&(pattern, _, schema)| (pattern, schema))
+            .collect();
+        let additional = self
+            .schema
+            .additional_schema()
+            .filter(|_| declared.is_none() && patterns.is_empty());
+        PropertySchemas {
+            declared,
+            patterns,
+            additional,
+        }
+    }
 }
 
 impl SchemaBase for ObjectSchema {
@@ -147,17 +644,25 @@ impl SchemaBase for ObjectSchema {
     ) {
         match value {
             &Value::Object(ref o) => {
-                self.validate_properties(ctx, o, value, errors);
-                self.validate_required(o, value, errors);
-                self.validate_count(o, value, errors);
+                if !ctx.partial {
+                    self.validate_required(ctx, o, value, errors);
+                }
+                self.validate_properties(ctx, o, errors);
                 self.validate_pattern_properties(ctx, o, value, errors);
+                self.validate_additional(ctx, o, value, errors);
+                self.validate_count(ctx, o, value, errors);
+                self.validate_key_order(ctx, o, errors);
+                if let Some(ref rules) = self.comparisons {
+                    if ctx.applies_extension("x-compare") {
+                        validate_comparisons(ctx, rules, value, errors);
+                    }
+                }
+                if ctx.collects_warnings() {
+                    self.warn_undeclared(ctx, o);
+                }
             }
-            val => {
-                errors.push(ValidationError::type_mismatch(
-                    value,
-                    JsonType::Object,
-                    val.get_type(),
-                ));
+            _ => {
+                errors.push(ValidationError::type_mismatch(value, JsonType::Object));
             }
         }
     }
@@ -169,14 +674,18 @@ pub struct ObjectSchemaBuilder {
     description: Option<String>,
     id: Option<String>,
     title: Option<String>,
+    examples: Option<Vec<Value>>,
+    counterexamples: Option<Vec<Value>>,
 
-    properties: Option<HashMap<String, Schema>>,
-    // TODO either object or bool
-    additional_properties: bool,
+    properties: Option<PropertyMap<Schema>>,
+    additional_properties: AdditionalProperties,
     required: Option<Vec<String>>,
     min_properties: Option<usize>,
     max_properties: Option<usize>,
-    pattern_properties: Option<HashMap<String, Schema>>,
+    pattern_properties: Option<PropertyMap<Schema>>,
+    definitions: Option<PropertyMap<Schema>>,
+    ordered_keys: Option<KeyOrder>,
+    comparisons: Option<Vec<Comparison>>,
 }
 
 impl Default for ObjectSchemaBuilder {
@@ -185,13 +694,18 @@ impl Default for ObjectSchemaBuilder {
             description: Default::default(),
             id: Default::default(),
             title: Default::default(),
+            examples: Default::default(),
+            counterexamples: Default::default(),
 
             properties: Default::default(),
-            additional_properties: true,
+            additional_properties: AdditionalProperties::Allowed(true),
             required: Default::default(),
             min_properties: Default::default(),
             max_properties: Default::default(),
             pattern_properties: Default::default(),
+            definitions: Default::default(),
+            ordered_keys: Default::default(),
+            comparisons: Default::default(),
         }
     }
 }
@@ -213,10 +727,23 @@ impl ObjectSchemaBuilder {
         self.title = Some(value.into());
         self
     }
+    /// Adds a value the schema accepts, see `Schema::self_test`.
+    pub fn example(mut self, value: Value) -> Self {
+        self.examples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
+    /// Adds a value the schema rejects, see `Schema::self_test`.
+    pub fn counterexample(mut self, value: Value) -> Self {
+        self.counterexamples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
 
     /// Set a map from property names to schemas.
-    pub fn properties(mut self, value: HashMap<String, Schema>) -> Self {
-        self.properties = Some(value);
+    pub fn properties<I>(mut self, value: I) -> Self
+    where
+        I: IntoIterator<Item = (String, Schema)>,
+    {
+        self.properties = Some(value.into_iter().collect());
         self
     }
 
@@ -230,7 +757,15 @@ impl ObjectSchemaBuilder {
     /// The `additional_properties` flag determines whether properties that aren't covered by
     /// this schema are allowed or not.
     pub fn additional_properties(mut self, value: bool) -> Self {
-        self.additional_properties = value;
+        self.additional_properties = AdditionalProperties::Allowed(value);
+        self
+    }
+
+    /// Set a schema that the values of all properties not covered by this schema
+    /// must conform to. Without `properties` and `pattern_properties` this makes
+    /// a map whose values share one schema, serialized as `additionalProperties`.
+    pub fn values_schema<V: Into<Schema>>(mut self, value: V) -> Self {
+        self.additional_properties = AdditionalProperties::Schema(Box::new(value.into()));
         self
     }
 
@@ -242,7 +777,7 @@ impl ObjectSchemaBuilder {
                 map = m;
             }
             None => {
-                map = HashMap::new();
+                map = PropertyMap::new();
             }
         }
         map.insert(name.into(), value.into());
@@ -250,20 +785,73 @@ impl ObjectSchemaBuilder {
         self
     }
 
+    /// Set the map of named schemas that references can point to with
+    /// `#/definitions/<name>`.
+    pub fn definitions<I>(mut self, value: I) -> Self
+    where
+        I: IntoIterator<Item = (String, Schema)>,
+    {
+        self.definitions = Some(value.into_iter().collect());
+        self
+    }
+
+    /// Adds a named schema to the definitions of this schema.
+    pub fn add_definition<K: Into<String>, V: Into<Schema>>(mut self, name: K, value: V) -> Self {
+        self.definitions
+            .get_or_insert_with(PropertyMap::new)
+            .insert(name.into(), value.into());
+        self
+    }
+
+    /// Requires the keys of objects to be in the given order, see
+    /// `ObjectSchema`.
+    #[cfg(feature = "preserve_order")]
+    pub fn ordered_keys(mut self, order: KeyOrder) -> Self {
+        self.ordered_keys = Some(order);
+        self
+    }
+
+    /// Adds a rule of the extension keyword `x-compare`, see `Comparison`.
+    pub fn compare(mut self, rule: Comparison) -> Self {
+        self.comparisons.get_or_insert_with(Vec::new).push(rule);
+        self
+    }
+
+    /// Finishes construction of the schema like `build` and combines it with
+    /// `schema` in an `allOf`, so values have to match both.
+    pub fn and<V: Into<Schema>>(self, schema: V) -> Schema {
+        all_of(vec![self.build(), schema.into()])
+    }
+
     /// Finishes construction of the schema, yielding the finished `Schema`.
     pub fn build(self) -> Schema {
-        From::from(ObjectSchema {
+        let mut schema = ObjectSchema {
             description: self.description,
             id: self.id,
             title: self.title,
+            nullable: false,
+            default: None,
+            examples: self.examples,
+            counterexamples: self.counterexamples,
+            deprecated: None,
+            removed_in: None,
+            enum_values: None,
+            const_value: None,
+            definitions: self.definitions,
+            dollar_defs: false,
 
             properties: self.properties,
             additional_properties: Some(self.additional_properties),
             required: self.required,
             min_properties: self.min_properties,
             max_properties: self.max_properties,
+            key_patterns: KeyPatterns::default(),
             pattern_properties: self.pattern_properties,
-        })
+            ordered_keys: self.ordered_keys,
+            comparisons: self.comparisons,
+        };
+        schema.compile_key_patterns();
+        From::from(schema)
     }
 }
 
@@ -273,11 +861,16 @@ mod tests {
 
     use serde_json;
 
+    use std::collections::HashMap;
+
     use super::*;
-    use string::StringSchema;
+    use string::{StringSchema, StringSchemaBuilder};
     use array::ArraySchemaBuilder;
+    use combinator::{not, one_of};
     use number::NumberSchema;
     use integer::IntegerSchema;
+    use errors::{Blank, ErrorCode};
+    use options::RequiredRejects;
 
 
     #[test]
@@ -298,7 +891,16 @@ mod tests {
             .additional_properties(false)
             .required(vec!["id".into(), "name".into()])
             .build();
-        schema.validate(&input).unwrap();
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "");
+        assert_eq!(errors[0].schema_path, "/additionalProperties");
+        match errors[0].reason {
+            ErrorKind::UnknownProperties(ref pointers) => {
+                assert_eq!(pointers, &["/id", "/name", "/unspecified"])
+            }
+            ref reason => panic!("unexpected error {}", reason),
+        }
     }
 
     #[test]
@@ -307,14 +909,136 @@ mod tests {
         let schema = ObjectSchemaBuilder::default()
             .required(vec!["id".into(), "name".into(), "missing".into()])
             .build();
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::MissingProperties]);
+        assert!(errors.contains_missing_property("missing"));
+        assert!(!errors.contains_missing_property("id"));
+
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "required": ["a", "b"],
+            "properties": {
+                "a": {"type": "string", "title": "First name"},
+                "c": {"type": "string"}
+            }
+        }))
+        .unwrap();
+        let input = json!({"padding": "x".repeat(100)});
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert_eq!(
+            errors.to_string(),
+            "Error at `/`: Missing object properties: `a` (First name), `b` (required by \
+             #/required)\n"
+        );
+        assert!(!errors.contains_missing_property("c"));
+    }
+
+    #[test]
+    fn required_attribution() {
+        let schema: Schema = serde_json::from_value(json!({
+            "allOf": [
+                {"type": "object", "additionalProperties": true, "required": ["id"]},
+                {
+                    "type": "object",
+                    "additionalProperties": true,
+                    "required": ["name", "id"]
+                },
+                {"type": "object", "properties": {"kind": {"type": "string"}}}
+            ]
+        }))
+        .unwrap();
+        let input = json!({});
         let errors = schema.validate(&input).unwrap_err().0;
-        assert_eq!(errors.len(), 1);
+        let required_by: Vec<Vec<(&str, &str)>> = errors
+            .iter()
+            .map(|error| match error.reason {
+                ErrorKind::MissingProperties(ref properties) => properties
+                    .iter()
+                    .map(|p| (p.name.as_str(), p.required_by.as_str()))
+                    .collect(),
+                ref other => panic!("unexpected error {}", other),
+            })
+            .collect();
+        assert_eq!(
+            required_by,
+            vec![
+                vec![("id", "/allOf/0/required")],
+                vec![("name", "/allOf/1/required"), ("id", "/allOf/1/required")],
+            ]
+        );
+        assert_eq!(
+            errors[1].reason.to_string(),
+            "Missing object properties: `name`, `id` (required by #/allOf/1/required)"
+        );
+    }
 
-        if let ErrorKind::MissingProperty(ref prop) = errors[0].reason {
-            assert_eq!(prop.as_str(), "missing");
-        } else {
-            assert!(false, "Wrong property");
-        }
+    #[test]
+    fn required_rejects() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["a", "b", "c", "d"]
+        }))
+        .unwrap();
+        let input = json!({"a": null, "b": "", "c": [], "d": 0});
+        let missing = |rejects: RequiredRejects| {
+            let options = ValidateOptions {
+                required_rejects: rejects,
+                ..ValidateOptions::default()
+            };
+            match schema.validate_with(&input, &options).errors.first() {
+                Some(error) => match error.reason {
+                    ErrorKind::MissingProperties(ref properties) => properties
+                        .iter()
+                        .map(|p| (p.name.clone(), p.blank))
+                        .collect(),
+                    ref other => panic!("unexpected error {}", other),
+                },
+                None => vec![],
+            }
+        };
+        // the specification only asks for the properties to be present
+        assert!(schema.validate(&input).is_ok());
+        assert_eq!(missing(RequiredRejects::default()), vec![]);
+        assert_eq!(
+            missing(RequiredRejects {
+                null: true,
+                ..RequiredRejects::default()
+            }),
+            vec![("a".into(), Some(Blank::Null))]
+        );
+        assert_eq!(
+            missing(RequiredRejects {
+                empty_string: true,
+                ..RequiredRejects::default()
+            }),
+            vec![("b".into(), Some(Blank::EmptyString))]
+        );
+        assert_eq!(
+            missing(RequiredRejects {
+                empty_array: true,
+                ..RequiredRejects::default()
+            }),
+            vec![("c".into(), Some(Blank::EmptyArray))]
+        );
+
+        let options = ValidateOptions {
+            required_rejects: RequiredRejects {
+                null: true,
+                empty_string: true,
+                empty_array: true,
+            },
+            ..ValidateOptions::default()
+        };
+        let input = json!({"b": ""});
+        let output = schema.validate_with(&input, &options);
+        assert_eq!(
+            output.errors[0].to_string(),
+            "Error at `/`: Missing object properties: `a`, \
+             `b` (is an empty string, which counts as missing), `c`, `d` \
+             (required by #/required)"
+        );
     }
 
     #[test]
@@ -365,10 +1089,20 @@ mod tests {
             )
             .build();
 
+        let polygon = |kind: &str| {
+            StringSchemaBuilder::default()
+                .pattern(format!("^{}$", kind))
+                .build()
+        };
         let geometry = ObjectSchemaBuilder::default()
-            .add_property("type", StringSchema::default())
+            .add_property(
+                "type",
+                one_of(vec![polygon("Polygon"), polygon("MultiPolygon")]),
+            )
             .add_property("coordinatees", coordinates)
-            .build();
+            .and(not(ObjectSchemaBuilder::default()
+                .required(vec!["crs".into()])
+                .build()));
 
         let features = ArraySchemaBuilder::default()
             .all_items_schema(
@@ -405,4 +1139,305 @@ mod tests {
         let input = serde_json::from_reader(File::open("data/canada.json").unwrap()).unwrap();
         schema.validate(&input).unwrap();
     }
+
+    #[test]
+    fn deterministic_errors() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "required": ["id", "kind"],
+            "properties": {
+                "zeta": {"type": "string"},
+                "alpha": {"type": "integer"},
+                "mid": {"type": "boolean"},
+                "beta": {"type": "array"},
+                "omega": {"type": "object"}
+            },
+            "patternProperties": {
+                "^a": {"type": "boolean"},
+                "^[mz]": {"type": "string"}
+            },
+            "maxProperties": 3
+        })).unwrap();
+        let input = json!({"zeta": 1, "alpha": "a", "mid": 1, "beta": 2, "omega": 3});
+        let run = || -> Vec<(String, String, String)> {
+            schema
+                .validate(&input)
+                .unwrap_err()
+                .0
+                .iter()
+                .map(|e| (e.instance_path.clone(), e.schema_path.clone(), e.reason.to_string()))
+                .collect()
+        };
+        let first = run();
+        assert_eq!(first.len(), 10);
+        assert!(first[0].2.ends_with("`id`, `kind` (required by #/required)"));
+        let first_property = if cfg!(feature = "preserve_order") {
+            "/zeta"
+        } else {
+            "/alpha"
+        };
+        assert_eq!(first[1].0, first_property);
+        assert!(first[9].2.starts_with("Property count"));
+        for _ in 0..50 {
+            assert_eq!(run(), first);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "preserve_order")]
+    fn ordered_keys() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "x-orderedKeys": "asc",
+            "additionalProperties": true
+        }))
+        .unwrap();
+        schema
+            .validate_str(r#"{"a": 1, "b": {"d": 1, "c": 2}, "c": 3}"#)
+            .unwrap();
+        let input: Value = serde_json::from_str(r#"{"a": 1, "c": 2, "b": 3, "A": 4}"#).unwrap();
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::UnorderedKeys]);
+        assert_eq!(errors.0[0].instance_path, "/b");
+        assert_eq!(errors.0[0].schema_path, "/x-orderedKeys");
+        assert_eq!(
+            errors.0[0].reason.to_string(),
+            "Key `b` comes after `c`, keys must be sorted"
+        );
+
+        let schema = ObjectSchemaBuilder::default()
+            .add_property("zeta", StringSchema::default())
+            .add_property("alpha", StringSchema::default())
+            .ordered_keys(KeyOrder::Schema)
+            .build();
+        let serialized = serde_json::to_value(&schema).unwrap();
+        assert_eq!(serialized["x-orderedKeys"], json!("schema"));
+        schema
+            .validate_str(r#"{"zeta": "z", "extra": 1, "alpha": "a"}"#)
+            .unwrap();
+        let input: Value = serde_json::from_str(r#"{"alpha": "a", "zeta": "z"}"#).unwrap();
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.0[0].instance_path, "/zeta");
+        assert_eq!(
+            errors.0[0].reason.to_string(),
+            "Key `zeta` comes after `alpha`, keys must be in the order of `properties`"
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "preserve_order"))]
+    fn ordered_keys_need_preserve_order() {
+        let error = serde_json::from_value::<Schema>(json!({
+            "type": "object",
+            "x-orderedKeys": "asc"
+        }))
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("x-orderedKeys needs the `preserve_order` feature"));
+    }
+
+    #[test]
+    #[cfg(feature = "preserve_order")]
+    fn preserve_order() {
+        let text = r#"{"type":"object","properties":{"zeta":{"type":"string"},"alpha":{"type":"integer"},"mid":{"type":"boolean"}}}"#;
+        let schema: Schema = serde_json::from_str(text).unwrap();
+        let serialized = serde_json::to_string(&schema).unwrap();
+        let position = |key: &str| serialized.find(&format!("\"{}\"", key)).unwrap();
+        assert!(position("zeta") < position("alpha") && position("alpha") < position("mid"));
+
+        let input = json!({"alpha": "1", "mid": "2", "zeta": 3});
+        let paths: Vec<_> = schema
+            .validate(&input)
+            .unwrap_err()
+            .0
+            .into_iter()
+            .map(|e| e.instance_path)
+            .collect();
+        assert_eq!(paths, vec!["/zeta", "/alpha", "/mid"]);
+
+        let schema = ObjectSchemaBuilder::default()
+            .add_property("zeta", StringSchema::default())
+            .add_property("alpha", StringSchema::default())
+            .build();
+        let serialized = serde_json::to_string(&schema).unwrap();
+        let position = |key: &str| serialized.find(&format!("\"{}\"", key)).unwrap();
+        assert!(position("zeta") < position("alpha"));
+    }
+
+    #[test]
+    fn single_property() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["name", "email"],
+            "minProperties": 3,
+            "properties": {
+                "name": {"type": "string", "minLength": 2},
+                "email": {"type": "string"}
+            },
+            "patternProperties": {
+                "^x-": {"type": "string"},
+                "^x-count": {"type": "integer"}
+            }
+        }))
+        .unwrap();
+        let object = schema.as_object().unwrap();
+
+        object.validate_property("name", &json!("Jane")).unwrap();
+        let short = json!("J");
+        let errors = object.validate_property("name", &short).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::MinLength]);
+        assert_eq!(errors.0[0].instance_path, "/name");
+        assert_eq!(errors.0[0].schema_path, "/properties/name");
+
+        assert!(object.is_property_allowed("x-note"));
+        object.validate_property("x-note", &json!("hi")).unwrap();
+        let text = json!("3");
+        let errors = object.validate_property("x-count", &text).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::TypeMismatch]);
+        assert_eq!(errors.0[0].schema_path, "/patternProperties/^x-count");
+
+        assert!(!object.is_property_allowed("age"));
+        let number = json!(3);
+        let errors = object.validate_property("age", &number).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::UnknownProperties]);
+        assert_eq!(errors.0[0].instance_path, "/age");
+
+        let open: Schema =
+            serde_json::from_value(json!({"type": "object", "properties": {}})).unwrap();
+        assert!(open.as_object().unwrap().is_property_allowed("age"));
+        assert!(Schema::from(StringSchema::default()).as_object().is_none());
+    }
+
+    #[test]
+    fn count_pattern_properties() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": false,
+            "maxProperties": 2,
+            "patternProperties": {"^x-": {"type": "string"}}
+        }))
+        .unwrap();
+        schema.validate(&json!({"x-a": "a", "x-b": "b"})).unwrap();
+        let input = json!({"x-a": "a", "x-b": "b", "x-c": "c"});
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::PropertyCount]);
+        assert_eq!(
+            errors.0[0].reason.to_string(),
+            "Property count out of range: bound is 2, the object has 3 \
+             (all keys count, including those of patternProperties)"
+        );
+    }
+
+    #[test]
+    fn values_schema() {
+        let schema = ObjectSchemaBuilder::default()
+            .values_schema(IntegerSchema::default())
+            .build();
+        assert_eq!(
+            serde_json::to_value(&schema).unwrap()["additionalProperties"]["type"],
+            json!("integer")
+        );
+        let parsed: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": {"type": "integer"}
+        }))
+        .unwrap();
+
+        let input = json!({"a": 1, "b": "two", "c/d": 3, "e": 4.5});
+        for schema in &[schema, parsed] {
+            let errors = schema.validate(&input).unwrap_err();
+            assert_eq!(errors.codes(), vec![ErrorCode::TypeMismatch; 2]);
+            let paths: Vec<_> = errors
+                .0
+                .iter()
+                .map(|e| (e.instance_path.as_str(), e.schema_path.as_str()))
+                .collect();
+            assert_eq!(
+                paths,
+                vec![
+                    ("/b", "/additionalProperties"),
+                    ("/e", "/additionalProperties")
+                ]
+            );
+            schema.validate(&json!({"a": 1, "c/d": 3})).unwrap();
+            schema.validate(&json!({})).unwrap();
+        }
+
+        let map: Map<String, Value> = (0..100_000)
+            .map(|i| (format!("key{}", i), json!(i)))
+            .collect();
+        let homogeneous = ObjectSchemaBuilder::default()
+            .values_schema(IntegerSchema::default())
+            .build();
+        homogeneous.validate(&Value::Object(map)).unwrap();
+
+        // declared properties keep their own schema
+        let mixed: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "patternProperties": {"^x-": {"type": "boolean"}},
+            "additionalProperties": {"type": "integer"}
+        }))
+        .unwrap();
+        let input = json!({"name": "n", "x-flag": true, "count": "3"});
+        let errors = mixed.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::TypeMismatch]);
+        assert_eq!(errors.0[0].instance_path, "/count");
+        let object = mixed.as_object().unwrap();
+        object.validate_property("name", &json!("n")).unwrap();
+        object.validate_property("count", &json!(3)).unwrap();
+        let text = json!("3");
+        let errors = object.validate_property("count", &text).unwrap_err();
+        assert_eq!(errors.0[0].schema_path, "/additionalProperties");
+    }
+
+    #[test]
+    fn applicable_schemas() {
+        let schema = |additional: bool| -> Schema {
+            let mut schema = json!({
+                "type": "object",
+                "properties": {"id": {"title": "id"}, "x-both": {"title": "x-both"}},
+                "patternProperties": {"^x-": {"title": "pattern"}}
+            });
+            if additional {
+                schema["additionalProperties"] = json!({"title": "additional"});
+            }
+            serde_json::from_value(schema).unwrap()
+        };
+        // (key, additionalProperties present, applicable schemas)
+        let table = [
+            ("other", false, vec![]),
+            ("other", true, vec!["additional"]),
+            ("x-tag", false, vec!["pattern"]),
+            ("x-tag", true, vec!["pattern"]),
+            ("id", false, vec!["id"]),
+            ("id", true, vec!["id"]),
+            ("x-both", false, vec!["x-both", "pattern"]),
+            ("x-both", true, vec!["x-both", "pattern"]),
+        ];
+        let input = json!({"other": 1, "x-tag": 2, "id": 3, "x-both": 4});
+        for &(key, additional, ref expected) in &table {
+            let schema = schema(additional);
+            let titles: Vec<_> = schema
+                .as_object()
+                .unwrap()
+                .applicable_schemas(key)
+                .into_iter()
+                .filter_map(Schema::title)
+                .collect();
+            assert_eq!(&titles, expected, "{} {}", key, additional);
+
+            // validation applies the same schemas
+            let output = schema.validate_with_output(&input, true);
+            assert!(output.is_valid());
+            let path = format!("/{}", key);
+            let applied: Vec<_> = output
+                .annotations_at(&path, "title")
+                .filter_map(|a| a.value.as_str())
+                .collect();
+            assert_eq!(&applied, expected, "{} {}", key, additional);
+        }
+    }
 }
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use audit::InstanceLimits;
+use errors::Blank;
+use extensions::Extensions;
+use limits::SchemaLimits;
+use preprocess::Preprocessor;
+use render::DEFAULT_PREVIEW_LENGTH;
+use stats::StatsCollector;
+
+/// The version of the JSON schema specification whose rules validation follows,
+/// where the versions differ.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Serialize)]
+pub enum Draft {
+    /// Draft 4.
+    Draft4,
+    /// Draft 6.
+    Draft6,
+    /// Draft 7.
+    #[default]
+    Draft7,
+    /// Draft 2019-09.
+    Draft201909,
+    /// Draft 2020-12.
+    Draft202012,
+}
+
+/// How the `pattern` keyword matches strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PatternMode {
+    /// The pattern has to match somewhere in the string, as the specification
+    /// says: `[a-z]+` accepts `"123abc"`.
+    #[default]
+    Search,
+    /// The pattern has to match the whole string: `[a-z]+` only accepts strings
+    /// made of lowercase letters.
+    FullMatch,
+}
+
+/// The values of required properties that count as missing, see
+/// `ValidateOptions::required_rejects`.
+///
+/// This is not part of the JSON schema specification, which only asks for the
+/// property to be present: with any of these set, documents valid according to
+/// the specification can be rejected. It's meant for producers that send
+/// `null` or `""` instead of leaving a property out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RequiredRejects {
+    /// `null` counts as missing.
+    pub null: bool,
+    /// `""` counts as missing.
+    pub empty_string: bool,
+    /// `[]` counts as missing.
+    pub empty_array: bool,
+}
+
+impl RequiredRejects {
+    /// The kind of blank `value` is, if it counts as missing.
+    pub(crate) fn blank(&self, value: &Value) -> Option<Blank> {
+        match *value {
+            Value::Null if self.null => Some(Blank::Null),
+            Value::String(ref s) if self.empty_string && s.is_empty() => Some(Blank::EmptyString),
+            Value::Array(ref a) if self.empty_array && a.is_empty() => Some(Blank::EmptyArray),
+            _ => None,
+        }
+    }
+}
+
+/// How many elements of long arrays are validated against `items`, see
+/// `ValidateOptions::array_sampling`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SamplingPolicy {
+    /// The number of elements checked per array, including the first and the
+    /// last. Arrays up to this length are checked completely.
+    pub max_per_array: usize,
+    /// Picks the elements in between. The same seed picks the same elements of
+    /// the same array on every run.
+    pub seed: u64,
+}
+
+/// Settings for a single validation run, see `Schema::validate_with`.
+#[derive(Clone, Debug)]
+pub struct ValidateOptions {
+    /// Collect the annotations (`title`, `default`, matching `oneOf` branch, ...)
+    /// of every subschema that matched.
+    pub annotations: bool,
+    /// Validate only a sample of the elements of arrays longer than
+    /// `SamplingPolicy::max_per_array` against `items` (and `additionalItems`),
+    /// for monitoring large documents. The other array keywords still look at
+    /// the whole array, except `uniqueItems` and `x-uniqueBy`, which are skipped
+    /// for sampled arrays and listed in `ValidationOutput::skipped`. A sampled
+    /// run is marked with `ValidationOutput::sampled`: its errors are real, but
+    /// a valid result is no proof that every element is valid.
+    pub array_sampling: Option<SamplingPolicy>,
+    /// Measure how long validation takes in total and in reference resolution,
+    /// regular expressions and the uniqueness checks, see
+    /// `ValidationOutput::timing`. Only these parts read the clock, so the cost is
+    /// small, and nothing is measured if unset.
+    pub collect_timing: bool,
+    /// Collect warnings about conditions that don't fail validation, like the use
+    /// of deprecated subschemas or unknown formats.
+    pub collect_warnings: bool,
+    /// Audit the instance with these limits before validating it, see
+    /// `audit_instance`. The issues found are reported as
+    /// `WarningKind::UnsupportedInput` warnings, even without
+    /// `collect_warnings`.
+    pub audit: Option<InstanceLimits>,
+    /// Reject values of subschemas marked `deprecated` whose `x-removed-in` date
+    /// has passed with `ErrorKind::Removed`, instead of only warning about them.
+    pub deny_expired: bool,
+    /// State of the integrator for custom schemas, read with
+    /// `Context::extension`. Shared by every run with these options and by
+    /// their clones.
+    pub extensions: Extensions,
+    /// The draft to follow. Up to draft 7 keywords next to `$ref` are ignored,
+    /// from draft 2019-09 on they're applied as well.
+    pub draft: Draft,
+    /// Fold the errors a subschema reports with the same code about values that
+    /// only differ in their array indices, like a property missing from every
+    /// element of an array, once there are more than this many of them. The
+    /// first error then stands for the rest, listing where they are in
+    /// `ValidationError::folded`. Nothing is folded if `None`.
+    pub fold_similar: Option<usize>,
+    /// The number of nested subschemas (properties, items, branches, references,
+    /// ...) after which validation gives up with `ErrorKind::DepthLimitExceeded`
+    /// instead of going deeper. Unlimited if `None`.
+    pub max_depth: Option<usize>,
+    /// The number of errors after which the rest are dropped. Validation
+    /// doesn't enter further subschemas once there are that many, and keywords
+    /// that report every offending element of an array on its own stop there
+    /// too, so the first few errors are found without walking the whole
+    /// document. The errors kept are the first ones a run without the limit
+    /// reports. Unlimited if `None`.
+    pub max_errors: Option<usize>,
+    /// The memory validation may hold at once, in bytes, after which it gives
+    /// up, reporting the errors found so far and an `ErrorKind::MemoryLimit`
+    /// error. The count is approximate: it covers the errors, without the strings
+    /// they hold, the buffers for the errors of `anyOf`, `oneOf` and `not`
+    /// branches and the items kept for `uniqueItems` and `x-uniqueBy`, not the
+    /// small amounts every subschema needs. Unlimited if `None`, but the memory
+    /// is counted anyway, see `ValidationOutput::peak_tracked_bytes`.
+    pub max_memory: Option<usize>,
+    /// The limits the regular expressions of `pattern` and `patternProperties`
+    /// are compiled within. Patterns beyond them fail with
+    /// `ErrorKind::InvalidRegex`.
+    pub limits: SchemaLimits,
+    /// The date the `x-removed-in` dates of deprecated subschemas are compared
+    /// with, today in UTC if `None`.
+    pub now: Option<NaiveDate>,
+    /// How `pattern` matches, unless the string schema sets its own mode.
+    pub pattern_mode: PatternMode,
+    /// Read the text given to `Schema::validate_str_with` and
+    /// `Schema::validate_reader_with` as a stream of concatenated JSON texts,
+    /// like `{"id": 1} {"id": 2}` or JSON lines, validating each of them. The
+    /// other ways of validating take a single value.
+    pub multiple_documents: bool,
+    /// Count required properties that are present but `null`, `""` or `[]` as
+    /// missing, against the specification. None of them by default.
+    pub required_rejects: RequiredRejects,
+    /// Follow the specification strictly: the extension keywords of this crate
+    /// that reject values, `x-uniqueBy`, `x-orderedKeys`, `x-compare`,
+    /// `x-formatMinimum`, `x-formatMaximum` and their exclusive variants, are
+    /// ignored with a `WarningKind::IgnoredExtension` warning.
+    pub strict_spec: bool,
+    /// Validate partial objects, like the payloads of PATCH requests: the
+    /// properties that are present are validated, but none are required and
+    /// `minProperties` doesn't apply. The elements of arrays, which updates
+    /// replace as a whole, are validated in full. See `Schema::validate_partial`.
+    pub partial: bool,
+    /// The transformations `Schema::validate_preprocessed` applies to documents
+    /// before validating them, see `ValidateOptions::preprocess`. The other ways
+    /// of validating don't run them.
+    pub preprocessors: Vec<Arc<dyn Preprocessor>>,
+    /// Where to count how often each subschema is evaluated and fails and how
+    /// long it takes, see `StatsCollector`. Nothing is counted if `None`.
+    pub stats: Option<Arc<StatsCollector>>,
+    /// The time after which validation gives up, reporting the errors found so
+    /// far and an `ErrorKind::Timeout` error. The clock is only read every few
+    /// hundred subschemas, so the limit can be exceeded a little.
+    pub timeout: Option<Duration>,
+    /// The number of characters of the invalid value kept in the `value_preview`
+    /// of errors, see `render::preview`. `None` leaves the previews out, for
+    /// values that must not end up in logs or responses.
+    pub value_preview: Option<usize>,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> ValidateOptions {
+        ValidateOptions {
+            annotations: false,
+            array_sampling: None,
+            collect_timing: false,
+            collect_warnings: false,
+            audit: None,
+            deny_expired: false,
+            extensions: Extensions::default(),
+            draft: Draft::default(),
+            fold_similar: None,
+            max_depth: None,
+            max_errors: None,
+            max_memory: None,
+            multiple_documents: false,
+            limits: SchemaLimits::default(),
+            now: None,
+            pattern_mode: PatternMode::default(),
+            required_rejects: RequiredRejects::default(),
+            strict_spec: false,
+            partial: false,
+            preprocessors: vec![],
+            stats: None,
+            timeout: None,
+            value_preview: Some(DEFAULT_PREVIEW_LENGTH),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use errors::{ErrorKind, ValidationError, ValidationErrors};
+use options::ValidateOptions;
+use output::ValidationOutput;
+use schema::Schema;
+
+/// A reason a schema couldn't be applied to a value, which isn't the fault of
+/// the value, see `ValidationOutcome::Indeterminate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaProblem {
+    /// A `$ref` that doesn't point to a schema.
+    UnresolvedReference {
+        /// The reference.
+        reference: String,
+        /// JSON pointer to the subschema holding it.
+        schema_path: String,
+    },
+    /// A `$ref` that leads back to itself without reaching a value.
+    ReferenceCycle {
+        /// The reference.
+        reference: String,
+        /// JSON pointer to the subschema holding it.
+        schema_path: String,
+    },
+    /// A `pattern` that doesn't compile or exceeds the regex limits, found
+    /// when it's first used.
+    InvalidRegex {
+        /// The pattern, or the limit it exceeds.
+        message: String,
+        /// JSON pointer to the subschema holding it.
+        schema_path: String,
+    },
+    /// Validation nested deeper than `ValidateOptions::max_depth`.
+    DepthLimit {
+        /// JSON pointer to the subschema validation stopped at.
+        schema_path: String,
+    },
+    /// Validation ran out of time, see `ValidateOptions::timeout`.
+    Timeout {
+        /// The time validation took until it gave up.
+        elapsed: Duration,
+    },
+    /// Validation ran out of memory, see `ValidateOptions::max_memory`.
+    MemoryLimit {
+        /// The limit in bytes.
+        limit: usize,
+    },
+}
+
+impl SchemaProblem {
+    /// The problem `error` stands for, if it isn't about the value.
+    pub fn from_error(error: &ValidationError) -> Option<SchemaProblem> {
+        let schema_path = error.schema_path.clone();
+        Some(match error.reason {
+            ErrorKind::UnresolvedPointer(ref reference) => SchemaProblem::UnresolvedReference {
+                reference: reference.clone(),
+                schema_path,
+            },
+            ErrorKind::ReferenceCycle(ref reference) => SchemaProblem::ReferenceCycle {
+                reference: reference.clone(),
+                schema_path,
+            },
+            ErrorKind::InvalidRegex(ref message) => SchemaProblem::InvalidRegex {
+                message: message.clone(),
+                schema_path,
+            },
+            ErrorKind::DepthLimitExceeded => SchemaProblem::DepthLimit { schema_path },
+            ErrorKind::Timeout { elapsed } => SchemaProblem::Timeout { elapsed },
+            ErrorKind::MemoryLimit { limit } => SchemaProblem::MemoryLimit { limit },
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for SchemaProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SchemaProblem::UnresolvedReference {
+                ref reference,
+                ref schema_path,
+            } => write!(
+                f,
+                "`{}` at \"{}\" doesn't point to a schema",
+                reference, schema_path
+            ),
+            SchemaProblem::ReferenceCycle {
+                ref reference,
+                ref schema_path,
+            } => write!(
+                f,
+                "`{}` at \"{}\" leads back to itself",
+                reference, schema_path
+            ),
+            SchemaProblem::InvalidRegex {
+                ref message,
+                ref schema_path,
+            } => write!(f, "invalid regex at \"{}\": {}", schema_path, message),
+            SchemaProblem::DepthLimit { ref schema_path } => {
+                write!(f, "the depth limit is exceeded at \"{}\"", schema_path)
+            }
+            SchemaProblem::Timeout { elapsed } => write!(f, "aborted after {:?}", elapsed),
+            SchemaProblem::MemoryLimit { limit } => {
+                write!(f, "aborted after holding more than {} bytes", limit)
+            }
+        }
+    }
+}
+
+/// Whether a value is valid, telling values the schema rejects from schemas
+/// that couldn't be applied, see `Schema::evaluate`.
+#[derive(Debug)]
+pub enum ValidationOutcome<'json> {
+    /// The schema accepts the value.
+    Valid,
+    /// The schema rejects the value.
+    Invalid(ValidationErrors<'json>),
+    /// The schema couldn't be applied, like when a reference doesn't resolve
+    /// or validation ran out of time. Whatever else was found about the value
+    /// can't be trusted.
+    Indeterminate(Vec<SchemaProblem>),
+}
+
+impl<'json> ValidationOutcome<'json> {
+    /// Whether the value is known to be valid.
+    pub fn is_valid(&self) -> bool {
+        matches!(*self, ValidationOutcome::Valid)
+    }
+}
+
+impl<'json> ValidationOutput<'json> {
+    /// The problems of the schema among the errors.
+    pub fn problems(&self) -> Vec<SchemaProblem> {
+        self.errors
+            .iter()
+            .filter_map(SchemaProblem::from_error)
+            .collect()
+    }
+
+    /// Splits the result into the outcomes of `Schema::evaluate`.
+    pub fn outcome(self) -> ValidationOutcome<'json> {
+        let problems = self.problems();
+        if !problems.is_empty() {
+            ValidationOutcome::Indeterminate(problems)
+        } else if self.errors.is_empty() {
+            ValidationOutcome::Valid
+        } else {
+            ValidationOutcome::Invalid(ValidationErrors(self.errors))
+        }
+    }
+}
+
+impl Schema {
+    /// Validates `value` like `validate`, but reports problems of the schema,
+    /// like unresolved references, instead of blaming the value. Problems in
+    /// branches of `anyOf`, `oneOf` and `not` that don't decide the result are
+    /// hidden like all errors of such branches.
+    pub fn evaluate<'json>(&self, value: &'json Value) -> ValidationOutcome<'json> {
+        self.evaluate_with(value, &ValidateOptions::default())
+    }
+
+    /// `evaluate` with the given options.
+    pub fn evaluate_with<'json>(
+        &self,
+        value: &'json Value,
+        options: &ValidateOptions,
+    ) -> ValidationOutcome<'json> {
+        self.validate_with(value, options).outcome()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::ErrorCode;
+
+    #[test]
+    fn outcomes() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer"},
+                "code": {"type": "string", "pattern": "^(a$"},
+                "parent": {"$ref": "#/definitions/missing"}
+            },
+            "additionalProperties": true
+        }))
+        .unwrap();
+        assert!(schema.evaluate(&json!({"id": 1})).is_valid());
+
+        let value = json!({"id": "1"});
+        match schema.evaluate(&value) {
+            ValidationOutcome::Invalid(errors) => {
+                assert_eq!(errors.codes(), vec![ErrorCode::TypeMismatch])
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+
+        // the value is invalid too, but the schema's problems come first
+        let value = json!({"id": "1", "code": "a", "parent": 1});
+        match schema.evaluate(&value) {
+            ValidationOutcome::Indeterminate(problems) => {
+                assert_eq!(problems.len(), 2);
+                assert_eq!(
+                    problems[1],
+                    SchemaProblem::UnresolvedReference {
+                        reference: "#/definitions/missing".into(),
+                        schema_path: "/properties/parent".into(),
+                    }
+                );
+                match problems[0] {
+                    SchemaProblem::InvalidRegex {
+                        ref schema_path, ..
+                    } => assert_eq!(schema_path, "/properties/code"),
+                    ref problem => panic!("unexpected {}", problem),
+                }
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+
+        let options = ValidateOptions {
+            max_depth: Some(0),
+            ..ValidateOptions::default()
+        };
+        let value = json!({"id": 1});
+        match schema.evaluate_with(&value, &options) {
+            ValidationOutcome::Indeterminate(problems) => match problems[0] {
+                SchemaProblem::DepthLimit { .. } => {}
+                ref problem => panic!("unexpected {}", problem),
+            },
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+    }
+}
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use serde::Serializer;
+use serde_json::Value;
+
+use audit::InstanceIssue;
+use errors::{ErrorCode, FoldedErrors, ValidationError};
+
+/// A piece of information a matching subschema attaches to a value, like its
+/// `title` or `default`, or which branch of a `oneOf` matched.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    /// The keyword that produced the annotation.
+    pub keyword: String,
+    /// JSON pointer to the subschema containing the keyword.
+    pub schema_path: String,
+    /// JSON pointer to the annotated value.
+    pub instance_path: String,
+    /// The annotation itself. For `oneOf` this is the index of the matching
+    /// branch, for `anyOf` the list of indices of all matching branches.
+    pub value: Value,
+}
+
+/// A keyword that wasn't checked because the array it applies to was sampled,
+/// see `ValidateOptions::array_sampling`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedKeyword {
+    /// The keyword, like `uniqueItems`.
+    pub keyword: String,
+    /// JSON pointer to the subschema containing the keyword.
+    pub schema_path: String,
+    /// JSON pointer to the array.
+    pub instance_path: String,
+}
+
+/// Where the time of a validation run went, see `ValidateOptions::collect_timing`.
+/// The parts don't overlap, the rest of `total` is spent applying the other
+/// keywords. Serializes the durations as microseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Timing {
+    /// The whole run.
+    #[serde(rename = "totalMicros", serialize_with = "micros")]
+    pub total: Duration,
+    /// Looking up the targets of `$ref`s.
+    #[serde(rename = "refResolutionMicros", serialize_with = "micros")]
+    pub ref_resolution: Duration,
+    /// Compiling and matching the regular expressions of `pattern` and
+    /// `patternProperties`.
+    #[serde(rename = "regexMicros", serialize_with = "micros")]
+    pub regex: Duration,
+    /// Checking `uniqueItems` and `x-uniqueBy`.
+    #[serde(rename = "uniquenessMicros", serialize_with = "micros")]
+    pub uniqueness: Duration,
+}
+
+pub(crate) fn micros<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(time.as_secs() * 1_000_000 + u64::from(time.subsec_micros()))
+}
+
+/// Conditions that are worth reporting but don't make a value invalid.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WarningKind {
+    /// The value was validated by a subschema marked `deprecated`.
+    Deprecated {
+        /// The `description` of the subschema, which usually says what to use
+        /// instead.
+        message: Option<String>,
+        /// The `x-removed-in` date of the subschema.
+        removed_in: Option<String>,
+        /// Whether the removal date has passed, see `ValidateOptions::now`.
+        expired: bool,
+    },
+    /// The property isn't declared by the object schema and was only accepted because
+    /// `additionalProperties` wasn't given.
+    UndeclaredProperty(String),
+    /// The `format` isn't known, so it wasn't checked.
+    UnknownFormat(String),
+    /// The `enum` keyword has no values, so no value can match it.
+    EmptyEnum,
+    /// The keywords next to a `$ref` were ignored, as the selected draft requires.
+    IgnoredRefSiblings(Vec<String>),
+    /// The schema only holds `definitions`, so it accepts any value. Validate
+    /// against one of the definitions instead.
+    DefinitionsOnly,
+    /// The extension keyword was ignored, as `ValidateOptions::strict_spec` asks.
+    IgnoredExtension(String),
+    /// The instance has data validation can't judge reliably, found by the
+    /// audit `ValidateOptions::audit` asks for.
+    UnsupportedInput(InstanceIssue),
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WarningKind::Deprecated {
+                ref message,
+                ref removed_in,
+                expired,
+            } => {
+                write!(f, "Value uses a deprecated schema")?;
+                if let Some(ref message) = *message {
+                    write!(f, ": {}", message)?;
+                }
+                match *removed_in {
+                    Some(ref date) if expired => write!(f, " (removed in {})", date),
+                    Some(ref date) => write!(f, " (to be removed in {})", date),
+                    None => Ok(()),
+                }
+            }
+            WarningKind::UndeclaredProperty(ref property) => write!(
+                f,
+                "Property `{}` is not declared and only accepted because additionalProperties is not set",
+                property
+            ),
+            WarningKind::UnknownFormat(ref format) => {
+                write!(f, "Unknown format `{}` is not checked", format)
+            }
+            WarningKind::EmptyEnum => write!(f, "Empty enum doesn't allow any value"),
+            WarningKind::IgnoredRefSiblings(ref keywords) => {
+                write!(f, "Keywords next to `$ref` are ignored: {}", keywords.join(", "))
+            }
+            WarningKind::DefinitionsOnly => write!(
+                f,
+                "Schema only has definitions and accepts any value, validate against a definition"
+            ),
+            WarningKind::IgnoredExtension(ref keyword) => {
+                write!(f, "Extension keyword `{}` is ignored in strict mode", keyword)
+            }
+            WarningKind::UnsupportedInput(ref issue) => issue.fmt(f),
+        }
+    }
+}
+
+/// A warning reported during validation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationWarning {
+    /// What the warning is about.
+    pub kind: WarningKind,
+    /// JSON pointer to the value the warning is about.
+    pub instance_path: String,
+    /// JSON pointer to the subschema that reported the warning.
+    pub schema_path: String,
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Warning at `{}`: {}", self.instance_path, self.kind)
+    }
+}
+
+/// The result of `Schema::validate_with`.
+#[derive(Debug)]
+pub struct ValidationOutput<'json> {
+    /// The validation errors, empty if the value is valid.
+    pub errors: Vec<ValidationError<'json>>,
+    /// The collected annotations, if annotation collection was requested.
+    pub annotations: Vec<Annotation>,
+    /// The collected warnings, if warning collection was requested.
+    pub warnings: Vec<ValidationWarning>,
+    /// Whether validation was aborted because it ran out of time or memory, see
+    /// `ValidateOptions::timeout` and `ValidateOptions::max_memory`. The errors
+    /// found until then are reported, but the value may have more.
+    pub incomplete: bool,
+    /// The most memory validation held at once, as far as it's counted for
+    /// `ValidateOptions::max_memory`, in bytes.
+    pub peak_tracked_bytes: usize,
+    /// Whether only a sample of the elements of some array was validated, see
+    /// `ValidateOptions::array_sampling`. A valid result then doesn't mean that
+    /// every element is valid.
+    pub sampled: bool,
+    /// The keywords skipped because of sampling.
+    pub skipped: Vec<SkippedKeyword>,
+    /// Where the time of the run went, if `ValidateOptions::collect_timing` is
+    /// set.
+    pub timing: Option<Timing>,
+}
+
+/// `instance_path` with the tokens that are array indices in `document`
+/// replaced by `*`.
+fn without_indices(document: &Value, instance_path: &str) -> String {
+    let mut current = Some(document);
+    let mut masked = String::with_capacity(instance_path.len());
+    for token in instance_path.split('/').skip(1) {
+        masked.push('/');
+        current = match current {
+            Some(Value::Array(array)) => {
+                masked.push('*');
+                token.parse().ok().and_then(|i: usize| array.get(i))
+            }
+            Some(Value::Object(object)) => {
+                masked.push_str(token);
+                object.get(&token.replace("~1", "/").replace("~0", "~"))
+            }
+            _ => {
+                masked.push_str(token);
+                None
+            }
+        };
+    }
+    masked
+}
+
+/// Folds the errors about `document` that only differ in array indices into
+/// the first of them, for groups of more than `shown` errors, see
+/// `ValidateOptions::fold_similar`.
+pub(crate) fn fold_similar(errors: &mut Vec<ValidationError>, document: &Value, shown: usize) {
+    let shown = shown.max(1);
+    let mut groups: HashMap<(String, ErrorCode, String), Vec<usize>> = HashMap::new();
+    for (i, error) in errors.iter().enumerate() {
+        let key = (
+            error.schema_path.clone(),
+            error.reason.code(),
+            without_indices(document, &error.instance_path),
+        );
+        groups.entry(key).or_default().push(i);
+    }
+    let mut keep = vec![true; errors.len()];
+    for indices in groups.values().filter(|indices| indices.len() > shown) {
+        let folded = FoldedErrors {
+            count: indices.len() - 1,
+            sample_paths: indices[1..shown]
+                .iter()
+                .map(|&i| errors[i].instance_path.clone())
+                .collect(),
+        };
+        errors[indices[0]].folded = Some(folded);
+        for &i in &indices[1..] {
+            keep[i] = false;
+        }
+    }
+    let mut keep = keep.into_iter();
+    errors.retain(|_| keep.next().unwrap_or(true));
+}
+
+/// Annotations and warnings recorded while a validation run is in progress.
+#[derive(Debug, Default)]
+pub(crate) struct Collected {
+    pub annotations: Vec<Annotation>,
+    pub warnings: Vec<ValidationWarning>,
+    /// The indices of the subschemas that accepted a value, see `CoverageSession`.
+    pub hits: Vec<usize>,
+    /// The most reference tokens left on the focus path where validation had to
+    /// look at whole values, see `Context::unfocused`.
+    pub unfocused: usize,
+    /// Whether an array was sampled, see `ValidateOptions::array_sampling`.
+    pub sampled: bool,
+    /// The keywords skipped for sampled arrays.
+    pub skipped: Vec<SkippedKeyword>,
+    /// The time spent in parts of the run so far, see `Context::time`.
+    pub timing: Timing,
+}
+
+impl<'json> ValidationOutput<'json> {
+    /// Whether the value is valid.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the annotations for `keyword` at `instance_path`.
+    pub fn annotations_at<'a>(
+        &'a self,
+        instance_path: &'a str,
+        keyword: &'a str,
+    ) -> impl Iterator<Item = &'a Annotation> + 'a {
+        self.annotations
+            .iter()
+            .filter(move |a| a.instance_path == instance_path && a.keyword == keyword)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde_json::{self, Value};
+
+    use errors::ErrorCode;
+    use options::ValidateOptions;
+    use schema::Schema;
+    use session::Session;
+    use super::*;
+
+    fn warnings(schema: Value, input: Value) -> Vec<ValidationWarning> {
+        let schema: Schema = serde_json::from_value(schema).unwrap();
+        let options = ValidateOptions {
+            collect_warnings: true,
+            ..ValidateOptions::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        assert!(schema.validate_with(&input, &ValidateOptions::default()).warnings.is_empty());
+        output.warnings
+    }
+
+    #[test]
+    fn one_of_branch() {
+        let schema: Schema = serde_json::from_value(json!({
+            "oneOf": [
+                {"type": "object", "title": "Circle", "required": ["radius"]},
+                {"type": "object", "title": "Square", "required": ["side"]}
+            ]
+        }))
+        .unwrap();
+        let input = json!({"side": 2});
+        let output = schema.validate_with_output(&input, true);
+        assert!(output.is_valid());
+
+        let branch: Vec<_> = output.annotations_at("", "oneOf").collect();
+        assert_eq!(branch.len(), 1);
+        assert_eq!(branch[0].value, json!(1));
+        assert_eq!(branch[0].schema_path, "");
+        // the title of the failed branch is dropped
+        let titles: Vec<_> = output
+            .annotations_at("", "title")
+            .map(|a| &a.value)
+            .collect();
+        assert_eq!(titles, vec!["Square"]);
+        assert_eq!(output.annotations[0].schema_path, "/oneOf/1");
+    }
+
+    #[test]
+    fn object_defaults() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "retries": {"type": "integer", "default": 3},
+                "verbose": {"type": "boolean", "default": false, "deprecated": true},
+                "name": {"type": "string"}
+            }
+        }))
+        .unwrap();
+        let input = json!({"retries": 5, "verbose": true, "name": "a"});
+        let output = schema.validate_with_output(&input, true);
+        assert!(output.is_valid());
+        let defaults: Vec<_> = output
+            .annotations
+            .iter()
+            .filter(|a| a.keyword == "default")
+            .map(|a| (a.instance_path.as_str(), a.schema_path.as_str(), &a.value))
+            .collect();
+        assert_eq!(defaults.len(), 2);
+        assert!(defaults.contains(&("/retries", "/properties/retries", &json!(3))));
+        assert!(defaults.contains(&("/verbose", "/properties/verbose", &json!(false))));
+        assert_eq!(output.annotations_at("/verbose", "deprecated").count(), 1);
+
+        let output = schema.validate_with_output(&input, false);
+        assert!(output.annotations.is_empty());
+    }
+
+    #[test]
+    fn deprecated_warning() {
+        let schema = json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "old": {"type": "string", "deprecated": true},
+                "new": {"type": "string"}
+            }
+        });
+        let warnings = warnings(schema, json!({"old": "a", "new": "b"}));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::Deprecated {
+                message: None,
+                removed_in: None,
+                expired: false,
+            }
+        );
+        assert_eq!(warnings[0].instance_path, "/old");
+        assert_eq!(warnings[0].schema_path, "/properties/old");
+    }
+
+    #[test]
+    fn removal_dates() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "region": {
+                    "type": "string",
+                    "deprecated": true,
+                    "description": "Use `zone` instead",
+                    "x-removed-in": "2025-01"
+                },
+                "zone": {"type": "string"}
+            }
+        }))
+        .unwrap();
+        let mut options = ValidateOptions {
+            collect_warnings: true,
+            now: Some(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()),
+            ..ValidateOptions::default()
+        };
+        let absent = json!({"zone": "eu"});
+        assert!(schema.validate_with(&absent, &options).warnings.is_empty());
+
+        let present = json!({"region": "eu", "zone": "eu"});
+        let output = schema.validate_with(&present, &options);
+        assert!(output.is_valid());
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].instance_path, "/region");
+        assert_eq!(
+            output.warnings[0].to_string(),
+            "Warning at `/region`: Value uses a deprecated schema: Use `zone` instead \
+             (to be removed in 2025-01)"
+        );
+
+        options.now = NaiveDate::from_ymd_opt(2025, 1, 1);
+        let output = schema.validate_with(&present, &options);
+        assert!(output.is_valid());
+        assert_eq!(
+            output.warnings[0].kind,
+            WarningKind::Deprecated {
+                message: Some("Use `zone` instead".into()),
+                removed_in: Some("2025-01".into()),
+                expired: true,
+            }
+        );
+
+        options.deny_expired = true;
+        let output = schema.validate_with(&present, &options);
+        assert!(output.warnings.is_empty());
+        assert_eq!(output.errors.len(), 1);
+        assert_eq!(output.errors[0].reason.code(), ErrorCode::Removed);
+        assert_eq!(output.errors[0].instance_path, "/region");
+        assert!(schema.validate_with(&absent, &options).is_valid());
+        options.now = NaiveDate::from_ymd_opt(2024, 12, 31);
+        assert!(schema.validate_with(&present, &options).is_valid());
+    }
+
+    #[test]
+    fn undeclared_property_warning() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "patternProperties": {"^x-": {"type": "string"}}
+        });
+        let warnings = warnings(schema, json!({"name": "a", "x-a": "b", "nmae": "c"}));
+        assert_eq!(
+            warnings,
+            vec![ValidationWarning {
+                kind: WarningKind::UndeclaredProperty("nmae".into()),
+                instance_path: "/nmae".into(),
+                schema_path: "/additionalProperties".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_format_warning() {
+        let schema = json!({"type": "string", "format": "color"});
+        let warnings = warnings(schema, json!("red"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::UnknownFormat("color".into()));
+    }
+
+    #[test]
+    fn empty_enum_warning() {
+        let schema = json!({"type": "string", "enum": []});
+        let warnings = warnings(schema, json!("a"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::EmptyEnum);
+    }
+
+    #[test]
+    fn fold_similar_errors() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "name": {"type": "string"},
+                "features": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "additionalProperties": true,
+                        "required": ["id"],
+                        "properties": {"id": {"type": "integer"}, "tags": {"type": "array"}}
+                    }
+                },
+                "layers": {"type": "array", "items": {"type": "string"}}
+            }
+        }))
+        .unwrap();
+        let features: Vec<_> = (0..10_000).map(|i| json!({"tags": [i]})).collect();
+        let input = json!({
+            "name": 5,
+            "features": features,
+            "layers": ["roads", 1, "rivers"]
+        });
+        let options = ValidateOptions {
+            fold_similar: Some(3),
+            ..ValidateOptions::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        let mut errors: Vec<_> = output
+            .errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.folded.as_ref().map(|f| f.count)))
+            .collect();
+        // the errors follow the order of the properties
+        errors.sort_unstable();
+        assert_eq!(
+            errors,
+            vec![
+                ("/features/0", Some(9_999)),
+                ("/layers/1", None),
+                ("/name", None),
+            ]
+        );
+        let folded = output.errors.iter().find(|e| e.folded.is_some()).unwrap();
+        assert_eq!(
+            folded.to_string(),
+            "Error at `/features/0`: Missing object property: `id` (required by \
+             #/properties/features/items/required) (also at `/features/1`, `/features/2` \
+             and 9,997 more)"
+        );
+        let json = serde_json::to_value(folded).unwrap();
+        assert_eq!(json["folded"]["count"], json!(9_999));
+        assert_eq!(json["folded"]["samplePaths"][1], json!("/features/2"));
+
+        // without the option every error is kept
+        let all = schema.validate(&input).unwrap_err().0;
+        assert_eq!(all.len(), 10_002);
+        assert!(all.iter().all(|e| e.folded.is_none()));
+        // errors about the same index of different arrays aren't folded together
+        let few = json!({"features": [{}, {}], "layers": [1, 2]});
+        let output = schema.validate_with(
+            &few,
+            &ValidateOptions {
+                fold_similar: Some(1),
+                ..ValidateOptions::default()
+            },
+        );
+        let errors: Vec<_> = output.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Error at `/features/0`: Missing object property: `id` (required by \
+                 #/properties/features/items/required) (and 1 more like it)",
+                "Error at JSON value `1`: Type mismatch: expected string, found number \
+                 (and 1 more like it)",
+            ]
+        );
+    }
+
+    #[test]
+    fn timing() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {"code": {"type": "string", "pattern": "^[A-Z]{3}$"}},
+            "type": "array",
+            "uniqueItems": true,
+            "items": {"$ref": "#/definitions/code"}
+        }))
+        .unwrap();
+        let codes: Vec<_> = (0..2000).map(|i| format!("A{:02}", i % 100)).collect();
+        let input = json!(codes);
+        let output = schema.validate_with(&input, &ValidateOptions::default());
+        assert_eq!(output.timing, None);
+
+        let options = ValidateOptions {
+            collect_timing: true,
+            ..ValidateOptions::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        let timing = output.timing.unwrap();
+        assert!(timing.total >= timing.ref_resolution + timing.regex + timing.uniqueness);
+        assert!(timing.regex > Duration::from_secs(0));
+        assert!(timing.uniqueness > Duration::from_secs(0));
+        let serialized = serde_json::to_value(timing).unwrap();
+        assert!(serialized["totalMicros"].is_u64());
+
+        let mut session = Session::with_options(&schema, options);
+        assert_eq!(session.timing(), None);
+        assert!(session.validate(&input).is_err());
+        assert!(session.timing().is_some());
+    }
+}
@@ -0,0 +1,38 @@
+//! The types most uses of this crate need, for a single glob import:
+//!
+//! ```
+//! # #[macro_use]
+//! # extern crate serde_json;
+//! # extern crate json_schema;
+//! use json_schema::prelude::*;
+//!
+//! # fn main() {
+//! let schema: Schema = r#"{"type": "integer", "minimum": 1}"#.parse().unwrap();
+//! assert!(schema.validate(&json!(2)).is_ok());
+//! let options = ValidateOptions::default();
+//! assert!(!schema.validate_with(&json!(0), &options).is_valid());
+//! assert!(json!(0).get_type() == JsonType::Integer);
+//! # }
+//! ```
+//!
+//! It doesn't bring `SchemaBase` into scope: validation works through the
+//! inherent methods of `Schema`, and only custom validators implement the trait.
+//!
+//! ```compile_fail
+//! # #[macro_use]
+//! # extern crate serde_json;
+//! # extern crate json_schema;
+//! use json_schema::prelude::*;
+//!
+//! # fn main() {
+//! let schema: Schema = r#"{"type": "integer"}"#.parse().unwrap();
+//! let value = json!(2);
+//! SchemaBase::validate_start(&schema, &value, &schema).unwrap();
+//! # }
+//! ```
+
+pub use errors::{Error, ErrorCode, ErrorKind, ValidationError, ValidationErrors};
+pub use options::{Draft, ValidateOptions};
+pub use schema::Schema;
+pub use store::SchemaStore;
+pub use types::{JsonType, JsonValueExt};
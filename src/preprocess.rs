@@ -0,0 +1,254 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+#[cfg(feature = "normalize-unicode")]
+use unicode_normalization::UnicodeNormalization;
+
+use options::ValidateOptions;
+use output::ValidationOutput;
+use render::{preview, DEFAULT_PREVIEW_LENGTH};
+use schema::Schema;
+use util::pointer_token;
+
+/// A transformation of documents before validation, like trimming strings.
+/// Preprocessors are added to `ValidateOptions::preprocess` and run by
+/// `Schema::validate_preprocessed`.
+pub trait Preprocessor: fmt::Debug + Send + Sync {
+    /// The name the changes are reported under, like `"trim-strings"`.
+    fn name(&self) -> &str;
+
+    /// Transforms `value`, returning what was changed if anything was. Called
+    /// for every value of the document, for the contents of arrays and objects
+    /// before the array or object itself.
+    fn process(&self, value: &mut Value) -> Option<String>;
+}
+
+/// A change a preprocessor made, for audit logs.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Change {
+    /// The name of the preprocessor.
+    pub processor: String,
+    /// JSON pointer to the changed value.
+    pub pointer: String,
+    /// What was changed.
+    pub description: String,
+}
+
+/// Runs `processors` one after the other on `value`, returning their changes.
+pub fn apply(processors: &[Arc<dyn Preprocessor>], value: &mut Value) -> Vec<Change> {
+    let mut changes = vec![];
+    for processor in processors {
+        walk(&**processor, value, String::new(), &mut changes);
+    }
+    changes
+}
+
+fn walk(
+    processor: &dyn Preprocessor,
+    value: &mut Value,
+    pointer: String,
+    changes: &mut Vec<Change>,
+) {
+    match *value {
+        Value::Array(ref mut array) => {
+            for (i, item) in array.iter_mut().enumerate() {
+                walk(processor, item, format!("{}/{}", pointer, i), changes);
+            }
+        }
+        Value::Object(ref mut object) => {
+            for (key, item) in object.iter_mut() {
+                let item_pointer = format!("{}/{}", pointer, pointer_token(key));
+                walk(processor, item, item_pointer, changes);
+            }
+        }
+        _ => {}
+    }
+    if let Some(description) = processor.process(value) {
+        changes.push(Change {
+            processor: processor.name().into(),
+            pointer,
+            description,
+        });
+    }
+}
+
+/// Removes leading and trailing whitespace from strings.
+#[derive(Clone, Copy, Debug)]
+pub struct TrimStrings;
+
+impl Preprocessor for TrimStrings {
+    fn name(&self) -> &str {
+        "trim-strings"
+    }
+
+    fn process(&self, value: &mut Value) -> Option<String> {
+        let trimmed = match *value {
+            Value::String(ref s) if s.trim() != s => Value::String(s.trim().into()),
+            _ => return None,
+        };
+        let description = format!(
+            "trimmed {} to {}",
+            preview(value, DEFAULT_PREVIEW_LENGTH),
+            preview(&trimmed, DEFAULT_PREVIEW_LENGTH)
+        );
+        *value = trimmed;
+        Some(description)
+    }
+}
+
+/// Removes the properties of objects whose value is `null`, so they count as
+/// absent. `null`s in arrays are kept, removing them would shift the elements.
+#[derive(Clone, Copy, Debug)]
+pub struct RemoveNulls;
+
+impl Preprocessor for RemoveNulls {
+    fn name(&self) -> &str {
+        "remove-nulls"
+    }
+
+    fn process(&self, value: &mut Value) -> Option<String> {
+        let object = value.as_object_mut()?;
+        let nulls: Vec<String> = object
+            .iter()
+            .filter(|&(_, value)| value.is_null())
+            .map(|(key, _)| key.clone())
+            .collect();
+        if nulls.is_empty() {
+            return None;
+        }
+        for key in &nulls {
+            object.remove(key);
+        }
+        let keys: Vec<_> = nulls.iter().map(|key| format!("`{}`", key)).collect();
+        Some(format!("removed null properties {}", keys.join(", ")))
+    }
+}
+
+/// Brings strings and object keys into Unicode normalization form C, so that
+/// `"é"` written as one or as two code points is the same string.
+#[cfg(feature = "normalize-unicode")]
+#[derive(Clone, Copy, Debug)]
+pub struct NormalizeUnicode;
+
+#[cfg(feature = "normalize-unicode")]
+impl Preprocessor for NormalizeUnicode {
+    fn name(&self) -> &str {
+        "normalize-unicode"
+    }
+
+    fn process(&self, value: &mut Value) -> Option<String> {
+        match *value {
+            Value::String(ref mut s) => {
+                let normalized: String = s.nfc().collect();
+                if normalized == *s {
+                    return None;
+                }
+                *s = normalized;
+                Some("normalized the string to NFC".into())
+            }
+            Value::Object(ref mut object) => {
+                if object.keys().all(|key| key.nfc().eq(key.chars())) {
+                    return None;
+                }
+                let entries = ::std::mem::take(object);
+                for (key, item) in entries {
+                    object.insert(key.nfc().collect(), item);
+                }
+                Some("normalized the keys to NFC".into())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ValidateOptions {
+    /// Adds `processors` after the preprocessors already added.
+    pub fn preprocess(mut self, processors: Vec<Box<dyn Preprocessor>>) -> ValidateOptions {
+        self.preprocessors
+            .extend(processors.into_iter().map(Arc::from));
+        self
+    }
+}
+
+impl Schema {
+    /// Runs the preprocessors of `options` on `value`, then validates the
+    /// transformed value. Returns the changes along with the output. To keep the
+    /// original, pass a copy of it.
+    pub fn validate_preprocessed<'json>(
+        &self,
+        value: &'json mut Value,
+        options: &ValidateOptions,
+    ) -> (Vec<Change>, ValidationOutput<'json>) {
+        let changes = apply(&options.preprocessors, value);
+        let value: &'json Value = value;
+        (changes, self.validate_with(value, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::ErrorCode;
+
+    #[test]
+    fn trim_and_remove_nulls() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string", "minLength": 2},
+                "nickname": {"type": "string"}
+            }
+        }))
+        .unwrap();
+        let options = ValidateOptions::default()
+            .preprocess(vec![Box::new(TrimStrings), Box::new(RemoveNulls)]);
+
+        let mut value = json!({"name": "  Ada ", "nickname": null});
+        let (changes, output) = schema.validate_preprocessed(&mut value, &options);
+        assert!(output.is_valid());
+        assert_eq!(value, json!({"name": "Ada"}));
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    processor: "trim-strings".into(),
+                    pointer: "/name".into(),
+                    description: r#"trimmed "  Ada " to "Ada""#.into(),
+                },
+                Change {
+                    processor: "remove-nulls".into(),
+                    pointer: "".into(),
+                    description: "removed null properties `nickname`".into(),
+                },
+            ]
+        );
+
+        let mut value = json!({"name": " A "});
+        let (changes, output) = schema.validate_preprocessed(&mut value, &options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            output
+                .errors
+                .iter()
+                .map(|e| e.reason.code())
+                .collect::<Vec<_>>(),
+            vec![ErrorCode::MinLength]
+        );
+    }
+
+    #[cfg(feature = "normalize-unicode")]
+    #[test]
+    fn normalize_unicode() {
+        let schema: Schema = serde_json::from_value(json!({"enum": ["caf\u{e9}"]})).unwrap();
+        let options = ValidateOptions::default().preprocess(vec![Box::new(NormalizeUnicode)]);
+        let mut value = json!("cafe\u{301}");
+        let (changes, output) = schema.validate_preprocessed(&mut value, &options);
+        assert!(output.is_valid());
+        assert_eq!(changes[0].description, "normalized the string to NFC");
+    }
+}
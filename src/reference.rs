@@ -1,19 +1,498 @@
-use serde_json::Value;
+use std::borrow::Cow;
 
-use schema::{Context, SchemaBase};
-use errors::ValidationError;
+use serde_json::{self, Value};
+use url::percent_encoding::{percent_decode, utf8_percent_encode, DEFAULT_ENCODE_SET};
+
+use errors::{ErrorKind, ValidationError};
+use object::PropertyMap;
+use options::Draft;
+use output::WarningKind;
+use schema::{Context, Schema, SchemaBase};
+use store::SchemaStore;
+use types::JsonType;
+use util::pointer_token;
 
 /// Schema that's a reference to another part of this schema.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ReferenceSchema {
-    #[serde(rename = "$ref")] reference: String,
+    #[serde(rename = "$ref")]
+    pub(crate) reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    /// Whether the definitions were read from `$defs`, and are written back there.
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+    /// The keywords next to `$ref`, see `ValidateOptions::draft`.
+    #[serde(skip)]
+    pub(crate) siblings: Option<Box<Schema>>,
+    /// Whether the siblings have no `type` of their own: their type is the one
+    /// their keywords belong to, and they only constrain values of that type.
+    #[serde(skip)]
+    pub(crate) untyped_siblings: bool,
+}
+
+impl ReferenceSchema {
+    /// Creates a reference to the subschema at `reference`, a URI fragment like
+    /// `#/definitions/node`.
+    pub fn new<R: Into<String>>(reference: R) -> ReferenceSchema {
+        ReferenceSchema {
+            reference: reference.into(),
+            definitions: None,
+            dollar_defs: false,
+            siblings: None,
+            untyped_siblings: false,
+        }
+    }
+
+    /// Whether `siblings` constrain `value`, which keywords without a `type`
+    /// only do for values of the type they belong to.
+    fn siblings_apply(&self, siblings: &Schema, value: &Value) -> bool {
+        if !self.untyped_siblings {
+            return true;
+        }
+        match (siblings.type_name(), JsonType::of(value)) {
+            (Some("number"), JsonType::Integer) => true,
+            (Some(name), ty) => ty.to_string() == name,
+            (None, _) => true,
+        }
+    }
+
+    /// The subschema of `root` this reference points to.
+    pub(crate) fn resolve<'r>(&self, root: &'r Schema) -> Option<&'r Schema> {
+        self.resolve_in(root, None).map(|(_, schema)| schema)
+    }
+
+    /// The document this reference points into and the subschema of it it points
+    /// to. References to other documents, like `lib.json#/definitions/money`, are
+    /// looked up in `store` relative to the `$id` of `root`.
+    pub(crate) fn resolve_in<'r>(
+        &self,
+        root: &'r Schema,
+        store: Option<&'r SchemaStore>,
+    ) -> Option<(&'r Schema, &'r Schema)> {
+        let (document, pointer) = self.target();
+        let root = if document.is_empty() {
+            root
+        } else {
+            store?.resolve(root.id(), document)?
+        };
+        root.pointer(&pointer).map(|schema| (root, schema))
+    }
+
+    /// The URI of the document this reference points into, empty for its own
+    /// document, and the decoded JSON pointer into it.
+    pub(crate) fn target(&self) -> (&str, Cow<'_, str>) {
+        let (document, fragment) = match self.reference.find('#') {
+            Some(i) => (&self.reference[..i], &self.reference[i + 1..]),
+            None => (self.reference.as_str(), ""),
+        };
+        (
+            document,
+            percent_decode(fragment.as_bytes()).decode_utf8_lossy(),
+        )
+    }
+}
+
+impl Schema {
+    /// Creates a schema that's a reference to the subschema at `reference`, see
+    /// `ReferenceSchema::new`.
+    pub fn reference<R: Into<String>>(reference: R) -> Schema {
+        Schema::Reference(ReferenceSchema::new(reference))
+    }
+
+    /// Builds a self-referential schema. `build` is given a reference to the
+    /// schema it returns, which is stored as the definition `name`. The result is
+    /// a reference to that definition, carrying the definitions with it.
+    ///
+    /// Recursive schemas built inside `build` can be used anywhere in the returned
+    /// schema: references always resolve against the root, so their definitions
+    /// are moved up to the new root and renamed (`node_2`, ...) if the name is
+    /// already taken.
+    pub fn recursive<N, F>(name: N, build: F) -> Schema
+    where
+        N: Into<String>,
+        F: FnOnce(Schema) -> Schema,
+    {
+        let name = name.into();
+        let reference = definition_pointer(&name);
+        let mut schema = build(Schema::reference(reference.clone()));
+        let mut definitions = PropertyMap::new();
+        // reserve the name before the nested definitions are moved here
+        definitions.insert(name.clone(), Schema::Empty(Default::default()));
+        hoist_definitions(&mut schema, &mut definitions);
+        definitions.insert(name, schema);
+        Schema::Reference(ReferenceSchema {
+            reference,
+            definitions: Some(definitions),
+            dollar_defs: false,
+            siblings: None,
+            untyped_siblings: false,
+        })
+    }
+}
+
+/// The reference to the definition `name` of the root schema.
+fn definition_pointer(name: &str) -> String {
+    let token = pointer_token(name).replace('%', "%25");
+    format!("#/definitions/{}", utf8_percent_encode(&token, DEFAULT_ENCODE_SET))
+}
+
+/// Moves the definitions of the references in `schema` into `definitions`,
+/// renaming them where the names collide.
+fn hoist_definitions(schema: &mut Schema, definitions: &mut PropertyMap<Schema>) {
+    if let Schema::Reference(ref mut reference) = *schema {
+        if let Some(inner) = reference.definitions.take() {
+            let mut renames = vec![];
+            let mut moved = vec![];
+            for (name, definition) in inner {
+                let mut unique = name.clone();
+                let mut suffix = 2;
+                while definitions.contains_key(&unique) {
+                    unique = format!("{}_{}", name, suffix);
+                    suffix += 1;
+                }
+                if unique != name {
+                    renames.push((definition_pointer(&name), definition_pointer(&unique)));
+                }
+                // keep the name taken for the following definitions
+                definitions.insert(unique.clone(), Schema::Empty(Default::default()));
+                moved.push((unique, definition));
+            }
+            rename_references(reference, &renames);
+            for (name, mut definition) in moved {
+                rename_all(&mut definition, &renames);
+                definitions.insert(name, definition);
+            }
+        }
+    }
+    for child in schema.children_mut() {
+        hoist_definitions(child, definitions);
+    }
+}
+
+/// Applies `renames` to the references in `schema` and all its subschemas.
+fn rename_all(schema: &mut Schema, renames: &[(String, String)]) {
+    if let Schema::Reference(ref mut reference) = *schema {
+        rename_references(reference, renames);
+    }
+    for child in schema.children_mut() {
+        rename_all(child, renames);
+    }
+}
+
+/// Points `reference` to the new name if it points into a renamed definition.
+fn rename_references(reference: &mut ReferenceSchema, renames: &[(String, String)]) {
+    let renamed = renames.iter().find_map(|(old, new)| {
+        let rest = reference.reference.strip_prefix(old.as_str())?;
+        if rest.is_empty() || rest.starts_with('/') {
+            Some(format!("{}{}", new, rest))
+        } else {
+            None
+        }
+    });
+    if let Some(renamed) = renamed {
+        reference.reference = renamed;
+    }
 }
 
 impl SchemaBase for ReferenceSchema {
-    fn validate_inner<'json>(&self,
-                             _ctx: &Context,
-                             _value: &'json Value,
-                             _errors: &mut Vec<ValidationError<'json>>) {
-        //self.resolve().validate_inner(ctx, value, errors)
+    fn validate_inner<'json>(
+        &self,
+        ctx: &Context,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        let resolved = ctx.time(
+            |timing| &mut timing.ref_resolution,
+            || self.resolve_in(ctx.root, ctx.store),
+        );
+        match resolved {
+            Some((root, schema)) => {
+                let followed = ctx.follow(root, &self.reference, |ctx| {
+                    schema.validate_inner(ctx, value, errors)
+                });
+                if !followed {
+                    errors.push(ValidationError::new(
+                        ErrorKind::ReferenceCycle(self.reference.clone()),
+                        value,
+                    ));
+                }
+            }
+            None => errors.push(ValidationError::new(
+                ErrorKind::UnresolvedPointer(self.reference.clone()),
+                value,
+            )),
+        }
+        if let Some(ref siblings) = self.siblings {
+            if ctx.options.draft >= Draft::Draft201909 {
+                if self.siblings_apply(siblings, value) {
+                    siblings.validate_inner(ctx, value, errors);
+                }
+            } else if ctx.collects_warnings() {
+                let keywords = match serde_json::to_value(siblings) {
+                    Ok(Value::Object(object)) => {
+                        let keywords = object.iter().filter(|(_, v)| !v.is_null());
+                        keywords.map(|(k, _)| format!("`{}`", k)).collect()
+                    }
+                    _ => vec![],
+                };
+                ctx.warn(WarningKind::IgnoredRefSiblings(keywords));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use serde_json;
+
+    use super::*;
+    use array::ArraySchemaBuilder;
+    use integer::IntegerSchema;
+    use object::ObjectSchemaBuilder;
+    use options::ValidateOptions;
+
+    fn schema() -> Schema {
+        serde_json::from_reader(File::open("data/schema-with-refs.json").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn resolve_definition() {
+        let schema = schema();
+        let address = json!({"street_address": "Main St 1", "city": "Springfield", "state": "OR"});
+        let valid = json!({"billing_address": address, "shipping_address": address});
+        schema.validate(&valid).unwrap();
+
+        let invalid = json!({"billing_address": address, "shipping_address": {"city": 1}});
+        let errors = schema.validate(&invalid).unwrap_err().0;
+        let paths: Vec<_> = errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.schema_path.as_str()))
+            .collect();
+        assert!(paths.contains(&(
+            "/shipping_address/city",
+            "/properties/shipping_address/$ref/properties/city"
+        )));
+        assert!(paths.contains(&("/shipping_address", "/properties/shipping_address/$ref")));
+    }
+
+    #[test]
+    fn unresolved_reference() {
+        let schema: Schema =
+            serde_json::from_value(json!({"$ref": "#/definitions/missing"})).unwrap();
+        assert_invalid!(schema, json!(1), ErrorKind::UnresolvedPointer(_));
+    }
+
+    #[test]
+    fn validate_fragment() {
+        let schema = schema();
+        let patch = json!({"street_address": "Main St 1", "city": "Springfield", "state": "OR"});
+        schema
+            .validate_at("/properties/billing_address", &patch)
+            .unwrap();
+
+        let patch = json!({"street_address": "Main St 1", "city": 1, "state": "OR"});
+        let errors = schema
+            .validate_at("/properties/billing_address", &patch)
+            .unwrap_err()
+            .0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/city");
+        assert_eq!(errors[0].schema_path, "/$ref/properties/city");
+
+        assert!(schema.validate_at("/properties/missing", &patch).is_err());
+    }
+
+    fn with_siblings() -> Schema {
+        serde_json::from_value(json!({
+            "definitions": {"name": {"type": "string"}},
+            "properties": {
+                "name": {
+                    "$ref": "#/definitions/name",
+                    "type": "string",
+                    "description": "The name",
+                    "minLength": 3
+                }
+            },
+            "type": "object"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn siblings_ignored_until_draft_7() {
+        let schema = with_siblings();
+        let input = json!({"name": "ab"});
+        let options = ValidateOptions {
+            collect_warnings: true,
+            ..Default::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        assert!(output.is_valid());
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].schema_path, "/properties/name");
+        match output.warnings[0].kind {
+            WarningKind::IgnoredRefSiblings(ref keywords) => assert_eq!(keywords.len(), 3),
+            ref kind => panic!("unexpected warning {}", kind),
+        }
+    }
+
+    #[test]
+    fn siblings_applied_from_draft_2019() {
+        let schema = with_siblings();
+        let options = ValidateOptions {
+            collect_warnings: true,
+            draft: Draft::Draft201909,
+            ..Default::default()
+        };
+        let input = json!({"name": "ab"});
+        let output = schema.validate_with(&input, &options);
+        assert_eq!(output.errors.len(), 1);
+        assert_eq!(output.errors[0].schema_path, "/properties/name");
+        assert!(output.warnings.is_empty());
+
+        let input = json!({"name": "abc"});
+        assert!(schema.validate_with(&input, &options).is_valid());
+    }
+
+    #[test]
+    fn siblings_round_trip() {
+        let schema = with_siblings();
+        let value = serde_json::to_value(&schema).unwrap();
+        let name = &value["properties"]["name"];
+        assert_eq!(name["$ref"], "#/definitions/name");
+        assert_eq!(name["type"], "string");
+        assert_eq!(name["description"], "The name");
+        assert_eq!(name["minLength"], 3);
+        let bare: Schema = serde_json::from_value(json!({"$ref": "#/definitions/a"})).unwrap();
+        assert!(serde_json::to_value(&bare).unwrap().get("type").is_none());
+    }
+
+    #[test]
+    fn siblings_without_type() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {"name": {"type": ["string", "null"]}},
+            "properties": {
+                "name": {"$ref": "#/definitions/name", "minLength": 3}
+            },
+            "type": "object"
+        }))
+        .unwrap();
+        let options = ValidateOptions {
+            draft: Draft::Draft201909,
+            ..Default::default()
+        };
+        let input = json!({"name": "ab"});
+        let output = schema.validate_with(&input, &options);
+        assert_eq!(output.errors.len(), 1);
+        assert_eq!(output.errors[0].instance_path, "/name");
+        // the keywords only constrain strings
+        for name in &[json!("abc"), json!(null)] {
+            assert!(schema.validate_with(&json!({ "name": name }), &options).is_valid());
+        }
+
+        let value = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            value["properties"]["name"],
+            json!({"$ref": "#/definitions/name", "minLength": 3})
+        );
+
+        // keywords of different types don't make up a schema
+        let mixed = json!({"$ref": "#/definitions/tags", "maxItems": 1, "minLength": 1});
+        assert!(serde_json::from_value::<Schema>(mixed).is_err());
+    }
+
+    #[test]
+    fn build_recursive_schema() {
+        let node = ObjectSchemaBuilder::default()
+            .required(vec!["value".into()])
+            .add_property("value", IntegerSchema::default())
+            .add_property(
+                "children",
+                ArraySchemaBuilder::default()
+                    .all_items_schema(Schema::reference("#/definitions/node"))
+                    .build(),
+            )
+            .build();
+        let schema = ObjectSchemaBuilder::default()
+            .add_definition("node", node)
+            .add_property("tree", ReferenceSchema::new("#/definitions/node"))
+            .build();
+
+        let tree = json!({"tree": {"value": 1, "children": [
+            {"value": 2, "children": [{"value": 3}]},
+            {"value": 4}
+        ]}});
+        schema.validate(&tree).unwrap();
+
+        let tree = json!({"tree": {"value": 1, "children": [
+            {"value": 2, "children": [{"value": "3"}]}
+        ]}});
+        let errors = schema.validate(&tree).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/tree/children/0/children/0/value");
+    }
+
+    #[test]
+    fn recursive_list() {
+        let list = Schema::recursive("list", |list| {
+            ObjectSchemaBuilder::default()
+                .required(vec!["head".into()])
+                .add_property("head", IntegerSchema::default())
+                .add_property("tail", list)
+                .build()
+        });
+        let input = json!({"head": 1, "tail": {"head": 2, "tail": {"head": 3}}});
+        list.validate(&input).unwrap();
+        let input = json!({"head": 1, "tail": {"head": 2, "tail": {"head": "3"}}});
+        let errors = list.validate(&input).unwrap_err().0;
+        assert_eq!(errors[0].instance_path, "/tail/tail/head");
+        assert!(list.pointer("/definitions/list/properties/head").is_some());
+    }
+
+    #[test]
+    fn nested_recursive_schemas() {
+        // a tree whose nodes carry a list of tags, both named `node`
+        let tree = Schema::recursive("node", |node| {
+            let tags = Schema::recursive("node", |tags| {
+                ObjectSchemaBuilder::default()
+                    .add_property("tag", IntegerSchema::default())
+                    .add_property("next", tags)
+                    .build()
+            });
+            ObjectSchemaBuilder::default()
+                .add_property("tags", tags)
+                .add_property(
+                    "children",
+                    ArraySchemaBuilder::default().all_items_schema(node).build(),
+                )
+                .build()
+        });
+        assert!(tree.pointer("/definitions/node/properties/children").is_some());
+        assert!(tree.pointer("/definitions/node_2/properties/next").is_some());
+
+        let input = json!({
+            "tags": {"tag": 1, "next": {"tag": 2}},
+            "children": [{"tags": {"tag": 3}, "children": [{}]}]
+        });
+        tree.validate(&input).unwrap();
+        let input = json!({"children": [{"tags": {"tag": 3, "next": {"tag": "4"}}}]});
+        let errors = tree.validate(&input).unwrap_err().0;
+        assert_eq!(errors[0].instance_path, "/children/0/tags/next/tag");
+
+        let round_trip: Schema =
+            serde_json::from_value(serde_json::to_value(&tree).unwrap()).unwrap();
+        assert!(round_trip.validate(&input).is_err());
+    }
+
+    #[test]
+    fn recursive_pointer_escaping() {
+        let schema = Schema::recursive("a/b c%", |s| {
+            ArraySchemaBuilder::default().all_items_schema(s).build()
+        });
+        schema.validate(&json!([[], [[]]])).unwrap();
+        assert!(schema.validate(&json!([[1]])).is_err());
     }
 }
@@ -0,0 +1,202 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use errors::ErrorKind;
+
+/// Where an error was found, for rendering its message.
+#[derive(Clone, Copy, Debug)]
+pub struct ErrorPaths<'a> {
+    /// JSON pointer to the value the error is about, `None` for errors that were
+    /// reported outside a validation run.
+    pub instance_path: Option<&'a str>,
+    /// JSON pointer to the subschema that reported the error.
+    pub schema_path: Option<&'a str>,
+    /// The value the error is about.
+    pub node: &'a Value,
+}
+
+/// Turns errors into the messages shown to users, for example to translate them.
+/// The details of an error, like the expected and found lengths, are fields of
+/// the `ErrorKind`, so a renderer can format them as it likes.
+pub trait MessageRenderer: fmt::Debug {
+    /// The message for an error of `kind` at `paths`.
+    fn render(&self, kind: &ErrorKind, paths: &ErrorPaths) -> String;
+}
+
+/// The number of characters of a value `Display` shows in error messages.
+pub const DEFAULT_PREVIEW_LENGTH: usize = 80;
+
+/// A short excerpt of `value` for showing it next to an error: strings, numbers
+/// and the other scalars as JSON, cut off after `max_chars` characters, arrays
+/// and objects only by their size, like `<array, 532 items>`.
+pub fn preview(value: &Value, max_chars: usize) -> String {
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    match *value {
+        Value::Array(ref array) => format!("<array, {} item{}>", array.len(), plural(array.len())),
+        Value::Object(ref object) => {
+            format!("<object, {} key{}>", object.len(), plural(object.len()))
+        }
+        ref scalar => {
+            let text = scalar.to_string();
+            match text.char_indices().nth(max_chars) {
+                Some((end, _)) => format!("{}...", &text[..end]),
+                None => text,
+            }
+        }
+    }
+}
+
+/// Renders errors the way `Display` does, in English: `Error at `/tags`: ...`.
+/// Objects and arrays are named by their path, other values are shown as far as
+/// `preview` shows them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnglishRenderer;
+
+impl MessageRenderer for EnglishRenderer {
+    fn render(&self, kind: &ErrorKind, paths: &ErrorPaths) -> String {
+        match (paths.node, paths.instance_path) {
+            (Value::Object(_), Some(path)) | (Value::Array(_), Some(path)) => {
+                let path = if path.is_empty() { "/" } else { path };
+                format!("Error at `{}`: {}", path, kind)
+            }
+            (node, _) => format!(
+                "Error at JSON value `{}`: {}",
+                preview(node, DEFAULT_PREVIEW_LENGTH),
+                kind
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use options::ValidateOptions;
+    use schema::Schema;
+
+    /// Renders keys for a translation table, with the details as parameters.
+    #[derive(Debug)]
+    struct KeyedRenderer;
+
+    impl MessageRenderer for KeyedRenderer {
+        fn render(&self, kind: &ErrorKind, paths: &ErrorPaths) -> String {
+            let path = paths.instance_path.unwrap_or_default();
+            match *kind {
+                ErrorKind::MinLength { expected, found } => format!(
+                    "{}: error.min_length{{expected={},found={}}}",
+                    path, expected, found
+                ),
+                ErrorKind::MissingProperties(ref properties) => {
+                    let names: Vec<_> = properties.iter().map(|p| p.name.as_str()).collect();
+                    format!("{}: error.missing{{names={}}}", path, names.join(","))
+                }
+                ref other => format!("{}: error.{:?}", path, other.code()),
+            }
+        }
+    }
+
+    #[test]
+    fn keyed_messages() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "required": ["name", "tags"],
+            "additionalProperties": true,
+            "properties": {
+                "name": {"type": "string", "minLength": 5},
+                "age": {"type": "integer"}
+            }
+        }))
+        .unwrap();
+        let input = json!({"name": "Bob", "age": "3"});
+        let errors = schema.validate(&input).unwrap_err();
+        let mut messages = errors.render(&KeyedRenderer);
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                "/age: error.TypeMismatch",
+                "/name: error.min_length{expected=5,found=3}",
+                ": error.missing{names=tags}",
+            ]
+        );
+        let english: Vec<_> = errors.0.iter().map(ToString::to_string).collect();
+        assert_eq!(errors.render(&EnglishRenderer), english);
+        let message =
+            "Error at JSON value `\"Bob\"`: Length mismatch: Expected a minimum of 5, found 3";
+        assert!(english.contains(&message.to_string()));
+    }
+
+    #[test]
+    fn previews() {
+        let long = "x".repeat(100);
+        let many: Vec<_> = (0..532).collect();
+        let n = DEFAULT_PREVIEW_LENGTH;
+        let cases = vec![
+            (json!(null), n, "null".to_string()),
+            (json!(true), n, "true".to_string()),
+            (json!(-2.5), n, "-2.5".to_string()),
+            (json!("short"), n, r#""short""#.to_string()),
+            (json!(long), n, format!("\"{}...", "x".repeat(79))),
+            (json!("ääää"), 3, r#""ää..."#.to_string()),
+            (json!(many), n, "<array, 532 items>".to_string()),
+            (json!([1]), n, "<array, 1 item>".to_string()),
+            (json!({"a": 1, "b": {"c": 2}}), n, "<object, 2 keys>".to_string()),
+        ];
+        for (value, max_chars, expected) in cases {
+            assert_eq!(preview(&value, max_chars), expected);
+        }
+    }
+
+    #[test]
+    fn serialized_preview() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "tags": {"type": "array", "maxItems": 1},
+                "password": {"type": "string", "minLength": 100}
+            }
+        }))
+        .unwrap();
+        let input = json!({"tags": ["a", "b"], "password": "hunter2"});
+        let errors = schema.validate(&input).unwrap_err();
+        let mut serialized: Vec<_> = errors
+            .0
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap())
+            .collect();
+        serialized.sort_by_key(|e| e["instancePath"].as_str().unwrap().to_string());
+        assert_eq!(
+            serialized[0],
+            json!({
+                "code": "MinLength",
+                "message": "Length mismatch: Expected a minimum of 100, found 7",
+                "instancePath": "/password",
+                "schemaPath": "/properties/password",
+                "valuePreview": "\"hunter2\""
+            })
+        );
+        assert_eq!(serialized[1]["valuePreview"], json!("<array, 2 items>"));
+
+        let options = ValidateOptions {
+            value_preview: None,
+            ..Default::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        assert_eq!(output.errors.len(), 2);
+        for error in &output.errors {
+            assert_eq!(error.value_preview, None);
+            assert!(serde_json::to_value(error).unwrap().get("valuePreview").is_none());
+        }
+        let options = ValidateOptions {
+            value_preview: Some(4),
+            ..Default::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        let previews: Vec<_> = output.errors.iter().map(|e| e.value_preview.clone()).collect();
+        assert!(previews.contains(&Some(r#""hun..."#.to_string())));
+    }
+}
@@ -0,0 +1,338 @@
+use serde_json::Value;
+
+use errors::{ErrorKind, Result, ValidationError, ValidationErrors};
+use schema::Schema;
+use util::pointer_token;
+
+/// Which repairs `ValidationErrors::suggest_patch` may suggest. Adding missing
+/// properties and removing unknown ones is on by default, the repairs that lose
+/// information are opt-in.
+#[derive(Clone, Copy, Debug)]
+pub struct RepairPolicy {
+    /// Add missing required properties whose schema has a `default`.
+    pub add_defaults: bool,
+    /// Remove properties the schema doesn't allow.
+    pub remove_unknown: bool,
+    /// Replace numbers out of range by the closest number in range.
+    pub clamp_numbers: bool,
+    /// Cut strings that are too long down to the maximum length.
+    pub truncate_strings: bool,
+}
+
+impl Default for RepairPolicy {
+    fn default() -> RepairPolicy {
+        RepairPolicy {
+            add_defaults: true,
+            remove_unknown: true,
+            clamp_numbers: false,
+            truncate_strings: false,
+        }
+    }
+}
+
+/// An operation of a JSON Patch (RFC 6902). Serializes to the JSON form, like
+/// `{"op": "add", "path": "/port", "value": 80}`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    /// Adds `value` at `path`, inserting it if `path` points into an array.
+    Add {
+        /// JSON pointer to the new value.
+        path: String,
+        /// The value to add.
+        value: Value,
+    },
+    /// Removes the value at `path`.
+    Remove {
+        /// JSON pointer to the removed value.
+        path: String,
+    },
+    /// Replaces the value at `path` by `value`.
+    Replace {
+        /// JSON pointer to the replaced value.
+        path: String,
+        /// The new value.
+        value: Value,
+    },
+}
+
+/// Splits `pointer` into the pointer to the parent and the unescaped last token.
+fn split_pointer(pointer: &str) -> Option<(&str, String)> {
+    let split = pointer.rfind('/')?;
+    let token = pointer[split + 1..].replace("~1", "/").replace("~0", "~");
+    Some((&pointer[..split], token))
+}
+
+impl PatchOp {
+    /// Applies the operation to `document`. Fails with
+    /// `ErrorKind::InvalidPointer` if the path doesn't point into it.
+    pub fn apply(&self, document: &mut Value) -> Result<()> {
+        let path = match *self {
+            PatchOp::Add { ref path, .. }
+            | PatchOp::Remove { ref path }
+            | PatchOp::Replace { ref path, .. } => path,
+        };
+        let invalid = || ErrorKind::InvalidPointer(path.clone());
+        if let PatchOp::Replace { ref value, .. } = *self {
+            *document.pointer_mut(path).ok_or_else(invalid)? = value.clone();
+            return Ok(());
+        }
+        let (parent, token) = split_pointer(path).ok_or_else(invalid)?;
+        match (document.pointer_mut(parent), self) {
+            (Some(&mut Value::Object(ref mut object)), PatchOp::Add { value, .. }) => {
+                object.insert(token, value.clone());
+            }
+            (Some(&mut Value::Object(ref mut object)), PatchOp::Remove { .. }) => {
+                object.remove(&token).ok_or_else(invalid)?;
+            }
+            (Some(&mut Value::Array(ref mut array)), PatchOp::Add { value, .. }) => {
+                let index = match token.as_str() {
+                    "-" => array.len(),
+                    index => index
+                        .parse()
+                        .ok()
+                        .filter(|&i| i <= array.len())
+                        .ok_or_else(invalid)?,
+                };
+                array.insert(index, value.clone());
+            }
+            (Some(&mut Value::Array(ref mut array)), PatchOp::Remove { .. }) => {
+                let index: usize = token
+                    .parse()
+                    .ok()
+                    .filter(|&i| i < array.len())
+                    .ok_or_else(invalid)?;
+                array.remove(index);
+            }
+            _ => bail!(invalid()),
+        }
+        Ok(())
+    }
+}
+
+/// The repairs for some of a list of validation errors, see
+/// `ValidationErrors::suggest_patch`.
+#[derive(Debug)]
+pub struct PatchSuggestion<'a, 'json: 'a> {
+    /// The operations fixing the repairable errors, in the order of the errors.
+    pub patch: Vec<PatchOp>,
+    /// The errors the patch doesn't fix.
+    pub unrepairable: Vec<&'a ValidationError<'json>>,
+}
+
+/// The subschema that reported an error at `schema_path`, following the
+/// references `$ref` segments of the path go through.
+fn reporting_schema<'s>(root: &'s Schema, schema_path: &str) -> Option<&'s Schema> {
+    let mut current = root;
+    let mut rest = schema_path;
+    while let Some(index) = rest.find("/$ref") {
+        let after = &rest[index + "/$ref".len()..];
+        if !after.is_empty() && !after.starts_with('/') {
+            break;
+        }
+        current = resolve(root, current.pointer(&rest[..index])?)?;
+        rest = after;
+    }
+    current.pointer(rest)
+}
+
+fn resolve<'s>(root: &'s Schema, schema: &'s Schema) -> Option<&'s Schema> {
+    match *schema {
+        Schema::Reference(ref s) => s.resolve(root),
+        Schema::Shared(ref s) => resolve(root, s),
+        _ => None,
+    }
+}
+
+/// The longest start of `text` that has at most `max` bytes, as string lengths
+/// are counted in bytes.
+fn truncate(text: &str, max: usize) -> &str {
+    let mut end = max.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// The operations repairing `error`, if it can be repaired under `policy`.
+fn repair(error: &ValidationError, root: &Schema, policy: &RepairPolicy) -> Option<Vec<PatchOp>> {
+    let schema = || reporting_schema(root, &error.schema_path);
+    match error.reason {
+        ErrorKind::MissingProperties(ref missing) if policy.add_defaults => {
+            let properties = schema()?.as_object()?.properties.as_ref()?;
+            missing
+                .iter()
+                .map(|property| {
+                    let default = properties.get(&property.name)?.default_value(root)?;
+                    Some(PatchOp::Add {
+                        path: format!("{}/{}", error.instance_path, pointer_token(&property.name)),
+                        value: default.clone(),
+                    })
+                })
+                .collect()
+        }
+        ErrorKind::UnknownProperties(ref pointers) if policy.remove_unknown => Some(
+            pointers
+                .iter()
+                .map(|pointer| PatchOp::Remove {
+                    path: pointer.clone(),
+                })
+                .collect(),
+        ),
+        ErrorKind::NumberRange { value, .. } if policy.clamp_numbers => {
+            let (range, integer) = match *schema()? {
+                Schema::Number(ref s) => (s.range(), false),
+                Schema::Integer(ref s) => (s.range(), true),
+                _ => return None,
+            };
+            let clamped = match (range.minimum, range.maximum) {
+                (Some(min), _) if value < min || value == min && range.exclusive_minimum => {
+                    match range.exclusive_minimum {
+                        false => min,
+                        true if integer => min.floor() + 1.0,
+                        true => return None,
+                    }
+                }
+                (_, Some(max)) if value > max || value == max && range.exclusive_maximum => {
+                    match range.exclusive_maximum {
+                        false => max,
+                        true if integer => max.ceil() - 1.0,
+                        true => return None,
+                    }
+                }
+                _ => return None,
+            };
+            let clamped = if clamped.fract() == 0.0 && clamped.abs() < 9_007_199_254_740_992.0 {
+                json!(clamped as i64)
+            } else {
+                json!(clamped)
+            };
+            Some(vec![PatchOp::Replace {
+                path: error.instance_path.clone(),
+                value: clamped,
+            }])
+        }
+        ErrorKind::MaxLength { expected, .. } if policy.truncate_strings => {
+            let text = error.node.as_str()?;
+            Some(vec![PatchOp::Replace {
+                path: error.instance_path.clone(),
+                value: json!(truncate(text, expected)),
+            }])
+        }
+        _ => None,
+    }
+}
+
+impl<'json> ValidationErrors<'json> {
+    /// A JSON Patch repairing the errors that have a mechanical fix: adds the
+    /// `default` of missing required properties and removes unknown properties,
+    /// with `policy` allowing it also clamps numbers out of range and cuts
+    /// strings that are too long. `schema` is the schema that reported the
+    /// errors. Returns `None` if no error can be repaired.
+    ///
+    /// An error is only repaired as a whole: if one of several missing
+    /// properties has no `default`, none of them is added.
+    pub fn suggest_patch<'a>(
+        &'a self,
+        schema: &Schema,
+        policy: &RepairPolicy,
+    ) -> Option<PatchSuggestion<'a, 'json>> {
+        let mut patch = vec![];
+        let mut unrepairable = vec![];
+        for error in &self.0 {
+            match repair(error, schema, policy) {
+                Some(ops) => patch.extend(ops),
+                None => unrepairable.push(error),
+            }
+        }
+        if patch.is_empty() {
+            None
+        } else {
+            Some(PatchSuggestion {
+                patch,
+                unrepairable,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::ErrorCode;
+
+    #[test]
+    fn repairs_reduce_errors() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {
+                "port": {"type": "integer", "minimum": 1, "maximum": 65535, "default": 80}
+            },
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["host", "port", "retries", "name", "mode"],
+            "properties": {
+                "host": {"type": "string"},
+                "port": {"$ref": "#/definitions/port"},
+                "retries": {"type": "integer", "maximum": 5},
+                "name": {"type": "string", "maxLength": 4},
+                "mode": {"type": "string", "default": "auto"}
+            }
+        }))
+        .unwrap();
+        let document = json!({
+            "host": 1,
+            "retries": 10,
+            "name": "primary",
+            "extra": true
+        });
+        let errors = schema.validate(&document).unwrap_err();
+        assert_eq!(errors.0.len(), 5);
+
+        let policy = RepairPolicy {
+            clamp_numbers: true,
+            truncate_strings: true,
+            ..RepairPolicy::default()
+        };
+        let suggestion = errors.suggest_patch(&schema, &policy).unwrap();
+        // the order of the operations follows the order of the properties
+        let mut patch: Vec<_> = suggestion
+            .patch
+            .iter()
+            .map(|op| serde_json::to_value(op).unwrap())
+            .collect();
+        patch.sort_by_key(|op| op["path"].to_string());
+        assert_eq!(
+            Value::Array(patch),
+            json!([
+                {"op": "remove", "path": "/extra"},
+                {"op": "add", "path": "/mode", "value": "auto"},
+                {"op": "replace", "path": "/name", "value": "prim"},
+                {"op": "add", "path": "/port", "value": 80},
+                {"op": "replace", "path": "/retries", "value": 5}
+            ])
+        );
+        assert_eq!(suggestion.unrepairable.len(), 1);
+        assert_eq!(
+            suggestion.unrepairable[0].reason.code(),
+            ErrorCode::TypeMismatch
+        );
+
+        let mut repaired = document.clone();
+        for op in &suggestion.patch {
+            op.apply(&mut repaired).unwrap();
+        }
+        let remaining = schema.validate(&repaired).unwrap_err().0;
+        assert!(remaining.len() < errors.0.len());
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].instance_path, "/host");
+
+        // without the opt-in repairs only the properties are fixed
+        let suggestion = errors
+            .suggest_patch(&schema, &RepairPolicy::default())
+            .unwrap();
+        assert_eq!(suggestion.patch.len(), 3);
+        assert_eq!(suggestion.unrepairable.len(), 3);
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use url::Url;
+
+use output::micros;
+use reference::ReferenceSchema;
+use schema::Schema;
+use store::{document_id, SchemaStore};
+
+/// How a reference was resolved, see `ResolvedReference`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ResolutionKind {
+    /// The reference points into its own document, like `#/definitions/node`.
+    Local,
+    /// The reference points into another document of the store by a URI
+    /// relative to the `$id` of its document, like `lib.json#/definitions/money`.
+    Store,
+    /// The reference names another document by an absolute URI, like
+    /// `https://example.com/lib.json#/definitions/money`. Documents aren't
+    /// fetched, the URI is looked up in the store.
+    Remote,
+    /// An earlier reference has the same target, its lookup was reused.
+    Cached,
+    /// The document isn't in the store or has no subschema at the pointer.
+    Unresolved,
+}
+
+impl fmt::Display for ResolutionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ResolutionKind::Local => "local",
+            ResolutionKind::Store => "store",
+            ResolutionKind::Remote => "remote",
+            ResolutionKind::Cached => "cached",
+            ResolutionKind::Unresolved => "unresolved",
+        })
+    }
+}
+
+/// A `$ref` and what it resolved to. Documents are named by their `$id`, a
+/// root schema without one by the empty string.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedReference {
+    /// The document containing the reference.
+    pub document: String,
+    /// The pointer to the reference within its document, like `/properties/price`.
+    pub path: String,
+    /// The value of `$ref`.
+    pub reference: String,
+    /// The document the reference points into.
+    pub target_document: String,
+    /// The pointer to the target within that document.
+    pub target_pointer: String,
+    /// How the reference was resolved.
+    pub kind: ResolutionKind,
+    /// How long resolving it took.
+    #[serde(rename = "durationMicros", serialize_with = "micros")]
+    pub duration: Duration,
+}
+
+impl fmt::Display for ResolvedReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}#{}: {} -> {}#{} ({}, {}µs)",
+            self.document,
+            self.path,
+            self.reference,
+            self.target_document,
+            self.target_pointer,
+            self.kind,
+            micros_of(self.duration)
+        )
+    }
+}
+
+/// What resolving the references of a schema did, see
+/// `SchemaStore::resolve_references`. Serializes to JSON for tooling.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ResolutionReport {
+    /// The references of the schema and of the documents it reaches, in the
+    /// order they were resolved.
+    pub references: Vec<ResolvedReference>,
+    /// The documents reached, each with the documents its references point into.
+    pub documents: BTreeMap<String, BTreeSet<String>>,
+    /// The loops between documents, like `["a.json", "b.json", "a.json"]`, one
+    /// for each reference closing a loop. They're legal, references are only
+    /// followed while validating a value.
+    pub cycles: Vec<Vec<String>>,
+}
+
+impl ResolutionReport {
+    /// Whether every reference resolved.
+    pub fn is_resolved(&self) -> bool {
+        self.references
+            .iter()
+            .all(|r| r.kind != ResolutionKind::Unresolved)
+    }
+}
+
+impl fmt::Display for ResolutionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for reference in &self.references {
+            writeln!(f, "{}", reference)?;
+        }
+        for (document, targets) in &self.documents {
+            let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+            writeln!(f, "{} references [{}]", name(document), targets.join(", "))?;
+        }
+        for cycle in &self.cycles {
+            let cycle: Vec<&str> = cycle.iter().map(|d| name(d)).collect();
+            writeln!(f, "cycle: {}", cycle.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+/// How a document is shown, the root without `$id` as `#`.
+fn name(document: &str) -> &str {
+    if document.is_empty() {
+        "#"
+    } else {
+        document
+    }
+}
+
+/// `duration` in whole microseconds.
+fn micros_of(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+/// Resolves the references of `root` and of the documents of `store` they
+/// reach, see `SchemaStore::resolve_references`.
+pub(crate) fn resolve_all(root: &Schema, store: Option<&SchemaStore>) -> ResolutionReport {
+    let key = root
+        .id()
+        .map(|id| document_id(None, id))
+        .unwrap_or_default();
+    let mut resolver = Resolver {
+        store,
+        report: ResolutionReport::default(),
+        found: HashMap::new(),
+        queue: vec![(key.clone(), root)],
+    };
+    resolver
+        .report
+        .documents
+        .insert(key.clone(), BTreeSet::new());
+    while let Some((key, document)) = resolver.queue.pop() {
+        resolver.walk(&key, document, document, String::new());
+    }
+    let mut cycles = vec![];
+    find_cycles(
+        &resolver.report.documents,
+        &key,
+        &mut vec![],
+        &mut HashSet::new(),
+        &mut cycles,
+    );
+    resolver.report.cycles = cycles;
+    resolver.report
+}
+
+struct Resolver<'s> {
+    store: Option<&'s SchemaStore>,
+    report: ResolutionReport,
+    /// Whether the targets looked up so far exist, by document and pointer.
+    found: HashMap<(String, String), bool>,
+    /// The documents reached whose references are yet to be resolved.
+    queue: Vec<(String, &'s Schema)>,
+}
+
+impl<'s> Resolver<'s> {
+    fn walk(&mut self, key: &str, document: &'s Schema, schema: &'s Schema, path: String) {
+        match *schema {
+            Schema::Shared(ref s) => return self.walk(key, document, s, path),
+            Schema::Reference(ref reference) => self.resolve(key, document, reference, &path),
+            _ => {}
+        }
+        for (pointer, child) in schema.children_with_pointers() {
+            self.walk(key, document, child, format!("{}{}", path, pointer));
+        }
+    }
+
+    fn resolve(
+        &mut self,
+        key: &str,
+        document: &'s Schema,
+        reference: &ReferenceSchema,
+        path: &str,
+    ) {
+        let started = Instant::now();
+        let (uri, pointer) = reference.target();
+        let (target, kind) = if uri.is_empty() {
+            (key.to_string(), ResolutionKind::Local)
+        } else if Url::parse(uri).is_ok() {
+            (document_id(None, uri), ResolutionKind::Remote)
+        } else {
+            (document_id(document.id(), uri), ResolutionKind::Store)
+        };
+        let lookup = (target.clone(), pointer.into_owned());
+        let kind = match self.found.get(&lookup) {
+            Some(&true) => ResolutionKind::Cached,
+            Some(&false) => ResolutionKind::Unresolved,
+            None => {
+                let root = if kind == ResolutionKind::Local {
+                    Some(document)
+                } else {
+                    self.store.and_then(|store| store.get(&target))
+                };
+                if let (Some(root), false) = (root, kind == ResolutionKind::Local) {
+                    self.reach(&target, root);
+                }
+                let found = root.and_then(|root| root.pointer(&lookup.1)).is_some();
+                self.found.insert(lookup.clone(), found);
+                if found {
+                    kind
+                } else {
+                    ResolutionKind::Unresolved
+                }
+            }
+        };
+        if uri.is_empty() {
+            // references within a document aren't edges of the graph
+        } else if self.report.documents.contains_key(&lookup.0) {
+            if let Some(targets) = self.report.documents.get_mut(key) {
+                targets.insert(lookup.0.clone());
+            }
+        }
+        self.report.references.push(ResolvedReference {
+            document: key.to_string(),
+            path: path.to_string(),
+            reference: reference.reference.clone(),
+            target_document: lookup.0,
+            target_pointer: lookup.1,
+            kind,
+            duration: started.elapsed(),
+        });
+    }
+
+    /// Queues the document `target` the first time it's reached.
+    fn reach(&mut self, target: &str, root: &'s Schema) {
+        if !self.report.documents.contains_key(target) {
+            self.report
+                .documents
+                .insert(target.to_string(), BTreeSet::new());
+            self.queue.push((target.to_string(), root));
+        }
+    }
+}
+
+/// Walks the documents reachable from `document` depth first, adding a cycle for
+/// each edge back to a document on `stack`.
+fn find_cycles<'g>(
+    graph: &'g BTreeMap<String, BTreeSet<String>>,
+    document: &'g str,
+    stack: &mut Vec<&'g str>,
+    done: &mut HashSet<&'g str>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    if let Some(start) = stack.iter().position(|&d| d == document) {
+        let mut cycle: Vec<String> = stack[start..].iter().map(|d| d.to_string()).collect();
+        cycle.push(document.to_string());
+        cycles.push(cycle);
+        return;
+    }
+    if !done.insert(document) {
+        return;
+    }
+    stack.push(document);
+    for target in graph.get(document).into_iter().flatten() {
+        find_cycles(graph, target, stack, done, cycles);
+    }
+    stack.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    fn store() -> SchemaStore {
+        let mut store = SchemaStore::new();
+        let documents = json!([
+            {
+                "$id": "https://example.com/order.json",
+                "type": "object",
+                "definitions": {
+                    "item": {"type": "string"}
+                },
+                "properties": {
+                    "price": {"$ref": "money.json#/definitions/money"},
+                    "buyer": {"$ref": "https://people.example.org/person.json"},
+                    "first": {"$ref": "#/definitions/item"},
+                    "last": {"$ref": "#/definitions/item"},
+                    "discount": {"$ref": "money.json#/definitions/percent"}
+                }
+            },
+            {
+                "$id": "https://example.com/money.json",
+                "definitions": {
+                    "money": {"type": "number", "minimum": 0}
+                }
+            },
+            {
+                "$id": "https://people.example.org/person.json",
+                "type": "object",
+                "properties": {
+                    "orders": {
+                        "type": "array",
+                        "items": {"$ref": "https://example.com/order.json"}
+                    }
+                }
+            }
+        ]);
+        store.load_document(documents).unwrap();
+        store
+    }
+
+    fn reference<'r>(report: &'r ResolutionReport, path: &str) -> &'r ResolvedReference {
+        report.references.iter().find(|r| r.path == path).unwrap()
+    }
+
+    #[test]
+    fn report_over_store() {
+        let store = store();
+        let order = store.get("https://example.com/order.json").unwrap();
+        let report = store.resolve_references(order);
+
+        let mut kinds: Vec<_> = report
+            .references
+            .iter()
+            .map(|r| (r.path.as_str(), r.kind))
+            .collect();
+        kinds.sort_by_key(|&(path, _)| path);
+        assert_eq!(
+            kinds,
+            vec![
+                ("/properties/buyer", ResolutionKind::Remote),
+                ("/properties/discount", ResolutionKind::Unresolved),
+                ("/properties/first", ResolutionKind::Local),
+                ("/properties/last", ResolutionKind::Cached),
+                ("/properties/orders/items", ResolutionKind::Remote),
+                ("/properties/price", ResolutionKind::Store),
+            ]
+        );
+        assert!(!report.is_resolved());
+        let price = reference(&report, "/properties/price");
+        assert_eq!(price.document, "https://example.com/order.json");
+        assert_eq!(price.target_document, "https://example.com/money.json");
+        assert_eq!(price.target_pointer, "/definitions/money");
+
+        let order_targets: Vec<&str> = report.documents["https://example.com/order.json"]
+            .iter()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            order_targets,
+            vec![
+                "https://example.com/money.json",
+                "https://people.example.org/person.json",
+            ]
+        );
+        assert!(report.documents["https://example.com/money.json"].is_empty());
+        assert_eq!(
+            report.cycles,
+            vec![vec![
+                "https://example.com/order.json".to_string(),
+                "https://people.example.org/person.json".to_string(),
+                "https://example.com/order.json".to_string(),
+            ]]
+        );
+
+        let buyer = serde_json::to_value(reference(&report, "/properties/buyer")).unwrap();
+        assert_eq!(buyer["kind"], json!("Remote"));
+        assert_eq!(
+            buyer["targetDocument"],
+            json!("https://people.example.org/person.json")
+        );
+        assert!(buyer["durationMicros"].is_u64());
+        let serialized = serde_json::to_value(&report).unwrap();
+        assert_eq!(serialized["cycles"][0].as_array().unwrap().len(), 3);
+        let text = report.to_string();
+        assert!(text.contains(
+            "https://example.com/order.json#/properties/first: #/definitions/item -> \
+             https://example.com/order.json#/definitions/item (local, "
+        ));
+        assert!(text.contains(
+            "cycle: https://example.com/order.json -> https://people.example.org/person.json -> \
+             https://example.com/order.json"
+        ));
+    }
+
+    #[test]
+    fn report_without_store() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "lib": {"$ref": "lib.json#/definitions/a"}
+            }
+        }))
+        .unwrap();
+        let report = schema.resolve_references();
+        assert_eq!(report.references[0].kind, ResolutionKind::Unresolved);
+        assert_eq!(report.references[0].target_document, "lib.json");
+        assert_eq!(report.documents.len(), 1);
+        assert!(report.documents[""].is_empty());
+        assert!(report.cycles.is_empty());
+    }
+}
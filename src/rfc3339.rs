@@ -0,0 +1,99 @@
+//! Checks of the formats `date-time`, `date` and `time` against the grammar of
+//! RFC 3339, section 5.6, the way the JSON Schema test suite reads it: `T` and
+//! `Z` in either case, seconds and an offset always, offsets up to `23:59` and
+//! a leap second only in the last minute of a day in UTC. chrono's parser is
+//! more lenient than the grammar in some places and stricter in others, so it
+//! only decides whether a day exists in its month.
+
+use chrono::NaiveDate;
+
+/// Whether `input` is a `date-time`, like `1963-06-19T08:30:06.283185Z`.
+pub(crate) fn is_date_time(input: &str) -> bool {
+    let rest = match full_date(input.as_bytes()) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    match rest.split_first() {
+        Some((&b'T', time)) | Some((&b't', time)) => full_time(time).is_some(),
+        _ => false,
+    }
+}
+
+/// Whether `input` is a `date`, like `1963-06-19`.
+pub(crate) fn is_date(input: &str) -> bool {
+    full_date(input.as_bytes()).is_some_and(<[u8]>::is_empty)
+}
+
+/// Whether `input` is a `time` with an offset, like `08:30:06Z`.
+pub(crate) fn is_time(input: &str) -> bool {
+    full_time(input.as_bytes()).is_some()
+}
+
+/// Reads the `n` ASCII digits `input` starts with as a number.
+fn digits(input: &[u8], n: usize) -> Option<(u32, &[u8])> {
+    if input.len() < n || !input[..n].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let number = input[..n]
+        .iter()
+        .fold(0, |number, digit| number * 10 + u32::from(digit - b'0'));
+    Some((number, &input[n..]))
+}
+
+/// Skips the byte `expected` `input` starts with.
+fn byte(input: &[u8], expected: u8) -> Option<&[u8]> {
+    match input.split_first() {
+        Some((&first, rest)) if first == expected => Some(rest),
+        _ => None,
+    }
+}
+
+/// Reads a `full-date` of a day that exists, returning what follows it.
+fn full_date(input: &[u8]) -> Option<&[u8]> {
+    let (year, rest) = digits(input, 4)?;
+    let (month, rest) = digits(byte(rest, b'-')?, 2)?;
+    let (day, rest) = digits(byte(rest, b'-')?, 2)?;
+    NaiveDate::from_ymd_opt(year as i32, month, day)?;
+    Some(rest)
+}
+
+/// Reads a `full-time`, a `partial-time` followed by a `time-offset`, that
+/// makes up all of `input`.
+fn full_time(input: &[u8]) -> Option<()> {
+    let (hour, rest) = digits(input, 2)?;
+    let (minute, rest) = digits(byte(rest, b':')?, 2)?;
+    let (second, mut rest) = digits(byte(rest, b':')?, 2)?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    if let Some(fraction) = byte(rest, b'.') {
+        let length = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+        if length == 0 {
+            return None;
+        }
+        rest = &fraction[length..];
+    }
+    let offset = time_offset(rest)?;
+    // a leap second is inserted after 23:59:59 UTC, whatever the local time
+    let utc = (hour * 60 + minute) as i32 - offset;
+    if second == 60 && utc.rem_euclid(24 * 60) != 23 * 60 + 59 {
+        return None;
+    }
+    Some(())
+}
+
+/// Reads a `time-offset` that makes up all of `input`, in minutes east of UTC.
+fn time_offset(input: &[u8]) -> Option<i32> {
+    let (sign, rest) = match input.split_first() {
+        Some((&b'Z', rest)) | Some((&b'z', rest)) => return Some(0).filter(|_| rest.is_empty()),
+        Some((&b'+', rest)) => (1, rest),
+        Some((&b'-', rest)) => (-1, rest),
+        _ => return None,
+    };
+    let (hours, rest) = digits(rest, 2)?;
+    let (minutes, rest) = digits(byte(rest, b':')?, 2)?;
+    if hours > 23 || minutes > 59 || !rest.is_empty() {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes) as i32)
+}
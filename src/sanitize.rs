@@ -0,0 +1,276 @@
+use serde_json::Value;
+
+use array::ArraySchema;
+use errors::{ErrorKind, Result};
+use object::{AdditionalProperties, ObjectSchema, PropertyResolver};
+use options::ValidateOptions;
+use output::ValidationOutput;
+use schema::Schema;
+use limits::SchemaLimits;
+use util::pointer_token;
+
+/// What `Schema::sanitize` does with object properties the schema doesn't
+/// declare.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownPolicy {
+    /// Leave them in the document.
+    Keep,
+    /// Remove them from the document.
+    Strip,
+    /// Fail with `ErrorKind::UnknownProperties`, leaving the document as it is.
+    Fail,
+}
+
+/// The result of `Schema::sanitize`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SanitizeReport {
+    /// JSON pointers to the removed properties.
+    pub removed: Vec<String>,
+}
+
+impl Schema {
+    /// Deals with the object properties in `value` that the schema doesn't know
+    /// about according to `policy`. A property is known if it's listed in
+    /// `properties`, matches one of the `patternProperties` or the object schema
+    /// allows other properties with `additionalProperties: true` or a schema.
+    /// Known properties are sanitized recursively, as are array elements.
+    ///
+    /// Values below `allOf`, `anyOf` and `oneOf` are left alone, since a property
+    /// one branch doesn't know may be declared by another.
+    pub fn sanitize(&self, value: &mut Value, policy: UnknownPolicy) -> Result<SanitizeReport> {
+        let mut report = SanitizeReport::default();
+        if policy == UnknownPolicy::Keep {
+            return Ok(report);
+        }
+        let mut sanitizer = Sanitizer {
+            root: self,
+            strip: policy == UnknownPolicy::Strip,
+            path: String::new(),
+            unknown: vec![],
+        };
+        sanitizer.schema(self, value);
+        if policy == UnknownPolicy::Fail && !sanitizer.unknown.is_empty() {
+            bail!(ErrorKind::UnknownProperties(sanitizer.unknown));
+        }
+        report.removed = sanitizer.unknown;
+        Ok(report)
+    }
+
+    /// Sanitizes `value` according to `policy`, then validates what's left. As
+    /// the unknown properties are gone by then, `minProperties` and
+    /// `maxProperties` count the properties after stripping, whereas plain
+    /// validation counts all properties of the document. Fails like `sanitize`
+    /// does, without validating.
+    pub fn validate_and_sanitize<'json>(
+        &self,
+        value: &'json mut Value,
+        policy: UnknownPolicy,
+        options: &ValidateOptions,
+    ) -> Result<(SanitizeReport, ValidationOutput<'json>)> {
+        let report = self.sanitize(value, policy)?;
+        let value: &'json Value = value;
+        Ok((report, self.validate_with(value, options)))
+    }
+}
+
+struct Sanitizer<'s> {
+    root: &'s Schema,
+    strip: bool,
+    path: String,
+    unknown: Vec<String>,
+}
+
+impl<'s> Sanitizer<'s> {
+    fn schema(&mut self, schema: &Schema, value: &mut Value) {
+        match *schema {
+            Schema::Object(ref s) => self.object(s, value),
+            Schema::Array(ref s) => self.array(s, value),
+            Schema::Reference(ref s) => {
+                if let Some(target) = s.resolve(self.root) {
+                    self.schema(target, value);
+                }
+            }
+            Schema::Shared(ref s) => self.schema(s, value),
+            _ => {}
+        }
+    }
+
+    /// Runs `f` with the path extended by `token`.
+    fn descend<F: FnOnce(&mut Self)>(&mut self, token: &str, f: F) {
+        let len = self.path.len();
+        self.path.push('/');
+        self.path.push_str(&pointer_token(token));
+        f(self);
+        self.path.truncate(len);
+    }
+
+    fn object(&mut self, schema: &ObjectSchema, value: &mut Value) {
+        let object = match *value {
+            Value::Object(ref mut o) => o,
+            _ => return,
+        };
+        let resolver = PropertyResolver::new(schema, &SchemaLimits::default());
+        let declared = |key: &str| resolver.resolve(key).covered();
+
+        // unlike validation, only an explicit `additionalProperties` makes the
+        // undeclared properties known
+        let allowed = match schema.additional_properties {
+            Some(AdditionalProperties::Allowed(allowed)) => allowed,
+            Some(AdditionalProperties::Schema(_)) => true,
+            None => false,
+        };
+        if !allowed {
+            for key in object.keys().filter(|k| !declared(k)) {
+                self.descend(key, |s| s.unknown.push(s.path.clone()));
+            }
+            if self.strip {
+                object.retain(|key, _| declared(key));
+            }
+        }
+
+        for (key, value) in object.iter_mut() {
+            for schema in resolver.resolve(key).schemas() {
+                self.descend(key, |s| s.schema(schema, value));
+            }
+        }
+    }
+
+    fn array(&mut self, schema: &ArraySchema, value: &mut Value) {
+        let array = match *value {
+            Value::Array(ref mut a) => a,
+            _ => return,
+        };
+        for (i, value) in array.iter_mut().enumerate() {
+            if let Some(schema) = schema.item_schema(i) {
+                self.descend(&i.to_string(), |s| s.schema(schema, value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::ErrorCode;
+
+    fn schema(value: Value) -> Schema {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn nested_strip() {
+        let schema = schema(json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "items": {
+                    "type": "array",
+                    "items": {"type": "object", "properties": {"id": {"type": "integer"}}}
+                },
+                "extra": {"type": "object", "additionalProperties": true}
+            }
+        }));
+        let mut input = json!({
+            "name": "a",
+            "debug": true,
+            "items": [{"id": 1, "x": 1}, {"id": 2}, {"y/z": 3}],
+            "extra": {"anything": 1}
+        });
+        let report = schema.sanitize(&mut input, UnknownPolicy::Strip).unwrap();
+        assert_eq!(
+            report.removed,
+            vec!["/debug", "/items/0/x", "/items/2/y~1z"]
+        );
+        assert_eq!(
+            input,
+            json!({
+                "name": "a",
+                "items": [{"id": 1}, {"id": 2}, {}],
+                "extra": {"anything": 1}
+            })
+        );
+
+        // stripping again doesn't find anything
+        let stripped = input.clone();
+        let report = schema.sanitize(&mut input, UnknownPolicy::Strip).unwrap();
+        assert!(report.removed.is_empty());
+        assert_eq!(input, stripped);
+    }
+
+    #[test]
+    fn pattern_properties() {
+        let schema = schema(json!({
+            "type": "object",
+            "properties": {"id": {"type": "integer"}},
+            "patternProperties": {
+                "^x-": {"type": "object", "properties": {"value": {"type": "string"}}}
+            }
+        }));
+        let mut input = json!({"id": 1, "x-a": {"value": "a", "other": 1}, "y-a": 1});
+        let report = schema.sanitize(&mut input, UnknownPolicy::Strip).unwrap();
+        assert_eq!(report.removed, vec!["/y-a", "/x-a/other"]);
+        assert_eq!(input, json!({"id": 1, "x-a": {"value": "a"}}));
+    }
+
+    #[test]
+    fn keep_and_fail() {
+        let schema = schema(json!({"type": "object", "properties": {"id": {"type": "integer"}}}));
+        let input = json!({"id": 1, "name": "a"});
+
+        let mut kept = input.clone();
+        let report = schema.sanitize(&mut kept, UnknownPolicy::Keep).unwrap();
+        assert!(report.removed.is_empty());
+        assert_eq!(kept, input);
+
+        let mut failed = input.clone();
+        match *schema
+            .sanitize(&mut failed, UnknownPolicy::Fail)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::UnknownProperties(ref pointers) => assert_eq!(pointers, &["/name"]),
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(failed, input);
+    }
+
+    #[test]
+    fn count_after_strip() {
+        let schema = schema(json!({
+            "type": "object",
+            "maxProperties": 2,
+            "properties": {"id": {"type": "integer"}, "name": {"type": "string"}}
+        }));
+        let input = json!({"id": 1, "name": "a", "debug": true});
+        let options = ValidateOptions::default();
+        let codes = |output: &ValidationOutput| {
+            output
+                .errors
+                .iter()
+                .map(|e| e.reason.code())
+                .collect::<Vec<_>>()
+        };
+        let output = schema.validate_with(&input, &options);
+        assert_eq!(codes(&output), vec![ErrorCode::PropertyCount]);
+
+        let mut stripped = input.clone();
+        let (report, output) = schema
+            .validate_and_sanitize(&mut stripped, UnknownPolicy::Strip, &options)
+            .unwrap();
+        assert_eq!(report.removed, vec!["/debug"]);
+        assert!(output.is_valid());
+
+        let mut kept = input.clone();
+        let (_, output) = schema
+            .validate_and_sanitize(&mut kept, UnknownPolicy::Keep, &options)
+            .unwrap();
+        assert_eq!(codes(&output), vec![ErrorCode::PropertyCount]);
+
+        let mut failed = input.clone();
+        assert!(schema
+            .validate_and_sanitize(&mut failed, UnknownPolicy::Fail, &options)
+            .is_err());
+    }
+}
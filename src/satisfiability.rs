@@ -0,0 +1,570 @@
+use array::Items;
+use number::Range;
+use schema::Schema;
+
+/// Whether a schema can accept any value at all, see `Schema::is_satisfiable`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Satisfiability {
+    /// The checks found no contradiction.
+    Yes,
+    /// No value is valid. Holds the reason and the JSON pointer to the subschema
+    /// with the contradiction.
+    No(String, String),
+    /// A part of the schema every value has to match is beyond the checks, like
+    /// several `pattern`s a string has to match at once.
+    Unknown,
+}
+
+use self::Satisfiability::*;
+
+impl Satisfiability {
+    /// The result for a value that has to satisfy both `self` and `other`.
+    fn and<F: FnOnce() -> Satisfiability>(self, other: F) -> Satisfiability {
+        match self {
+            No(..) => self,
+            Yes => other(),
+            Unknown => match other() {
+                No(reason, path) => No(reason, path),
+                _ => Unknown,
+            },
+        }
+    }
+}
+
+// The kinds of values, as bits of a set. Integers are told apart from the other
+// numbers, as `integer` and numeric ranges can rule out one but not the other.
+const NULL: u8 = 1;
+const BOOLEAN: u8 = 1 << 1;
+const OBJECT: u8 = 1 << 2;
+const ARRAY: u8 = 1 << 3;
+const FRACTION: u8 = 1 << 4;
+const INTEGER: u8 = 1 << 5;
+const STRING: u8 = 1 << 6;
+const ANY: u8 = (1 << 7) - 1;
+
+/// The kinds of values `schema` allows by its `type`.
+pub(crate) fn kinds(schema: &Schema) -> u8 {
+    let kinds = match schema.type_name() {
+        Some("boolean") => BOOLEAN,
+        Some("object") => OBJECT,
+        Some("array") => ARRAY,
+        Some("number") => FRACTION | INTEGER,
+        Some("integer") => INTEGER,
+        Some("string") => STRING,
+        _ => return ANY,
+    };
+    if schema.is_nullable() {
+        kinds | NULL
+    } else {
+        kinds
+    }
+}
+
+/// The bounds of numbers, merged from several ranges.
+#[derive(Clone, Copy, Debug)]
+struct Bounds {
+    minimum: f64,
+    exclusive_minimum: bool,
+    maximum: f64,
+    exclusive_maximum: bool,
+}
+
+impl Bounds {
+    fn new() -> Bounds {
+        Bounds {
+            minimum: f64::NEG_INFINITY,
+            exclusive_minimum: false,
+            maximum: f64::INFINITY,
+            exclusive_maximum: false,
+        }
+    }
+
+    fn restrict(&mut self, range: &Range) {
+        if let Some(minimum) = range.minimum {
+            if minimum > self.minimum || minimum == self.minimum && range.exclusive_minimum {
+                self.minimum = minimum;
+                self.exclusive_minimum = range.exclusive_minimum;
+            }
+        }
+        if let Some(maximum) = range.maximum {
+            if maximum < self.maximum || maximum == self.maximum && range.exclusive_maximum {
+                self.maximum = maximum;
+                self.exclusive_maximum = range.exclusive_maximum;
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.minimum > self.maximum
+            || self.minimum == self.maximum && (self.exclusive_minimum || self.exclusive_maximum)
+    }
+
+    /// The smallest multiple of `step` within the bounds, if there is one.
+    fn first_multiple(&self, step: f64) -> Option<f64> {
+        let mut first = (self.minimum / step).ceil() * step;
+        if self.exclusive_minimum && first == self.minimum {
+            first += step;
+        }
+        let within = first < self.maximum || first == self.maximum && !self.exclusive_maximum;
+        if within || !first.is_finite() {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} {} and {} {}",
+            if self.exclusive_minimum {
+                "exclusive minimum"
+            } else {
+                "minimum"
+            },
+            self.minimum,
+            if self.exclusive_maximum {
+                "exclusive maximum"
+            } else {
+                "maximum"
+            },
+            self.maximum
+        )
+    }
+}
+
+/// Walks a schema looking for contradictions.
+struct Checker<'s> {
+    root: &'s Schema,
+    /// The references being followed, to stop at cycles.
+    following: Vec<*const Schema>,
+}
+
+impl<'s> Checker<'s> {
+    /// `schema` and the schemas of its `allOf`, all of which a value has to match.
+    fn conjuncts(&self, schema: &'s Schema, path: String, into: &mut Vec<(&'s Schema, String)>) {
+        match *schema {
+            Schema::Shared(ref s) => return self.conjuncts(s, path, into),
+            Schema::Combinator(ref s) => {
+                for (i, member) in s.all_of.iter().flatten().enumerate() {
+                    self.conjuncts(member, format!("{}/allOf/{}", path, i), into);
+                }
+            }
+            _ => {}
+        }
+        into.push((schema, path));
+    }
+
+    fn check(&mut self, schema: &'s Schema, path: &str) -> Satisfiability {
+        match *schema {
+            Schema::Shared(ref s) => return self.check(s, path),
+            Schema::Custom(_) => return Unknown,
+            Schema::Reference(ref s) => {
+                let target = match s.resolve(self.root) {
+                    Some(target) => target,
+                    None => {
+                        let reason = format!("reference `{}` can't be resolved", s.reference);
+                        return No(reason, path.into());
+                    }
+                };
+                let key: *const Schema = target;
+                if self.following.contains(&key) {
+                    return Unknown;
+                }
+                self.following.push(key);
+                let result = self.check(target, path);
+                self.following.pop();
+                return result;
+            }
+            _ => {}
+        }
+
+        let mut members = vec![];
+        self.conjuncts(schema, path.into(), &mut members);
+
+        // with `enum` or `const` the possible values are known, so they decide
+        let values = members
+            .iter()
+            .filter_map(|&(member, ref path)| member.allowed_values().map(|v| (v, path)))
+            .min_by_key(|&((_, ref values), _)| values.len());
+        if let Some(((keyword, values), values_path)) = values {
+            if values.iter().any(|value| schema.accepts_within(self.root, value)) {
+                return Yes;
+            }
+            let reason = format!("none of the values of `{}` is valid", keyword);
+            return No(reason, values_path.clone());
+        }
+
+        let mut kinds = members
+            .iter()
+            .fold(ANY, |kinds, &(member, _)| kinds & self::kinds(member));
+        if kinds == 0 {
+            let reason = "the subschemas of `allOf` have no type in common".to_string();
+            return No(reason, path.into());
+        }
+        let mut unknown = 0;
+        let mut contradiction = None;
+        {
+            let mut rule_out = |ruled_out: u8, reason: String, path: &str| {
+                if kinds & ruled_out != 0 {
+                    kinds &= !ruled_out;
+                    contradiction.get_or_insert((reason, path.to_string()));
+                }
+            };
+            self.check_numbers(&members, path, &mut rule_out);
+            self.check_strings(&members, path, &mut rule_out, &mut unknown);
+            let arrays = self.check_arrays(&members);
+            let objects = self.check_objects(&members);
+            for (kind, result) in [(ARRAY, arrays), (OBJECT, objects)] {
+                match result {
+                    No(reason, path) => rule_out(kind, reason, &path),
+                    Unknown => unknown |= kind,
+                    Yes => {}
+                }
+            }
+        }
+        let mut result = match contradiction {
+            Some((reason, path)) if kinds == 0 => No(reason, path),
+            _ if kinds & !unknown == 0 => Unknown,
+            _ => Yes,
+        };
+        for &(member, ref path) in &members {
+            if let Schema::Combinator(ref s) = *member {
+                result =
+                    result.and(|| self.check_branches(s.any_of.as_ref(), "anyOf", kinds, path));
+                result =
+                    result.and(|| self.check_branches(s.one_of.as_ref(), "oneOf", kinds, path));
+                result = result.and(|| match s.not {
+                    Some(ref not) => self.check_not(not, kinds, path),
+                    None => Yes,
+                });
+            }
+        }
+        result
+    }
+
+    fn check_numbers<F>(&self, members: &[(&'s Schema, String)], path: &str, rule_out: &mut F)
+    where
+        F: FnMut(u8, String, &str),
+    {
+        let mut bounds = Bounds::new();
+        let mut multiples = vec![];
+        for &(member, _) in members {
+            let range = match *member {
+                Schema::Number(ref s) => s.range(),
+                Schema::Integer(ref s) => s.range(),
+                _ => continue,
+            };
+            bounds.restrict(&range);
+            multiples.extend(range.multiple_of);
+        }
+        if bounds.is_empty() {
+            let reason = format!("no number satisfies {}", bounds.describe());
+            return rule_out(FRACTION | INTEGER, reason, path);
+        }
+        if let [step] = multiples[..] {
+            if bounds.first_multiple(step).is_none() {
+                let reason = format!("no multiple of {} satisfies {}", step, bounds.describe());
+                return rule_out(FRACTION | INTEGER, reason, path);
+            }
+        }
+        if bounds.first_multiple(1.0).is_none() {
+            let reason = format!("no integer satisfies {}", bounds.describe());
+            rule_out(INTEGER, reason, path);
+        }
+    }
+
+    fn check_strings<F>(
+        &self,
+        members: &[(&'s Schema, String)],
+        path: &str,
+        rule_out: &mut F,
+        unknown: &mut u8,
+    ) where
+        F: FnMut(u8, String, &str),
+    {
+        let (mut min, mut max, mut patterns) = (0, None, 0);
+        for &(member, _) in members {
+            if let Schema::String(ref s) = *member {
+                min = min.max(s.min_length.unwrap_or(0));
+                max = match (max, s.max_length) {
+                    (Some(max), Some(other)) => Some(::std::cmp::min(max, other)),
+                    (max, other) => max.or(other),
+                };
+                patterns += s.pattern.iter().count();
+            }
+        }
+        match max {
+            Some(max) if min > max => {
+                let reason = format!("minLength {} is greater than maxLength {}", min, max);
+                rule_out(STRING, reason, path)
+            }
+            // whether several regular expressions have a match in common is
+            // beyond these checks
+            _ if patterns > 1 => *unknown |= STRING,
+            _ => {}
+        }
+    }
+
+    /// Checks the size and the items arrays need to have.
+    fn check_arrays(&mut self, members: &[(&'s Schema, String)]) -> Satisfiability {
+        let (mut min, mut max) = (0, None);
+        for &(member, _) in members {
+            if let Schema::Array(ref s) = *member {
+                min = min.max(s.min_items.unwrap_or(0));
+                max = match (max, s.max_items) {
+                    (Some(max), Some(other)) => Some(::std::cmp::min(max, other)),
+                    (max, other) => max.or(other),
+                };
+            }
+        }
+        let path = &members[members.len() - 1].1;
+        if let Some(max) = max.filter(|&max| min > max) {
+            let reason = format!("minItems {} is greater than maxItems {}", min, max);
+            return No(reason, path.clone());
+        }
+        let mut result = Yes;
+        for &(member, ref path) in members {
+            let s = match *member {
+                Schema::Array(ref s) => s,
+                _ => continue,
+            };
+            if let (None, Some(Items::Allowed(false))) = (&s.prefix_items, &s.items) {
+                if min > 0 {
+                    let reason = format!("minItems {} but `items` allows no items", min);
+                    return No(reason, path.clone());
+                }
+            }
+            // the first `min` items have to match their schemas
+            let prefix = match (&s.prefix_items, &s.items) {
+                (Some(prefix), _) | (None, Some(Items::Tuple(prefix))) => prefix.len(),
+                _ => 0,
+            };
+            let keyword = if s.prefix_items.is_some() {
+                "prefixItems"
+            } else {
+                "items"
+            };
+            for i in 0..min {
+                let item = match s.item_schema(i) {
+                    Some(item) => item,
+                    None => continue,
+                };
+                let item_path = if i < prefix {
+                    format!("{}/{}/{}", path, keyword, i)
+                } else if s.prefix_items.is_none() && prefix > 0 {
+                    format!("{}/additionalItems", path)
+                } else {
+                    format!("{}/items", path)
+                };
+                result = result.and(|| self.check(item, &item_path));
+                if i >= prefix {
+                    // the remaining items share this schema
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    /// Checks the number of properties and the properties objects need to have.
+    fn check_objects(&mut self, members: &[(&'s Schema, String)]) -> Satisfiability {
+        let (mut min, mut max) = (0, None);
+        let mut required: Vec<&String> = vec![];
+        for &(member, _) in members {
+            if let Some(s) = member.as_object() {
+                min = min.max(s.min_properties.unwrap_or(0));
+                max = match (max, s.max_properties) {
+                    (Some(max), Some(other)) => Some(::std::cmp::min(max, other)),
+                    (max, other) => max.or(other),
+                };
+                for name in s.required_properties() {
+                    if !required.contains(&name) {
+                        required.push(name);
+                    }
+                }
+            }
+        }
+        let path = &members[members.len() - 1].1;
+        if let Some(max) = max {
+            if min > max {
+                let reason = format!(
+                    "minProperties {} is greater than maxProperties {}",
+                    min, max
+                );
+                return No(reason, path.clone());
+            }
+            if required.len() > max {
+                let reason = format!(
+                    "{} properties are required but maxProperties is {}",
+                    required.len(),
+                    max
+                );
+                return No(reason, path.clone());
+            }
+        }
+        let mut result = Yes;
+        for &(member, ref path) in members {
+            let s = match member.as_object() {
+                Some(s) => s,
+                None => continue,
+            };
+            for (name, property) in s.properties.iter().flatten() {
+                if required.contains(&name) {
+                    let property_path = format!("{}/properties/{}", path, name);
+                    result = result.and(|| self.check(property, &property_path));
+                }
+            }
+        }
+        result
+    }
+
+    /// Checks `anyOf` or `oneOf`, whose branches can only match values of `kinds`.
+    fn check_branches(
+        &mut self,
+        branches: Option<&'s Vec<Schema>>,
+        keyword: &str,
+        kinds: u8,
+        path: &str,
+    ) -> Satisfiability {
+        let branches = match branches {
+            Some(branches) => branches,
+            None => return Yes,
+        };
+        let mut results = vec![];
+        for (i, branch) in branches.iter().enumerate() {
+            if self::kinds(branch) & kinds == 0 {
+                continue;
+            }
+            match self.check(branch, &format!("{}/{}/{}", path, keyword, i)) {
+                No(..) => {}
+                result => results.push((branch, result)),
+            }
+        }
+        let possible = results.iter().filter(|&(_, result)| *result == Yes);
+        let kinds: Vec<u8> = possible.map(|&(branch, _)| self::kinds(branch)).collect();
+        match results.len() {
+            0 => No(format!("no branch of `{}` can match", keyword), path.into()),
+            _ if keyword == "anyOf" && !kinds.is_empty() => Yes,
+            1 => results.remove(0).1,
+            // exactly one branch matching is certain if the branches that can
+            // match don't share a type
+            n if kinds.len() == n && disjoint(&kinds) => Yes,
+            _ => Unknown,
+        }
+    }
+
+    fn check_not(&mut self, not: &'s Schema, kinds: u8, path: &str) -> Satisfiability {
+        match *not {
+            Schema::Empty(_) => No("`not` rejects every value".into(), path.into()),
+            _ if self::kinds(not) & kinds == 0 => Yes,
+            _ => Unknown,
+        }
+    }
+}
+
+fn disjoint(kinds: &[u8]) -> bool {
+    let mut seen = 0;
+    for &k in kinds {
+        if seen & k != 0 {
+            return false;
+        }
+        seen |= k;
+    }
+    true
+}
+
+impl Schema {
+    /// Whether any value can be valid against this schema, as far as a
+    /// conservative analysis can tell: empty numeric ranges and length ranges,
+    /// `enum` and `const` values ruled out by the other keywords, `allOf`
+    /// members without a type in common, `anyOf`/`oneOf` without a possible
+    /// branch. Parts the analysis can't decide, like several patterns that have
+    /// to match at once, give `Satisfiability::Unknown`.
+    pub fn is_satisfiable(&self) -> Satisfiability {
+        let mut checker = Checker {
+            root: self,
+            following: vec![],
+        };
+        checker.check(self, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{self, Value};
+
+    use super::*;
+
+    fn check(schema: Value) -> Satisfiability {
+        let schema: Schema = serde_json::from_value(schema).unwrap();
+        schema.is_satisfiable()
+    }
+
+    fn no(reason: &str, path: &str) -> Satisfiability {
+        No(reason.into(), path.into())
+    }
+
+    #[test]
+    fn contradictions() {
+        assert_eq!(
+            check(json!({"type": "string", "minLength": 5, "maxLength": 2})),
+            no("minLength 5 is greater than maxLength 2", "")
+        );
+        assert_eq!(
+            check(json!({"type": "integer", "minimum": 0.2, "maximum": 0.8})),
+            no("no integer satisfies minimum 0.2 and maximum 0.8", "")
+        );
+        assert_eq!(
+            check(json!({"allOf": [{"const": 1}, {"const": 2}]})),
+            no("none of the values of `const` is valid", "/allOf/0")
+        );
+        assert_eq!(
+            check(json!({"type": "string", "enum": ["a", "b"], "pattern": "^c"})),
+            no("none of the values of `enum` is valid", "")
+        );
+        assert_eq!(
+            check(json!({"allOf": [{"type": "string"}, {"type": "integer"}]})),
+            no("the subschemas of `allOf` have no type in common", "")
+        );
+        assert_eq!(
+            check(json!({
+                "type": "object",
+                "additionalProperties": true,
+                "required": ["size"],
+                "properties": {"size": {"type": "number", "minimum": 3, "maximum": 3, "exclusiveMaximum": true}}
+            })),
+            no(
+                "no number satisfies minimum 3 and exclusive maximum 3",
+                "/properties/size"
+            )
+        );
+        assert_eq!(
+            check(json!({"allOf": [
+                {"type": "integer"},
+                {"anyOf": [{"type": "string"}, {"type": "boolean"}]}
+            ]})),
+            no("no branch of `anyOf` can match", "/allOf/1")
+        );
+    }
+
+    #[test]
+    fn satisfiable() {
+        assert_eq!(check(json!({"type": "string", "minLength": 2})), Yes);
+        // null is still valid
+        assert_eq!(
+            check(json!({"type": ["string", "null"], "minLength": 2, "maxLength": 1})),
+            Yes
+        );
+        assert_eq!(
+            check(json!({"oneOf": [{"type": "string"}, {"type": "integer"}]})),
+            Yes
+        );
+        assert_eq!(
+            check(json!({"allOf": [
+                {"type": "string", "pattern": "^a"},
+                {"type": "string", "pattern": "b$"}
+            ]})),
+            Unknown
+        );
+    }
+}
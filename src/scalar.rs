@@ -0,0 +1,250 @@
+use std::cell::RefCell;
+
+use serde_json::{Number, Value};
+
+use boolean::BooleanSchema;
+use errors::{ErrorKind, Result, ValidationError};
+use integer::IntegerSchema;
+use number::{NumberSchema, Range};
+use options::ValidateOptions;
+use render::preview;
+use schema::{validate_allowed, Context, Deadline, EmptySchema, Schema};
+use session::{RecordedError, Scratch};
+use string::StringSchema;
+use util::json_equal;
+
+/// What errors about strings point at in place of a JSON value, their value
+/// previews are made from the string.
+static PLACEHOLDER: Value = Value::Null;
+
+/// Runs `validate` like a validation of a whole document with the default
+/// options. `value` makes the JSON value of the scalar for the previews of the
+/// errors, so it's only called if there are any.
+fn validate_scalar<'json, F, V>(validate: F, value: V) -> Result<()>
+where
+    F: FnOnce(&Context, &mut Vec<ValidationError<'json>>),
+    V: FnOnce() -> Value,
+{
+    let root = Schema::Empty(EmptySchema::default());
+    let options = ValidateOptions::default();
+    let collected = RefCell::default();
+    let scratch = Scratch::default();
+    let deadline = Deadline::new(options.timeout);
+    let ctx = Context::new(&root, &options, &collected, &scratch, &deadline);
+    let mut errors = vec![];
+    validate(&ctx, &mut errors);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let value_preview = options.value_preview.map(|n| preview(&value(), n));
+    let errors = errors
+        .into_iter()
+        .map(|error| RecordedError {
+            reason: error.reason,
+            instance_path: String::new(),
+            schema_path: String::new(),
+            value_preview: value_preview.clone(),
+            folded: None,
+        })
+        .collect();
+    Err(ErrorKind::Invalid(errors).into())
+}
+
+impl StringSchema {
+    /// Validates the string `value` like `Schema::validate_str` validates it as
+    /// a JSON string, without building a JSON value for it. The errors are an
+    /// `ErrorKind::Invalid`.
+    pub fn validate_str(&self, value: &str) -> Result<()> {
+        validate_scalar(
+            |ctx, errors| {
+                self.validate_string(ctx, value, &PLACEHOLDER, errors);
+                let equal = |allowed: &Value| allowed.as_str() == Some(value);
+                let allowed = (&self.enum_values, &self.const_value);
+                validate_allowed(ctx, allowed, &equal, Some(value), &PLACEHOLDER, errors);
+            },
+            || Value::from(value),
+        )
+    }
+}
+
+/// Validates the number `number` with the `range` of a number or integer
+/// schema, whose `enum` and `const` are `allowed`.
+fn validate_number(
+    range: &Range,
+    allowed: (&Option<Vec<Value>>, &Option<Value>),
+    number: Number,
+) -> Result<()> {
+    let node = Value::Number(number);
+    validate_scalar(
+        |ctx, errors| {
+            range.validate(&node, errors);
+            let equal = |value: &Value| json_equal(value, &node);
+            validate_allowed(ctx, allowed, &equal, None, &node, errors);
+        },
+        || node.clone(),
+    )
+}
+
+impl NumberSchema {
+    /// Validates the number `value` like a JSON number, without building a JSON
+    /// value for it. `NaN` and infinities, which JSON can't express, are
+    /// reported as `ErrorKind::NonFiniteNumber`.
+    pub fn validate_f64(&self, value: f64) -> Result<()> {
+        let allowed = (&self.enum_values, &self.const_value);
+        match Number::from_f64(value) {
+            Some(number) => validate_number(&self.range(), allowed, number),
+            None => {
+                let reason = ErrorKind::NonFiniteNumber(value.to_string());
+                validate_scalar(
+                    |_, errors| errors.push(ValidationError::new(reason, &PLACEHOLDER)),
+                    || Value::Null,
+                )
+            }
+        }
+    }
+}
+
+impl IntegerSchema {
+    /// Validates the integer `value` like a JSON integer, without building a
+    /// JSON value for it.
+    pub fn validate_i64(&self, value: i64) -> Result<()> {
+        let allowed = (&self.enum_values, &self.const_value);
+        validate_number(&self.range(), allowed, Number::from(value))
+    }
+
+    /// Like `validate_i64`, for unsigned integers.
+    pub fn validate_u64(&self, value: u64) -> Result<()> {
+        let allowed = (&self.enum_values, &self.const_value);
+        validate_number(&self.range(), allowed, Number::from(value))
+    }
+}
+
+impl BooleanSchema {
+    /// Validates the boolean `value` like a JSON boolean, without building a
+    /// JSON value for it.
+    pub fn validate_bool(&self, value: bool) -> Result<()> {
+        validate_scalar(
+            |ctx, errors| {
+                let equal = |allowed: &Value| allowed.as_bool() == Some(value);
+                let allowed = (&self.enum_values, &self.const_value);
+                validate_allowed(ctx, allowed, &equal, None, &PLACEHOLDER, errors);
+            },
+            || Value::Bool(value),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::Error;
+
+    /// The errors of a result as text, with their previews.
+    fn described(result: Result<()>) -> Vec<String> {
+        let error: Error = match result {
+            Ok(()) => return vec![],
+            Err(error) => error,
+        };
+        match *error.kind() {
+            ErrorKind::Invalid(ref errors) => errors
+                .iter()
+                .map(|e| format!("{} {:?}", e, e.value_preview))
+                .collect(),
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+    }
+
+    fn parse(schema: Value) -> Schema {
+        serde_json::from_value(schema).unwrap()
+    }
+
+    /// Asserts that the scalar methods and `Schema::validate` agree about the
+    /// values, and that each of them is accepted or rejected as `valid` says.
+    fn assert_parity<T, F>(schema: &Schema, values: &[(T, bool)], validate: F)
+    where
+        T: Copy + Into<Value>,
+        F: Fn(T) -> Result<()>,
+    {
+        for &(value, valid) in values {
+            let json = value.into();
+            let expected = described(schema.validate_parsed(&json, &ValidateOptions::default()));
+            let found = described(validate(value));
+            assert_eq!(found, expected, "{}", json);
+            assert_eq!(found.is_empty(), valid, "{}", json);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        let schema = parse(json!({
+            "type": "string",
+            "minLength": 2,
+            "maxLength": 5,
+            "pattern": "^[a-z]",
+            "enum": ["apple", "pear", "1"]
+        }));
+        let string = schema.as_string().unwrap();
+        let values = [("pear", true), ("a", false), ("appel", false), ("1", false)];
+        assert_parity(&schema, &values, |value| string.validate_str(value));
+
+        let schema = parse(json!({
+            "type": "string",
+            "format": "date",
+            "x-formatMinimum": "2020-01-01",
+            "const": "2020-02-01"
+        }));
+        let string = schema.as_string().unwrap();
+        let values = [
+            ("2020-02-01", true),
+            ("2019-12-31", false),
+            ("tomorrow", false),
+        ];
+        assert_parity(&schema, &values, |value| string.validate_str(value));
+    }
+
+    #[test]
+    fn numbers() {
+        let schema = parse(json!({
+            "type": "number",
+            "minimum": 0,
+            "maximum": 10,
+            "exclusiveMaximum": true,
+            "multipleOf": 0.5,
+            "enum": [1, 2.5, 10, -1]
+        }));
+        let number = schema.as_number().unwrap();
+        let values = [
+            (2.5, true),
+            (1.0, true),
+            (10.0, false),
+            (-1.0, false),
+            (3.0, false),
+        ];
+        assert_parity(&schema, &values, |value| number.validate_f64(value));
+        assert!(number.validate_f64(f64::NAN).is_err());
+
+        let schema = parse(json!({
+            "type": "integer",
+            "minimum": -5,
+            "maximum": 9007199254740993u64,
+            "multipleOf": 3,
+            "const": 9
+        }));
+        let integer = schema.as_integer().unwrap();
+        let values = [(9, true), (-6, false), (6, false)];
+        assert_parity(&schema, &values, |value| integer.validate_i64(value));
+        let values = [(9, true), (9_007_199_254_740_994, false)];
+        assert_parity(&schema, &values, |value| integer.validate_u64(value));
+    }
+
+    #[test]
+    fn booleans() {
+        let schema = parse(json!({"type": "boolean", "const": true}));
+        let boolean = schema.as_boolean().unwrap();
+        assert_parity(&schema, &[(true, true), (false, false)], |value| {
+            boolean.validate_bool(value)
+        });
+    }
+}
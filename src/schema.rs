@@ -1,25 +1,656 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::mem::{size_of, size_of_val};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use serde_json::{self, Value};
+use chrono::Utc;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::DeserializeOwned;
+use serde_json::{self, Map, Value};
+use url::Url;
 
 use boolean::BooleanSchema;
 use integer::IntegerSchema;
-use errors::{Error, ValidationError, ValidationErrors};
-use array::ArraySchema;
-use object::ObjectSchema;
+use errors::{self, Error, ErrorKind, ValidationError, ValidationErrors};
+use array::{AdditionalItems, ArraySchema, Items};
+use object::{AdditionalProperties, ObjectSchema, PropertyMap};
 use number::NumberSchema;
 use string::StringSchema;
 use reference::ReferenceSchema;
+use resolution::{resolve_all, ResolutionReport};
+use combinator::{any_of, CombinatorSchema};
+use options::ValidateOptions;
+use session::{RecordedError, Scratch};
+use store::SchemaStore;
+use coverage::SchemaIndex;
+use diagnostics::non_schema_keys;
+use render::preview;
+use audit::audit_instance;
+use output::{fold_similar, Annotation, Collected, SkippedKeyword, Timing, ValidationOutput,
+             ValidationWarning, WarningKind};
+use types::JsonType;
+use util::{
+    closest_match, deserialize_some, json_equal, pointer_token, removal_date, sample_indices,
+    strip_bom,
+};
 
-// TODO move the other parameters to the context?
-#[doc(hidden)]
+/// One reference token of a JSON pointer.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+impl<'a> Segment<'a> {
+    /// Whether this segment is the unescaped reference token `token`.
+    fn is(&self, token: &str) -> bool {
+        match *self {
+            Segment::Key(key) => key == token,
+            Segment::Index(index) => token.parse() == Ok(index),
+        }
+    }
+}
+
+/// A JSON pointer stored as a linked list of segments that lives on the stack of
+/// the validation walk. It's only turned into a string when it's needed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Path<'a> {
+    parent: Option<&'a Path<'a>>,
+    segment: Segment<'a>,
+}
+
+fn to_pointer(path: Option<&Path>) -> String {
+    let mut segments = vec![];
+    let mut current = path;
+    while let Some(path) = current {
+        segments.push(path.segment);
+        current = path.parent;
+    }
+    let mut pointer = String::new();
+    for segment in segments.iter().rev() {
+        pointer.push('/');
+        match *segment {
+            Segment::Key(key) => pointer.push_str(&pointer_token(key)),
+            Segment::Index(index) => pointer.push_str(&index.to_string()),
+        }
+    }
+    pointer
+}
+
+/// Keeps track of the time a validation run takes, see `ValidateOptions::timeout`,
+/// and of the memory it holds, see `ValidateOptions::max_memory`.
+#[derive(Debug)]
+pub(crate) struct Deadline {
+    limit: Option<(Instant, Duration)>,
+    nodes: Cell<u32>,
+    expired: Cell<Option<Duration>>,
+    memory: Cell<usize>,
+    peak_memory: Cell<usize>,
+    /// The number of errors whose memory was counted, see `Context::track_errors`.
+    tracked_errors: Cell<usize>,
+    out_of_memory: Cell<bool>,
+}
+
+impl Deadline {
+    /// The number of schemas applied between two reads of the clock.
+    const CHECK_INTERVAL: u32 = 256;
+
+    pub(crate) fn new(timeout: Option<Duration>) -> Deadline {
+        Deadline {
+            limit: timeout.map(|timeout| (Instant::now(), timeout)),
+            nodes: Cell::new(0),
+            expired: Cell::new(None),
+            memory: Cell::new(0),
+            peak_memory: Cell::new(0),
+            tracked_errors: Cell::new(0),
+            out_of_memory: Cell::new(false),
+        }
+    }
+
+    /// Counts another applied schema and returns whether the run is out of time.
+    fn check(&self) -> bool {
+        let (start, timeout) = match self.limit {
+            Some(limit) => limit,
+            None => return false,
+        };
+        if self.expired.get().is_some() {
+            return true;
+        }
+        let nodes = self.nodes.get() + 1;
+        if nodes < Deadline::CHECK_INTERVAL {
+            self.nodes.set(nodes);
+        } else {
+            self.nodes.set(0);
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                self.expired.set(Some(elapsed));
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The time the run took if it ran out of time.
+    pub(crate) fn expired(&self) -> Option<Duration> {
+        self.expired.get()
+    }
+
+    /// The most memory the run held at once, in bytes.
+    pub(crate) fn peak_memory(&self) -> usize {
+        self.peak_memory.get()
+    }
+
+    /// Whether the run was aborted, because it ran out of time or memory.
+    pub(crate) fn aborted(&self) -> bool {
+        self.expired.get().is_some() || self.out_of_memory.get()
+    }
+}
+
+/// The number of annotations, warnings and hits recorded at some point of a
+/// validation run, see `Context::mark`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Mark {
+    annotations: usize,
+    warnings: usize,
+    hits: usize,
+}
+
+/// A `$ref` that was followed on the way to the current schema, stored like `Path`.
+#[derive(Debug, Clone, Copy)]
+struct FollowedRef<'a> {
+    parent: Option<&'a FollowedRef<'a>>,
+    /// The document the reference was in.
+    root: &'a Schema,
+    reference: &'a str,
+    /// The value the reference was followed for.
+    instance_path: Option<&'a Path<'a>>,
+}
+
+/// The state of a validation run: the options, where in the schema and the value
+/// it is, and what has been collected so far. Custom schemas get it in
+/// `SchemaBase::validate_inner`, to read the options and their extensions.
 #[derive(Debug)]
 pub struct Context<'s> {
+    /// The schema references are resolved against.
     pub root: &'s Schema,
+    pub(crate) store: Option<&'s SchemaStore>,
+    pub(crate) coverage: Option<&'s SchemaIndex>,
+    instance_path: Option<&'s Path<'s>>,
+    schema_path: Option<&'s Path<'s>>,
+    references: Option<&'s FollowedRef<'s>>,
+    depth: usize,
+    pub(crate) options: &'s ValidateOptions,
+    collected: &'s RefCell<Collected>,
+    pub(crate) scratch: &'s Scratch,
+    deadline: &'s Deadline,
+    /// The reference tokens from the current value to the value that changed, see
+    /// `Session::revalidate_at`. Values off this path are skipped.
+    pub(crate) focus: Option<&'s [String]>,
+    trial: bool,
+    /// Whether the current value is part of a partial update, see
+    /// `ValidateOptions::partial`.
+    pub(crate) partial: bool,
+    /// Set where `validate_all` walks the elements or properties of the value
+    /// validation starts at for all schemas at once: the array or object schema
+    /// of that value stores where their errors go instead of validating them.
+    pub(crate) deferred: Option<&'s Cell<Option<usize>>>,
+}
+
+impl<'s> Context<'s> {
+    pub(crate) fn new(
+        root: &'s Schema,
+        options: &'s ValidateOptions,
+        collected: &'s RefCell<Collected>,
+        scratch: &'s Scratch,
+        deadline: &'s Deadline,
+    ) -> Context<'s> {
+        Context {
+            root,
+            store: None,
+            coverage: None,
+            instance_path: None,
+            schema_path: None,
+            references: None,
+            depth: 0,
+            options,
+            collected,
+            scratch,
+            deadline,
+            focus: None,
+            trial: false,
+            partial: options.partial,
+            deferred: None,
+        }
+    }
+
+    /// The options of the validation run.
+    pub fn options(&self) -> &ValidateOptions {
+        self.options
+    }
+
+    /// The value of type `T` of `ValidateOptions::extensions`, if one was
+    /// stored.
+    pub fn extension<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.options.extensions.get()
+    }
+
+    /// Runs `f` with the context of a subschema. `instance` is the step from the
+    /// current value to the value the subschema applies to (`None` if it applies to
+    /// the same value), `keyword` and `index` are the steps from the current schema
+    /// to the subschema.
+    pub(crate) fn descend<F>(
+        &self,
+        instance: Option<Segment>,
+        keyword: &str,
+        index: Option<Segment>,
+        f: F,
+    ) where
+        F: FnOnce(&Context),
+    {
+        let mut focus = self.focus;
+        if let (Some(tokens), Some(segment)) = (self.focus, instance) {
+            if !segment.is(&tokens[0]) {
+                return;
+            }
+            focus = Some(&tokens[1..]).filter(|rest| !rest.is_empty());
+        }
+        let instance_node;
+        let instance_path = match instance {
+            Some(segment) => {
+                instance_node = Path {
+                    parent: self.instance_path,
+                    segment,
+                };
+                Some(&instance_node)
+            }
+            None => self.instance_path,
+        };
+        let keyword_node = Path {
+            parent: self.schema_path,
+            segment: Segment::Key(keyword),
+        };
+        let index_node;
+        let schema_path = match index {
+            Some(segment) => {
+                index_node = Path {
+                    parent: Some(&keyword_node),
+                    segment,
+                };
+                &index_node
+            }
+            None => &keyword_node,
+        };
+        // array elements are replaced as a whole by updates
+        let partial = self.partial && !matches!(instance, Some(Segment::Index(_)));
+        f(&Context {
+            instance_path,
+            schema_path: Some(schema_path),
+            focus,
+            depth: self.depth + 1,
+            partial,
+            deferred: None,
+            ..*self
+        })
+    }
+
+    /// Runs `f` with the context of the schema `reference` points to, which is
+    /// in the document `root`. Returns false without running `f` if `reference`
+    /// is already being followed for the current value, as that would never end.
+    pub(crate) fn follow<F>(&self, root: &'s Schema, reference: &str, f: F) -> bool
+    where
+        F: FnOnce(&Context),
+    {
+        let same_value = |a: Option<&Path>, b: Option<&Path>| match (a, b) {
+            (Some(a), Some(b)) => ::std::ptr::eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        let mut current = self.references;
+        while let Some(link) = current {
+            if link.reference == reference
+                && ::std::ptr::eq(link.root, self.root)
+                && same_value(link.instance_path, self.instance_path)
+            {
+                return false;
+            }
+            current = link.parent;
+        }
+        let link = FollowedRef {
+            parent: self.references,
+            root: self.root,
+            reference,
+            instance_path: self.instance_path,
+        };
+        Context {
+            root,
+            references: Some(&link),
+            ..*self
+        }
+        .descend(None, "$ref", None, f);
+        true
+    }
+
+    /// Runs `f` for a subschema whose result can depend on every part of the
+    /// current value, like `oneOf`, without skipping values off the focus path.
+    pub(crate) fn unfocused<F>(&self, f: F)
+    where
+        F: FnOnce(&Context),
+    {
+        if let Some(tokens) = self.focus {
+            let mut collected = self.collected.borrow_mut();
+            collected.unfocused = collected.unfocused.max(tokens.len());
+        }
+        f(&Context {
+            focus: None,
+            ..*self
+        })
+    }
+
+    /// Whether the subschemas entered on the way to the current schema exceed
+    /// `ValidateOptions::max_depth`.
+    pub(crate) fn too_deep(&self) -> bool {
+        self.options.max_depth.is_some_and(|max| self.depth > max)
+    }
+
+    /// Runs `f` for a subschema whose errors are only counted, not reported, like a
+    /// branch of `oneOf`. The errors of such subschemas aren't located.
+    pub(crate) fn trial<F>(&self, f: F)
+    where
+        F: FnOnce(&Context),
+    {
+        f(&Context {
+            trial: true,
+            ..*self
+        })
+    }
+
+    /// JSON pointer to the value that is currently validated.
+    pub(crate) fn instance_pointer(&self) -> String {
+        to_pointer(self.instance_path)
+    }
+
+    /// JSON pointer to the schema that is currently applied.
+    pub(crate) fn schema_pointer(&self) -> String {
+        to_pointer(self.schema_path)
+    }
+
+    /// Fills in the paths of errors that were reported by the current schema.
+    pub(crate) fn locate(&self, errors: &mut [ValidationError]) {
+        // statistics tell the errors of a subschema from those of its subschemas
+        // by whether they're located, also in trials
+        if self.trial && self.options.stats.is_none() {
+            return;
+        }
+        let mut paths = None;
+        for error in errors.iter_mut().filter(|e| !e.is_located()) {
+            let &mut (ref instance, ref schema) = paths
+                .get_or_insert_with(|| (self.instance_pointer(), self.schema_pointer()));
+            error.locate(instance, schema);
+            error.value_preview = self.options.value_preview.map(|n| preview(error.node, n));
+        }
+    }
+
+    /// Locates the errors reported by the root schema, counts the run for the
+    /// statistics and adds the error about running out of time if the run was
+    /// aborted.
+    pub(crate) fn finish<'json>(
+        &self,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        self.locate(errors);
+        if let Some(ref stats) = self.options.stats {
+            stats.record_document();
+        }
+        if let Some(shown) = self.options.fold_similar {
+            fold_similar(errors, value, shown);
+        }
+        if let Some(max) = self.options.max_errors {
+            errors.truncate(max);
+        }
+        if let Some(elapsed) = self.deadline.expired() {
+            let mut error = ValidationError::new(ErrorKind::Timeout { elapsed }, value);
+            self.locate(::std::slice::from_mut(&mut error));
+            errors.push(error);
+        }
+        if let (true, Some(limit)) = (self.deadline.out_of_memory.get(), self.options.max_memory) {
+            let mut error = ValidationError::new(ErrorKind::MemoryLimit { limit }, value);
+            self.locate(::std::slice::from_mut(&mut error));
+            errors.push(error);
+        }
+    }
+
+    /// Counts `bytes` more memory held by the run. Returns false if that exceeds
+    /// `ValidateOptions::max_memory`, after which no more subschemas are applied.
+    pub(crate) fn track(&self, bytes: usize) -> bool {
+        let deadline = self.deadline;
+        let memory = deadline.memory.get() + bytes;
+        deadline.memory.set(memory);
+        if memory > deadline.peak_memory.get() {
+            deadline.peak_memory.set(memory);
+        }
+        if self.options.max_memory.is_some_and(|max| memory > max) {
+            deadline.out_of_memory.set(true);
+        }
+        !deadline.out_of_memory.get()
+    }
+
+    /// Counts `bytes` of memory the run held as freed.
+    pub(crate) fn untrack(&self, bytes: usize) {
+        let memory = &self.deadline.memory;
+        memory.set(memory.get().saturating_sub(bytes));
+    }
+
+    /// The number of errors whose memory is counted.
+    fn tracked_errors(&self) -> usize {
+        self.deadline.tracked_errors.get()
+    }
+
+    /// Counts the memory of `count` more errors. The strings they hold aren't
+    /// counted.
+    fn track_errors(&self, count: usize) {
+        let tracked = &self.deadline.tracked_errors;
+        tracked.set(tracked.get() + count);
+        self.track(count * size_of::<ValidationError>());
+    }
+
+    /// Counts the memory of `errors` as freed, when a buffer for the errors of
+    /// branches is cleared.
+    pub(crate) fn untrack_errors(&self, errors: &[ValidationError]) {
+        let tracked = &self.deadline.tracked_errors;
+        tracked.set(tracked.get().saturating_sub(errors.len()));
+        self.untrack(size_of_val(errors));
+    }
+
+    /// Whether `errors` already holds as many errors as `ValidateOptions::max_errors`
+    /// allows.
+    pub(crate) fn has_enough_errors(&self, errors: &[ValidationError]) -> bool {
+        self.options.max_errors.is_some_and(|max| errors.len() >= max)
+    }
+
+    /// Whether annotations are being collected.
+    pub(crate) fn collects_annotations(&self) -> bool {
+        self.options.annotations
+    }
+
+    /// Runs `f`, which matches the regular expression `pattern`, timing it if
+    /// statistics or timing are collected.
+    pub(crate) fn time_pattern<T, F: FnOnce() -> T>(&self, pattern: &str, f: F) -> T {
+        if self.options.stats.is_none() {
+            return self.time(|timing| &mut timing.regex, f);
+        }
+        let started = Instant::now();
+        let result = f();
+        let elapsed = started.elapsed();
+        if let Some(ref stats) = self.options.stats {
+            stats.record_pattern(pattern, elapsed);
+        }
+        if self.options.collect_timing {
+            self.collected.borrow_mut().timing.regex += elapsed;
+        }
+        result
+    }
+
+    /// Runs `f`, adding the time it takes to the part of `Timing` that `part`
+    /// picks if timing is collected.
+    pub(crate) fn time<T, F: FnOnce() -> T>(
+        &self,
+        part: fn(&mut Timing) -> &mut Duration,
+        f: F,
+    ) -> T {
+        if !self.options.collect_timing {
+            return f();
+        }
+        let started = Instant::now();
+        let result = f();
+        *part(&mut self.collected.borrow_mut().timing) += started.elapsed();
+        result
+    }
+
+    /// Whether warnings are being collected.
+    pub(crate) fn collects_warnings(&self) -> bool {
+        self.options.collect_warnings
+    }
+
+    /// Reports that the current value uses a subschema marked `deprecated`: a
+    /// warning, or an error if its removal date has passed and
+    /// `ValidateOptions::deny_expired` is set.
+    fn deprecated<'json>(
+        &self,
+        description: &Option<String>,
+        removed_in: &Option<String>,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        if !self.collects_warnings() && !self.options.deny_expired {
+            return;
+        }
+        let expired = match removed_in.as_ref().and_then(|date| removal_date(date)) {
+            Some(date) => date <= self.options.now.unwrap_or_else(|| Utc::now().date_naive()),
+            None => false,
+        };
+        if expired && self.options.deny_expired {
+            let removed_in = removed_in.clone().unwrap_or_default();
+            errors.push(ValidationError::new(ErrorKind::Removed { removed_in }, value));
+        } else {
+            self.warn(WarningKind::Deprecated {
+                message: description.clone(),
+                removed_in: removed_in.clone(),
+                expired,
+            });
+        }
+    }
+
+    /// Records an annotation for the current value and schema.
+    pub(crate) fn annotate(&self, keyword: &str, value: Value) {
+        if self.collects_annotations() {
+            self.collected.borrow_mut().annotations.push(Annotation {
+                keyword: keyword.into(),
+                schema_path: self.schema_pointer(),
+                instance_path: self.instance_pointer(),
+                value,
+            });
+        }
+    }
+
+    /// Records a warning for the current value and schema.
+    pub(crate) fn warn(&self, kind: WarningKind) {
+        if self.collects_warnings() {
+            self.collected.borrow_mut().warnings.push(ValidationWarning {
+                kind,
+                instance_path: self.instance_pointer(),
+                schema_path: self.schema_pointer(),
+            });
+        }
+    }
+
+    /// Whether the extension `keyword` applies, which it doesn't with
+    /// `ValidateOptions::strict_spec`. Warns about ignoring it then.
+    pub(crate) fn applies_extension(&self, keyword: &str) -> bool {
+        if self.options.strict_spec {
+            self.warn(WarningKind::IgnoredExtension(keyword.into()));
+        }
+        !self.options.strict_spec
+    }
+
+    /// The indices of the elements of an array of `len` elements to validate,
+    /// from `start` on, or `None` if all of them are, see
+    /// `ValidateOptions::array_sampling`.
+    pub(crate) fn sample(&self, start: usize, len: usize) -> Option<Vec<usize>> {
+        let policy = self.options.array_sampling?;
+        let rest = len.saturating_sub(start);
+        if rest <= policy.max_per_array {
+            return None;
+        }
+        self.collected.borrow_mut().sampled = true;
+        let indices = sample_indices(rest, policy.max_per_array, policy.seed);
+        Some(indices.into_iter().map(|i| i + start).collect())
+    }
+
+    /// Whether an array of `len` elements is sampled, so keywords that need all
+    /// of its elements are skipped.
+    pub(crate) fn samples(&self, len: usize) -> bool {
+        self.options
+            .array_sampling
+            .is_some_and(|policy| len > policy.max_per_array)
+    }
+
+    /// Records that `keyword` of the current schema was skipped for the current
+    /// value, which is a sampled array.
+    pub(crate) fn skip(&self, keyword: &str) {
+        self.collected.borrow_mut().skipped.push(SkippedKeyword {
+            keyword: keyword.into(),
+            schema_path: self.schema_pointer(),
+            instance_path: self.instance_pointer(),
+        });
+    }
+
+    /// Records that `schema` accepted the current value, if coverage is collected.
+    pub(crate) fn hit(&self, schema: &Schema) {
+        if let Some(index) = self.coverage.and_then(|coverage| coverage.get(schema)) {
+            self.collected.borrow_mut().hits.push(index);
+        }
+    }
+
+    /// Returns a marker for the annotations, warnings and hits recorded so far.
+    pub(crate) fn mark(&self) -> Mark {
+        let collected = self.collected.borrow();
+        Mark {
+            annotations: collected.annotations.len(),
+            warnings: collected.warnings.len(),
+            hits: collected.hits.len(),
+        }
+    }
+
+    /// Drops the annotations recorded after `mark`, used when the schema that
+    /// produced them turned out not to match.
+    pub(crate) fn discard_annotations(&self, mark: Mark) {
+        self.collected.borrow_mut().annotations.truncate(mark.annotations);
+    }
+
+    /// Drops everything recorded after `mark`, used for subschemas whose result
+    /// is thrown away, like failed `anyOf` branches.
+    pub(crate) fn discard(&self, mark: Mark) {
+        let mut collected = self.collected.borrow_mut();
+        collected.annotations.truncate(mark.annotations);
+        collected.warnings.truncate(mark.warnings);
+        collected.hits.truncate(mark.hits);
+    }
 }
 
-/// The trait that all schema types implement.
-pub trait SchemaBase {
+/// The trait that all schema types implement. It's object safe, so validators
+/// can be stored as `Box<dyn SchemaBase>` or embedded in a schema with
+/// `Schema::Custom`. Validating with a `Schema` doesn't need it, its entry
+/// points are inherent methods.
+///
+/// A custom validator reports problems by pushing errors made with
+/// `ValidationError::new` or `ValidationError::custom` for the value it's given
+/// onto `errors` in `validate_inner`. It leaves their paths empty, the validator
+/// fills them in with the path to the value and to the custom schema.
+pub trait SchemaBase: fmt::Debug {
     #[doc(hidden)]
     fn validate_inner<'json>(
         &self,
@@ -35,8 +666,13 @@ pub trait SchemaBase {
         root: &Schema,
     ) -> Result<(), ValidationErrors<'json>> {
         let mut errors = vec![];
-        let context = Context { root };
+        let options = ValidateOptions::default();
+        let collected = RefCell::default();
+        let scratch = Scratch::default();
+        let deadline = Deadline::new(options.timeout);
+        let context = Context::new(root, &options, &collected, &scratch, &deadline);
         self.validate_inner(&context, value, &mut errors);
+        context.finish(value, &mut errors);
 
         if errors.is_empty() {
             Ok(())
@@ -46,9 +682,57 @@ pub trait SchemaBase {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Copy)]
-#[doc(hidden)]
-pub struct EmptySchema;
+impl<T: SchemaBase + ?Sized> SchemaBase for &T {
+    #[doc(hidden)]
+    fn validate_inner<'json>(
+        &self,
+        ctx: &Context,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        (**self).validate_inner(ctx, value, errors)
+    }
+}
+
+impl<T: SchemaBase + ?Sized> SchemaBase for Box<T> {
+    #[doc(hidden)]
+    fn validate_inner<'json>(
+        &self,
+        ctx: &Context,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        (**self).validate_inner(ctx, value, errors)
+    }
+}
+
+/// A schema without a `type` and without keywords that constrain values, like
+/// `{}` or `{"description": "anything"}`. It accepts every value.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct EmptySchema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+}
 
 #[doc(hidden)]
 impl SchemaBase for EmptySchema {
@@ -63,120 +747,1931 @@ impl SchemaBase for EmptySchema {
 }
 
 /// Enum representing the different types of schemas.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(tag = "type")]
+///
+/// The variant is selected by the `type` keyword. A `type` of the form
+/// `["string", "null"]` selects the non-null variant and marks the schema
+/// as nullable.
+#[derive(Clone, Debug)]
 pub enum Schema {
     /// Boolean schema. `true` or `false`.
-    #[serde(rename = "boolean")]
     Boolean(BooleanSchema),
     /// A schema for a JSON object like `{"food": "noodles"}`
-    #[serde(rename = "object")]
     Object(ObjectSchema),
     /// A schema for a JSON array like `["noodles", "eggs", "bacon"]`
-    #[serde(rename = "array")]
     Array(ArraySchema),
     /// A schema for a JSON number, usually floating points like `3.14`.
-    #[serde(rename = "number")]
     Number(NumberSchema),
     /// A schema for a string, like `"food"`
-    #[serde(rename = "string")]
     String(StringSchema),
     /// A schem a for an integer like `42`.
-    #[serde(rename = "integer")]
     Integer(IntegerSchema),
-    /// The empty schema `{}`.
+    /// A schema without a `type` combining other schemas with `allOf`, `anyOf`,
+    /// `oneOf` or `not`, or listing the allowed values with `enum` or `const`.
+    Combinator(CombinatorSchema),
+    /// The empty schema `{}`, possibly with annotations like `description`.
     Empty(EmptySchema),
     /// A reference to some other schema
     Reference(ReferenceSchema),
+    /// A subschema that is shared with other parts of the schema, see `Schema::dedup`.
+    Shared(Arc<Schema>),
+    /// A validator implemented in code, for checks JSON schema can't express.
+    /// Custom schemas can only be built programmatically: serializing a schema
+    /// that contains one fails, as there's no JSON for it.
+    Custom(Arc<dyn SchemaBase + Send + Sync>),
 }
 
-impl Schema {
-    /// Kicks off validation for this schema.
-    pub fn validate<'json>(&self, value: &'json Value) -> Result<(), ValidationErrors<'json>> {
-        self.validate_start(value, self)
-    }
-    /// Resolve references for this schema
-    pub fn resolve_references(&mut self, schema: &Value) {
-        if let Some(obj) = schema.as_object() {
-            for (key, value) in obj {
-                if key == "$ref" && value.is_string() {
-                    let path = value.as_str().unwrap();
-                    // This document
-                    if path.starts_with('#') {
-                        if let Some(definition) = schema.pointer(&path[1..]) {
-                            println!("{}", definition);
-                        }
-                    } 
-                    // URI reference
-                    else {
-                        
-                    }
+const TYPES: &[&str] = &["boolean", "object", "array", "number", "string", "integer"];
+/// The keywords of `EmptySchema`.
+const EMPTY_KEYWORDS: &[&str] = &[
+    "description",
+    "id",
+    "$id",
+    "title",
+    "default",
+    "examples",
+    "x-counterexamples",
+    "deprecated",
+    "x-removed-in",
+];
+
+/// Keywords that make a schema without `type` a `Schema::Combinator`.
+const COMBINATORS: &[&str] = &["allOf", "anyOf", "oneOf", "not", "enum", "const", "definitions"];
+
+/// Collects the direct subschemas of `$schema` into a vector of references, which
+/// are mutable if `mut` is given.
+macro_rules! children {
+    ($schema:expr, $($mut:ident)*) => {{
+        let mut children = vec![];
+        macro_rules! map {
+            ($map:expr) => {
+                let map: Option<& $($mut)* PropertyMap<Schema>> = Option::from(& $($mut)* $map);
+                children.extend(map.into_iter().flat_map(|m| m).map(|(_, s)| s));
+            };
+        }
+        macro_rules! list {
+            ($list:expr) => {
+                let list: Option<& $($mut)* Vec<Schema>> = Option::from(& $($mut)* $list);
+                children.extend(list.into_iter().flat_map(|l| l));
+            };
+        }
+
+        use self::Schema::*;
+        match *$schema {
+            Boolean(ref $($mut)* s) => { map!(s.definitions); }
+            Number(ref $($mut)* s) => { map!(s.definitions); }
+            String(ref $($mut)* s) => { map!(s.definitions); }
+            Integer(ref $($mut)* s) => { map!(s.definitions); }
+            Reference(ref $($mut)* s) => {
+                map!(s.definitions);
+                if let Some(ref $($mut)* siblings) = s.siblings {
+                    children.push(& $($mut)* **siblings);
+                }
+            }
+            Object(ref $($mut)* s) => {
+                map!(s.definitions);
+                map!(s.properties);
+                map!(s.pattern_properties);
+                if let Some(AdditionalProperties::Schema(ref $($mut)* schema)) = s.additional_properties {
+                    children.push(& $($mut)* **schema);
                 }
             }
+            Array(ref $($mut)* s) => {
+                map!(s.definitions);
+                list!(s.prefix_items);
+                match s.items {
+                    Some(Items::List(ref $($mut)* schema)) => children.push(& $($mut)* **schema),
+                    Some(Items::Tuple(ref $($mut)* schemas)) => children.extend(schemas),
+                    Some(Items::Allowed(_)) | None => {}
+                }
+                if let Some(AdditionalItems::Schema(ref $($mut)* schema)) = s.additional_items {
+                    children.push(& $($mut)* **schema);
+                }
+            }
+            Combinator(ref $($mut)* s) => {
+                map!(s.definitions);
+                list!(s.all_of);
+                list!(s.any_of);
+                list!(s.one_of);
+                if let Some(ref $($mut)* schema) = s.not {
+                    children.push(& $($mut)* **schema);
+                }
+            }
+            Empty(_) | Shared(_) | Custom(_) => {}
         }
-    }
+        children
+    }};
 }
 
-impl FromStr for Schema {
-    type Err = Error;
-    fn from_str(s: &str) -> ::std::result::Result<Schema, Self::Err> {
-        serde_json::from_str(s).map_err(From::from)
+impl Schema {
+    /// Returns the value of this schema's `type` keyword, if it has one.
+    pub fn type_name(&self) -> Option<&'static str> {
+        use self::Schema::*;
+        match *self {
+            Boolean(_) => Some("boolean"),
+            Object(_) => Some("object"),
+            Array(_) => Some("array"),
+            Number(_) => Some("number"),
+            String(_) => Some("string"),
+            Integer(_) => Some("integer"),
+            Combinator(_) | Empty(_) | Reference(_) | Custom(_) => None,
+            Shared(ref s) => s.type_name(),
+        }
     }
-}
 
-macro_rules! impl_traits {
-    ($name:ty, $schema:path) => (
-        impl  From<$name> for Schema {
-            fn from(value: $name) -> Schema {
-                $schema(value)
-            }
+    /// Whether this schema also accepts `null`.
+    pub fn is_nullable(&self) -> bool {
+        use self::Schema::*;
+        match *self {
+            Boolean(ref s) => s.nullable,
+            Object(ref s) => s.nullable,
+            Array(ref s) => s.nullable,
+            Number(ref s) => s.nullable,
+            String(ref s) => s.nullable,
+            Integer(ref s) => s.nullable,
+            Combinator(_) | Empty(_) | Reference(_) | Custom(_) => false,
+            Shared(ref s) => s.is_nullable(),
         }
-    )
-}
+    }
 
-impl_traits! { BooleanSchema, Schema::Boolean }
-impl_traits! { ObjectSchema, Schema::Object }
-impl_traits! { ArraySchema, Schema::Array }
-impl_traits! { NumberSchema, Schema::Number }
-impl_traits! { StringSchema, Schema::String }
-impl_traits! { IntegerSchema, Schema::Integer }
-impl_traits! { EmptySchema, Schema::Empty }
-impl_traits! { ReferenceSchema, Schema::Reference }
+    /// Makes this schema accept `null` in addition to its own type. A schema
+    /// without a type, like one made by `all_of`, becomes an `anyOf` of itself
+    /// and `{"enum": [null]}`. Has no effect on the empty schema and on
+    /// references.
+    pub fn nullable(mut self) -> Schema {
+        if let Schema::Combinator(_) = self {
+            let null = CombinatorSchema {
+                enum_values: Some(vec![Value::Null]),
+                ..CombinatorSchema::default()
+            };
+            return any_of(vec![self, Schema::from(null)]);
+        }
+        self.set_nullable(true);
+        self
+    }
 
-impl SchemaBase for Schema {
-    #[doc(hidden)]
-    fn validate_inner<'json>(
-        &self,
-        ctx: &Context,
-        value: &'json Value,
-        errors: &mut Vec<ValidationError<'json>>,
-    ) {
+    pub(crate) fn set_nullable(&mut self, nullable: bool) {
         use self::Schema::*;
         match *self {
-            Boolean(ref s) => s.validate_inner(ctx, value, errors),
-            Object(ref s) => s.validate_inner(ctx, value, errors),
-            Array(ref s) => s.validate_inner(ctx, value, errors),
-            Number(ref s) => s.validate_inner(ctx, value, errors),
-            String(ref s) => s.validate_inner(ctx, value, errors),
-            Integer(ref s) => s.validate_inner(ctx, value, errors),
-            Empty(ref s) => s.validate_inner(ctx, value, errors),
-            Reference(ref s) => s.validate_inner(ctx, value, errors),
+            Boolean(ref mut s) => s.nullable = nullable,
+            Object(ref mut s) => s.nullable = nullable,
+            Array(ref mut s) => s.nullable = nullable,
+            Number(ref mut s) => s.nullable = nullable,
+            String(ref mut s) => s.nullable = nullable,
+            Integer(ref mut s) => s.nullable = nullable,
+            Combinator(_) | Empty(_) | Reference(_) | Custom(_) => {}
+            Shared(ref mut s) => Arc::make_mut(s).set_nullable(nullable),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::fs::File;
+    /// Remembers that the definitions were read from `$defs`, so they're written
+    /// back under that keyword.
+    fn set_dollar_defs(&mut self, dollar_defs: bool) {
+        use self::Schema::*;
+        match *self {
+            Boolean(ref mut s) => s.dollar_defs = dollar_defs,
+            Object(ref mut s) => s.dollar_defs = dollar_defs,
+            Array(ref mut s) => s.dollar_defs = dollar_defs,
+            Number(ref mut s) => s.dollar_defs = dollar_defs,
+            String(ref mut s) => s.dollar_defs = dollar_defs,
+            Integer(ref mut s) => s.dollar_defs = dollar_defs,
+            Combinator(ref mut s) => s.dollar_defs = dollar_defs,
+            Reference(ref mut s) => s.dollar_defs = dollar_defs,
+            Empty(_) | Custom(_) => {}
+            Shared(ref mut s) => Arc::make_mut(s).set_dollar_defs(dollar_defs),
+        }
+    }
 
-    use serde_json::{self, Value};
+    /// The keyword the definitions of this schema are written under, `$defs` if
+    /// they were read from it and `definitions` otherwise.
+    pub(crate) fn definitions_keyword(&self) -> &'static str {
+        use self::Schema::*;
+        let dollar_defs = match *self {
+            Boolean(ref s) => s.dollar_defs,
+            Object(ref s) => s.dollar_defs,
+            Array(ref s) => s.dollar_defs,
+            Number(ref s) => s.dollar_defs,
+            String(ref s) => s.dollar_defs,
+            Integer(ref s) => s.dollar_defs,
+            Combinator(ref s) => s.dollar_defs,
+            Reference(ref s) => s.dollar_defs,
+            Empty(_) | Custom(_) => false,
+            Shared(ref s) => return s.definitions_keyword(),
+        };
+        if dollar_defs {
+            "$defs"
+        } else {
+            "definitions"
+        }
+    }
 
-    use super::Schema;
+    /// The identifier of this schema, given by `$id` (or `id` in draft 4).
+    pub fn id(&self) -> Option<&str> {
+        use self::Schema::*;
+        let id = match *self {
+            Boolean(ref s) => &s.id,
+            Object(ref s) => &s.id,
+            Array(ref s) => &s.id,
+            Number(ref s) => &s.id,
+            String(ref s) => &s.id,
+            Integer(ref s) => &s.id,
+            Combinator(ref s) => &s.id,
+            Empty(ref s) => &s.id,
+            Reference(_) | Custom(_) => return None,
+            Shared(ref s) => return s.id(),
+        };
+        id.as_ref().map(|id| id.as_str())
+    }
+
+    /// The `$id` of this schema as a URL, if it has one and it's absolute.
+    pub fn id_url(&self) -> Option<Url> {
+        self.id().and_then(|id| Url::parse(id).ok())
+    }
+
+    /// The object schema, if this is one.
+    pub fn as_object(&self) -> Option<&ObjectSchema> {
+        match *self {
+            Schema::Object(ref s) => Some(s),
+            Schema::Shared(ref s) => s.as_object(),
+            _ => None,
+        }
+    }
+
+    /// The string schema, if this is one.
+    pub fn as_string(&self) -> Option<&StringSchema> {
+        match *self {
+            Schema::String(ref s) => Some(s),
+            Schema::Shared(ref s) => s.as_string(),
+            _ => None,
+        }
+    }
+
+    /// The number schema, if this is one.
+    pub fn as_number(&self) -> Option<&NumberSchema> {
+        match *self {
+            Schema::Number(ref s) => Some(s),
+            Schema::Shared(ref s) => s.as_number(),
+            _ => None,
+        }
+    }
+
+    /// The integer schema, if this is one.
+    pub fn as_integer(&self) -> Option<&IntegerSchema> {
+        match *self {
+            Schema::Integer(ref s) => Some(s),
+            Schema::Shared(ref s) => s.as_integer(),
+            _ => None,
+        }
+    }
+
+    /// The boolean schema, if this is one.
+    pub fn as_boolean(&self) -> Option<&BooleanSchema> {
+        match *self {
+            Schema::Boolean(ref s) => Some(s),
+            Schema::Shared(ref s) => s.as_boolean(),
+            _ => None,
+        }
+    }
+
+    /// The values of `enum` or `const` with the name of the keyword, if this
+    /// schema has one. `const` wins if both are given.
+    pub(crate) fn allowed_values(&self) -> Option<(&'static str, Vec<&Value>)> {
+        use self::Schema::*;
+        let (enum_values, const_value) = match *self {
+            Boolean(ref s) => (&s.enum_values, &s.const_value),
+            Object(ref s) => (&s.enum_values, &s.const_value),
+            Array(ref s) => (&s.enum_values, &s.const_value),
+            Number(ref s) => (&s.enum_values, &s.const_value),
+            String(ref s) => (&s.enum_values, &s.const_value),
+            Integer(ref s) => (&s.enum_values, &s.const_value),
+            Combinator(ref s) => (&s.enum_values, &s.const_value),
+            Shared(ref s) => return s.allowed_values(),
+            Empty(_) | Reference(_) | Custom(_) => return None,
+        };
+        match (enum_values, const_value) {
+            (_, Some(value)) => Some(("const", vec![value])),
+            (Some(values), None) => Some(("enum", values.iter().collect())),
+            (None, None) => None,
+        }
+    }
+
+    /// The value of `default`, looking through references to `root`.
+    pub(crate) fn default_value<'s>(&'s self, root: &'s Schema) -> Option<&'s Value> {
+        use self::Schema::*;
+        match *self {
+            Boolean(ref s) => s.default.as_ref(),
+            Object(ref s) => s.default.as_ref(),
+            Array(ref s) => s.default.as_ref(),
+            Number(ref s) => s.default.as_ref(),
+            String(ref s) => s.default.as_ref(),
+            Integer(ref s) => s.default.as_ref(),
+            Combinator(ref s) => s.default.as_ref(),
+            Empty(ref s) => s.default.as_ref(),
+            Reference(ref s) => s.resolve(root)?.default_value(root),
+            Shared(ref s) => s.default_value(root),
+            Custom(_) => None,
+        }
+    }
+
+    /// The values of `default` and `examples`, the values the schema author gave
+    /// as typical.
+    pub(crate) fn examples(&self) -> Vec<&Value> {
+        use self::Schema::*;
+        let (default, examples) = match *self {
+            Boolean(ref s) => (&s.default, &s.examples),
+            Object(ref s) => (&s.default, &s.examples),
+            Array(ref s) => (&s.default, &s.examples),
+            Number(ref s) => (&s.default, &s.examples),
+            String(ref s) => (&s.default, &s.examples),
+            Integer(ref s) => (&s.default, &s.examples),
+            Combinator(ref s) => (&s.default, &s.examples),
+            Empty(ref s) => (&s.default, &s.examples),
+            Shared(ref s) => return s.examples(),
+            Reference(_) | Custom(_) => return vec![],
+        };
+        default.iter().chain(examples.iter().flatten()).collect()
+    }
+
+    /// The values of `examples` and `x-counterexamples`, the values the schema
+    /// should accept and reject, see `Schema::self_test`.
+    pub(crate) fn test_vectors(&self) -> (&[Value], &[Value]) {
+        use self::Schema::*;
+        let (examples, counterexamples) = match *self {
+            Boolean(ref s) => (&s.examples, &s.counterexamples),
+            Object(ref s) => (&s.examples, &s.counterexamples),
+            Array(ref s) => (&s.examples, &s.counterexamples),
+            Number(ref s) => (&s.examples, &s.counterexamples),
+            String(ref s) => (&s.examples, &s.counterexamples),
+            Integer(ref s) => (&s.examples, &s.counterexamples),
+            Combinator(ref s) => (&s.examples, &s.counterexamples),
+            Empty(ref s) => (&s.examples, &s.counterexamples),
+            Shared(ref s) => return s.test_vectors(),
+            Reference(_) | Custom(_) => return (&[], &[]),
+        };
+        (
+            examples.as_ref().map_or(&[], |e| &e[..]),
+            counterexamples.as_ref().map_or(&[], |c| &c[..]),
+        )
+    }
+
+    /// The `title` of this schema.
+    pub fn title(&self) -> Option<&str> {
+        use self::Schema::*;
+        let title = match *self {
+            Boolean(ref s) => &s.title,
+            Object(ref s) => &s.title,
+            Array(ref s) => &s.title,
+            Number(ref s) => &s.title,
+            String(ref s) => &s.title,
+            Integer(ref s) => &s.title,
+            Combinator(ref s) => &s.title,
+            Empty(ref s) => &s.title,
+            Reference(ref s) => return s.siblings.as_ref().and_then(|s| s.title()),
+            Custom(_) => return None,
+            Shared(ref s) => return s.title(),
+        };
+        title.as_ref().map(|title| title.as_str())
+    }
+
+    /// The `description` of this schema.
+    pub(crate) fn description(&self) -> Option<&str> {
+        use self::Schema::*;
+        let description = match *self {
+            Boolean(ref s) => &s.description,
+            Object(ref s) => &s.description,
+            Array(ref s) => &s.description,
+            Number(ref s) => &s.description,
+            String(ref s) => &s.description,
+            Integer(ref s) => &s.description,
+            Combinator(ref s) => &s.description,
+            Empty(ref s) => &s.description,
+            Reference(ref s) => return s.siblings.as_ref().and_then(|s| s.description()),
+            Custom(_) => return None,
+            Shared(ref s) => return s.description(),
+        };
+        description.as_ref().map(|description| description.as_str())
+    }
+
+    /// Whether this schema is marked `deprecated`.
+    pub(crate) fn is_deprecated(&self) -> bool {
+        use self::Schema::*;
+        let deprecated = match *self {
+            Boolean(ref s) => s.deprecated,
+            Object(ref s) => s.deprecated,
+            Array(ref s) => s.deprecated,
+            Number(ref s) => s.deprecated,
+            String(ref s) => s.deprecated,
+            Integer(ref s) => s.deprecated,
+            Combinator(ref s) => s.deprecated,
+            Empty(ref s) => s.deprecated,
+            Reference(ref s) => return s.siblings.as_ref().is_some_and(|s| s.is_deprecated()),
+            Custom(_) => return false,
+            Shared(ref s) => return s.is_deprecated(),
+        };
+        deprecated == Some(true)
+    }
+
+    /// Whether this schema is a document that only holds `definitions` for other
+    /// schemas to reference, like a library of shared types. Validating a value
+    /// against it accepts any value and reports a
+    /// `WarningKind::DefinitionsOnly` warning if warnings are collected.
+    pub fn is_definitions_only(&self) -> bool {
+        match *self {
+            Schema::Combinator(ref s) => s.is_definitions_only(),
+            Schema::Shared(ref s) => s.is_definitions_only(),
+            _ => false,
+        }
+    }
+
+    /// The schemas of the `definitions` keyword.
+    pub(crate) fn definitions(&self) -> Option<&PropertyMap<Schema>> {
+        use self::Schema::*;
+        match *self {
+            Boolean(ref s) => s.definitions.as_ref(),
+            Object(ref s) => s.definitions.as_ref(),
+            Array(ref s) => s.definitions.as_ref(),
+            Number(ref s) => s.definitions.as_ref(),
+            String(ref s) => s.definitions.as_ref(),
+            Integer(ref s) => s.definitions.as_ref(),
+            Combinator(ref s) => s.definitions.as_ref(),
+            Reference(ref s) => s.definitions.as_ref(),
+            Shared(ref s) => s.definitions(),
+            Empty(_) | Custom(_) => None,
+        }
+    }
+
+    /// Returns the subschema `pointer` points to, like `/definitions/address` or
+    /// `/properties/tags/items`. The empty pointer points to this schema.
+    /// `/$defs/address` and `/definitions/address` point to the same schema,
+    /// whichever keyword the document used.
+    pub fn pointer(&self, pointer: &str) -> Option<&Schema> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+        let tokens: Vec<_> = pointer[1..]
+            .split('/')
+            .map(|t| t.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        let mut tokens = tokens.iter().map(|t| t.as_str());
+        let mut current = self;
+        while let Some(keyword) = tokens.next() {
+            current = current.subschema(keyword, &mut tokens)?;
+        }
+        Some(current)
+    }
+
+    /// Returns the subschema below `keyword`, taking the property name or index
+    /// from `tokens` for keywords that need one.
+    fn subschema<'a, I>(&self, keyword: &str, tokens: &mut I) -> Option<&Schema>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        fn entry<'s>(
+            map: Option<&'s PropertyMap<Schema>>,
+            key: Option<&str>,
+        ) -> Option<&'s Schema> {
+            map?.get(key?)
+        }
+        fn element<'s>(list: &'s Option<Vec<Schema>>, index: Option<&str>) -> Option<&'s Schema> {
+            list.as_ref()?.get(index?.parse::<usize>().ok()?)
+        }
+
+        use self::Schema::*;
+        match (self, keyword) {
+            (_, "definitions") | (_, "$defs") => entry(self.definitions(), tokens.next()),
+            (Object(s), "properties") => entry(s.properties.as_ref(), tokens.next()),
+            (Object(s), "patternProperties") => {
+                entry(s.pattern_properties.as_ref(), tokens.next())
+            }
+            (Object(s), "additionalProperties") => s.additional_schema(),
+            (Array(s), "items") => match s.items.as_ref()? {
+                Items::List(schema) => Some(schema),
+                Items::Tuple(schemas) => schemas.get(tokens.next()?.parse::<usize>().ok()?),
+                Items::Allowed(_) => None,
+            },
+            (Array(s), "prefixItems") => element(&s.prefix_items, tokens.next()),
+            (Array(s), "additionalItems") => match s.additional_items.as_ref()? {
+                AdditionalItems::Schema(schema) => Some(schema),
+                AdditionalItems::Allowed(_) => None,
+            },
+            (Combinator(s), "allOf") => element(&s.all_of, tokens.next()),
+            (Combinator(s), "anyOf") => element(&s.any_of, tokens.next()),
+            (Combinator(s), "oneOf") => element(&s.one_of, tokens.next()),
+            (Combinator(s), "not") => s.not.as_deref(),
+            (Reference(s), _) => s.siblings.as_ref()?.subschema(keyword, tokens),
+            (Shared(s), _) => s.subschema(keyword, tokens),
+            _ => None,
+        }
+    }
+
+    /// The direct subschemas of this schema, each with the pointer from this schema
+    /// to it, like `/properties/name`. The keywords next to a `$ref` are part of
+    /// the reference, so their subschemas are returned instead.
+    pub(crate) fn children_with_pointers(&self) -> Vec<(String, &Schema)> {
+        fn map<'s>(
+            children: &mut Vec<(String, &'s Schema)>,
+            keyword: &str,
+            map: &'s Option<PropertyMap<Schema>>,
+        ) {
+            for (key, schema) in map.iter().flatten() {
+                children.push((format!("/{}/{}", keyword, pointer_token(key)), schema));
+            }
+        }
+        fn list<'s>(
+            children: &mut Vec<(String, &'s Schema)>,
+            keyword: &str,
+            list: Option<&'s Vec<Schema>>,
+        ) {
+            for (i, schema) in list.into_iter().flatten().enumerate() {
+                children.push((format!("/{}/{}", keyword, i), schema));
+            }
+        }
+
+        let mut children = vec![];
+        match *self {
+            Schema::Boolean(ref s) => map(&mut children, self.definitions_keyword(), &s.definitions),
+            Schema::Number(ref s) => map(&mut children, self.definitions_keyword(), &s.definitions),
+            Schema::String(ref s) => map(&mut children, self.definitions_keyword(), &s.definitions),
+            Schema::Integer(ref s) => map(&mut children, self.definitions_keyword(), &s.definitions),
+            Schema::Reference(ref s) => {
+                map(&mut children, self.definitions_keyword(), &s.definitions);
+                if let Some(ref siblings) = s.siblings {
+                    children.extend(siblings.children_with_pointers());
+                }
+            }
+            Schema::Object(ref s) => {
+                map(&mut children, self.definitions_keyword(), &s.definitions);
+                map(&mut children, "properties", &s.properties);
+                map(&mut children, "patternProperties", &s.pattern_properties);
+                if let Some(schema) = s.additional_schema() {
+                    children.push(("/additionalProperties".into(), schema));
+                }
+            }
+            Schema::Array(ref s) => {
+                map(&mut children, self.definitions_keyword(), &s.definitions);
+                list(&mut children, "prefixItems", s.prefix_items.as_ref());
+                match s.items {
+                    Some(Items::List(ref schema)) => children.push(("/items".into(), schema)),
+                    Some(Items::Tuple(ref schemas)) => list(&mut children, "items", Some(schemas)),
+                    Some(Items::Allowed(_)) | None => {}
+                }
+                if let Some(AdditionalItems::Schema(ref schema)) = s.additional_items {
+                    children.push(("/additionalItems".into(), schema));
+                }
+            }
+            Schema::Combinator(ref s) => {
+                map(&mut children, self.definitions_keyword(), &s.definitions);
+                list(&mut children, "allOf", s.all_of.as_ref());
+                list(&mut children, "anyOf", s.any_of.as_ref());
+                list(&mut children, "oneOf", s.one_of.as_ref());
+                if let Some(ref schema) = s.not {
+                    children.push(("/not".into(), schema));
+                }
+            }
+            Schema::Shared(ref s) => return s.children_with_pointers(),
+            Schema::Empty(_) | Schema::Custom(_) => {}
+        }
+        children
+    }
+
+    /// The direct subschemas of this schema.
+    pub(crate) fn children(&self) -> Vec<&Schema> {
+        match *self {
+            Schema::Shared(ref s) => s.children(),
+            _ => children!(self,),
+        }
+    }
+
+    /// The direct subschemas of this schema. A shared schema can't be modified, so
+    /// none are returned for it.
+    pub(crate) fn children_mut(&mut self) -> Vec<&mut Schema> {
+        children!(self, mut)
+    }
+
+    /// Counts the empty schemas (`{}` or schemas with only annotations) in this
+    /// schema, including itself. They accept every value, so inside a bigger
+    /// schema they often point to a mistake.
+    pub fn count_empty_subschemas(&self) -> usize {
+        let own = match *self {
+            Schema::Empty(_) => 1,
+            Schema::Shared(ref s) => return s.count_empty_subschemas(),
+            _ => 0,
+        };
+        own + self
+            .children()
+            .iter()
+            .map(|s| s.count_empty_subschemas())
+            .sum::<usize>()
+    }
+
+    /// Applies the keywords all kinds of schemas share: the annotation keywords,
+    /// `enum` and `const`.
+    fn validate_common<'json>(
+        &self,
+        ctx: &Context,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        macro_rules! annotations {
+            ($schema:expr) => {{
+                let schema = $schema;
+                if ctx.collects_annotations() {
+                    if let Some(ref title) = schema.title {
+                        ctx.annotate("title", json!(title));
+                    }
+                    if let Some(ref description) = schema.description {
+                        ctx.annotate("description", json!(description));
+                    }
+                    if let Some(ref default) = schema.default {
+                        ctx.annotate("default", default.clone());
+                    }
+                    if let Some(ref examples) = schema.examples {
+                        ctx.annotate("examples", json!(examples));
+                    }
+                    if let Some(deprecated) = schema.deprecated {
+                        ctx.annotate("deprecated", json!(deprecated));
+                    }
+                }
+                if schema.deprecated == Some(true) {
+                    ctx.deprecated(&schema.description, &schema.removed_in, value, errors);
+                }
+            }};
+        }
+
+        macro_rules! validate_common {
+            ($schema:expr) => {{
+                let schema = $schema;
+                annotations!(schema);
+                let equal = |allowed: &Value| json_equal(allowed, value);
+                let allowed = (&schema.enum_values, &schema.const_value);
+                validate_allowed(ctx, allowed, &equal, value.as_str(), value, errors);
+            }};
+        }
+
+        use self::Schema::*;
+        match *self {
+            Boolean(ref s) => validate_common!(s),
+            Object(ref s) => validate_common!(s),
+            Array(ref s) => validate_common!(s),
+            Number(ref s) => validate_common!(s),
+            String(ref s) => validate_common!(s),
+            Integer(ref s) => validate_common!(s),
+            Combinator(ref s) => validate_common!(s),
+            Empty(ref s) => annotations!(s),
+            Reference(_) | Shared(_) | Custom(_) => {}
+        }
+    }
+
+    /// Kicks off validation for this schema.
+    pub fn validate<'json>(&self, value: &'json Value) -> Result<(), ValidationErrors<'json>> {
+        self.validate_start(value, self)
+    }
+
+    /// Validates a JSON value with this schema, a part of `root`, which local
+    /// references resolve against. Like `validate`, this needs no import of
+    /// `SchemaBase`:
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate serde_json;
+    /// # extern crate json_schema;
+    /// use json_schema::Schema;
+    ///
+    /// # fn main() {
+    /// let root: Schema = r##"{
+    ///     "definitions": {"id": {"type": "integer"}},
+    ///     "type": "object",
+    ///     "properties": {"ids": {"type": "array", "items": {"$ref": "#/definitions/id"}}}
+    /// }"##
+    /// .parse()
+    /// .unwrap();
+    /// let ids = root.pointer("/properties/ids").unwrap();
+    /// assert!(ids.validate_start(&json!([1, 2]), &root).is_ok());
+    /// assert!(ids.validate_start(&json!(["1"]), &root).is_err());
+    /// # }
+    /// ```
+    pub fn validate_start<'json>(
+        &self,
+        value: &'json Value,
+        root: &Schema,
+    ) -> Result<(), ValidationErrors<'json>> {
+        SchemaBase::validate_start(self, value, root)
+    }
+
+    /// Parses `text` as JSON and validates it. JSON that doesn't parse is an
+    /// `ErrorKind::Serde` error, a value the schema rejects an
+    /// `ErrorKind::Invalid` error, whose errors don't borrow the parsed value. A
+    /// leading byte-order mark and trailing whitespace are accepted, anything
+    /// else after the value is an error.
+    pub fn validate_str(&self, text: &str) -> errors::Result<()> {
+        let value = serde_json::from_str(strip_bom(text))?;
+        self.validate_parsed(&value, &ValidateOptions::default())
+    }
+
+    /// Like `validate_str`, for bytes that should be JSON in UTF-8. Invalid UTF-8
+    /// is an `ErrorKind::Serde` error too.
+    pub fn validate_bytes(&self, bytes: &[u8]) -> errors::Result<()> {
+        let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+        let value = serde_json::from_slice(bytes)?;
+        self.validate_parsed(&value, &ValidateOptions::default())
+    }
+
+    /// Like `validate_str`, reading the JSON from `reader`.
+    pub fn validate_reader<R: Read>(&self, reader: R) -> errors::Result<()> {
+        let value = serde_json::from_reader(skip_bom(reader)?)?;
+        self.validate_parsed(&value, &ValidateOptions::default())
+    }
+
+    /// Like `validate_str`, with the given options. Returns a result for each
+    /// JSON text of `text` if `options.multiple_documents` is set, or else for
+    /// the single one. A text that doesn't parse ends the stream with an
+    /// `ErrorKind::Serde` error.
+    pub fn validate_str_with(
+        &self,
+        text: &str,
+        options: &ValidateOptions,
+    ) -> Vec<errors::Result<()>> {
+        let text = strip_bom(text);
+        if options.multiple_documents {
+            let documents = serde_json::Deserializer::from_str(text).into_iter();
+            self.validate_documents(documents, options)
+        } else {
+            self.validate_documents(Some(serde_json::from_str(text)).into_iter(), options)
+        }
+    }
+
+    /// Like `validate_str_with`, reading the JSON from `reader`.
+    pub fn validate_reader_with<R: Read>(
+        &self,
+        reader: R,
+        options: &ValidateOptions,
+    ) -> Vec<errors::Result<()>> {
+        let reader = match skip_bom(reader) {
+            Ok(reader) => reader,
+            Err(error) => return vec![Err(error.into())],
+        };
+        if options.multiple_documents {
+            let documents = serde_json::Deserializer::from_reader(reader).into_iter();
+            self.validate_documents(documents, options)
+        } else {
+            self.validate_documents(Some(serde_json::from_reader(reader)).into_iter(), options)
+        }
+    }
+
+    fn validate_documents<I>(
+        &self,
+        documents: I,
+        options: &ValidateOptions,
+    ) -> Vec<errors::Result<()>>
+    where
+        I: Iterator<Item = serde_json::Result<Value>>,
+    {
+        let mut results = vec![];
+        for document in documents {
+            match document {
+                Ok(value) => results.push(self.validate_parsed(&value, options)),
+                Err(error) => {
+                    results.push(Err(error.into()));
+                    break;
+                }
+            }
+        }
+        results
+    }
+
+    pub(crate) fn validate_parsed(
+        &self,
+        value: &Value,
+        options: &ValidateOptions,
+    ) -> errors::Result<()> {
+        let output = self.validate_with(value, options);
+        if output.errors.is_empty() {
+            Ok(())
+        } else {
+            let errors = output.errors.into_iter().map(RecordedError::from).collect();
+            Err(ErrorKind::Invalid(errors).into())
+        }
+    }
+
+    /// Validates `instance` against the subschema at `schema_pointer`. References in
+    /// the subschema are resolved against this schema, and the paths of the errors
+    /// are relative to `instance` and the subschema.
+    pub fn validate_at<'json>(
+        &self,
+        schema_pointer: &str,
+        instance: &'json Value,
+    ) -> Result<(), ValidationErrors<'json>> {
+        match self.pointer(schema_pointer) {
+            Some(schema) => schema.validate_start(instance, self),
+            None => Err(ValidationErrors(vec![ValidationError::new(
+                ErrorKind::UnresolvedPointer(schema_pointer.into()),
+                instance,
+            )])),
+        }
+    }
+
+    /// Validates `value`, returning the errors together with the annotations
+    /// (`title`, `description`, `default`, `examples`, `deprecated` and the matching
+    /// branches of `anyOf`/`oneOf`) of every subschema that matched, if `annotations`
+    /// is set.
+    pub fn validate_with_output<'json>(
+        &self,
+        value: &'json Value,
+        annotations: bool,
+    ) -> ValidationOutput<'json> {
+        let options = ValidateOptions {
+            annotations,
+            ..ValidateOptions::default()
+        };
+        self.validate_with(value, &options)
+    }
+
+    /// Validates `value` with the given options. Annotations and warnings are only
+    /// collected if the options ask for them.
+    pub fn validate_with<'json>(
+        &self,
+        value: &'json Value,
+        options: &ValidateOptions,
+    ) -> ValidationOutput<'json> {
+        self.validate_in(value, options, None)
+    }
+
+    /// Validates `value`, looking up references to other documents in `store`.
+    pub(crate) fn validate_in<'json>(
+        &self,
+        value: &'json Value,
+        options: &ValidateOptions,
+        store: Option<&SchemaStore>,
+    ) -> ValidationOutput<'json> {
+        let collected = RefCell::<Collected>::default();
+        if let Some(ref limits) = options.audit {
+            let issues = audit_instance(value, limits).into_iter();
+            collected
+                .borrow_mut()
+                .warnings
+                .extend(issues.map(|issue| ValidationWarning {
+                    instance_path: issue.pointer().into(),
+                    schema_path: String::new(),
+                    kind: WarningKind::UnsupportedInput(issue),
+                }));
+        }
+        let mut errors = vec![];
+        let started = Some(Instant::now()).filter(|_| options.collect_timing);
+        let deadline = Deadline::new(options.timeout);
+        {
+            let scratch = Scratch::default();
+            let mut context = Context::new(self, options, &collected, &scratch, &deadline);
+            context.store = store;
+            self.validate_inner(&context, value, &mut errors);
+            context.finish(value, &mut errors);
+        }
+        let Collected {
+            annotations,
+            warnings,
+            sampled,
+            skipped,
+            timing,
+            ..
+        } = collected.into_inner();
+        ValidationOutput {
+            errors,
+            annotations,
+            warnings,
+            incomplete: deadline.aborted(),
+            peak_tracked_bytes: deadline.peak_memory(),
+            sampled,
+            skipped,
+            timing: started.map(|started| Timing {
+                total: started.elapsed(),
+                ..timing
+            }),
+        }
+    }
+
+    /// Whether `value` is valid against this subschema of `root`, with the
+    /// references resolved against `root`. Stops at the first error.
+    pub(crate) fn accepts_within(&self, root: &Schema, value: &Value) -> bool {
+        let options = ValidateOptions {
+            max_errors: Some(1),
+            ..ValidateOptions::default()
+        };
+        self.validate_within_with(root, value, &options).is_empty()
+    }
+
+    /// Validates `value` against this subschema of `root`, with the references
+    /// resolved against `root`. The schema paths of the errors are relative to
+    /// this subschema.
+    pub(crate) fn validate_within<'json>(
+        &self,
+        root: &Schema,
+        value: &'json Value,
+    ) -> Vec<ValidationError<'json>> {
+        self.validate_within_with(root, value, &ValidateOptions::default())
+    }
+
+    fn validate_within_with<'json>(
+        &self,
+        root: &Schema,
+        value: &'json Value,
+        options: &ValidateOptions,
+    ) -> Vec<ValidationError<'json>> {
+        let collected = RefCell::default();
+        let scratch = Scratch::default();
+        let deadline = Deadline::new(None);
+        let context = Context::new(root, options, &collected, &scratch, &deadline);
+        let mut errors = vec![];
+        self.validate_inner(&context, value, &mut errors);
+        errors
+    }
+
+    /// Resolves the references of this schema and reports how, see
+    /// `SchemaStore::resolve_references`. References to other documents are
+    /// unresolved without a store.
+    pub fn resolve_references(&self) -> ResolutionReport {
+        resolve_all(self, None)
+    }
+}
+
+impl<'de> Deserialize<'de> for Schema {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Schema, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut value = Value::deserialize(deserializer)?;
+        if !value.is_object() {
+            return Err(not_an_object(&value));
+        }
+        let dollar_defs = merge_dollar_defs(&mut value)?;
+        // `$schema` only names the dialect, the draft to follow comes from the options
+        if let Some(object) = value.as_object_mut() {
+            object.remove("$schema");
+        }
+        if value.get("$ref").is_some() {
+            let mut schema = Schema::Reference(deserialize_reference(value)?);
+            schema.set_dollar_defs(dollar_defs);
+            return Ok(schema);
+        }
+        let (type_name, nullable) = match value.as_object_mut().and_then(|o| o.remove("type")) {
+            None => (None, false),
+            Some(Value::String(name)) => (Some(name), false),
+            Some(Value::Array(types)) => {
+                let names: Vec<_> = types.iter().filter_map(Value::as_str).collect();
+                match (names.len(), types.len()) {
+                    (2, 2) if names[0] == "null" => (Some(names[1].to_string()), true),
+                    (2, 2) if names[1] == "null" => (Some(names[0].to_string()), true),
+                    _ => {
+                        return Err(de::Error::custom(format!(
+                            "unsupported type list `{}`: only `[<type>, \"null\"]` is supported",
+                            Value::Array(types)
+                        )))
+                    }
+                }
+            }
+            Some(other) => {
+                return Err(de::Error::invalid_type(
+                    de::Unexpected::Other(&other.to_string()),
+                    &"a type name or a list of type names",
+                ))
+            }
+        };
+
+        let mut schema = match type_name.as_deref() {
+            Some("boolean") => Schema::Boolean(from_value(value)?),
+            Some("object") => {
+                let mut schema: ObjectSchema = from_value(value)?;
+                schema.compile_key_patterns();
+                Schema::Object(schema)
+            }
+            Some("array") => Schema::Array(from_value(value)?),
+            Some("number") => Schema::Number(from_value(value)?),
+            Some("string") => {
+                let schema: StringSchema = from_value(value)?;
+                schema.check_format_bounds().map_err(de::Error::custom)?;
+                Schema::String(schema)
+            }
+            Some("integer") => Schema::Integer(from_value(value)?),
+            Some(other) => return Err(de::Error::unknown_variant(other, TYPES)),
+            None if COMBINATORS.iter().any(|&c| value.get(c).is_some()) => {
+                Schema::Combinator(from_value(value)?)
+            }
+            None => {
+                let unknown: Vec<_> = value
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|o| o.keys())
+                    .filter(|k| !EMPTY_KEYWORDS.contains(&k.as_str()))
+                    .map(|k| format!("`{}`", k))
+                    .collect();
+                if !unknown.is_empty() {
+                    return Err(de::Error::custom(format!(
+                        "schema without `type` can't use {}, expected one of the types {}",
+                        unknown.join(", "),
+                        TYPES.join(", ")
+                    )));
+                }
+                Schema::Empty(from_value(value)?)
+            }
+        };
+        schema.set_nullable(nullable);
+        schema.set_dollar_defs(dollar_defs);
+        Ok(schema)
+    }
+}
+
+/// The UTF-8 encoding of the byte-order mark.
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Buffers `reader`, skipping a leading byte-order mark.
+fn skip_bom<R: Read>(reader: R) -> serde_json::Result<BufReader<R>> {
+    let mut reader = BufReader::new(reader);
+    if reader
+        .fill_buf()
+        .map_err(serde_json::Error::io)?
+        .starts_with(UTF8_BOM)
+    {
+        reader.consume(UTF8_BOM.len());
+    }
+    Ok(reader)
+}
+
+/// The error for a schema that isn't a JSON object.
+fn not_an_object<E: de::Error>(value: &Value) -> E {
+    de::Error::invalid_type(
+        de::Unexpected::Other(&value.to_string()),
+        &"a schema object",
+    )
+}
+
+/// Moves the entries of `$defs`, the name of `definitions` since draft 2019-09,
+/// into `definitions`, so both keywords can be used in one document. A name
+/// given under both keywords is an error. Returns whether the definitions
+/// should be written back under `$defs`, which is the case if the document
+/// only used that keyword.
+fn merge_dollar_defs<E>(value: &mut Value) -> ::std::result::Result<bool, E>
+where
+    E: de::Error,
+{
+    let object = match value.as_object_mut() {
+        Some(object) => object,
+        None => return Err(not_an_object(value)),
+    };
+    let defs = match object.remove("$defs") {
+        Some(Value::Object(defs)) => defs,
+        Some(other) => {
+            return Err(de::Error::invalid_type(
+                de::Unexpected::Other(&other.to_string()),
+                &"a map of schemas",
+            ))
+        }
+        None => return Ok(false),
+    };
+    match object.get_mut("definitions") {
+        Some(&mut Value::Object(ref mut definitions)) => {
+            for (name, schema) in defs {
+                if definitions.contains_key(&name) {
+                    return Err(de::Error::custom(format!(
+                        "the definition `{}` is given in both `definitions` and `$defs`",
+                        name
+                    )));
+                }
+                definitions.insert(name, schema);
+            }
+            Ok(false)
+        }
+        // not a map, which fails parsing `definitions`
+        Some(_) => Ok(false),
+        None => {
+            object.insert("definitions".into(), Value::Object(defs));
+            Ok(true)
+        }
+    }
+}
+
+/// Splits a schema with `$ref` into the reference and a schema made of the
+/// keywords next to it.
+fn deserialize_reference<E>(mut value: Value) -> ::std::result::Result<ReferenceSchema, E>
+where
+    E: de::Error,
+{
+    let object = match value.as_object_mut() {
+        Some(object) => object,
+        None => return Err(not_an_object(&value)),
+    };
+    let mut reference = Map::new();
+    for keyword in &["$ref", "definitions"] {
+        if let Some(v) = object.remove(*keyword) {
+            reference.insert(keyword.to_string(), v);
+        }
+    }
+    let untyped = !object.contains_key("type");
+    let mut schema: ReferenceSchema = from_value(Value::Object(reference))?;
+    if object.is_empty() {
+        return Ok(schema);
+    }
+    let siblings = match from_value(value.clone()) {
+        Ok(siblings) => siblings,
+        // `{"$ref": ..., "minLength": 3}` refines the referenced schema without
+        // repeating its type
+        Err(error) => {
+            if !untyped {
+                return Err(error);
+            }
+            schema.untyped_siblings = true;
+            typed_siblings(&value).ok_or(error)?
+        }
+    };
+    schema.siblings = Some(Box::new(siblings));
+    Ok(schema)
+}
+
+/// Reads keywords without a `type`, like `{"minLength": 3}`, as a schema of
+/// the type they belong to.
+fn typed_siblings(siblings: &Value) -> Option<Schema> {
+    let keywords = siblings.as_object()?;
+    ["string", "number", "array", "object"].iter().find_map(|&name| {
+        let mut typed = keywords.clone();
+        typed.insert("type".into(), name.into());
+        let schema: Schema = serde_json::from_value(Value::Object(typed)).ok()?;
+        // object schemas don't reject keywords of other types, they drop them
+        let written = serde_json::to_value(&schema).ok()?;
+        if keywords.keys().all(|k| written.get(k).is_some()) {
+            Some(schema)
+        } else {
+            None
+        }
+    })
+}
+
+fn from_value<T, E>(value: Value) -> ::std::result::Result<T, E>
+where
+    T: DeserializeOwned,
+    E: de::Error,
+{
+    serde_json::from_value(value).map_err(E::custom)
+}
+
+impl Serialize for Schema {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use self::Schema::*;
+        let value = match *self {
+            Boolean(ref s) => serde_json::to_value(s),
+            Object(ref s) => serde_json::to_value(s),
+            Array(ref s) => serde_json::to_value(s),
+            Number(ref s) => serde_json::to_value(s),
+            String(ref s) => serde_json::to_value(s),
+            Integer(ref s) => serde_json::to_value(s),
+            Reference(ref s) => serde_json::to_value(s).and_then(|mut value| {
+                if let (Some(siblings), Some(object)) = (&s.siblings, value.as_object_mut()) {
+                    if let Value::Object(mut siblings) = serde_json::to_value(siblings)? {
+                        if s.untyped_siblings {
+                            siblings.remove("type");
+                        }
+                        object.extend(siblings);
+                    }
+                }
+                Ok(value)
+            }),
+            Combinator(ref s) => serde_json::to_value(s),
+            Empty(ref s) => serde_json::to_value(s),
+            Shared(ref s) => return s.serialize(serializer),
+            Custom(_) => return Err(ser::Error::custom("custom schemas can't be serialized")),
+        };
+        let mut value = value.map_err(ser::Error::custom)?;
+        if let (Some(name), Some(object)) = (self.type_name(), value.as_object_mut()) {
+            let type_value = if self.is_nullable() {
+                json!([name, "null"])
+            } else {
+                json!(name)
+            };
+            object.insert("type".into(), type_value);
+        }
+        if let (Some(object), "$defs") = (value.as_object_mut(), self.definitions_keyword()) {
+            if let Some(definitions) = object.remove("definitions") {
+                object.insert("$defs".into(), definitions);
+            }
+        }
+        value.serialize(serializer)
+    }
+}
+
+impl FromStr for Schema {
+    type Err = Error;
+    fn from_str(s: &str) -> ::std::result::Result<Schema, Self::Err> {
+        let value: Value = serde_json::from_str(s)?;
+        if let Some(keys) = non_schema_keys(&value) {
+            bail!(ErrorKind::NotASchema(keys));
+        }
+        serde_json::from_value(value).map_err(From::from)
+    }
+}
+
+macro_rules! impl_traits {
+    ($name:ty, $schema:path) => (
+        impl  From<$name> for Schema {
+            fn from(value: $name) -> Schema {
+                $schema(value)
+            }
+        }
+    )
+}
+
+impl_traits! { BooleanSchema, Schema::Boolean }
+impl_traits! { ObjectSchema, Schema::Object }
+impl_traits! { ArraySchema, Schema::Array }
+impl_traits! { NumberSchema, Schema::Number }
+impl_traits! { StringSchema, Schema::String }
+impl_traits! { IntegerSchema, Schema::Integer }
+impl_traits! { EmptySchema, Schema::Empty }
+impl_traits! { ReferenceSchema, Schema::Reference }
+impl_traits! { CombinatorSchema, Schema::Combinator }
+
+/// Checks `enum` and `const`, given as `(enum, const)`. `equal` tells whether an
+/// allowed value equals the value, whose text is `text` if it's a string. The
+/// errors are about `node`.
+pub(crate) fn validate_allowed<'json>(
+    ctx: &Context,
+    (enum_values, const_value): (&Option<Vec<Value>>, &Option<Value>),
+    equal: &dyn Fn(&Value) -> bool,
+    text: Option<&str>,
+    node: &'json Value,
+    errors: &mut Vec<ValidationError<'json>>,
+) {
+    if let Some(ref allowed) = *enum_values {
+        if allowed.is_empty() {
+            ctx.warn(WarningKind::EmptyEnum);
+        }
+        if !allowed.iter().any(equal) {
+            errors.push(ValidationError::new(
+                ErrorKind::NotInEnum {
+                    allowed: allowed.clone(),
+                    suggestion: text
+                        .and_then(|text| closest_match(text, allowed))
+                        .map(Into::into),
+                },
+                node,
+            ));
+        }
+    }
+    if let Some(ref expected) = *const_value {
+        if !equal(expected) {
+            errors.push(ValidationError::new(
+                ErrorKind::ConstMismatch {
+                    expected: expected.clone(),
+                },
+                node,
+            ));
+        }
+    }
+}
+
+impl SchemaBase for Schema {
+    #[doc(hidden)]
+    fn validate_inner<'json>(
+        &self,
+        ctx: &Context,
+        value: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        if ctx.deadline.check() || ctx.deadline.out_of_memory.get() {
+            return;
+        }
+        // outside of trials `errors` holds every error reported so far, and the
+        // errors of the subschemas left out could only come after them
+        if !ctx.trial && ctx.has_enough_errors(errors) {
+            return;
+        }
+        if ctx.too_deep() {
+            let mut error = ValidationError::new(ErrorKind::DepthLimitExceeded, value);
+            ctx.locate(::std::slice::from_mut(&mut error));
+            errors.push(error);
+            return;
+        }
+
+        let start = errors.len();
+        let tracked = ctx.tracked_errors();
+        let mark = ctx.mark();
+        let started = ctx.options.stats.as_ref().map(|_| Instant::now());
+
+        // the type specific keywords, starting with the type itself, are checked
+        // before `enum` and `const`, which also apply to `null` if the schema is
+        // nullable
+        use self::Schema::*;
+        let typed = !(value.is_null() && self.is_nullable());
+        if typed {
+            match *self {
+                Boolean(ref s) => s.validate_inner(ctx, value, errors),
+                Object(ref s) => s.validate_inner(ctx, value, errors),
+                Array(ref s) => s.validate_inner(ctx, value, errors),
+                Number(ref s) => s.validate_inner(ctx, value, errors),
+                String(ref s) => s.validate_inner(ctx, value, errors),
+                Integer(ref s) => s.validate_inner(ctx, value, errors),
+                Combinator(ref s) => s.validate_inner(ctx, value, errors),
+                Empty(ref s) => s.validate_inner(ctx, value, errors),
+                Reference(ref s) => s.validate_inner(ctx, value, errors),
+                Shared(ref s) => s.validate_inner(ctx, value, errors),
+                Custom(ref s) => ctx.unfocused(|ctx| s.validate_inner(ctx, value, errors)),
+            }
+        }
+        if self.is_nullable() {
+            if let Some(error) = errors.get_mut(start) {
+                let expected = error
+                    .expected_types(value)
+                    .filter(|expected| !expected.contains(&JsonType::Null))
+                    .map(|expected| [expected, &[JsonType::Null]].concat());
+                if let Some(expected) = expected {
+                    let found = JsonType::reported(value).0;
+                    error.reason = ErrorKind::MultiTypeMismatch { expected, found };
+                }
+            }
+        }
+        self.validate_common(ctx, value, errors);
+
+        if errors.len() > start {
+            ctx.discard_annotations(mark);
+        } else {
+            ctx.hit(self);
+        }
+        if let (Some(stats), Some(started)) = (ctx.options.stats.as_ref(), started) {
+            // the errors of subschemas are located already
+            let own = errors[start..]
+                .iter()
+                .filter(|e| !e.is_located())
+                .map(|e| e.reason.code());
+            let failed = errors.len() > start;
+            stats.record_subschema(ctx.schema_pointer(), started.elapsed(), failed, own);
+        }
+        // the errors of subschemas were counted by the subschemas
+        let own = (errors.len() - start).saturating_sub(ctx.tracked_errors().saturating_sub(tracked));
+        ctx.track_errors(own);
+        ctx.locate(&mut errors[start..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use serde_json::{self, Value};
+
+    use super::{Context, Schema, SchemaBase};
+    use array::ArraySchemaBuilder;
+    use errors::{ErrorCode, ErrorKind, ValidationError};
+    use object::ObjectSchemaBuilder;
+    use resolution::ResolutionKind;
+    use options::{Draft, ValidateOptions};
+    use types::JsonType;
+
+    #[test]
+    fn type_typo() {
+        let error = serde_json::from_value::<Schema>(json!({"type": "Object"})).unwrap_err();
+        assert!(error.to_string().contains("unknown variant `Object`"));
+        assert!(error.to_string().contains("`object`"));
+
+        let error = serde_json::from_value::<Schema>(json!({
+            "type": "object",
+            "properties": {"name": {"minLength": 1}}
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("can't use `minLength`"));
+    }
+
+    #[test]
+    fn empty_schemas() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "anything": {},
+                "documented": {"description": "any value"},
+                "name": {"type": "string"}
+            }
+        }))
+        .unwrap();
+        assert_eq!(schema.count_empty_subschemas(), 2);
+        let serialized = serde_json::to_value(&schema).unwrap();
+        assert_eq!(serialized["properties"]["anything"], json!({}));
+        assert_eq!(
+            serialized["properties"]["documented"],
+            json!({"description": "any value"})
+        );
+        let input = json!({"anything": [1], "documented": null, "name": "a"});
+        schema.validate(&input).unwrap();
+    }
+
+    #[test]
+    fn enum_and_const() {
+        let schema: Schema =
+            serde_json::from_value(json!({"type": "number", "enum": [1, 2.5, 4]})).unwrap();
+        schema.validate(&json!(1.0)).unwrap();
+        schema.validate(&json!(2.5)).unwrap();
+        assert!(schema.validate(&json!(3)).is_err());
+
+        let schema: Schema = serde_json::from_value(json!({"const": {"a": [1]}})).unwrap();
+        schema.validate(&json!({"a": [1.0]})).unwrap();
+        assert!(schema.validate(&json!({"a": [1], "b": 2})).is_err());
+        assert!(schema.validate(&json!("a")).is_err());
+    }
+
+    #[test]
+    fn null_keyword_values() {
+        let schema: Schema = serde_json::from_value(json!({"const": null})).unwrap();
+        schema.validate(&json!(null)).unwrap();
+        assert!(schema.validate(&json!(1)).is_err());
+        assert_eq!(serde_json::to_value(&schema).unwrap(), json!({"const": null}));
+
+        let document = json!({"type": ["string", "null"], "const": null, "default": null});
+        let schema: Schema = serde_json::from_value(document.clone()).unwrap();
+        assert!(schema.validate(&json!("a")).is_err());
+        assert_eq!(serde_json::to_value(&schema).unwrap(), document);
+        assert_eq!(schema.default_value(&schema), Some(&Value::Null));
+
+        // absent keywords aren't written as `null`
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {"a": {"type": "array"}, "b": {"anyOf": [{"type": "integer"}]}}
+        }))
+        .unwrap();
+        let serialized = serde_json::to_string(&schema).unwrap();
+        assert!(!serialized.contains("null"), "{}", serialized);
+    }
+
+    #[test]
+    fn nullable_enum_and_const() {
+        for document in [
+            json!({"type": ["string", "null"], "enum": ["a"]}),
+            json!({"type": ["string", "null"], "const": "a"}),
+        ] {
+            let schema: Schema = serde_json::from_value(document).unwrap();
+            schema.validate(&json!("a")).unwrap();
+            assert!(schema.validate(&json!(null)).is_err());
+        }
+        let schema: Schema =
+            serde_json::from_value(json!({"type": ["string", "null"], "enum": ["a", null]}))
+                .unwrap();
+        schema.validate(&json!(null)).unwrap();
+        assert!(schema.validate(&json!("b")).is_err());
+    }
+
+    #[test]
+    fn enum_suggestion() {
+        let statuses = vec![
+            "active", "inactive", "pending", "suspended", "archived", "deleted", "draft",
+        ];
+        let schema: Schema =
+            serde_json::from_value(json!({"type": "string", "enum": statuses})).unwrap();
+        let suggestion = |input: Value| match schema.validate(&input).unwrap_err().0[0].reason {
+            ErrorKind::NotInEnum { ref suggestion, .. } => suggestion.clone(),
+            ref e => panic!("unexpected error {}", e),
+        };
+        assert_eq!(suggestion(json!("actve")), Some("active".to_string()));
+        assert_eq!(suggestion(json!("Pending")), Some("pending".to_string()));
+        assert_eq!(suggestion(json!("drafts")), Some("draft".to_string()));
+        assert_eq!(suggestion(json!("enabled")), None);
+        assert_eq!(suggestion(json!("x")), None);
+
+        let input = json!("actve");
+        let error = schema.validate(&input).unwrap_err().0.remove(0);
+        assert!(error.reason.to_string().ends_with(r#", did you mean "active"?"#));
+    }
+
+    #[test]
+    fn nullable_type_mismatch() {
+        let schema: Schema =
+            serde_json::from_value(json!({"type": ["string", "null"], "minLength": 1})).unwrap();
+        schema.validate(&json!(null)).unwrap();
+        let input = json!({});
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        match errors[0].reason {
+            ErrorKind::MultiTypeMismatch {
+                ref expected,
+                found,
+            } => {
+                assert_eq!(expected, &[JsonType::String, JsonType::Null]);
+                assert_eq!(found, JsonType::Object);
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(
+            errors[0].reason.to_string(),
+            "Type mismatch: expected string or null, found object"
+        );
+    }
+
+    #[test]
+    fn default_options() {
+        let options = ValidateOptions::default();
+        assert!(!options.annotations && !options.collect_warnings);
+        assert_eq!(options.draft, Draft::Draft7);
+        assert_eq!(options.max_depth, None);
+
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {"a": {"type": "array", "items": {"type": "integer"}}}
+        }))
+        .unwrap();
+        let input = json!({"a": [1, "2", 3.5]});
+        let errors = schema.validate(&input).unwrap_err().0;
+        let output = schema.validate_with(&input, &options);
+        assert_eq!(output.errors.len(), errors.len());
+        for (a, b) in output.errors.iter().zip(&errors) {
+            assert_eq!(a.to_string(), b.to_string());
+            assert_eq!(a.instance_path, b.instance_path);
+            assert_eq!(a.schema_path, b.schema_path);
+        }
+        assert!(output.annotations.is_empty() && output.warnings.is_empty());
+    }
+
+    #[test]
+    fn dollar_defs() {
+        let document = |definitions: Value| {
+            let mut value = json!({
+                "type": "object",
+                "properties": {
+                    "a": {"$ref": "#/$defs/a"},
+                    "b": {"$ref": "#/definitions/b"}
+                }
+            });
+            value
+                .as_object_mut()
+                .unwrap()
+                .extend(definitions.as_object().unwrap().clone());
+            value
+        };
+        let a = json!({"type": "string"});
+        let b = json!({"type": "integer"});
+        let input = json!({"a": "x", "b": 1});
+        let invalid = json!({"a": 1, "b": "x"});
+
+        for (definitions, keyword) in [
+            (json!({"$defs": {"a": a, "b": b}}), "$defs"),
+            (json!({"definitions": {"a": a, "b": b}}), "definitions"),
+            (json!({"$defs": {"a": a}, "definitions": {"b": b}}), "definitions"),
+        ] {
+            let schema: Schema = serde_json::from_value(document(definitions)).unwrap();
+            schema.validate(&input).unwrap();
+            assert_eq!(schema.validate(&invalid).unwrap_err().0.len(), 2);
+            assert!(schema.pointer("/$defs/b").is_some());
+            assert!(schema.pointer("/definitions/a").is_some());
+            let paths: Vec<_> = schema
+                .children_with_pointers()
+                .into_iter()
+                .map(|(pointer, _)| pointer)
+                .collect();
+            assert!(paths.contains(&format!("/{}/a", keyword)));
+
+            let json = serde_json::to_value(&schema).unwrap();
+            let other = if keyword == "$defs" { "definitions" } else { "$defs" };
+            let mut names: Vec<_> = json[keyword].as_object().unwrap().keys().collect();
+            names.sort();
+            assert_eq!(names, vec!["a", "b"]);
+            assert!(json.get(other).is_none());
+        }
+
+        let colliding = document(json!({"$defs": {"a": a, "b": b}, "definitions": {"b": b}}));
+        let error = serde_json::from_value::<Schema>(colliding).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the definition `b` is given in both `definitions` and `$defs`"
+        );
+    }
+
+    #[test]
+    fn max_depth() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {
+                "list": {"type": "array", "items": {"$ref": "#/definitions/list"}}
+            },
+            "$ref": "#/definitions/list"
+        }))
+        .unwrap();
+        let input = json!([[[[[[]]]]]]);
+        schema.validate(&input).unwrap();
+
+        let options = ValidateOptions {
+            max_depth: Some(6),
+            ..Default::default()
+        };
+        let errors = schema.validate_with(&input, &options).errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].reason.code(), ErrorCode::DepthLimitExceeded);
+        assert_eq!(errors[0].instance_path, "/0/0/0");
+    }
+
+    #[test]
+    fn timeout() {
+        // every item is checked against all branches of the `anyOf` nested in it
+        let mut schema = json!({"type": "integer"});
+        for _ in 0..8 {
+            schema = json!({"anyOf": [{"type": "string"}, {"type": "array", "items": schema}]});
+        }
+        let schema: Schema = serde_json::from_value(schema).unwrap();
+        let mut input = json!(true);
+        for _ in 0..8 {
+            input = json!([input]);
+        }
+        let input = Value::Array(vec![input; 200_000]);
+
+        let limit = Duration::from_millis(50);
+        let options = ValidateOptions {
+            timeout: Some(limit),
+            ..Default::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        assert!(output.incomplete);
+        // a full run has an error per item, the aborted one stops short of that
+        assert!(output.errors.len() < 200_000);
+        match output.errors.last().unwrap().reason {
+            ErrorKind::Timeout { elapsed } => assert!(elapsed >= limit),
+            ref reason => panic!("unexpected error {}", reason),
+        }
+
+        let input = json!([input[0]]);
+        let output = schema.validate_with(&input, &options);
+        assert!(!output.incomplete);
+        assert_eq!(output.errors.last().unwrap().reason.code(), ErrorCode::AnyOfNoMatch);
+    }
+
+    #[test]
+    fn reference_cycle() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {"a": {"$ref": "#/definitions/b"}, "b": {"$ref": "#/definitions/a"}},
+            "type": "object",
+            "properties": {"x": {"$ref": "#/definitions/a"}}
+        }))
+        .unwrap();
+        let input = json!({"x": 1});
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::ReferenceCycle]);
+        assert_eq!(errors.0[0].schema_path, "/properties/x/$ref/$ref");
+    }
+
+    /// Accepts even integers only.
+    #[derive(Debug)]
+    struct Even;
+
+    impl SchemaBase for Even {
+        fn validate_inner<'json>(
+            &self,
+            _ctx: &Context,
+            value: &'json Value,
+            errors: &mut Vec<ValidationError<'json>>,
+        ) {
+            if value.as_i64().is_some_and(|i| i % 2 != 0) {
+                errors.push(ValidationError::new(ErrorKind::Msg("odd".into()), value));
+            }
+        }
+    }
+
+    #[test]
+    fn trait_objects() {
+        let schema: Schema = serde_json::from_value(json!({"type": "integer"})).unwrap();
+        let validators: Vec<Box<dyn SchemaBase>> = vec![Box::new(schema.clone()), Box::new(Even)];
+        let input = json!(3);
+        assert!(validators[0].validate_start(&input, &schema).is_ok());
+        assert!(validators[1].validate_start(&input, &schema).is_err());
+        assert!(<&Even as SchemaBase>::validate_start(&&Even, &json!(2), &schema).is_ok());
+    }
+
+    #[test]
+    fn custom_leaf() {
+        let schema = ObjectSchemaBuilder::default()
+            .add_property("count", Schema::Custom(Arc::new(Even)))
+            .build();
+        schema.validate(&json!({"count": 2})).unwrap();
+        let input = json!({"count": 3});
+        let errors = schema.validate(&input).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/count");
+        assert_eq!(errors[0].schema_path, "/properties/count");
+        assert!(serde_json::to_value(&schema).is_err());
+    }
+
+    /// Accepts arrays of strings in ascending order.
+    #[derive(Debug)]
+    struct Sorted;
+
+    impl SchemaBase for Sorted {
+        fn validate_inner<'json>(
+            &self,
+            _ctx: &Context,
+            value: &'json Value,
+            errors: &mut Vec<ValidationError<'json>>,
+        ) {
+            let items: Vec<_> = value.as_array().into_iter().flatten().collect();
+            if let Some(i) = (1..items.len()).find(|&i| items[i - 1].as_str() > items[i].as_str()) {
+                let message = format!("item {} comes before item {}", i, i - 1);
+                errors.push(ValidationError::custom(message, value));
+            }
+        }
+    }
+
+    #[test]
+    fn custom_errors() {
+        let reference: Schema =
+            serde_json::from_value(json!({"$ref": "#/definitions/sorted"})).unwrap();
+        let schema = ObjectSchemaBuilder::default()
+            .add_definition("sorted", Schema::Custom(Arc::new(Sorted)))
+            .add_property(
+                "groups",
+                ArraySchemaBuilder::default()
+                    .all_items_schema(reference)
+                    .build(),
+            )
+            .add_property("tags", Schema::Custom(Arc::new(Sorted)))
+            .build();
+        let input = json!({"tags": ["b", "a"], "groups": [["x", "y"], ["z", "c"]]});
+        let errors = schema.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::Custom; 2]);
+        assert_eq!(errors.0[0].instance_path, "/groups/1");
+        assert_eq!(errors.0[0].schema_path, "/properties/groups/items/$ref");
+        assert_eq!(errors.0[1].instance_path, "/tags");
+        assert_eq!(errors.0[1].schema_path, "/properties/tags");
+        // what the command line tool prints
+        assert_eq!(
+            errors.to_string(),
+            "Error at `/groups/1`: item 1 comes before item 0\n\
+             Error at `/tags`: item 1 comes before item 0\n"
+        );
+    }
+
+    /// Rejects every value, counting how often it's applied.
+    #[derive(Debug, Default)]
+    struct Rejecting {
+        calls: AtomicUsize,
+    }
+
+    impl SchemaBase for Rejecting {
+        fn validate_inner<'json>(
+            &self,
+            _ctx: &Context,
+            value: &'json Value,
+            errors: &mut Vec<ValidationError<'json>>,
+        ) {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            errors.push(ValidationError::custom("rejected", value));
+        }
+    }
+
+    #[test]
+    fn max_errors_stops_early() {
+        let rejecting = Arc::new(Rejecting::default());
+        let schema = ArraySchemaBuilder::default()
+            .all_items_schema(Schema::Custom(rejecting.clone()))
+            .build();
+        let input = json!((0..100).collect::<Vec<_>>());
+        let first = |n| {
+            let options = ValidateOptions {
+                max_errors: Some(n),
+                ..ValidateOptions::default()
+            };
+            schema.validate_with(&input, &options).errors
+        };
+        assert_eq!(first(1)[0].instance_path, "/0");
+        assert_eq!(rejecting.calls.load(Ordering::SeqCst), 1);
+        let some = first(5);
+        assert_eq!(some.len(), 5);
+        assert_eq!(some[4].instance_path, "/4");
+
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["id", "tags"],
+            "properties": {
+                "id": {"anyOf": [{"type": "integer"}, {"type": "string", "minLength": 3}]},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}},
+                "owner": {"not": {"type": "boolean"}}
+            }
+        }))
+        .unwrap();
+        let input = json!({
+            "id": "x",
+            "tags": ["a", 1, "long", true, "b", "longer"],
+            "owner": true,
+            "extra": 1
+        });
+        let describe = |e: &ValidationError| {
+            (
+                e.instance_path.clone(),
+                e.schema_path.clone(),
+                e.reason.to_string(),
+            )
+        };
+        // the errors kept are the first ones of a run without the limit
+        let errors = schema.validate(&input).unwrap_err();
+        let all: Vec<_> = errors.0.iter().map(describe).collect();
+        assert!(all.len() > 4);
+        for n in 1..all.len() {
+            let options = ValidateOptions {
+                max_errors: Some(n),
+                ..ValidateOptions::default()
+            };
+            let found: Vec<_> = schema
+                .validate_with(&input, &options)
+                .errors
+                .iter()
+                .map(describe)
+                .collect();
+            assert_eq!(found, &all[..n]);
+        }
+    }
+
+    #[test]
+    fn validate_text() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["id"],
+            "properties": {"id": {"type": "integer"}}
+        }))
+        .unwrap();
+        schema.validate_str(r#"{"id": 1}"#).unwrap();
+        schema.validate_reader(&b"{\"id\": 2}"[..]).unwrap();
+
+        match *schema.validate_bytes(b"{\"id\": \"\xff\"}").unwrap_err().kind() {
+            ErrorKind::Serde(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        match *schema.validate_str(r#"{"id": "#).unwrap_err().kind() {
+            ErrorKind::Serde(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        let error = schema.validate_str(r#"{"id": "one"}"#).unwrap_err();
+        match *error.kind() {
+            ErrorKind::Invalid(ref errors) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].instance_path, "/id");
+                assert_eq!(errors[0].reason.code(), ErrorCode::TypeMismatch);
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(error.kind().code(), ErrorCode::Invalid);
+    }
+
+    #[test]
+    fn validate_text_with_bom_and_documents() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {"id": {"type": "integer"}}
+        }))
+        .unwrap();
+        let text = ::std::fs::read_to_string("data/with-bom.json").unwrap();
+        assert!(text.starts_with('\u{feff}'));
+        schema.validate_str(&text).unwrap();
+        schema.validate_bytes(text.as_bytes()).unwrap();
+        schema
+            .validate_reader(File::open("data/with-bom.json").unwrap())
+            .unwrap();
+
+        match *schema.validate_str("{\"id\": 1}\n}").unwrap_err().kind() {
+            ErrorKind::Serde(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        let single = ValidateOptions::default();
+        let results = schema.validate_str_with("{\"id\": 1} {\"id\": 2}", &single);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+
+        let multiple = ValidateOptions {
+            multiple_documents: true,
+            ..ValidateOptions::default()
+        };
+        let stream = "\u{feff}{\"id\": 1}\n{\"id\": \"two\"}{\"id\": 3}\n";
+        for results in &[
+            schema.validate_str_with(stream, &multiple),
+            schema.validate_reader_with(stream.as_bytes(), &multiple),
+        ] {
+            assert_eq!(results.len(), 3);
+            assert!(results[0].is_ok());
+            assert_eq!(
+                results[1].as_ref().unwrap_err().kind().code(),
+                ErrorCode::Invalid
+            );
+            assert!(results[2].is_ok());
+        }
+        let results = schema.validate_str_with("{\"id\": 1} garbage {\"id\": 3}", &multiple);
+        assert_eq!(results.len(), 2);
+        match *results[1].as_ref().unwrap_err().kind() {
+            ErrorKind::Serde(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
 
     #[test]
     fn test_schema_references() {
         let schema_raw: Value = serde_json::from_reader(File::open("data/schema-with-refs.json").unwrap()).unwrap();
-        let mut parsed_schema: Schema = serde_json::from_value(schema_raw.clone()).unwrap();
-        parsed_schema.resolve_references(&schema_raw);
+        let parsed_schema: Schema = serde_json::from_value(schema_raw).unwrap();
+        let report = parsed_schema.resolve_references();
+        let kinds: Vec<_> = report.references.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, vec![ResolutionKind::Local, ResolutionKind::Cached]);
+        assert_eq!(report.references[0].target_pointer, "/definitions/address");
     }
 }
\ No newline at end of file
@@ -0,0 +1,226 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use schema::Schema;
+use session::RecordedError;
+
+/// An example a subschema rejects or a counterexample it accepts, see
+/// `Schema::self_test`.
+#[derive(Debug)]
+pub struct SelfTestMismatch {
+    /// JSON pointer to the subschema holding the value.
+    pub schema_path: String,
+    /// Whether the value is one of the `examples`, which should be accepted,
+    /// rather than one of the `x-counterexamples`, which should be rejected.
+    pub example: bool,
+    /// The position of the value in its list.
+    pub index: usize,
+    /// The value.
+    pub value: Value,
+    /// Why an example was rejected, empty for a counterexample. The schema paths
+    /// are relative to the subschema.
+    pub errors: Vec<RecordedError>,
+}
+
+impl SelfTestMismatch {
+    /// JSON pointer to the value in the schema.
+    pub fn value_path(&self) -> String {
+        let keyword = if self.example {
+            "examples"
+        } else {
+            "x-counterexamples"
+        };
+        format!("{}/{}/{}", self.schema_path, keyword, self.index)
+    }
+}
+
+impl fmt::Display for SelfTestMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.schema_path.is_empty() {
+            "/"
+        } else {
+            &self.schema_path
+        };
+        if self.example {
+            writeln!(
+                f,
+                "Example {} of `{}` is rejected: {}",
+                self.index, path, self.value
+            )?;
+            for error in &self.errors {
+                writeln!(f, "  {}", error)?;
+            }
+            Ok(())
+        } else {
+            writeln!(
+                f,
+                "Counterexample {} of `{}` is accepted: {}",
+                self.index, path, self.value
+            )
+        }
+    }
+}
+
+/// The result of checking a schema against its own `examples` and
+/// `x-counterexamples`, see `Schema::self_test`.
+#[derive(Debug, Default)]
+pub struct SelfTestReport {
+    /// The number of examples checked.
+    pub examples: usize,
+    /// The number of counterexamples checked.
+    pub counterexamples: usize,
+    /// The values the schema judged differently than its author, in document
+    /// order.
+    pub mismatches: Vec<SelfTestMismatch>,
+}
+
+impl SelfTestReport {
+    /// Whether every example is accepted and every counterexample rejected.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// A line like `3 examples and 1 counterexamples checked, 0 judged
+    /// differently`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} examples and {} counterexamples checked, {} judged differently",
+            self.examples,
+            self.counterexamples,
+            self.mismatches.len()
+        )
+    }
+
+    fn check(&mut self, root: &Schema, schema: &Schema, pointer: &str) {
+        let (examples, counterexamples) = schema.test_vectors();
+        for (index, value) in examples.iter().enumerate() {
+            self.examples += 1;
+            let errors = schema.validate_within(root, value);
+            if !errors.is_empty() {
+                self.mismatches.push(SelfTestMismatch {
+                    schema_path: pointer.into(),
+                    example: true,
+                    index,
+                    value: value.clone(),
+                    errors: errors.into_iter().map(RecordedError::from).collect(),
+                });
+            }
+        }
+        for (index, value) in counterexamples.iter().enumerate() {
+            self.counterexamples += 1;
+            if schema.accepts_within(root, value) {
+                self.mismatches.push(SelfTestMismatch {
+                    schema_path: pointer.into(),
+                    example: false,
+                    index,
+                    value: value.clone(),
+                    errors: vec![],
+                });
+            }
+        }
+        for (step, child) in schema.children_with_pointers() {
+            self.check(root, child, &format!("{}{}", pointer, step));
+        }
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for mismatch in &self.mismatches {
+            write!(f, "{}", mismatch)?;
+        }
+        writeln!(f, "{}", self.summary())
+    }
+}
+
+impl Schema {
+    /// Validates the `examples` of every subschema, expecting them to be
+    /// accepted, and the values of the `x-counterexamples` extension keyword,
+    /// expecting them to be rejected. This catches mistakes in a schema without
+    /// separate fixtures, for example:
+    /// `{"type": "string", "pattern": "^[a-z]+$", "examples": ["abc"],
+    /// "x-counterexamples": ["ABC"]}`.
+    pub fn self_test(&self) -> SelfTestReport {
+        let mut report = SelfTestReport::default();
+        report.check(self, self, "");
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use errors::ErrorCode;
+    use object::ObjectSchemaBuilder;
+    use string::StringSchemaBuilder;
+
+    #[test]
+    fn mismatches() {
+        let schema: Schema = serde_json::from_value(json!({
+            "definitions": {
+                "code": {
+                    "type": "string",
+                    "pattern": "^[A-Z]{3}$",
+                    "examples": ["EUR", "usd"],
+                    "x-counterexamples": ["EURO", "GBP"]
+                }
+            },
+            "type": "object",
+            "properties": {
+                "price": {"type": "number", "minimum": 0.0, "examples": [1.5]},
+                "currency": {"$ref": "#/definitions/code"}
+            },
+            "examples": [{"price": 2, "currency": "CHF"}],
+            "x-counterexamples": [{"price": -1, "currency": "CHF"}]
+        }))
+        .unwrap();
+        let report = schema.self_test();
+        assert_eq!((report.examples, report.counterexamples), (4, 3));
+        assert!(!report.is_ok());
+        let found: Vec<_> = report
+            .mismatches
+            .iter()
+            .map(|m| (m.schema_path.as_str(), m.example, m.index))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("/definitions/code", true, 1),
+                ("/definitions/code", false, 1),
+            ]
+        );
+        let rejected = &report.mismatches[0];
+        assert_eq!(rejected.value, json!("usd"));
+        assert_eq!(rejected.errors[0].reason.code(), ErrorCode::RegexMismatch);
+        assert_eq!(
+            report.mismatches[1].value_path(),
+            "/definitions/code/x-counterexamples/1"
+        );
+        assert!(report
+            .to_string()
+            .starts_with("Example 1 of `/definitions/code` is rejected: \"usd\"\n"));
+
+        let built = ObjectSchemaBuilder::default()
+            .add_property(
+                "name",
+                StringSchemaBuilder::default()
+                    .pattern("^[a-z]+$".into())
+                    .example(json!("ada"))
+                    .counterexample(json!("Ada"))
+                    .build(),
+            )
+            .example(json!({"name": "ada"}))
+            .build();
+        let report = built.self_test();
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!((report.examples, report.counterexamples), (2, 1));
+        let json = serde_json::to_value(&built).unwrap();
+        assert_eq!(
+            json["properties"]["name"]["x-counterexamples"],
+            json!(["Ada"])
+        );
+    }
+}
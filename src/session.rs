@@ -0,0 +1,628 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::time::Instant;
+
+use serde_json::Value;
+
+use array::Items;
+use errors::{ErrorKind, FoldedErrors, Result, ValidationError, ValidationErrors};
+use object::PropertyMap;
+use options::ValidateOptions;
+use output::{Collected, Timing};
+use schema::{Context, Deadline, Schema, SchemaBase, Segment};
+
+/// Reuses the allocation of `errors` for errors about a different document.
+fn recycle<'a, 'b>(mut errors: Vec<ValidationError<'a>>) -> Vec<ValidationError<'b>> {
+    errors.clear();
+    // collecting an iterator over a vector into a vector of a type with the
+    // same layout reuses the allocation
+    errors.into_iter().map(|_| unreachable!()).collect()
+}
+
+/// Buffers the combinators use to validate their branches, kept for reuse.
+#[derive(Debug, Default)]
+pub(crate) struct Scratch {
+    errors: RefCell<Vec<Vec<ValidationError<'static>>>>,
+    indices: RefCell<Vec<Vec<usize>>>,
+}
+
+impl Scratch {
+    pub(crate) fn take_errors<'json>(&self) -> Vec<ValidationError<'json>> {
+        recycle(self.errors.borrow_mut().pop().unwrap_or_default())
+    }
+
+    pub(crate) fn give_errors(&self, errors: Vec<ValidationError>) {
+        self.errors.borrow_mut().push(recycle(errors));
+    }
+
+    pub(crate) fn take_indices(&self) -> Vec<usize> {
+        self.indices.borrow_mut().pop().unwrap_or_default()
+    }
+
+    pub(crate) fn give_indices(&self, mut indices: Vec<usize>) {
+        indices.clear();
+        self.indices.borrow_mut().push(indices);
+    }
+}
+
+/// An error kept by a session for the document it holds, see `Session::load`.
+#[derive(Debug)]
+pub struct RecordedError {
+    /// Reason for the error.
+    pub reason: ErrorKind,
+    /// JSON pointer to the value the error is about.
+    pub instance_path: String,
+    /// JSON pointer to the subschema that reported the error.
+    pub schema_path: String,
+    /// A short excerpt of the invalid value, see `ValidationError::value_preview`.
+    pub value_preview: Option<String>,
+    /// The similar errors this error stands for, see `ValidationError::folded`.
+    pub folded: Option<FoldedErrors>,
+}
+
+impl<'json> From<ValidationError<'json>> for RecordedError {
+    fn from(error: ValidationError<'json>) -> RecordedError {
+        RecordedError {
+            reason: error.reason,
+            instance_path: error.instance_path,
+            schema_path: error.schema_path,
+            value_preview: error.value_preview,
+            folded: error.folded,
+        }
+    }
+}
+
+impl fmt::Display for RecordedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error at `{}`: {}", self.instance_path, self.reason)?;
+        if let Some(ref folded) = self.folded {
+            write!(f, " ({})", folded)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `path` is `pointer` or a pointer into the value at `pointer`.
+fn is_within(path: &str, pointer: &str) -> bool {
+    path.strip_prefix(pointer)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Validates many values against the same schema, reusing the buffers needed for
+/// validation between calls. Once it has seen a few values, validating a valid
+/// value doesn't allocate (unless the schema uses `pattern`, `patternProperties`
+/// or `uniqueItems`).
+///
+/// A session can also hold a document that is edited piece by piece, see
+/// `Session::revalidate_at`.
+///
+/// A session can't be shared between threads, use one session per thread.
+#[derive(Debug)]
+pub struct Session<'s> {
+    schema: &'s Schema,
+    options: ValidateOptions,
+    errors: Vec<ValidationError<'static>>,
+    collected: RefCell<Collected>,
+    scratch: Scratch,
+    document: Value,
+    recorded: Vec<RecordedError>,
+    timing: Option<Timing>,
+}
+
+impl<'s> Session<'s> {
+    /// Creates a session for validating values against `schema`.
+    pub fn new(schema: &'s Schema) -> Session<'s> {
+        Session::with_options(schema, ValidateOptions::default())
+    }
+
+    /// Creates a session that validates with the given options.
+    pub fn with_options(schema: &'s Schema, options: ValidateOptions) -> Session<'s> {
+        Session {
+            schema,
+            options,
+            errors: vec![],
+            collected: RefCell::default(),
+            scratch: Scratch::default(),
+            document: Value::Null,
+            recorded: vec![],
+            timing: None,
+        }
+    }
+
+    /// Validates `value`. The buffer for the errors is handed out with the errors,
+    /// so only invalid values cause a new one to be allocated.
+    pub fn validate<'json>(
+        &mut self,
+        value: &'json Value,
+    ) -> ::std::result::Result<(), ValidationErrors<'json>> {
+        let mut errors = recycle(::std::mem::take(&mut self.errors));
+        let started = self.start_timing();
+        {
+            let deadline = Deadline::new(self.options.timeout);
+            let context = Context::new(
+                self.schema,
+                &self.options,
+                &self.collected,
+                &self.scratch,
+                &deadline,
+            );
+            self.schema.validate_inner(&context, value, &mut errors);
+            context.finish(value, &mut errors);
+        }
+        self.finish_timing(started);
+        if errors.is_empty() {
+            self.errors = recycle(errors);
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Takes `document` to edit it with `revalidate_at` and validates it. Returns
+    /// the errors, which are kept until the next edit.
+    pub fn load(&mut self, document: Value) -> &[RecordedError] {
+        self.document = document;
+        self.recorded.clear();
+        let errors = self.run(&[]).0;
+        self.recorded = errors;
+        &self.recorded
+    }
+
+    /// The document given to `load`, with the edits made since.
+    pub fn document(&self) -> &Value {
+        &self.document
+    }
+
+    /// The errors of the document as of the last edit.
+    pub fn errors(&self) -> &[RecordedError] {
+        &self.recorded
+    }
+
+    /// Where the time of the last validation went, if
+    /// `ValidateOptions::collect_timing` is set.
+    pub fn timing(&self) -> Option<Timing> {
+        self.timing
+    }
+
+    /// Replaces the value at `pointer` in the loaded document with `new_subtree`
+    /// and updates the errors without validating the whole document again. The
+    /// pointer has to point to an existing value or to a new property of an
+    /// existing object. Returns the errors of the edited document.
+    ///
+    /// Only the values on the way from the root to `pointer` and the new subtree
+    /// are validated. On the way, all keywords of the schemas applying to each
+    /// value are checked, so the constraints an edit can affect above the edited
+    /// value are covered: `required`, `minProperties`/`maxProperties`,
+    /// `minItems`/`maxItems`, `uniqueItems`, `enum` and `const` of every
+    /// enclosing object and array. The properties and items next to the path are
+    /// skipped, as their errors can't change, except below `anyOf`, `oneOf`,
+    /// `not` and custom schemas: which of their branches match depends on the
+    /// whole value, so from there on everything is validated again.
+    pub fn revalidate_at(
+        &mut self,
+        pointer: &str,
+        new_subtree: &Value,
+    ) -> Result<&[RecordedError]> {
+        if pointer.is_empty() {
+            return Ok(self.load(new_subtree.clone()));
+        }
+        if !pointer.starts_with('/') {
+            bail!(ErrorKind::InvalidPointer(pointer.into()));
+        }
+        let split = pointer.rfind('/').unwrap_or(0);
+        let (parent, last) = (&pointer[..split], &pointer[split + 1..]);
+        let last = last.replace("~1", "/").replace("~0", "~");
+        if let Some(value) = self.document.pointer_mut(pointer) {
+            *value = new_subtree.clone();
+        } else if let Some(&mut Value::Object(ref mut object)) = self.document.pointer_mut(parent) {
+            object.insert(last, new_subtree.clone());
+        } else {
+            bail!(ErrorKind::InvalidPointer(pointer.into()));
+        }
+
+        let tokens: Vec<String> = pointer[1..]
+            .split('/')
+            .map(|t| t.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        let (errors, unfocused) = self.run(&tokens);
+        // `unfocused` tokens before the end of the path, everything was validated
+        let mut boundaries = pointer
+            .match_indices('/')
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        boundaries.push(pointer.len());
+        let revalidated = &pointer[..boundaries[tokens.len() - unfocused]];
+        let ancestors = &boundaries[..tokens.len() - unfocused];
+        self.recorded.retain(|error| {
+            let path = &error.instance_path;
+            !(is_within(path, revalidated) || ancestors.iter().any(|&i| *path == pointer[..i]))
+        });
+        self.recorded.extend(errors);
+        Ok(&self.recorded)
+    }
+
+    /// Validates the loaded document, skipping the values off the path `focus`.
+    /// Returns the errors and how many tokens before the end of the path values
+    /// couldn't be skipped anymore.
+    fn run(&mut self, focus: &[String]) -> (Vec<RecordedError>, usize) {
+        let mut errors = recycle(::std::mem::take(&mut self.errors));
+        self.collected.borrow_mut().unfocused = 0;
+        let started = self.start_timing();
+        {
+            let deadline = Deadline::new(self.options.timeout);
+            let mut context = Context::new(
+                self.schema,
+                &self.options,
+                &self.collected,
+                &self.scratch,
+                &deadline,
+            );
+            context.focus = Some(focus).filter(|focus| !focus.is_empty());
+            self.schema
+                .validate_inner(&context, &self.document, &mut errors);
+            context.finish(&self.document, &mut errors);
+        }
+        let recorded = errors.drain(..).map(RecordedError::from).collect();
+        self.errors = recycle(errors);
+        self.finish_timing(started);
+        (recorded, self.collected.borrow().unfocused)
+    }
+
+    /// Starts timing a run if timing is collected.
+    fn start_timing(&self) -> Option<Instant> {
+        if !self.options.collect_timing {
+            return None;
+        }
+        self.collected.borrow_mut().timing = Timing::default();
+        Some(Instant::now())
+    }
+
+    fn finish_timing(&mut self, started: Option<Instant>) {
+        let timing = self.collected.borrow().timing;
+        self.timing = started.map(|started| Timing {
+            total: started.elapsed(),
+            ..timing
+        });
+    }
+}
+
+/// What the schema of a `validate_all` run applies to the elements or
+/// properties of the value, which are walked once for all schemas.
+enum Fanout<'a> {
+    /// The `items` schema of every element.
+    Items(&'a Schema),
+    /// The schemas of `properties`.
+    Properties(&'a PropertyMap<Schema>),
+}
+
+impl<'a> Fanout<'a> {
+    /// The fan-out of `schema` over `value`, if its validator can leave the
+    /// elements or properties to `validate_all`.
+    fn of(schema: &'a Schema, value: &Value) -> Option<Fanout<'a>> {
+        match (schema, value) {
+            (Schema::Shared(schema), _) => Fanout::of(schema, value),
+            (Schema::Array(s), Value::Array(_)) if s.prefix_items.is_none() => match s.items {
+                Some(Items::List(ref items)) => Some(Fanout::Items(items)),
+                _ => None,
+            },
+            (Schema::Object(s), Value::Object(_)) => s.properties.as_ref().map(Fanout::Properties),
+            _ => None,
+        }
+    }
+}
+
+/// Validates `value` against each of `schemas` and returns their results in the
+/// same order, as if each schema's `validate` had been called.
+///
+/// The keywords about `value` itself are checked schema by schema, but where the
+/// schemas are array schemas with a single `items` schema or object schemas with
+/// `properties`, the elements or properties of `value` are walked once, each
+/// of them validated against all schemas before moving on to the next. For
+/// large documents this keeps each element in the cache while it's validated.
+/// The buffers needed for validation are shared between the schemas.
+pub fn validate_all<'json>(
+    schemas: &[&Schema],
+    value: &'json Value,
+) -> Vec<::std::result::Result<(), ValidationErrors<'json>>> {
+    let options = ValidateOptions::default();
+    let collected = RefCell::default();
+    let scratch = Scratch::default();
+    let deadlines: Vec<_> = schemas.iter().map(|_| Deadline::new(options.timeout)).collect();
+    let positions: Vec<Cell<Option<usize>>> = schemas.iter().map(|_| Cell::new(None)).collect();
+    let contexts: Vec<_> = schemas
+        .iter()
+        .zip(&deadlines)
+        .zip(&positions)
+        .map(|((&schema, deadline), position)| {
+            let mut context = Context::new(schema, &options, &collected, &scratch, deadline);
+            if Fanout::of(schema, value).is_some() {
+                context.deferred = Some(position);
+            }
+            context
+        })
+        .collect();
+    let mut results: Vec<_> = schemas
+        .iter()
+        .zip(&contexts)
+        .map(|(&schema, context)| {
+            let mut errors = vec![];
+            schema.validate_inner(context, value, &mut errors);
+            errors
+        })
+        .collect();
+
+    let fanouts: Vec<_> = schemas
+        .iter()
+        .zip(&positions)
+        .map(|(&schema, position)| position.get().and_then(|_| Fanout::of(schema, value)))
+        .collect();
+    let mut deferred: Vec<Vec<ValidationError>> = schemas.iter().map(|_| vec![]).collect();
+    match *value {
+        Value::Array(ref array) => {
+            for (i, element) in array.iter().enumerate() {
+                let runs = fanouts.iter().zip(&contexts).zip(&mut deferred);
+                for ((fanout, context), errors) in runs {
+                    if let Some(Fanout::Items(schema)) = *fanout {
+                        context.descend(Some(Segment::Index(i)), "items", None, |ctx| {
+                            schema.validate_inner(ctx, element, errors)
+                        });
+                    }
+                }
+            }
+        }
+        Value::Object(ref object) => {
+            // the properties with errors and where their errors start, as a
+            // separate run validates them in the order of `properties`
+            let mut chunks: Vec<Vec<(&str, usize)>> = schemas.iter().map(|_| vec![]).collect();
+            for (name, property) in object {
+                let runs = fanouts.iter().zip(&contexts).zip(&mut deferred);
+                for (((fanout, context), errors), chunks) in runs.zip(&mut chunks) {
+                    let schema = match *fanout {
+                        Some(Fanout::Properties(properties)) => properties.get(name),
+                        _ => None,
+                    };
+                    if let Some(schema) = schema {
+                        let start = errors.len();
+                        let key = Segment::Key(name);
+                        context.descend(Some(key), "properties", Some(key), |ctx| {
+                            schema.validate_inner(ctx, property, errors)
+                        });
+                        if errors.len() > start {
+                            chunks.push((name, start));
+                        }
+                    }
+                }
+            }
+            for ((fanout, errors), chunks) in fanouts.iter().zip(&mut deferred).zip(&chunks) {
+                if let Some(Fanout::Properties(properties)) = *fanout {
+                    in_schema_order(errors, chunks, properties);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for ((errors, deferred), position) in results.iter_mut().zip(deferred).zip(&positions) {
+        if let Some(position) = position.get() {
+            errors.splice(position..position, deferred);
+        }
+    }
+    results
+        .into_iter()
+        .zip(&contexts)
+        .map(|(mut errors, context)| {
+            context.finish(value, &mut errors);
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(ValidationErrors(errors))
+            }
+        })
+        .collect()
+}
+
+/// Reorders `errors`, made of the `chunks` of errors of a property each, in the
+/// order of the properties in `properties`. Both orders are the same unless the
+/// `preserve_order` feature keeps the order of the schema and the document.
+fn in_schema_order(
+    errors: &mut Vec<ValidationError>,
+    chunks: &[(&str, usize)],
+    properties: &PropertyMap<Schema>,
+) {
+    let position = |name: &str| properties.keys().position(|key| key == name);
+    let mut order: Vec<_> = (0..chunks.len()).collect();
+    order.sort_by_key(|&i| position(chunks[i].0));
+    if order.iter().enumerate().all(|(i, &chunk)| i == chunk) {
+        return;
+    }
+    let mut slots: Vec<_> = errors.drain(..).map(Some).collect();
+    for i in order {
+        let end = chunks.get(i + 1).map_or(slots.len(), |&(_, start)| start);
+        errors.extend(slots[chunks[i].1..end].iter_mut().filter_map(Option::take));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json;
+
+    use super::*;
+
+    /// The errors of `session` and of validating its document from scratch, as
+    /// comparable strings.
+    fn compare_with_full(session: &Session, schema: &Schema) {
+        let mut incremental: Vec<_> = session
+            .errors()
+            .iter()
+            .map(|e| format!("{} {} {}", e.instance_path, e.schema_path, e.reason))
+            .collect();
+        let mut full: Vec<_> = match schema.validate(session.document()) {
+            Ok(()) => vec![],
+            Err(errors) => errors
+                .0
+                .into_iter()
+                .map(|e| format!("{} {} {}", e.instance_path, e.schema_path, e.reason))
+                .collect(),
+        };
+        incremental.sort();
+        full.sort();
+        assert_eq!(incremental, full, "document {}", session.document());
+    }
+
+    #[test]
+    fn revalidate_at() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "required": ["name", "rows"],
+            "maxProperties": 4,
+            "additionalProperties": true,
+            "properties": {
+                "name": {"type": "string", "minLength": 2},
+                "rows": {
+                    "type": "array",
+                    "uniqueItems": true,
+                    "maxItems": 3,
+                    "items": {
+                        "type": "object",
+                        "required": ["id"],
+                        "additionalProperties": true,
+                        "properties": {
+                            "id": {"type": "integer", "minimum": 1},
+                            "value": {"oneOf": [
+                                {"type": "string"},
+                                {"type": "object", "required": ["unit"], "additionalProperties": true}
+                            ]}
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let mut session = Session::new(&schema);
+        session.load(json!({
+            "name": "x",
+            "rows": [{"id": 1, "value": "a"}, {"id": 0, "value": 5}]
+        }));
+        compare_with_full(&session, &schema);
+        assert_eq!(session.errors().len(), 3);
+
+        let edits = vec![
+            ("/name", json!("fixed")),
+            ("/rows/1/id", json!(2)),
+            ("/rows/1/value", json!({"unit": "kg"})),
+            ("/rows/1/value/unit", json!(1)),
+            ("/rows/1/value", json!({})),
+            ("/rows/0", json!({"id": 2, "value": {}})),
+            ("/rows/0/value/unit", json!("g")),
+            ("/extra", json!(true)),
+            ("/more", json!(true)),
+            ("/rows/0/id", json!("1")),
+            ("/rows", json!([{"id": 1}, {"id": 1}, {"id": 2}, {"id": 3}])),
+            ("/rows/3", json!({"id": 1})),
+            ("", json!({"rows": []})),
+        ];
+        for (pointer, value) in edits {
+            session.revalidate_at(pointer, &value).unwrap();
+            assert_eq!(session.document().pointer(pointer), Some(&value));
+            compare_with_full(&session, &schema);
+        }
+
+        assert!(session.revalidate_at("/missing/id", &json!(1)).is_err());
+        assert!(session.revalidate_at("/rows/5", &json!(1)).is_err());
+        assert!(session.revalidate_at("rows", &json!(1)).is_err());
+        assert!(session.revalidate_at("é", &json!(1)).is_err());
+    }
+
+    #[test]
+    fn validate_all_matches_validate() {
+        let structure: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        }))
+        .unwrap();
+        let policy: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "name": {"type": "string", "pattern": "^[a-z]+$"},
+                "tags": {"type": "array", "maxItems": 2}
+            }
+        }))
+        .unwrap();
+        let anything: Schema = serde_json::from_value(json!({})).unwrap();
+        let schemas = [&structure, &policy, &anything];
+        let documents = [
+            json!({"name": "ada", "tags": ["x"]}),
+            json!({"name": "Ada", "tags": ["x", "y", "z"]}),
+            json!({"name": 5, "tags": [1, "y", 3]}),
+            json!({"tags": "none"}),
+        ];
+        let summary = |result: &::std::result::Result<(), ValidationErrors>| match *result {
+            Ok(()) => vec![],
+            Err(ref errors) => errors
+                .0
+                .iter()
+                .map(|e| (e.instance_path.clone(), e.schema_path.clone(), e.to_string()))
+                .collect(),
+        };
+        for document in &documents {
+            let results = validate_all(&schemas, document);
+            assert_eq!(results.len(), schemas.len());
+            for (schema, result) in schemas.iter().zip(&results) {
+                assert_eq!(summary(result), summary(&schema.validate(document)));
+            }
+        }
+        assert!(validate_all(&[], &documents[0]).is_empty());
+
+        // arrays, with errors before, between and after those of the elements
+        let items: Schema = serde_json::from_value(json!({
+            "type": "array",
+            "minItems": 5,
+            "uniqueItems": true,
+            "items": {"type": "object", "required": ["id"], "additionalProperties": true}
+        }))
+        .unwrap();
+        let tuple: Schema = serde_json::from_value(json!({
+            "type": "array",
+            "items": [{"type": "object"}, {"type": "string"}],
+            "additionalItems": false
+        }))
+        .unwrap();
+        let shared = Schema::Shared(Arc::new(
+            serde_json::from_value(json!({
+                "type": ["array", "null"],
+                "enum": [[]],
+                "items": {"type": "object", "maxProperties": 1}
+            }))
+            .unwrap(),
+        ));
+        let strict: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "required": ["z"],
+            "additionalProperties": false,
+            "maxProperties": 1,
+            "properties": {"b": {"type": "string"}, "a": {"type": "integer"}}
+        }))
+        .unwrap();
+        let schemas = [&items, &tuple, &shared, &strict, &structure];
+        let documents = [
+            json!([{"id": 1}, {"id": 1}, {}, 4]),
+            json!([{"a": 1, "b": 2}, "x", true]),
+            json!(null),
+            json!({"a": "x", "b": 1, "name": 1, "c": 3}),
+            json!([]),
+        ];
+        for document in &documents {
+            let results = validate_all(&schemas, document);
+            for (schema, result) in schemas.iter().zip(&results) {
+                assert_eq!(summary(result), summary(&schema.validate(document)));
+            }
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use errors::ErrorCode;
+use schema::Schema;
+
+/// The number of patterns `StatsReport::slowest_patterns` lists.
+const SLOWEST_PATTERNS: usize = 10;
+
+#[derive(Debug, Default)]
+struct Counts {
+    evaluations: u64,
+    failures: u64,
+    errors: HashMap<String, u64>,
+    time: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    documents: u64,
+    subschemas: HashMap<String, Counts>,
+    patterns: HashMap<String, (u64, Duration)>,
+}
+
+/// Counts how often each subschema was evaluated and failed, which errors it
+/// reported and how long it took, over any number of validation runs. Attach it
+/// to `ValidateOptions::stats` to find the constraints worth reordering or
+/// simplifying; clones of the options share the collector.
+///
+/// The time of a subschema includes the time of its subschemas. Branches of
+/// `anyOf`, `oneOf` and `not` are counted although their errors are dropped.
+#[derive(Debug, Default)]
+pub struct StatsCollector {
+    stats: Mutex<Stats>,
+}
+
+impl StatsCollector {
+    /// Creates a collector that hasn't counted anything yet.
+    pub fn new() -> StatsCollector {
+        StatsCollector::default()
+    }
+
+    fn with_stats<F: FnOnce(&mut Stats)>(&self, f: F) {
+        // counting can't leave the stats inconsistent, so a poisoned lock is fine
+        match self.stats.lock() {
+            Ok(mut stats) => f(&mut stats),
+            Err(poisoned) => f(&mut poisoned.into_inner()),
+        }
+    }
+
+    pub(crate) fn record_document(&self) {
+        self.with_stats(|stats| stats.documents += 1);
+    }
+
+    /// Records an evaluation of the subschema at `schema_path`, with the codes of
+    /// the errors its own keywords reported.
+    pub(crate) fn record_subschema<I>(
+        &self,
+        schema_path: String,
+        time: Duration,
+        failed: bool,
+        own: I,
+    ) where
+        I: IntoIterator<Item = ErrorCode>,
+    {
+        self.with_stats(|stats| {
+            let counts = stats.subschemas.entry(schema_path).or_default();
+            counts.evaluations += 1;
+            counts.time += time;
+            if failed {
+                counts.failures += 1;
+            }
+            for code in own {
+                *counts.errors.entry(format!("{:?}", code)).or_insert(0) += 1;
+            }
+        });
+    }
+
+    pub(crate) fn record_pattern(&self, pattern: &str, time: Duration) {
+        self.with_stats(|stats| {
+            let entry = stats.patterns.entry(pattern.into()).or_default();
+            entry.0 += 1;
+            entry.1 += time;
+        });
+    }
+
+    /// The statistics of the runs so far.
+    pub fn report(&self) -> StatsReport {
+        let mut report = StatsReport::default();
+        self.with_stats(|stats| {
+            report.documents = stats.documents;
+            report.subschemas = stats
+                .subschemas
+                .iter()
+                .map(|(path, counts)| SubschemaStats {
+                    schema_path: path.clone(),
+                    id: None,
+                    evaluations: counts.evaluations,
+                    failures: counts.failures,
+                    errors: counts.errors.iter().map(|(k, &v)| (k.clone(), v)).collect(),
+                    micros: micros(counts.time),
+                })
+                .collect();
+            report.slowest_patterns = stats
+                .patterns
+                .iter()
+                .map(|(pattern, &(evaluations, time))| PatternStats {
+                    pattern: pattern.clone(),
+                    evaluations,
+                    micros: micros(time),
+                })
+                .collect();
+        });
+        report
+            .subschemas
+            .sort_by(|a, b| a.schema_path.cmp(&b.schema_path));
+        report.slowest_patterns.sort_by(|a, b| {
+            b.micros
+                .cmp(&a.micros)
+                .then_with(|| a.pattern.cmp(&b.pattern))
+        });
+        report.slowest_patterns.truncate(SLOWEST_PATTERNS);
+        report
+    }
+}
+
+fn micros(time: Duration) -> u64 {
+    time.as_secs() * 1_000_000 + u64::from(time.subsec_micros())
+}
+
+/// How often a subschema was evaluated and failed, see `StatsCollector`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubschemaStats {
+    /// JSON pointer to the subschema. A subschema reached through references
+    /// is counted under each path it's reached by.
+    pub schema_path: String,
+    /// The identifier of the subschema, see `StatsReport::assign_ids`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// How many values the subschema was applied to.
+    pub evaluations: u64,
+    /// How many of them it rejected, because of its own keywords or those of
+    /// its subschemas.
+    pub failures: u64,
+    /// The errors reported by the keywords of the subschema itself, by the name
+    /// of their `ErrorCode`.
+    pub errors: BTreeMap<String, u64>,
+    /// The time spent in the subschema, in microseconds.
+    pub micros: u64,
+}
+
+/// How often a regular expression of `pattern` or `patternProperties` was
+/// matched and how long that took.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternStats {
+    /// The regular expression.
+    pub pattern: String,
+    /// How often it was compiled or matched against a string or property name.
+    /// A `pattern` is compiled and matched in one go.
+    pub evaluations: u64,
+    /// The time spent on it, in microseconds.
+    pub micros: u64,
+}
+
+/// The statistics of a `StatsCollector`. `Display` shows them as a table, with
+/// the slowest subschemas first.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsReport {
+    /// The number of validation runs.
+    pub documents: u64,
+    /// Every subschema that was evaluated, sorted by path.
+    pub subschemas: Vec<SubschemaStats>,
+    /// The regular expressions that took the most time, slowest first.
+    pub slowest_patterns: Vec<PatternStats>,
+}
+
+impl StatsReport {
+    /// Fills in the identifiers of the subschemas from `Schema::assign_ids` of
+    /// `schema`, which the statistics have to be about. Paths that go through
+    /// references have none.
+    pub fn assign_ids(&mut self, schema: &Schema) {
+        let ids = schema.assign_ids();
+        for subschema in &mut self.subschemas {
+            subschema.id = ids.get(&subschema.schema_path).cloned();
+        }
+    }
+}
+
+impl fmt::Display for StatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} documents validated", self.documents)?;
+        let mut subschemas: Vec<_> = self.subschemas.iter().collect();
+        subschemas.sort_by_key(|s| Reverse(s.micros));
+        writeln!(
+            f,
+            "{:>11} {:>9} {:>12}  schema path",
+            "evaluations", "failures", "time (us)"
+        )?;
+        for s in subschemas {
+            let path = if s.schema_path.is_empty() {
+                "/"
+            } else {
+                &s.schema_path
+            };
+            write!(
+                f,
+                "{:>11} {:>9} {:>12}  {}",
+                s.evaluations, s.failures, s.micros, path
+            )?;
+            if !s.errors.is_empty() {
+                let errors: Vec<_> = s
+                    .errors
+                    .iter()
+                    .map(|(k, v)| format!("{} {}", k, v))
+                    .collect();
+                write!(f, " ({})", errors.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        if !self.slowest_patterns.is_empty() {
+            writeln!(
+                f,
+                "{:>11} {:>9} {:>12}  pattern",
+                "evaluations", "", "time (us)"
+            )?;
+            for p in &self.slowest_patterns {
+                writeln!(
+                    f,
+                    "{:>11} {:>9} {:>12}  {}",
+                    p.evaluations, "", p.micros, p.pattern
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json;
+
+    use super::*;
+    use options::ValidateOptions;
+    use session::Session;
+
+    #[test]
+    fn counts() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string", "pattern": "^[a-z]+$"},
+                "id": {"anyOf": [{"type": "integer"}, {"type": "string"}]},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        }))
+        .unwrap();
+        let stats = Arc::new(StatsCollector::new());
+        let options = ValidateOptions {
+            stats: Some(stats.clone()),
+            ..ValidateOptions::default()
+        };
+        let mut session = Session::with_options(&schema, options);
+        let documents = [
+            json!({"name": "ada", "id": "x", "tags": ["x", "y"]}),
+            json!({"name": "Ada"}),
+            json!({"tags": [1]}),
+            json!({"name": 5}),
+        ];
+        let valid: Vec<_> = documents
+            .iter()
+            .map(|document| session.validate(document).is_ok())
+            .collect();
+        assert_eq!(valid, vec![true, false, false, false]);
+
+        let report = stats.report();
+        assert_eq!(report.documents, 4);
+        let counts: Vec<_> = report
+            .subschemas
+            .iter()
+            .map(|s| {
+                let errors: Vec<_> = s.errors.iter().map(|(k, &v)| (k.as_str(), v)).collect();
+                (s.schema_path.as_str(), s.evaluations, s.failures, errors)
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("", 4, 3, vec![("MissingProperties", 1)]),
+                ("/properties/id", 1, 0, vec![]),
+                ("/properties/id/anyOf/0", 1, 1, vec![("TypeMismatch", 1)]),
+                ("/properties/id/anyOf/1", 1, 0, vec![]),
+                (
+                    "/properties/name",
+                    3,
+                    2,
+                    vec![("RegexMismatch", 1), ("TypeMismatch", 1)]
+                ),
+                ("/properties/tags", 2, 1, vec![]),
+                ("/properties/tags/items", 3, 1, vec![("TypeMismatch", 1)]),
+            ]
+        );
+        assert_eq!(report.slowest_patterns.len(), 1);
+        assert_eq!(report.slowest_patterns[0].pattern, "^[a-z]+$");
+        assert_eq!(report.slowest_patterns[0].evaluations, 2);
+
+        let table = report.to_string();
+        assert!(table.starts_with("4 documents validated\n"));
+        assert!(table.contains("  /properties/name (RegexMismatch 1, TypeMismatch 1)\n"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["subschemas"][0]["errors"],
+            json!({"MissingProperties": 1})
+        );
+        assert!(json["subschemas"][0].get("id").is_none());
+
+        let mut report = report;
+        report.assign_ids(&schema);
+        let ids = schema.assign_ids();
+        assert_eq!(report.subschemas[6].id.as_ref(), Some(&ids["/properties/tags/items"]));
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{self, Value};
+use url::Url;
+
+use errors::{ErrorKind, Result, ValidationErrors};
+use options::ValidateOptions;
+use outcome::ValidationOutcome;
+use output::ValidationOutput;
+use resolution::{resolve_all, ResolutionReport};
+use schema::Schema;
+
+/// A set of schema documents that reference each other, like a library of shared
+/// definitions and the schemas using them. A reference like
+/// `lib.json#/definitions/money` is resolved against the `$id` of the document
+/// it's in and looked up here.
+///
+/// A store can also hold several versions of the same schema, told apart by
+/// their `$id`, see `SchemaStore::versions`.
+#[derive(Debug)]
+pub struct SchemaStore {
+    documents: HashMap<String, Schema>,
+    versioning: Box<dyn Versioning>,
+}
+
+impl Default for SchemaStore {
+    fn default() -> SchemaStore {
+        SchemaStore {
+            documents: HashMap::new(),
+            versioning: Box::new(PathVersioning),
+        }
+    }
+}
+
+/// A version number like `3` or `2.1.0`. Versions compare component by
+/// component, so `2.10` is newer than `2.9`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(pub Vec<u64>);
+
+impl Version {
+    /// Parses versions like `3`, `v3` or `2.1.0`.
+    pub fn parse(text: &str) -> Option<Version> {
+        let text = text.trim_start_matches(['v', 'V']);
+        let components: Option<Vec<u64>> = text.split('.').map(|c| c.parse().ok()).collect();
+        components.map(Version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let components: Vec<_> = self.0.iter().map(ToString::to_string).collect();
+        write!(f, "v{}", components.join("."))
+    }
+}
+
+/// How the version of a schema is encoded in its `$id`.
+pub trait Versioning: fmt::Debug {
+    /// Splits `id` into the id shared by all versions of the schema and the
+    /// version, or returns `None` if `id` has no version.
+    fn split(&self, id: &Url) -> Option<(String, Version)>;
+}
+
+/// The version is a path segment of the `$id`, like
+/// `https://example.com/order/v3.json` or `https://example.com/v2.1/order.json`.
+/// The id without that segment (`https://example.com/order` and
+/// `https://example.com/order.json`) is shared by all versions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PathVersioning;
+
+impl Versioning for PathVersioning {
+    fn split(&self, id: &Url) -> Option<(String, Version)> {
+        let segments: Vec<&str> = id.path_segments()?.collect();
+        let (index, version) = segments.iter().enumerate().rev().find_map(|(i, segment)| {
+            let segment = segment.trim_end_matches(".json");
+            Version::parse(segment).map(|version| (i, version))
+        })?;
+        let path: Vec<&str> = segments
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != index)
+            .map(|(_, segment)| *segment)
+            .collect();
+        let mut base = id.clone();
+        base.set_path(path.join("/").trim_end_matches('/'));
+        base.set_fragment(None);
+        Some((base.into_string(), version))
+    }
+}
+
+/// Brings different spellings of the same document URI to the same key.
+fn normalize(id: &str) -> String {
+    let id = id.trim_end_matches('#');
+    match Url::parse(id) {
+        Ok(url) => url.into_string(),
+        Err(_) => id.into(),
+    }
+}
+
+/// The key of the document the URI `document` refers to, relative to `base`, the
+/// `$id` of the document containing the reference.
+pub(crate) fn document_id(base: Option<&str>, document: &str) -> String {
+    let joined = base
+        .and_then(|base| Url::parse(base).ok())
+        .and_then(|base| base.join(document).ok());
+    match joined {
+        Some(url) => normalize(url.as_str()),
+        None => normalize(document),
+    }
+}
+
+impl SchemaStore {
+    /// Creates an empty store.
+    pub fn new() -> SchemaStore {
+        SchemaStore::default()
+    }
+
+    /// Registers `schema` under its `$id`. Fails if the schema has none.
+    pub fn add(&mut self, schema: Schema) -> Result<()> {
+        let id = match schema.id() {
+            Some(id) => normalize(id),
+            None => bail!(ErrorKind::MissingId),
+        };
+        self.documents.insert(id, schema);
+        Ok(())
+    }
+
+    /// Registers the schemas of a schema document: a single schema or an array
+    /// of schemas, like related schemas kept in one file. Each is registered
+    /// under its `$id`, so they can reference each other like separate
+    /// documents. Returns the ids in document order. Fails without registering
+    /// anything if a schema doesn't parse or has no `$id`.
+    pub fn load_document(&mut self, document: Value) -> Result<Vec<String>> {
+        let (schemas, several): (Vec<Schema>, bool) = match document {
+            Value::Array(values) => (
+                values
+                    .into_iter()
+                    .map(serde_json::from_value)
+                    .collect::<::std::result::Result<_, _>>()?,
+                true,
+            ),
+            value => (vec![serde_json::from_value(value)?], false),
+        };
+        let mut ids = vec![];
+        for (index, schema) in schemas.iter().enumerate() {
+            match schema.id() {
+                Some(id) => ids.push(normalize(id)),
+                None if several => bail!(ErrorKind::MissingIdInDocument(index)),
+                None => bail!(ErrorKind::MissingId),
+            }
+        }
+        for (id, schema) in ids.iter().zip(schemas) {
+            self.documents.insert(id.clone(), schema);
+        }
+        Ok(ids)
+    }
+
+    /// Registers `schema` under `id`, regardless of its `$id`.
+    pub fn insert<I: AsRef<str>>(&mut self, id: I, schema: Schema) {
+        self.documents.insert(normalize(id.as_ref()), schema);
+    }
+
+    /// The document registered under `id`.
+    pub fn get(&self, id: &str) -> Option<&Schema> {
+        self.documents.get(&normalize(id))
+    }
+
+    /// Sets how the versions of schemas are read from their `$id`, instead of
+    /// `PathVersioning`.
+    pub fn set_versioning<V: Versioning + 'static>(&mut self, versioning: V) {
+        self.versioning = Box::new(versioning);
+    }
+
+    /// The registered versions of the schema whose versions share `base_id`,
+    /// oldest first.
+    pub fn versions(&self, base_id: &str) -> Vec<(Version, &Schema)> {
+        let base_id = normalize(base_id);
+        let mut versions: Vec<_> = self
+            .documents
+            .iter()
+            .filter_map(|(id, schema)| {
+                let (base, version) = self.versioning.split(&Url::parse(id).ok()?)?;
+                if base == base_id {
+                    Some((version, schema))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        versions.sort_by(|a, b| a.0.cmp(&b.0));
+        versions
+    }
+
+    /// Validates `value` against the newest registered version of the schema
+    /// whose versions share `base_id`. Returns the version used along with the
+    /// result. Fails if no version is registered.
+    pub fn validate_against_latest<'json>(
+        &self,
+        value: &'json Value,
+        base_id: &str,
+    ) -> Result<(Version, ValidationOutput<'json>)> {
+        match self.versions(base_id).pop() {
+            Some((version, schema)) => {
+                let output = self.validate_with(schema, value, &ValidateOptions::default());
+                Ok((version, output))
+            }
+            None => bail!(ErrorKind::NoVersions(base_id.into())),
+        }
+    }
+
+    /// The registered versions of the schema whose versions share `base_id` that
+    /// `value` is valid against, oldest first.
+    pub fn compatible_versions(&self, value: &Value, base_id: &str) -> Vec<Version> {
+        self.versions(base_id)
+            .into_iter()
+            .filter(|&(_, schema)| self.validate(schema, value).is_ok())
+            .map(|(version, _)| version)
+            .collect()
+    }
+
+    /// The document the URI `document` refers to, relative to `base`, the `$id` of
+    /// the document containing the reference.
+    pub(crate) fn resolve(&self, base: Option<&str>, document: &str) -> Option<&Schema> {
+        self.documents.get(&document_id(base, document))
+    }
+
+    /// Resolves the references of `schema` and of the documents of this store
+    /// they reach, reporting how each resolved and which documents reference
+    /// each other. Nothing is fetched, references by absolute URI are looked up
+    /// here too.
+    pub fn resolve_references(&self, schema: &Schema) -> ResolutionReport {
+        resolve_all(schema, Some(self))
+    }
+
+    /// Validates a self-describing document against the schema registered under
+    /// the URI of its `$schema` member. Errors about the `$schema` member itself
+    /// are left out, it only names the schema, but it's counted by
+    /// `maxProperties`. Fails if `value` isn't an object with a `$schema`
+    /// string, or if no schema is registered under it. Nothing is fetched.
+    pub fn validate_self_described<'json>(
+        &self,
+        value: &'json Value,
+    ) -> Result<ValidationOutcome<'json>> {
+        let output = self.validate_self_described_with(value, &ValidateOptions::default())?;
+        Ok(output.outcome())
+    }
+
+    /// `validate_self_described` with the given options.
+    pub fn validate_self_described_with<'json>(
+        &self,
+        value: &'json Value,
+        options: &ValidateOptions,
+    ) -> Result<ValidationOutput<'json>> {
+        let id = match value.get("$schema").and_then(Value::as_str) {
+            Some(id) => id,
+            None => bail!(ErrorKind::NotSelfDescribed),
+        };
+        let schema = match self.get(id) {
+            Some(schema) => schema,
+            None => bail!(ErrorKind::UnknownSchema(id.into())),
+        };
+        let mut output = self.validate_with(schema, value, options);
+        output.errors.retain(|error| {
+            let path = &error.instance_path;
+            !(path == "/$schema" || path.starts_with("/$schema/"))
+        });
+        Ok(output)
+    }
+
+    /// Validates `value` against `schema`, which may reference the documents of
+    /// this store.
+    pub fn validate<'json>(
+        &self,
+        schema: &Schema,
+        value: &'json Value,
+    ) -> ::std::result::Result<(), ValidationErrors<'json>> {
+        let output = self.validate_with(schema, value, &ValidateOptions::default());
+        if output.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(output.errors))
+        }
+    }
+
+    /// Validates `value` against `schema` with the given options, see
+    /// `Schema::validate_with`.
+    pub fn validate_with<'json>(
+        &self,
+        schema: &Schema,
+        value: &'json Value,
+        options: &ValidateOptions,
+    ) -> ValidationOutput<'json> {
+        schema.validate_in(value, options, Some(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use serde_json;
+
+    use super::*;
+    use errors::ErrorCode;
+    use output::WarningKind;
+
+    fn load(path: &str) -> Schema {
+        serde_json::from_reader(File::open(path).unwrap()).unwrap()
+    }
+
+    fn store() -> SchemaStore {
+        let mut store = SchemaStore::new();
+        store.add(load("data/library.json")).unwrap();
+        store
+    }
+
+    #[test]
+    fn definitions_only_document() {
+        let library = load("data/library.json");
+        assert!(library.is_definitions_only());
+        assert_eq!(library.id(), Some("https://example.com/schemas/library.json"));
+        for name in &["money", "currency", "address", "email"] {
+            assert!(library.pointer(&format!("/definitions/{}", name)).is_some());
+        }
+
+        let input = json!({"anything": 1});
+        let output = library.validate_with(
+            &input,
+            &ValidateOptions {
+                collect_warnings: true,
+                ..Default::default()
+            },
+        );
+        assert!(output.is_valid());
+        assert_eq!(output.warnings[0].kind, WarningKind::DefinitionsOnly);
+        assert!(!load("data/order.json").is_definitions_only());
+    }
+
+    #[test]
+    fn external_references() {
+        let store = store();
+        let order = load("data/order.json");
+        let valid = json!({
+            "total": {"amount": 12.5, "currency": "EUR"},
+            "shipping": {"street": "Main St 1", "city": "Springfield"},
+            "contact": "jane@example.com"
+        });
+        store.validate(&order, &valid).unwrap();
+
+        let invalid = json!({
+            "total": {"amount": -1, "currency": "euro"},
+            "shipping": {"street": "Main St 1"},
+            "contact": "jane"
+        });
+        let errors = store.validate(&order, &invalid).unwrap_err();
+        let mut paths: Vec<_> = errors.0.iter().map(|e| e.instance_path.as_str()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec!["/contact", "/shipping", "/total/amount", "/total/currency"]
+        );
+        assert!(errors.contains_missing_property("city"));
+    }
+
+    #[test]
+    fn unregistered_document() {
+        let order = load("data/order.json");
+        let input = json!({"contact": "jane@example.com"});
+        let errors = order.validate(&input).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::UnresolvedPointer]);
+        SchemaStore::new().validate(&order, &input).unwrap_err();
+        store().validate(&order, &input).unwrap();
+    }
+
+    fn order_version(version: u32, quantity: Value) -> Schema {
+        serde_json::from_value(json!({
+            "$id": format!("https://example.com/schemas/order/v{}.json", version),
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["quantity"],
+            "properties": {"quantity": quantity}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn versions() {
+        let mut store = SchemaStore::new();
+        store.add(order_version(1, json!({"type": "string"}))).unwrap();
+        store
+            .add(order_version(2, json!({"type": "integer", "minimum": 1})))
+            .unwrap();
+        store
+            .add(order_version(3, json!({"type": "integer", "minimum": 10})))
+            .unwrap();
+        store.add(load("data/library.json")).unwrap();
+
+        let base_id = "https://example.com/schemas/order";
+        let versions = store.versions(base_id);
+        let numbers: Vec<_> = versions.iter().map(|(v, _)| v.clone()).collect();
+        assert_eq!(numbers, vec![Version(vec![1]), Version(vec![2]), Version(vec![3])]);
+        assert_eq!(versions[2].1.id_url().unwrap().path(), "/schemas/order/v3.json");
+
+        let input = json!({"quantity": 5});
+        let (version, output) = store.validate_against_latest(&input, base_id).unwrap();
+        assert_eq!(version.to_string(), "v3");
+        assert!(!output.is_valid());
+        assert_eq!(store.compatible_versions(&input, base_id), vec![Version(vec![2])]);
+
+        match *store
+            .validate_against_latest(&input, "https://example.com/schemas/invoice")
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::NoVersions(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn version_in_path() {
+        let split = |id: &str| PathVersioning.split(&Url::parse(id).unwrap());
+        assert_eq!(
+            split("https://example.com/v2.1/order.json"),
+            Some((
+                "https://example.com/order.json".to_string(),
+                Version(vec![2, 1])
+            ))
+        );
+        assert_eq!(split("https://example.com/order.json"), None);
+        assert!(Version::parse("2.10").unwrap() > Version::parse("v2.9").unwrap());
+    }
+
+    #[test]
+    fn multi_schema_document() {
+        let document = json!([
+            {
+                "$id": "https://example.com/schemas/common.json",
+                "definitions": {"sku": {"type": "string", "pattern": "^[A-Z]{3}-[0-9]+$"}}
+            },
+            {
+                "$id": "https://example.com/schemas/item.json",
+                "type": "object",
+                "properties": {
+                    "sku": {"$ref": "common.json#/definitions/sku"},
+                    "count": {"type": "integer"}
+                }
+            }
+        ]);
+        let mut store = SchemaStore::new();
+        let ids = store.load_document(document.clone()).unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                "https://example.com/schemas/common.json",
+                "https://example.com/schemas/item.json"
+            ]
+        );
+        let item = store.get("https://example.com/schemas/item.json").unwrap();
+        store.validate(item, &json!({"sku": "ABC-12", "count": 1})).unwrap();
+        let invalid = json!({"sku": "abc", "count": 1});
+        let errors = store.validate(item, &invalid).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::RegexMismatch]);
+        assert_eq!(errors.0[0].instance_path, "/sku");
+
+        let mut store = SchemaStore::new();
+        let mut document = document;
+        document[1].as_object_mut().unwrap().remove("$id");
+        match *store.load_document(document).unwrap_err().kind() {
+            ErrorKind::MissingIdInDocument(1) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(store.get("https://example.com/schemas/common.json").is_none());
+
+        let single: Value =
+            serde_json::from_reader(File::open("data/library.json").unwrap()).unwrap();
+        let ids = store.load_document(single).unwrap();
+        assert_eq!(ids, vec!["https://example.com/schemas/library.json"]);
+    }
+
+    #[test]
+    fn registration() {
+        let mut store = SchemaStore::new();
+        let anonymous: Schema = serde_json::from_value(json!({"type": "string"})).unwrap();
+        match *store.add(anonymous.clone()).unwrap_err().kind() {
+            ErrorKind::MissingId => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        store.insert("https://example.com/name.json#", anonymous);
+        assert!(store.get("https://example.com/name.json").is_some());
+    }
+
+    #[test]
+    fn self_described() {
+        let mut store = SchemaStore::new();
+        let event = json!({
+            "$id": "https://example.com/event-v2.json",
+            "type": "object",
+            "additionalProperties": {"type": "integer"},
+            "required": ["at"],
+            "properties": {"name": {"type": "string"}}
+        });
+        let user = json!({
+            "$id": "https://example.com/user.json",
+            "type": "object",
+            "required": ["name"],
+            "additionalProperties": true,
+            "properties": {"name": {"type": "string"}}
+        });
+        store.load_document(json!([event, user])).unwrap();
+
+        let document = json!({"$schema": "https://example.com/event-v2.json", "at": 5});
+        assert!(store.validate_self_described(&document).unwrap().is_valid());
+        let document = json!({"$schema": "https://example.com/event-v2.json", "at": "5"});
+        match store.validate_self_described(&document).unwrap() {
+            ValidationOutcome::Invalid(errors) => {
+                assert_eq!(errors.codes(), vec![ErrorCode::TypeMismatch]);
+                assert_eq!(errors.0[0].instance_path, "/at");
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+        let document = json!({"$schema": "https://example.com/user.json", "name": "Ann"});
+        assert!(store.validate_self_described(&document).unwrap().is_valid());
+        let document = json!({"$schema": "https://example.com/user.json"});
+        assert!(!store.validate_self_described(&document).unwrap().is_valid());
+
+        let document = json!({"$schema": "https://example.com/order.json"});
+        let error = store.validate_self_described(&document).unwrap_err();
+        match *error.kind() {
+            ErrorKind::UnknownSchema(ref id) => assert_eq!(id, "https://example.com/order.json"),
+            ref e => panic!("unexpected error {}", e),
+        }
+        let error = store.validate_self_described(&json!({"at": 5})).unwrap_err();
+        assert_eq!(error.kind().code(), ErrorCode::NotSelfDescribed);
+    }
+}
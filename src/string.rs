@@ -1,13 +1,19 @@
+use std::cmp::Ordering;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
-use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use chrono::prelude::*;
 use url::Url;
 
-use util::{JsonType, JsonValueExt};
+use types::JsonType;
+use object::PropertyMap;
 use schema::{Context, Schema, SchemaBase};
 use errors::{ErrorKind, ValidationError};
+use options::PatternMode;
+use output::WarningKind;
+use util::{compile_regex, deserialize_some};
+use rfc3339;
 
 #[allow(unused)]
 mod regex_serde {
@@ -34,6 +40,29 @@ mod regex_serde {
     }
 }
 
+/// Deserializes `pattern`, rejecting patterns that use constructs of ECMA-262
+/// regular expressions the regex engine doesn't have.
+mod pattern_serde {
+    use serde::{de, Deserialize, Deserializer};
+
+    use util::unsupported_regex_construct;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = Option::<String>::deserialize(deserializer)?;
+        match pattern.as_ref().and_then(|p| unsupported_regex_construct(p)) {
+            Some(construct) => Err(de::Error::custom(format!(
+                "unsupported pattern `{}`: {} can't be used in patterns",
+                pattern.unwrap_or_default(),
+                construct
+            ))),
+            None => Ok(pattern),
+        }
+    }
+}
+
 /// A schema for a JSON string like `"123"`. Supports validation
 /// of length (maximum or minimum), content (via `Regex`) or format
 /// (see `Format`)
@@ -41,68 +70,233 @@ mod regex_serde {
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
 pub struct StringSchema {
-    description: Option<String>,
-    id: Option<String>,
-    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "$id")]
+    pub(crate) id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-counterexamples")]
+    pub(crate) counterexamples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-removed-in")]
+    pub(crate) removed_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
+    pub(crate) enum_values: Option<Vec<Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "const",
+        default,
+        deserialize_with = "deserialize_some"
+    )]
+    pub(crate) const_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) definitions: Option<PropertyMap<Schema>>,
+    #[serde(skip)]
+    pub(crate) dollar_defs: bool,
+    #[serde(skip)]
+    pub(crate) nullable: bool,
 
-    min_length: Option<usize>,
-    max_length: Option<usize>,
-    pattern: Option<String>,
-    format: Option<Format>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) min_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_length: Option<usize>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "pattern_serde::deserialize"
+    )]
+    pub(crate) pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) format: Option<Format>,
+    /// The extension keywords `x-formatMinimum`, `x-formatMaximum` and their
+    /// exclusive variants bound strings of the formats `date-time`, `date` and
+    /// `time`, like `{"format": "date", "x-formatMinimum": "2020-01-01"}`.
+    /// Strings that don't parse under the format are only reported by `format`.
+    /// Date times and times are compared in UTC.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-formatMinimum")]
+    pub(crate) format_minimum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-formatExclusiveMinimum")]
+    pub(crate) format_exclusive_minimum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-formatMaximum")]
+    pub(crate) format_maximum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "x-formatExclusiveMaximum")]
+    pub(crate) format_exclusive_maximum: Option<String>,
+    /// Overrides `ValidateOptions::pattern_mode`, only set by the builder.
+    #[serde(skip)]
+    pattern_mode: Option<PatternMode>,
 }
 
 impl StringSchema {
-    fn validate_string<'json>(
+    /// The `x-format...` bounds with their keywords and how the strings within
+    /// them compare to them.
+    fn format_bounds(&self) -> [(&'static str, Option<&String>, &'static [Ordering]); 4] {
+        [
+            (
+                "x-formatMinimum",
+                self.format_minimum.as_ref(),
+                &[Ordering::Greater, Ordering::Equal],
+            ),
+            (
+                "x-formatExclusiveMinimum",
+                self.format_exclusive_minimum.as_ref(),
+                &[Ordering::Greater],
+            ),
+            (
+                "x-formatMaximum",
+                self.format_maximum.as_ref(),
+                &[Ordering::Less, Ordering::Equal],
+            ),
+            (
+                "x-formatExclusiveMaximum",
+                self.format_exclusive_maximum.as_ref(),
+                &[Ordering::Less],
+            ),
+        ]
+    }
+
+    /// Fails if an `x-format...` bound is given without the format `date-time`,
+    /// `date` or `time`, or doesn't parse under it.
+    pub(crate) fn check_format_bounds(&self) -> Result<(), String> {
+        for &(keyword, bound, _) in &self.format_bounds() {
+            let bound = match bound {
+                Some(bound) => bound,
+                None => continue,
+            };
+            match self.format {
+                Some(ref format) if TEMPORAL_FORMATS.contains(&format.as_str()) => {
+                    if parse_temporal(format, bound).is_none() {
+                        return Err(format!(
+                            "`{}` `{}` isn't a valid {}",
+                            keyword,
+                            bound,
+                            format.as_str()
+                        ));
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "`{}` needs the format `date-time`, `date` or `time`",
+                        keyword
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports a string of the format `date-time`, `date` or `time` outside the
+    /// `x-format...` bounds.
+    fn validate_format_bounds<'json>(
         &self,
+        ctx: &Context,
         value: &'json str,
         node: &'json Value,
         errors: &mut Vec<ValidationError<'json>>,
     ) {
-        if let Some(format) = self.format {
-            if !format.is_valid(value) {
-                errors.push(ValidationError {
-                    reason: ErrorKind::InvalidFormat(format),
-                    node: node,
-                })
+        let (format, parsed) = match self.format {
+            Some(ref format) => match parse_temporal(format, value) {
+                Some(parsed) => (format, parsed),
+                None => return,
+            },
+            None => return,
+        };
+        for &(keyword, bound, within) in &self.format_bounds() {
+            let (bound, limit) = match bound {
+                Some(bound) => match parse_temporal(format, bound) {
+                    Some(limit) => (bound, limit),
+                    None => continue,
+                },
+                None => continue,
+            };
+            if !ctx.applies_extension(keyword) || within.contains(&parsed.cmp(&limit)) {
+                continue;
             }
+            errors.push(ValidationError::new(
+                ErrorKind::FormatRange {
+                    keyword: keyword.into(),
+                    bound: bound.clone(),
+                    value: show_temporal(format, parsed),
+                },
+                node,
+            ));
+        }
+    }
+
+    pub(crate) fn validate_string<'json>(
+        &self,
+        ctx: &Context,
+        value: &'json str,
+        node: &'json Value,
+        errors: &mut Vec<ValidationError<'json>>,
+    ) {
+        match self.format {
+            Some(Format::Other(ref name)) => ctx.warn(WarningKind::UnknownFormat(name.clone())),
+            Some(ref format) if !format.is_valid(value) => errors.push(ValidationError::new(
+                ErrorKind::InvalidFormat(format.clone()),
+                node,
+            )),
+            _ => {}
         }
+        self.validate_format_bounds(ctx, value, node, errors);
 
         if let Some(min) = self.min_length {
             if value.len() < min {
-                errors.push(ValidationError {
-                    reason: ErrorKind::MinLength {
+                errors.push(ValidationError::new(
+                    ErrorKind::MinLength {
                         expected: min,
                         found: value.len(),
                     },
-                    node: node,
-                })
+                    node,
+                ))
             }
         }
 
         if let Some(max) = self.max_length {
             if value.len() > max {
-                errors.push(ValidationError {
-                    reason: ErrorKind::MinLength {
+                errors.push(ValidationError::new(
+                    ErrorKind::MaxLength {
                         expected: max,
                         found: value.len(),
                     },
-                    node: node,
-                })
+                    node,
+                ))
             }
         }
 
         if let Some(ref re) = self.pattern {
-            match Regex::new(re) {
-                Ok(re) => if !re.is_match(value) {
-                    errors.push(ValidationError {
-                        reason: ErrorKind::RegexMismatch { regex: re.clone() },
-                        node: node,
-                    })
+            let mode = self.pattern_mode.unwrap_or(ctx.options.pattern_mode);
+            let compiled = ctx.time_pattern(re, || {
+                let limits = &ctx.options.limits;
+                let compiled = match mode {
+                    PatternMode::Search => compile_regex(re, limits),
+                    PatternMode::FullMatch => compile_regex(&format!(r"\A(?:{})\z", re), limits),
+                };
+                compiled.map(|compiled| compiled.is_match(value))
+            });
+            match compiled {
+                Ok(matched) => if !matched {
+                    errors.push(ValidationError::new(
+                        ErrorKind::RegexMismatch {
+                            pattern: re.clone(),
+                        },
+                        node,
+                    ))
                 },
-                Err(_) => errors.push(ValidationError {
-                    reason: ErrorKind::InvalidRegex(re.clone()),
-                    node: node,
-                }),
+                // patterns beyond the limits are named by the limit
+                Err(e) => errors.push(ValidationError::new(
+                    ErrorKind::InvalidRegex(e.limit.map_or_else(|| re.clone(), |_| e.message)),
+                    node,
+                )),
             }
         }
     }
@@ -112,19 +306,15 @@ impl SchemaBase for StringSchema {
     #[doc(hidden)]
     fn validate_inner<'json>(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         value: &'json Value,
         errors: &mut Vec<ValidationError<'json>>,
     ) {
         match *value {
             Value::String(ref s) => {
-                self.validate_string(s.as_str(), value, errors);
+                self.validate_string(ctx, s.as_str(), value, errors);
             }
-            _ => errors.push(ValidationError::type_mismatch(
-                value,
-                JsonType::String,
-                value.get_type(),
-            )),
+            _ => errors.push(ValidationError::type_mismatch(value, JsonType::String)),
         }
     }
 }
@@ -135,11 +325,14 @@ pub struct StringSchemaBuilder {
     description: Option<String>,
     id: Option<String>,
     title: Option<String>,
+    examples: Option<Vec<Value>>,
+    counterexamples: Option<Vec<Value>>,
 
     min_length: Option<usize>,
     max_length: Option<usize>,
     pattern: Option<String>,
     format: Option<Format>,
+    pattern_mode: Option<PatternMode>,
 }
 
 #[allow(unused)]
@@ -160,6 +353,16 @@ impl StringSchemaBuilder {
         self.title = Some(value.into());
         self
     }
+    /// Adds a value the schema accepts, see `Schema::self_test`.
+    pub fn example(mut self, value: Value) -> Self {
+        self.examples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
+    /// Adds a value the schema rejects, see `Schema::self_test`.
+    pub fn counterexample(mut self, value: Value) -> Self {
+        self.counterexamples.get_or_insert_with(Vec::new).push(value);
+        self
+    }
 
     /// Set the minimum length for the string
     pub fn min_length(mut self, value: usize) -> Self {
@@ -176,6 +379,12 @@ impl StringSchemaBuilder {
         self.pattern = Some(pattern);
         self
     }
+    /// Set how the pattern matches, overriding `ValidateOptions::pattern_mode`.
+    /// Schemas parsed from JSON always follow the options.
+    pub fn pattern_mode(mut self, mode: PatternMode) -> Self {
+        self.pattern_mode = Some(mode);
+        self
+    }
     /// Set the string's format (e.g. `Format::DateTime`)
     pub fn format(mut self, format: Format) -> Self {
         self.format = Some(format);
@@ -187,42 +396,84 @@ impl StringSchemaBuilder {
             description: self.description,
             id: self.id,
             title: self.title,
+            nullable: false,
+            default: None,
+            examples: self.examples,
+            counterexamples: self.counterexamples,
+            deprecated: None,
+            removed_in: None,
+            enum_values: None,
+            const_value: None,
+            definitions: None,
+            dollar_defs: false,
 
             min_length: self.min_length,
             max_length: self.max_length,
             pattern: self.pattern,
             format: self.format,
+            format_minimum: None,
+            format_exclusive_minimum: None,
+            format_maximum: None,
+            format_exclusive_maximum: None,
+            pattern_mode: self.pattern_mode,
         })
     }
 }
 
 /// Checking the string's contents according to a given format.
-#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Format {
     /// Date time format according to RFC 3339
-    #[serde(rename = "date-time")]
     DateTime,
+    /// A full date of RFC 3339, like `2020-01-31`
+    Date,
+    /// A time of day with an offset, of RFC 3339, like `08:30:06Z`
+    Time,
     /// An email address
-    #[serde(rename = "email")]
     Email,
     /// A host name
-    #[serde(rename = "hostname")]
     Hostname,
     /// An IPv4 address
-    #[serde(rename = "ipv4")]
     Ipv4,
     /// An IPv6 address
-    #[serde(rename = "ipv6")]
     Ipv6,
     /// A URI
-    #[serde(rename = "uri")]
     Uri,
+    /// A format this crate doesn't know. Any string is accepted.
+    Other(String),
 }
 
 impl Format {
+    /// The name of the format as used in the `format` keyword.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Format::DateTime => "date-time",
+            Format::Date => "date",
+            Format::Time => "time",
+            Format::Email => "email",
+            Format::Hostname => "hostname",
+            Format::Ipv4 => "ipv4",
+            Format::Ipv6 => "ipv6",
+            Format::Uri => "uri",
+            Format::Other(ref name) => name,
+        }
+    }
+
+    /// Whether strings are actually checked against the format. Unknown formats,
+    /// `email` and `hostname` accept any string.
+    pub fn is_checked(&self) -> bool {
+        match *self {
+            Format::DateTime | Format::Date | Format::Time => true,
+            Format::Uri | Format::Ipv4 | Format::Ipv6 => true,
+            Format::Email | Format::Hostname | Format::Other(_) => false,
+        }
+    }
+
     fn is_valid(&self, input: &str) -> bool {
         match *self {
-            Format::DateTime => DateTime::parse_from_rfc3339(input).is_ok(),
+            Format::DateTime => rfc3339::is_date_time(input),
+            Format::Date => rfc3339::is_date(input),
+            Format::Time => rfc3339::is_time(input),
             Format::Uri => Url::parse(input).is_ok(),
             Format::Ipv4 => input.parse::<Ipv4Addr>().is_ok(),
             Format::Ipv6 => input.parse::<Ipv6Addr>().is_ok(),
@@ -231,10 +482,71 @@ impl Format {
     }
 }
 
+/// The formats the `x-format...` bounds apply to.
+const TEMPORAL_FORMATS: &[&str] = &["date-time", "date", "time"];
+
+/// Parses a string of the format `date-time`, `date` or `time` for comparing
+/// with the `x-format...` bounds. Date times and times are moved to UTC, times
+/// onto the same day.
+fn parse_temporal(format: &Format, value: &str) -> Option<NaiveDateTime> {
+    if !format.is_valid(value) {
+        return None;
+    }
+    match format.as_str() {
+        "date-time" => DateTime::parse_from_rfc3339(value).ok().map(|d| d.naive_utc()),
+        "date" => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0)),
+        "time" => DateTime::parse_from_rfc3339(&format!("2000-01-01T{}", value))
+            .ok()
+            .map(|d| d.naive_utc()),
+        _ => None,
+    }
+}
+
+/// Writes a value `parse_temporal` returned in the format, in UTC.
+fn show_temporal(format: &Format, value: NaiveDateTime) -> String {
+    match format.as_str() {
+        "date" => value.format("%Y-%m-%d").to_string(),
+        "time" => value.format("%H:%M:%S%.fZ").to_string(),
+        _ => value.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
+    }
+}
+
+impl<'a> From<&'a str> for Format {
+    fn from(name: &'a str) -> Format {
+        match name {
+            "date-time" => Format::DateTime,
+            "date" => Format::Date,
+            "time" => Format::Time,
+            "email" => Format::Email,
+            "hostname" => Format::Hostname,
+            "ipv4" => Format::Ipv4,
+            "ipv6" => Format::Ipv6,
+            "uri" => Format::Uri,
+            other => Format::Other(other.into()),
+        }
+    }
+}
+
+impl Serialize for Format {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Format, D::Error> {
+        String::deserialize(deserializer).map(|name| Format::from(name.as_str()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json;
+    use errors::ErrorCode;
+    use options::ValidateOptions;
 
     #[test]
     fn string_len() {
@@ -273,4 +585,141 @@ mod tests {
         let input = serde_json::from_str(r#" "1990-12-31T23:59:60" "#).unwrap();
         assert!(schema.validate(&input).is_err());
     }
+
+    #[test]
+    fn pattern_modes() {
+        let schema: Schema =
+            serde_json::from_value(json!({"type": "string", "pattern": "[a-z]+"})).unwrap();
+        let input = json!("123abc");
+        schema.validate(&input).unwrap();
+        let options = ValidateOptions {
+            pattern_mode: PatternMode::FullMatch,
+            ..Default::default()
+        };
+        let output = schema.validate_with(&input, &options);
+        // the pattern as written, not as compiled for full matches
+        match output.errors[0].reason {
+            ErrorKind::RegexMismatch { ref pattern } => assert_eq!(pattern, "[a-z]+"),
+            ref reason => panic!("unexpected {}", reason),
+        }
+        assert_eq!(
+            output.errors[0].reason.to_string(),
+            "Regex did not match: [a-z]+"
+        );
+        assert!(schema.validate_with(&json!("abc"), &options).is_valid());
+
+        // the mode of the schema wins over the options
+        let schema = StringSchemaBuilder::default()
+            .pattern("a|b".into())
+            .pattern_mode(PatternMode::FullMatch)
+            .build();
+        schema.validate(&json!("b")).unwrap();
+        assert!(schema.validate(&json!("ab")).is_err());
+    }
+
+    #[test]
+    fn unsupported_patterns() {
+        for &(pattern, construct) in &[
+            ("^(?!admin)", "lookahead"),
+            ("(?<=\\$)\\d+", "lookbehind"),
+            ("(a)\\1", "backreferences"),
+        ] {
+            let schema = json!({"type": "string", "pattern": pattern});
+            let error = serde_json::from_value::<Schema>(schema)
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("unsupported pattern"), "{}", error);
+            assert!(error.contains(construct), "{}", error);
+        }
+        let schema: Schema =
+            serde_json::from_value(json!({"type": "string", "pattern": "[\\1(?=]\\(?!"})).unwrap();
+        schema.validate(&json!("1(?!")).unwrap();
+    }
+
+    #[test]
+    fn format_bounds() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "string",
+            "format": "date-time",
+            "x-formatMinimum": "2020-01-01T00:00:00+02:00",
+            "x-formatExclusiveMaximum": "2021-01-01T00:00:00Z"
+        }))
+        .unwrap();
+        // the minimum is 2019-12-31T22:00:00 in UTC
+        schema.validate(&json!("2019-12-31T22:00:00Z")).unwrap();
+        schema
+            .validate(&json!("2020-01-01T00:00:00+02:00"))
+            .unwrap();
+        schema.validate(&json!("2020-12-31T23:59:59Z")).unwrap();
+        let early = json!("2019-12-31T23:00:00+02:00");
+        let errors = schema.validate(&early).unwrap_err();
+        assert_eq!(
+            errors.0[0].to_string(),
+            "Error at JSON value `\"2019-12-31T23:00:00+02:00\"`: Date or time out of range: \
+             `x-formatMinimum` is 2020-01-01T00:00:00+02:00, value is 2019-12-31T21:00:00Z"
+        );
+        let late = json!("2021-01-01T02:00:00+02:00");
+        let errors = schema.validate(&late).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::FormatRange]);
+        assert!(schema.validate(&json!("2021-01-01T01:00:00Z")).is_err());
+
+        // values that don't parse only fail `format`
+        let date = json!("2019-12-31");
+        let errors = schema.validate(&date).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::InvalidFormat]);
+
+        let dates: Schema = serde_json::from_value(json!({
+            "type": "string",
+            "format": "date",
+            "x-formatExclusiveMinimum": "2020-01-01",
+            "x-formatMaximum": "2020-12-31"
+        }))
+        .unwrap();
+        dates.validate(&json!("2020-01-02")).unwrap();
+        dates.validate(&json!("2020-12-31")).unwrap();
+        assert!(dates.validate(&json!("2020-01-01")).is_err());
+        assert!(dates.validate(&json!("2021-01-01")).is_err());
+        let date = json!("January 1st");
+        let errors = dates.validate(&date).unwrap_err();
+        assert_eq!(errors.codes(), vec![ErrorCode::InvalidFormat]);
+
+        let times: Schema = serde_json::from_value(json!({
+            "type": "string",
+            "format": "time",
+            "x-formatMinimum": "09:00:00Z",
+            "x-formatMaximum": "17:00:00Z"
+        }))
+        .unwrap();
+        times.validate(&json!("12:00:00+02:00")).unwrap();
+        times.validate(&json!("17:00:00Z")).unwrap();
+        assert!(times.validate(&json!("10:00:00+02:00")).is_err());
+
+        // strict mode ignores the bounds with a warning
+        let options = ValidateOptions {
+            strict_spec: true,
+            collect_warnings: true,
+            ..Default::default()
+        };
+        let early = json!("10:00:00+02:00");
+        let output = times.validate_with(&early, &options);
+        assert!(output.is_valid());
+        let ignored = WarningKind::IgnoredExtension("x-formatMinimum".into());
+        assert!(output.warnings.iter().any(|w| w.kind == ignored));
+
+        for &(ref schema, message) in &[
+            (
+                json!({"type": "string", "x-formatMinimum": "2020-01-01"}),
+                "`x-formatMinimum` needs the format `date-time`, `date` or `time`",
+            ),
+            (
+                json!({"type": "string", "format": "date", "x-formatMaximum": "2020-01-01T00:00:00Z"}),
+                "`x-formatMaximum` `2020-01-01T00:00:00Z` isn't a valid date",
+            ),
+        ] {
+            let error = serde_json::from_value::<Schema>(schema.clone())
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains(message), "{}", error);
+        }
+    }
 }
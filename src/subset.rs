@@ -0,0 +1,768 @@
+use std::fmt;
+
+use serde_json::{self, Value};
+
+use array::{ArraySchema, Items};
+use combinator::CombinatorSchema;
+use diagnostics::{keyword_enforcement, Enforcement};
+use number::Range;
+use object::ObjectSchema;
+use satisfiability::kinds;
+use schema::Schema;
+use string::StringSchema;
+use util::pointer_token;
+
+/// Whether one schema accepts every value another accepts, see `is_subschema`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubsetResult {
+    /// Every value the first schema accepts is accepted by the second.
+    Subset,
+    /// A value the first schema accepts is rejected by the second. Holds the
+    /// JSON pointer to the value within an instance and the reason, which names
+    /// the value.
+    NotSubset(String, String),
+    /// A part of the schemas is beyond the checks, like two different
+    /// `pattern`s. Holds the JSON pointer to the values concerned within an
+    /// instance and the reason.
+    Unknown(String, String),
+}
+
+use self::SubsetResult::*;
+
+impl SubsetResult {
+    /// The result for both `self` and `other` having to hold.
+    fn and<F: FnOnce() -> SubsetResult>(self, other: F) -> SubsetResult {
+        match self {
+            NotSubset(..) => self,
+            Subset => other(),
+            Unknown(..) => match other() {
+                not @ NotSubset(..) => not,
+                _ => self,
+            },
+        }
+    }
+}
+
+impl fmt::Display for SubsetResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let display_path = |path: &str| {
+            if path.is_empty() {
+                "/".to_string()
+            } else {
+                path.to_string()
+            }
+        };
+        match *self {
+            Subset => write!(
+                f,
+                "subset: the second schema accepts every value the first accepts"
+            ),
+            NotSubset(ref path, ref reason) => {
+                write!(f, "not a subset at `{}`: {}", display_path(path), reason)
+            }
+            Unknown(ref path, ref reason) => {
+                write!(f, "unknown at `{}`: {}", display_path(path), reason)
+            }
+        }
+    }
+}
+
+/// Whether `b` accepts every value `a` accepts, that is whether `b` is a
+/// relaxation of `a`, as far as a conservative analysis can tell: numeric and
+/// length ranges, `required`, `enum` and `const`, `type`, `uniqueItems` and,
+/// recursively, properties and items. `NotSubset` is only returned with a value
+/// `a` accepts and `b` rejects. Parts the analysis can't decide, like different
+/// `pattern`s, give `Unknown`.
+pub fn is_subschema(a: &Schema, b: &Schema) -> SubsetResult {
+    let any = Schema::Empty(Default::default());
+    let mut checker = Checker {
+        a_root: a,
+        b_root: b,
+        any: &any,
+        following: vec![],
+    };
+    checker.check(a, b, "")
+}
+
+/// The keywords whose constraints the checks compare. Other keywords of the
+/// second schema have to be the same in the first.
+const COMPARED: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "format",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "items",
+    "required",
+    "minProperties",
+    "maxProperties",
+    "properties",
+    "additionalProperties",
+];
+
+/// Walks two schemas side by side.
+struct Checker<'s> {
+    a_root: &'s Schema,
+    b_root: &'s Schema,
+    /// The empty schema, for values nothing constrains.
+    any: &'s Schema,
+    /// The pairs of subschemas being compared, to stop at cycles.
+    following: Vec<(*const Schema, *const Schema)>,
+}
+
+impl<'s> Checker<'s> {
+    /// Looks through shared schemas and references. Fails with the reference if
+    /// it can't be resolved or has keywords next to it.
+    fn target(&self, schema: &'s Schema, root: &'s Schema) -> Result<&'s Schema, String> {
+        match *schema {
+            Schema::Shared(ref s) => self.target(s, root),
+            Schema::Reference(ref s) if s.siblings.is_none() => match s.resolve(root) {
+                Some(target) => self.target(target, root),
+                None => Err(s.reference.clone()),
+            },
+            Schema::Reference(ref s) => Err(s.reference.clone()),
+            _ => Ok(schema),
+        }
+    }
+
+    fn check(&mut self, a: &'s Schema, b: &'s Schema, path: &str) -> SubsetResult {
+        let (a, b) = match (self.target(a, self.a_root), self.target(b, self.b_root)) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(reference), _) | (_, Err(reference)) => {
+                let reason = format!("the reference `{}` is beyond the checks", reference);
+                return Unknown(path.into(), reason);
+            }
+        };
+        // recursive schemas are contained in each other if nothing else says no
+        let key: (*const Schema, *const Schema) = (a, b);
+        if self.following.contains(&key) {
+            return Subset;
+        }
+        self.following.push(key);
+        let result = self.compare(a, b, path);
+        self.following.pop();
+        result
+    }
+
+    fn compare(&mut self, a: &'s Schema, b: &'s Schema, path: &str) -> SubsetResult {
+        if let (Schema::Custom(_), _) | (_, Schema::Custom(_)) = (a, b) {
+            return Unknown(path.into(), "custom schemas are beyond the checks".into());
+        }
+        // with `enum` or `const` the values of `a` are known, so they decide
+        if let Some((_, values)) = a.allowed_values() {
+            for value in values {
+                if a.accepts_within(self.a_root, value) && !b.accepts_within(self.b_root, value) {
+                    return self.not_subset(b, value, path);
+                }
+            }
+            return Subset;
+        }
+        if let Schema::Empty(_) = *b {
+            return Subset;
+        }
+        if let Schema::Combinator(ref s) = *a {
+            return self.check_first_combinator(s, b, path);
+        }
+        if let Schema::Combinator(ref s) = *b {
+            return self.check_second_combinator(a, s, path);
+        }
+
+        let extra = kinds(a) & !kinds(b);
+        if extra != 0 {
+            let reason = "the first schema allows types the `type` of the second doesn't";
+            return self.gap(a, b, path, reason);
+        }
+        let mut gaps = vec![];
+        match (a, b) {
+            (Schema::Number(x), Schema::Number(y)) => {
+                check_range(&x.range(), &y.range(), false, &mut gaps)
+            }
+            (Schema::Integer(x), Schema::Number(y)) => {
+                check_range(&x.range(), &y.range(), true, &mut gaps)
+            }
+            (Schema::Integer(x), Schema::Integer(y)) => {
+                check_range(&x.range(), &y.range(), true, &mut gaps)
+            }
+            (Schema::String(x), Schema::String(y)) => check_string(x, y, &mut gaps),
+            (Schema::Array(x), Schema::Array(y)) => check_array_size(x, y, &mut gaps),
+            (Schema::Object(x), Schema::Object(y)) => check_object_size(x, y, &mut gaps),
+            _ => {}
+        }
+        gaps.extend(other_keywords(a, b));
+        if let Some(reason) = gaps.first() {
+            return self.gap(a, b, path, reason);
+        }
+        match (a, b) {
+            (Schema::Array(x), Schema::Array(y)) => self.check_items(x, y, path),
+            (Schema::Object(x), Schema::Object(y)) => self.check_properties(x, y, path),
+            _ => Subset,
+        }
+    }
+
+    /// `a` accepts the values that match all of its combinators, so it's
+    /// contained in `b` if one of them is. `oneOf` branches can also match
+    /// values `a` rejects, so only `allOf` and `anyOf` on their own tell that
+    /// it isn't.
+    fn check_first_combinator(
+        &mut self,
+        a: &'s CombinatorSchema,
+        b: &'s Schema,
+        path: &str,
+    ) -> SubsetResult {
+        let mut results = vec![];
+        for member in a.all_of.iter().flatten() {
+            results.push(self.check(member, b, path));
+        }
+        if let Some(ref branches) = a.any_of {
+            let mut result = Subset;
+            for branch in branches {
+                result = result.and(|| self.check(branch, b, path));
+            }
+            results.push(result);
+        }
+        if let Some(ref branches) = a.one_of {
+            let mut result = Subset;
+            for branch in branches {
+                result = result.and(|| self.check(branch, b, path));
+            }
+            results.push(match result {
+                NotSubset(..) => Unknown(path.into(), "`oneOf` is beyond the checks".into()),
+                result => result,
+            });
+        }
+        if a.not.is_some() {
+            results.push(Unknown(path.into(), "`not` is beyond the checks".into()));
+        }
+        if results.is_empty() {
+            return self.check(self.any, b, path);
+        }
+        if results.contains(&Subset) {
+            return Subset;
+        }
+        match results.len() {
+            1 => results.remove(0),
+            _ => Unknown(
+                path.into(),
+                "the combination of `allOf`, `anyOf`, `oneOf` and `not` is beyond the checks"
+                    .into(),
+            ),
+        }
+    }
+
+    /// `b` accepts the values that match all of its combinators, so `a` has to
+    /// be contained in each of them.
+    fn check_second_combinator(
+        &mut self,
+        a: &'s Schema,
+        b: &'s CombinatorSchema,
+        path: &str,
+    ) -> SubsetResult {
+        let mut result = Subset;
+        for member in b.all_of.iter().flatten() {
+            result = result.and(|| self.check(a, member, path));
+        }
+        if let Some(ref branches) = b.any_of {
+            result = result.and(|| {
+                for branch in branches {
+                    if self.check(a, branch, path) == Subset {
+                        return Subset;
+                    }
+                }
+                let reason = "no branch of `anyOf` on its own accepts every value";
+                self.gap(a, self.b_root, path, reason)
+                    .and(|| Unknown(path.into(), reason.into()))
+            });
+        }
+        if b.one_of.is_some() {
+            result = result.and(|| Unknown(path.into(), "`oneOf` is beyond the checks".into()));
+        }
+        if let Some(ref not) = b.not {
+            result = result.and(|| match self.target(not, self.b_root) {
+                Ok(not) if kinds(not) & kinds(a) == 0 => Subset,
+                _ => Unknown(path.into(), "`not` is beyond the checks".into()),
+            });
+        }
+        result
+    }
+
+    fn check_items(&mut self, a: &'s ArraySchema, b: &'s ArraySchema, path: &str) -> SubsetResult {
+        let item_path = format!("{}/0", path);
+        // `a` allows no items
+        if let (0, _) | (_, (None, Some(Items::Allowed(false)))) =
+            (a.max_items.unwrap_or(1), (&a.prefix_items, &a.items))
+        {
+            return Subset;
+        }
+        let b_items = match (&b.prefix_items, &b.items) {
+            (None, None) | (None, Some(Items::Allowed(true))) => return Subset,
+            (None, Some(Items::List(items))) => items,
+            _ if same(&b.items, &a.items) && same(&b.prefix_items, &a.prefix_items) => {
+                return Subset;
+            }
+            _ => return Unknown(item_path, "tuples of items are beyond the checks".into()),
+        };
+        match (&a.prefix_items, &a.items) {
+            (None, Some(Items::List(items))) => self.check(items, b_items, &item_path),
+            (None, None) | (None, Some(Items::Allowed(true))) => {
+                self.check(self.any, b_items, &item_path)
+            }
+            _ => Unknown(item_path, "tuples of items are beyond the checks".into()),
+        }
+    }
+
+    fn check_properties(
+        &mut self,
+        a: &'s ObjectSchema,
+        b: &'s ObjectSchema,
+        path: &str,
+    ) -> SubsetResult {
+        let mut result = Subset;
+        for name in b.properties.iter().flat_map(|p| p.keys()) {
+            if !a.is_property_allowed(name) {
+                continue;
+            }
+            let property_path = format!("{}/{}", path, pointer_token(name));
+            let b_schemas = b.applicable_schemas(name);
+            let a_schemas = a.applicable_schemas(name);
+            for b_schema in b_schemas {
+                result = result.and(|| match a_schemas[..] {
+                    [] => self.check(self.any, b_schema, &property_path),
+                    [a_schema] => self.check(a_schema, b_schema, &property_path),
+                    _ => {
+                        for &a_schema in &a_schemas {
+                            if self.check(a_schema, b_schema, &property_path) == Subset {
+                                return Subset;
+                            }
+                        }
+                        let reason = "several schemas of the first apply to the property";
+                        Unknown(property_path.clone(), reason.into())
+                    }
+                });
+            }
+        }
+        // the properties `b` doesn't declare
+        let undeclared = a
+            .properties
+            .iter()
+            .flat_map(|p| p.keys())
+            .find(|name| !b.is_property_allowed(name));
+        if let Some(name) = undeclared {
+            let reason = format!("the second schema doesn't allow the property `{}`", name);
+            return result.and(|| Unknown(format!("{}/{}", path, pointer_token(name)), reason));
+        }
+        if a.additional_properties() {
+            result = result.and(
+                || match (a.additional_schema(), b.additional_properties()) {
+                    (_, false) if b.pattern_properties.is_none() => {
+                        let reason = "the second schema doesn't allow undeclared properties";
+                        Unknown(path.into(), reason.into())
+                    }
+                    (Some(a_schema), true) => match b.additional_schema() {
+                        Some(b_schema) => self.check(a_schema, b_schema, path),
+                        None => Subset,
+                    },
+                    (None, true) => match b.additional_schema() {
+                        Some(b_schema) => self.check(self.any, b_schema, path),
+                        None => Subset,
+                    },
+                    _ => Unknown(
+                        path.into(),
+                        "`patternProperties` is beyond the checks".into(),
+                    ),
+                },
+            );
+        }
+        result
+    }
+
+    /// The result for a difference between `a` and `b` the checks found: a
+    /// value `a` accepts and `b` rejects if one is found, `Unknown` otherwise.
+    fn gap(&self, a: &'s Schema, b: &'s Schema, path: &str, reason: &str) -> SubsetResult {
+        let witness = candidates(a, b).into_iter().find(|value| {
+            a.accepts_within(self.a_root, value) && !b.accepts_within(self.b_root, value)
+        });
+        match witness {
+            Some(value) => self.not_subset(b, &value, path),
+            None => Unknown(path.into(), reason.into()),
+        }
+    }
+
+    fn not_subset(&self, b: &'s Schema, value: &Value, path: &str) -> SubsetResult {
+        let errors = b.validate_within(self.b_root, value);
+        let reason = match errors.first() {
+            Some(error) => format!(
+                "{} is accepted by the first schema, the second rejects it: {}",
+                value, error.reason
+            ),
+            None => format!("{} is accepted by the first schema only", value),
+        };
+        NotSubset(path.into(), reason)
+    }
+}
+
+/// Whether two optional keywords have the same value.
+fn same<T: ::serde::Serialize>(a: &Option<T>, b: &Option<T>) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Compares the bounds and `multipleOf` of numbers. Integers are multiples of 1.
+fn check_range(a: &Range, b: &Range, integers: bool, gaps: &mut Vec<String>) {
+    if let Some(b_minimum) = b.minimum {
+        let contained = a.minimum.is_some_and(|a_minimum| {
+            a_minimum > b_minimum
+                || a_minimum == b_minimum && (a.exclusive_minimum || !b.exclusive_minimum)
+        });
+        if !contained {
+            gaps.push(format!(
+                "the second schema has a greater minimum of {}",
+                b_minimum
+            ));
+        }
+    }
+    if let Some(b_maximum) = b.maximum {
+        let contained = a.maximum.is_some_and(|a_maximum| {
+            a_maximum < b_maximum
+                || a_maximum == b_maximum && (a.exclusive_maximum || !b.exclusive_maximum)
+        });
+        if !contained {
+            gaps.push(format!(
+                "the second schema has a smaller maximum of {}",
+                b_maximum
+            ));
+        }
+    }
+    if let Some(b_step) = b.multiple_of {
+        let a_step = a.multiple_of.or(if integers { Some(1.0) } else { None });
+        let contained = a_step.is_some_and(|a_step| {
+            let quotient = a_step / b_step;
+            (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+        });
+        if !contained {
+            gaps.push(format!("the second schema needs multiples of {}", b_step));
+        }
+    }
+}
+
+/// Whether the range `a_min..=a_max` lies within `b_min..=b_max`.
+fn check_size(
+    (a_min, a_max): (usize, Option<usize>),
+    (b_min, b_max): (usize, Option<usize>),
+    what: &str,
+    gaps: &mut Vec<String>,
+) {
+    if a_min < b_min {
+        gaps.push(format!(
+            "the second schema needs at least {} {}",
+            b_min, what
+        ));
+    }
+    if let Some(b_max) = b_max {
+        if a_max.map_or(true, |a_max| a_max > b_max) {
+            gaps.push(format!(
+                "the second schema allows at most {} {}",
+                b_max, what
+            ));
+        }
+    }
+}
+
+fn check_string(a: &StringSchema, b: &StringSchema, gaps: &mut Vec<String>) {
+    check_size(
+        (a.min_length.unwrap_or(0), a.max_length),
+        (b.min_length.unwrap_or(0), b.max_length),
+        "characters",
+        gaps,
+    );
+    if b.pattern.is_some() && a.pattern != b.pattern {
+        gaps.push("whether the `pattern`s match the same strings is beyond the checks".into());
+    }
+    if let Some(ref format) = b.format {
+        if format.is_checked() && a.format.as_ref() != Some(format) {
+            gaps.push(format!(
+                "the second schema needs the format `{}`",
+                format.as_str()
+            ));
+        }
+    }
+}
+
+fn check_array_size(a: &ArraySchema, b: &ArraySchema, gaps: &mut Vec<String>) {
+    check_size(
+        (a.min_items.unwrap_or(0), a.max_items),
+        (b.min_items.unwrap_or(0), b.max_items),
+        "items",
+        gaps,
+    );
+    if b.unique_items == Some(true) && a.unique_items != Some(true) {
+        gaps.push("the second schema needs unique items".into());
+    }
+}
+
+fn check_object_size(a: &ObjectSchema, b: &ObjectSchema, gaps: &mut Vec<String>) {
+    check_size(
+        (a.min_properties.unwrap_or(0), a.max_properties),
+        (b.min_properties.unwrap_or(0), b.max_properties),
+        "properties",
+        gaps,
+    );
+    let required: Vec<&String> = a.required_properties().collect();
+    if let Some(name) = b
+        .required_properties()
+        .find(|name| !required.contains(name))
+    {
+        gaps.push(format!(
+            "the second schema requires the property `{}`",
+            name
+        ));
+    }
+}
+
+/// The keywords of `b` the checks don't compare that `a` doesn't have alike.
+fn other_keywords(a: &Schema, b: &Schema) -> Vec<String> {
+    let (a, b) = match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(Value::Object(a)), Ok(Value::Object(b))) => (a, b),
+        _ => return vec![],
+    };
+    b.iter()
+        .filter(|&(keyword, value)| {
+            !value.is_null()
+                && !COMPARED.contains(&keyword.as_str())
+                && keyword_enforcement(keyword) != Enforcement::AnnotationOnly
+                && a.get(keyword) != Some(value)
+        })
+        .map(|(keyword, _)| format!("`{}` is beyond the checks", keyword))
+        .collect()
+}
+
+/// Values that may be accepted by `a` and rejected by `b`: a value of every
+/// type, the examples of `a` and values at the bounds of both schemas.
+fn candidates(a: &Schema, b: &Schema) -> Vec<Value> {
+    let mut values = vec![
+        json!(null),
+        json!(true),
+        json!(0),
+        json!(0.5),
+        json!(""),
+        json!([]),
+        json!({}),
+    ];
+    values.extend(a.examples().into_iter().cloned());
+    for schema in &[a, b] {
+        match **schema {
+            Schema::Number(ref s) => number_candidates(&s.range(), &mut values),
+            Schema::Integer(ref s) => number_candidates(&s.range(), &mut values),
+            Schema::String(ref s) => {
+                let lengths = length_candidates(s.min_length, s.max_length);
+                values.extend(lengths.map(|n| Value::String("a".repeat(n))));
+            }
+            Schema::Array(ref s) => {
+                for n in length_candidates(s.min_items, s.max_items) {
+                    values.push(Value::Array(vec![json!(0); n]));
+                    values.push(Value::Array((0..n).map(|i| json!(i)).collect()));
+                }
+            }
+            Schema::Object(ref s) => {
+                let mut object = serde_json::Map::new();
+                for name in s.required_properties() {
+                    object.insert(name.clone(), Value::Null);
+                }
+                values.push(Value::Object(object));
+            }
+            _ => {}
+        }
+    }
+    values
+}
+
+fn number_candidates(range: &Range, values: &mut Vec<Value>) {
+    let bounds = range
+        .minimum
+        .iter()
+        .chain(&range.maximum)
+        .chain(&range.multiple_of);
+    for &bound in bounds {
+        for &offset in &[0.0, -1.0, 1.0, -0.5, 0.5] {
+            let value = bound + offset;
+            if value == value.trunc() && value.abs() < 9e15 {
+                values.push(json!(value as i64));
+            } else {
+                values.push(json!(value));
+            }
+        }
+    }
+}
+
+/// The lengths at and next to the bounds, up to a size worth building.
+fn length_candidates(min: Option<usize>, max: Option<usize>) -> impl Iterator<Item = usize> {
+    let bounds = min.into_iter().chain(max);
+    bounds
+        .flat_map(|n| vec![n.saturating_sub(1), n, n + 1])
+        .filter(|&n| n <= 10_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{self, Value};
+
+    use super::*;
+
+    fn check(a: Value, b: Value) -> SubsetResult {
+        let a: Schema = serde_json::from_value(a).unwrap();
+        let b: Schema = serde_json::from_value(b).unwrap();
+        is_subschema(&a, &b)
+    }
+
+    fn order(maximum: u64, name: Value, tags: Value) -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": true,
+            "required": ["id"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 1, "maximum": maximum},
+                "name": name,
+                "tags": {"type": "array", "items": tags}
+            }
+        })
+    }
+
+    #[test]
+    fn subset() {
+        let strict = order(
+            10,
+            json!({"type": "string", "minLength": 2, "maxLength": 5}),
+            json!({"type": "string", "enum": ["x", "y"]}),
+        );
+        let relaxed = order(
+            100,
+            json!({"type": "string", "maxLength": 10}),
+            json!({"type": "string"}),
+        );
+        assert_eq!(check(strict.clone(), relaxed.clone()), Subset);
+        assert_eq!(check(strict.clone(), strict.clone()), Subset);
+        assert_eq!(
+            check(
+                json!({"type": "integer", "multipleOf": 4}),
+                json!({"type": "number", "multipleOf": 2})
+            ),
+            Subset
+        );
+        assert_eq!(
+            check(
+                json!({"anyOf": [{"type": "string"}, {"type": "integer", "minimum": 0}]}),
+                json!({"anyOf": [{"type": "string"}, {"type": "number"}]})
+            ),
+            Subset
+        );
+        assert_eq!(
+            check(
+                json!({"type": "string", "minLength": 3}),
+                json!({"allOf": [{"type": "string"}, {"minLength": 1, "type": "string"}]})
+            ),
+            Subset
+        );
+    }
+
+    #[test]
+    fn not_subset() {
+        let strict = order(10, json!({"type": "string"}), json!({"type": "string"}));
+        let tighter = order(5, json!({"type": "string"}), json!({"type": "string"}));
+        match check(strict.clone(), tighter) {
+            NotSubset(path, reason) => {
+                assert_eq!(path, "/id");
+                assert!(
+                    reason.starts_with("10 is accepted by the first schema"),
+                    "{}",
+                    reason
+                );
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        let numeric_tags = order(10, json!({"type": "string"}), json!({"type": "integer"}));
+        match check(strict.clone(), numeric_tags) {
+            NotSubset(path, _) => assert_eq!(path, "/tags/0"),
+            result => panic!("unexpected result {:?}", result),
+        }
+        match check(json!({"type": "number"}), json!({"type": "integer"})) {
+            NotSubset(path, reason) => {
+                assert_eq!(path, "");
+                assert!(reason.starts_with("0.5 is accepted"), "{}", reason);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        match check(json!({"enum": ["a", "b", 3]}), json!({"type": "string"})) {
+            NotSubset(_, reason) => assert!(reason.starts_with("3 is accepted"), "{}", reason),
+            result => panic!("unexpected result {:?}", result),
+        }
+        match check(
+            json!({"type": "object", "additionalProperties": true}),
+            json!({"type": "object", "additionalProperties": true, "required": ["id"]}),
+        ) {
+            NotSubset(path, reason) => {
+                assert_eq!(path, "");
+                assert!(reason.starts_with("{} is accepted"), "{}", reason);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn missing_additional_properties() {
+        let open = json!({"type": "object", "properties": {"id": {"type": "integer"}}});
+        assert_eq!(check(open.clone(), json!({"type": "object"})), Subset);
+        assert_eq!(
+            check(
+                json!({"type": "object", "additionalProperties": false}),
+                open.clone()
+            ),
+            Subset
+        );
+        let closed = json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {"id": {"type": "integer"}}
+        });
+        let typed = json!({
+            "type": "object",
+            "additionalProperties": {"type": "integer"},
+            "properties": {"id": {"type": "integer"}}
+        });
+        for b in [closed, typed] {
+            match check(open.clone(), b) {
+                NotSubset(path, _) | Unknown(path, _) => assert_eq!(path, ""),
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn unknown() {
+        match check(
+            json!({"type": "string", "pattern": "^[a-z]+$"}),
+            json!({"type": "string", "pattern": "^[a-z]*$"}),
+        ) {
+            Unknown(path, reason) => {
+                assert_eq!(path, "");
+                assert!(reason.contains("`pattern`"), "{}", reason);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        // a witness is found even where patterns are involved
+        match check(
+            json!({"type": "string", "pattern": "^[a-z]+$"}),
+            json!({"type": "string", "pattern": "^[a-z]+$", "minLength": 2}),
+        ) {
+            NotSubset(..) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
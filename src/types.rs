@@ -0,0 +1,132 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use errors::{Error, ErrorKind};
+
+/// The type of a JSON value, as named by the `type` keyword. Numbers without a
+/// fractional part are integers, however they are written: `2` and `2.0` are
+/// both integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonType {
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Boolean,
+    /// An object
+    Object,
+    /// An array
+    Array,
+    /// A number with a fractional part
+    Number,
+    /// A string
+    String,
+    /// A number without a fractional part
+    Integer,
+}
+
+impl JsonType {
+    /// The type of `value`.
+    pub fn of(value: &Value) -> JsonType {
+        match *value {
+            Value::Null => JsonType::Null,
+            Value::Bool(_) => JsonType::Boolean,
+            Value::Number(ref n) => {
+                let integral = n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0);
+                if integral {
+                    JsonType::Integer
+                } else {
+                    JsonType::Number
+                }
+            }
+            Value::Array(_) => JsonType::Array,
+            Value::Object(_) => JsonType::Object,
+            Value::String(_) => JsonType::String,
+        }
+    }
+}
+
+impl JsonType {
+    /// The type `value` is reported as in type mismatches. Numbers are all
+    /// `number`, along with whether they have no fractional part.
+    pub(crate) fn reported(value: &Value) -> (JsonType, Option<bool>) {
+        match JsonType::of(value) {
+            JsonType::Integer => (JsonType::Number, Some(true)),
+            JsonType::Number => (JsonType::Number, Some(false)),
+            ty => (ty, None),
+        }
+    }
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::JsonType::*;
+        match *self {
+            Null => write!(f, "null"),
+            Boolean => write!(f, "boolean"),
+            Object => write!(f, "object"),
+            Array => write!(f, "array"),
+            Number => write!(f, "number"),
+            String => write!(f, "string"),
+            Integer => write!(f, "integer"),
+        }
+    }
+}
+
+impl FromStr for JsonType {
+    type Err = Error;
+    fn from_str(s: &str) -> ::std::result::Result<JsonType, Self::Err> {
+        use self::JsonType::*;
+        match s {
+            "null" => Ok(Null),
+            "boolean" => Ok(Boolean),
+            "object" => Ok(Object),
+            "array" => Ok(Array),
+            "number" => Ok(Number),
+            "string" => Ok(String),
+            "integer" => Ok(Integer),
+            _ => Err(ErrorKind::UnknownType(s.into()).into()),
+        }
+    }
+}
+
+/// Adds `get_type` to JSON values.
+pub trait JsonValueExt {
+    /// The type of this value, see `JsonType::of`.
+    fn get_type(&self) -> JsonType;
+}
+
+impl JsonValueExt for Value {
+    fn get_type(&self) -> JsonType {
+        JsonType::of(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for name in &["null", "boolean", "object", "array", "number", "string", "integer"] {
+            let ty: JsonType = name.parse().unwrap();
+            assert_eq!(ty.to_string(), *name);
+            assert_eq!(serde_json::to_value(ty).unwrap(), json!(name));
+            assert_eq!(serde_json::from_value::<JsonType>(json!(name)).unwrap(), ty);
+        }
+        assert!("float".parse::<JsonType>().is_err());
+    }
+
+    #[test]
+    fn of() {
+        assert_eq!(JsonType::of(&json!(1)), JsonType::Integer);
+        assert_eq!(JsonType::of(&json!(1.0)), JsonType::Integer);
+        assert_eq!(JsonType::of(&json!(-0.0)), JsonType::Integer);
+        assert_eq!(JsonType::of(&json!(1.5)), JsonType::Number);
+        assert_eq!(json!({}).get_type(), JsonType::Object);
+    }
+}
@@ -1,49 +1,263 @@
-use std::fmt;
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+use regex::{self, Regex, RegexBuilder};
+use regex_syntax::ParserBuilder;
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 
-pub trait JsonValueExt {
-    fn get_type(&self) -> JsonType;
+use limits::{RegexError, SchemaLimits};
+
+/// Deserializes a keyword whose value may be `null`, like `const` or `default`,
+/// to `Some` whenever it's present. With `#[serde(default)]` an absent keyword
+/// stays `None`, while a plain `Option` would read `null` as absent too.
+pub(crate) fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// `count` distinct indices below `len` in ascending order, always including the
+/// first and the last, picked by a generator seeded with `seed`: the same
+/// arguments give the same indices. All indices if `count` isn't less than
+/// `len`.
+pub(crate) fn sample_indices(len: usize, count: usize, seed: u64) -> Vec<usize> {
+    match count {
+        _ if count >= len => return (0..len).collect(),
+        0 => return vec![],
+        1 => return vec![0],
+        _ => {}
+    }
+    // splitmix64
+    let mut state = seed ^ (len as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    let mut next = move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let mut picked = BTreeSet::new();
+    picked.insert(0);
+    picked.insert(len - 1);
+    // Floyd's algorithm for the indices between the first and the last
+    let inner = len - 2;
+    for j in inner - (count - 2)..inner {
+        let t = (next() % (j as u64 + 1)) as usize;
+        if !picked.insert(t + 1) {
+            picked.insert(j + 1);
+        }
+    }
+    picked.into_iter().collect()
+}
+
+/// `text` without a leading UTF-8 byte-order mark, which tools on Windows like
+/// to write and JSON parsers reject.
+pub(crate) fn strip_bom(text: &str) -> &str {
+    text.strip_prefix('\u{feff}').unwrap_or(text)
 }
 
-impl JsonValueExt for Value {
-    fn get_type(&self) -> JsonType {
-        match *self {
-            Value::Null => JsonType::Null,
-            Value::Bool(_) => JsonType::Boolean,
-            Value::Number(ref n) => if n.is_f64() {
-                JsonType::Number
-            } else {
-                JsonType::Integer
+/// Escapes a key for use as a JSON pointer reference token (RFC 6901).
+pub(crate) fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Compares two values the way `enum` and `const` do: numbers are equal if they
+/// have the same value, regardless of whether they were written as integers or floats.
+pub(crate) fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a == b || a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| json_equal(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+/// Enums with more values than this get no suggestion, comparing against all of
+/// them would cost more than the suggestion is worth.
+const MAX_SUGGESTION_CANDIDATES: usize = 1000;
+
+/// The string among `candidates` closest to `value` by edit distance, if it's
+/// close enough to likely be what was meant: at most a third of its characters
+/// differ.
+pub(crate) fn closest_match<'a>(value: &str, candidates: &'a [Value]) -> Option<&'a str> {
+    if candidates.len() > MAX_SUGGESTION_CANDIDATES {
+        return None;
+    }
+    let value: Vec<char> = value.chars().collect();
+    let mut row = Vec::with_capacity(value.len() + 1);
+    let mut best = None;
+    for candidate in candidates.iter().filter_map(Value::as_str) {
+        let length = candidate.chars().count();
+        let limit = ::std::cmp::max(1, ::std::cmp::max(length, value.len()) / 3);
+        let distance = edit_distance(&value, candidate, &mut row);
+        if distance <= limit && best.map_or(true, |(_, best)| distance < best) {
+            best = Some((candidate, distance));
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b`, using `row` as the buffer.
+fn edit_distance(a: &[char], b: &str, row: &mut Vec<usize>) -> usize {
+    row.clear();
+    row.extend(0..=a.len());
+    for (j, cb) in b.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = j + 1;
+        for (i, &ca) in a.iter().enumerate() {
+            let substitution = diagonal + if ca == cb { 0 } else { 1 };
+            diagonal = row[i + 1];
+            row[i + 1] = substitution.min(row[i] + 1).min(diagonal + 1);
+        }
+    }
+    row[a.len()]
+}
+
+/// Finds ECMA-262 regex constructs the `regex` crate doesn't support, returning
+/// a description of the first one found.
+pub(crate) fn unsupported_regex_construct(pattern: &str) -> Option<&'static str> {
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('1'..='9') if !in_class => return Some("backreferences"),
+                Some('k') if !in_class && chars.peek() == Some(&'<') => {
+                    return Some("named backreferences")
+                }
+                _ => {}
             },
-            Value::Array(_) => JsonType::Array,
-            Value::Object(_) => JsonType::Object,
-            Value::String(_) => JsonType::String,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class && chars.peek() == Some(&'?') => {
+                chars.next();
+                match chars.next() {
+                    Some('=') | Some('!') => return Some("lookahead"),
+                    Some('<') => match chars.peek() {
+                        Some(&'=') | Some(&'!') => return Some("lookbehind"),
+                        _ => return Some("named groups written `(?<name>...)`"),
+                    },
+                    _ => {}
+                }
+            }
+            _ => {}
         }
     }
+    None
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum JsonType {
-    Null,
-    Boolean,
-    Object,
-    Array,
-    Number,
-    String,
-    Integer,
-}
-
-impl fmt::Display for JsonType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use self::JsonType::*;
-        match *self {
-            Null => write!(f, "null"),
-            Boolean => write!(f, "boolean"),
-            Object => write!(f, "object"),
-            Array => write!(f, "array"),
-            Number => write!(f, "number"),
-            String => write!(f, "string"),
-            Integer => write!(f, "integer"),
+/// Whether `pattern` repeats a group that itself contains a repetition, like
+/// `(a+)+` or `(\w*){2,}`, constructs that are known to be slow to match.
+pub(crate) fn has_nested_quantifier(pattern: &str) -> bool {
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    // for each open group, whether it contains a repetition
+    let mut groups: Vec<bool> = vec![];
+    let mut after_repeating_group = false;
+    while let Some(c) = chars.next() {
+        let repeats_group = ::std::mem::replace(&mut after_repeating_group, false);
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            _ if in_class => {}
+            '(' => groups.push(false),
+            ')' => {
+                let repeating = groups.pop().unwrap_or(false);
+                if let Some(parent) = groups.last_mut() {
+                    *parent |= repeating;
+                }
+                after_repeating_group = repeating;
+            }
+            '*' | '+' | '{' if c != '{' || chars.peek().is_some_and(char::is_ascii_digit) => {
+                if repeats_group {
+                    return true;
+                }
+                if let Some(group) = groups.last_mut() {
+                    *group = true;
+                }
+            }
+            _ => {}
         }
     }
+    false
+}
+
+/// Parses the date of `x-removed-in`, a full date like `2025-01-31` or a month
+/// like `2025-01`, which stands for its first day.
+pub(crate) fn removal_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{}-01", text), "%Y-%m-%d"))
+        .ok()
+}
+
+/// Compiles `pattern` within `limits`. All regular expressions taken from
+/// schemas are compiled with this.
+pub(crate) fn compile_regex(pattern: &str, limits: &SchemaLimits) -> Result<Regex, RegexError> {
+    check_pattern(pattern, limits)?;
+    RegexBuilder::new(pattern)
+        .size_limit(limits.regex_size_limit)
+        .dfa_size_limit(limits.regex_dfa_size_limit)
+        .build()
+        .map_err(|e| match e {
+            regex::Error::CompiledTooBig(limit) => RegexError::limit(
+                "regex_size_limit",
+                format!(
+                    "the pattern `{}` compiles to more than {} bytes",
+                    pattern, limit
+                ),
+            ),
+            e => RegexError {
+                limit: None,
+                message: e.to_string(),
+            },
+        })
+}
+
+/// Checks that `pattern` is a regular expression within `limits`, without
+/// compiling it, for patterns that are matched some other way.
+pub(crate) fn parse_regex(pattern: &str, limits: &SchemaLimits) -> Result<(), RegexError> {
+    check_pattern(pattern, limits)?;
+    // the parser set up like the one of `Regex`
+    ParserBuilder::new()
+        .octal(true)
+        .build()
+        .parse(pattern)
+        .map(|_| ())
+        .map_err(|e| RegexError {
+            limit: None,
+            message: e.to_string(),
+        })
+}
+
+/// The limits checked before a pattern is parsed.
+fn check_pattern(pattern: &str, limits: &SchemaLimits) -> Result<(), RegexError> {
+    if pattern.len() > limits.max_pattern_length {
+        return Err(RegexError::limit(
+            "max_pattern_length",
+            format!(
+                "the pattern has {} bytes, at most {} are allowed",
+                pattern.len(),
+                limits.max_pattern_length
+            ),
+        ));
+    }
+    if limits.reject_nested_quantifiers && has_nested_quantifier(pattern) {
+        return Err(RegexError::limit(
+            "reject_nested_quantifiers",
+            format!("the pattern `{}` repeats a repetition", pattern),
+        ));
+    }
+    Ok(())
 }
@@ -0,0 +1,173 @@
+extern crate json_schema;
+#[macro_use]
+extern crate serde_json;
+
+use serde_json::Value;
+
+use json_schema::errors::{ErrorCode, ErrorKind};
+use json_schema::Schema;
+
+/// The instances each schema is validated against: no keys, a key matching
+/// the pattern `^x-`, a key matching it with a value of the wrong type, and a
+/// key that neither `properties` nor `patternProperties` cover.
+fn instances() -> Vec<Value> {
+    vec![
+        json!({}),
+        json!({"x-note": "a"}),
+        json!({"x-note": 1}),
+        json!({"other": 1}),
+    ]
+}
+
+/// Validates the instances against `schema`, returning the error codes for
+/// each.
+fn codes(schema: Value) -> Vec<Vec<ErrorCode>> {
+    let schema: Schema = serde_json::from_value(schema).unwrap();
+    instances()
+        .iter()
+        .map(|instance| match schema.validate(instance) {
+            Ok(()) => vec![],
+            Err(errors) => errors.codes(),
+        })
+        .collect()
+}
+
+#[test]
+fn absent_properties() {
+    let codes = codes(json!({"type": "object"}));
+    assert_eq!(codes, vec![vec![], vec![], vec![], vec![]]);
+}
+
+#[test]
+fn empty_properties() {
+    let codes = codes(json!({"type": "object", "properties": {}}));
+    assert_eq!(codes, vec![vec![], vec![], vec![], vec![]]);
+}
+
+#[test]
+fn only_pattern_properties() {
+    let codes = codes(json!({
+        "type": "object",
+        "patternProperties": {"^x-": {"type": "string"}}
+    }));
+    assert_eq!(
+        codes,
+        vec![vec![], vec![], vec![ErrorCode::TypeMismatch], vec![]]
+    );
+}
+
+#[test]
+fn only_additional_properties() {
+    let unknown = vec![ErrorCode::UnknownProperties];
+    let codes = codes(json!({"type": "object", "additionalProperties": false}));
+    assert_eq!(
+        codes,
+        vec![vec![], unknown.clone(), unknown.clone(), unknown]
+    );
+
+    let schema: Schema =
+        serde_json::from_value(json!({"type": "object", "additionalProperties": false})).unwrap();
+    let instance = json!({"a": 1, "b/c": 2});
+    let errors = schema.validate(&instance).unwrap_err().0;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].instance_path, "");
+    assert_eq!(errors[0].schema_path, "/additionalProperties");
+    assert_eq!(errors[0].node, &instance);
+    match errors[0].reason {
+        ErrorKind::UnknownProperties(ref pointers) => assert_eq!(pointers, &["/a", "/b~1c"]),
+        ref reason => panic!("unexpected error {}", reason),
+    }
+}
+
+#[test]
+fn empty_properties_without_additional_properties() {
+    let unknown = vec![ErrorCode::UnknownProperties];
+    let codes = codes(json!({
+        "type": "object",
+        "properties": {},
+        "additionalProperties": false
+    }));
+    assert_eq!(
+        codes,
+        vec![vec![], unknown.clone(), unknown.clone(), unknown]
+    );
+}
+
+#[test]
+fn pattern_properties_without_additional_properties() {
+    let codes = codes(json!({
+        "type": "object",
+        "additionalProperties": false,
+        "patternProperties": {"^x-": {"type": "string"}}
+    }));
+    assert_eq!(
+        codes,
+        vec![
+            vec![],
+            vec![],
+            vec![ErrorCode::TypeMismatch],
+            vec![ErrorCode::UnknownProperties],
+        ]
+    );
+}
+
+#[test]
+fn additional_properties_schema() {
+    let codes = codes(json!({
+        "type": "object",
+        "additionalProperties": {"type": "string"},
+        "patternProperties": {"^x-": {"type": "string"}}
+    }));
+    assert_eq!(
+        codes,
+        vec![
+            vec![],
+            vec![],
+            vec![ErrorCode::TypeMismatch],
+            vec![ErrorCode::TypeMismatch],
+        ]
+    );
+}
+
+#[test]
+fn properties_without_required() {
+    // declaring a property doesn't require it
+    let without_additional = codes(json!({
+        "type": "object",
+        "properties": {"name": {"type": "string"}}
+    }));
+    assert_eq!(without_additional, vec![vec![], vec![], vec![], vec![]]);
+
+    let with_additional = codes(json!({
+        "type": "object",
+        "additionalProperties": true,
+        "properties": {"name": {"type": "string"}}
+    }));
+    assert_eq!(with_additional, vec![vec![], vec![], vec![], vec![]]);
+}
+
+#[test]
+fn properties_without_required_or_additional_properties() {
+    let unknown = vec![ErrorCode::UnknownProperties];
+    let codes = codes(json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {"name": {"type": "string"}, "other": {"type": "integer"}}
+    }));
+    assert_eq!(codes, vec![vec![], unknown.clone(), unknown, vec![]]);
+
+    let schema: Schema = serde_json::from_value(json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {"name": {"type": "string"}}
+    }))
+    .unwrap();
+    schema.validate(&json!({"name": "a"})).unwrap();
+    let instance = json!({"name": 1, "x": 2, "y": 3});
+    let errors = schema.validate(&instance).unwrap_err();
+    assert_eq!(
+        errors.codes(),
+        vec![ErrorCode::TypeMismatch, ErrorCode::UnknownProperties]
+    );
+    assert_eq!(errors.0[1].instance_path, "");
+}
@@ -0,0 +1,25 @@
+extern crate json_schema;
+#[macro_use]
+extern crate serde_json;
+
+use json_schema::errors::ErrorKind;
+use json_schema::{JsonType, JsonValueExt, Schema};
+
+#[test]
+fn match_type_mismatch() {
+    let schema: Schema = serde_json::from_value(json!({"type": "string"})).unwrap();
+    let input = json!([1]);
+    let errors = schema.validate(&input).unwrap_err().0;
+    match errors[0].reason {
+        ErrorKind::TypeMismatch {
+            expected: JsonType::String,
+            found,
+            ..
+        } => {
+            assert_eq!(found, JsonType::Array);
+            assert_eq!(found, input.get_type());
+            assert_eq!(serde_json::to_value(found).unwrap(), json!("array"));
+        }
+        ref e => panic!("unexpected error {}", e),
+    }
+}
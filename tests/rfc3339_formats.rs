@@ -0,0 +1,202 @@
+extern crate json_schema;
+#[macro_use]
+extern crate serde_json;
+
+use json_schema::Schema;
+
+/// Validates each string of `vectors`, a description, the string and whether
+/// it's valid as in the JSON Schema test suite, against the format `format`,
+/// failing with the descriptions of the vectors the validation disagrees on.
+fn check(format: &str, vectors: &[(&str, &str, bool)]) {
+    let schema: Schema =
+        serde_json::from_value(json!({"type": "string", "format": format})).unwrap();
+    let wrong: Vec<_> = vectors
+        .iter()
+        .filter(|&&(_, data, valid)| schema.validate(&json!(data)).is_ok() != valid)
+        .map(|&(description, data, _)| format!("{} ({})", description, data))
+        .collect();
+    assert!(wrong.is_empty(), "{}: {:#?}", format, wrong);
+}
+
+#[test]
+fn date_time() {
+    check(
+        "date-time",
+        &[
+            (
+                "a valid date-time string",
+                "1963-06-19T08:30:06.283185Z",
+                true,
+            ),
+            ("without second fraction", "1963-06-19T08:30:06Z", true),
+            ("with plus offset", "1937-01-01T12:00:27.87+00:20", true),
+            ("with minus offset", "1990-12-31T15:59:50.123-08:00", true),
+            ("a leap second, UTC", "1998-12-31T23:59:60Z", true),
+            (
+                "a leap second, with minus offset",
+                "1998-12-31T15:59:60.123-08:00",
+                true,
+            ),
+            ("past leap second, UTC", "1998-12-31T23:59:61Z", false),
+            (
+                "leap second on a wrong minute, UTC",
+                "1998-12-31T23:58:60Z",
+                false,
+            ),
+            (
+                "leap second on a wrong hour, UTC",
+                "1998-12-31T22:59:60Z",
+                false,
+            ),
+            ("an invalid day", "1990-02-31T15:59:59.123-08:00", false),
+            ("an invalid offset", "1990-12-31T15:59:59-24:00", false),
+            (
+                "a closing Z after the offset",
+                "1963-06-19T08:30:06.28123+01:00Z",
+                false,
+            ),
+            (
+                "an invalid date-time string",
+                "06/19/1963 08:30:06 PST",
+                false,
+            ),
+            (
+                "case-insensitive T and Z",
+                "1963-06-19t08:30:06.283185z",
+                true,
+            ),
+            ("an ISO 8601 ordinal date", "2013-350T01:01:01", false),
+            ("a non-padded month", "1963-6-19T08:30:06.283185Z", false),
+            ("a non-padded day", "1963-06-1T08:30:06.283185Z", false),
+            ("a Bengali 4 in the date", "1963-06-1৪T00:00:00Z", false),
+            ("a Bengali 4 in the time", "1963-06-11T0৪:00:00Z", false),
+        ],
+    );
+}
+
+#[test]
+fn date() {
+    check(
+        "date",
+        &[
+            ("a valid date string", "1963-06-19", true),
+            ("31 days in January", "2020-01-31", true),
+            ("32 days in January", "2020-01-32", false),
+            ("28 days in February (normal)", "2021-02-28", true),
+            ("29 days in February (normal)", "2021-02-29", false),
+            ("29 days in February (leap)", "2020-02-29", true),
+            ("30 days in February (leap)", "2020-02-30", false),
+            ("31 days in March", "2020-03-31", true),
+            ("32 days in March", "2020-03-32", false),
+            ("30 days in April", "2020-04-30", true),
+            ("31 days in April", "2020-04-31", false),
+            ("31 days in May", "2020-05-31", true),
+            ("32 days in May", "2020-05-32", false),
+            ("30 days in June", "2020-06-30", true),
+            ("31 days in June", "2020-06-31", false),
+            ("31 days in July", "2020-07-31", true),
+            ("32 days in July", "2020-07-32", false),
+            ("31 days in August", "2020-08-31", true),
+            ("32 days in August", "2020-08-32", false),
+            ("30 days in September", "2020-09-30", true),
+            ("31 days in September", "2020-09-31", false),
+            ("31 days in October", "2020-10-31", true),
+            ("32 days in October", "2020-10-32", false),
+            ("30 days in November", "2020-11-30", true),
+            ("31 days in November", "2020-11-31", false),
+            ("31 days in December", "2020-12-31", true),
+            ("32 days in December", "2020-12-32", false),
+            ("an invalid month", "2020-13-01", false),
+            ("an invalid date string", "06/19/1963", false),
+            ("an ISO 8601 ordinal date", "2013-350", false),
+            ("a non-padded month", "1998-1-20", false),
+            ("a non-padded day", "1998-01-1", false),
+            ("an invalid month-day combination", "1998-04-31", false),
+            ("2021 is not a leap year", "2021-02-29", false),
+            ("2020 is a leap year", "2020-02-29", true),
+            ("a Bengali 4", "1963-06-1৪", false),
+            ("YYYYMMDD without dashes", "20230328", false),
+            ("a week number", "2023-W01", false),
+            ("a week number with day of week", "2023-W13-2", false),
+            ("a week number rolling over", "2022W527", false),
+        ],
+    );
+}
+
+#[test]
+fn time() {
+    check(
+        "time",
+        &[
+            ("a valid time string", "08:30:06Z", true),
+            ("extra leading zeros", "008:030:006Z", false),
+            ("no leading zero for single digits", "8:3:6Z", false),
+            ("hour, minute, second of other lengths", "8:0030:6Z", false),
+            ("leap second, Zulu", "23:59:60Z", true),
+            ("leap second, Zulu (wrong hour)", "22:59:60Z", false),
+            ("leap second, Zulu (wrong minute)", "23:58:60Z", false),
+            ("leap second, zero offset", "23:59:60+00:00", true),
+            (
+                "leap second, zero offset (wrong hour)",
+                "22:59:60+00:00",
+                false,
+            ),
+            (
+                "leap second, zero offset (wrong minute)",
+                "23:58:60+00:00",
+                false,
+            ),
+            ("leap second, positive offset", "01:29:60+01:30", true),
+            ("leap second, large positive offset", "23:29:60+23:30", true),
+            (
+                "leap second, positive offset (wrong hour)",
+                "23:59:60+01:00",
+                false,
+            ),
+            (
+                "leap second, positive offset (wrong minute)",
+                "23:59:60+00:30",
+                false,
+            ),
+            ("leap second, negative offset", "15:59:60-08:00", true),
+            ("leap second, large negative offset", "00:29:60-23:30", true),
+            (
+                "leap second, negative offset (wrong hour)",
+                "23:59:60-01:00",
+                false,
+            ),
+            (
+                "leap second, negative offset (wrong minute)",
+                "23:59:60-00:30",
+                false,
+            ),
+            ("with second fraction", "23:20:50.52Z", true),
+            ("with precise second fraction", "08:30:06.283185Z", true),
+            ("with plus offset", "08:30:06+00:20", true),
+            ("with minus offset", "08:30:06-08:00", true),
+            (
+                "offset hour and minute of other lengths",
+                "08:30:06-8:000",
+                false,
+            ),
+            ("case-insensitive Z", "08:30:06z", true),
+            ("an invalid hour", "24:00:00Z", false),
+            ("an invalid minute", "00:60:00Z", false),
+            ("an invalid second", "00:00:61Z", false),
+            ("an invalid offset hour", "01:02:03+24:00", false),
+            ("an invalid offset minute", "01:02:03+00:60", false),
+            ("both Z and an offset", "01:02:03Z+00:30", false),
+            ("an invalid offset indicator", "08:30:06 PST", false),
+            ("an ISO 8601 decimal comma", "01:01:01,1111", false),
+            ("no offset", "12:00:00", false),
+            ("no offset with second fraction", "12:00:00.52", false),
+            ("a Bengali 2", "1২:00:00Z", false),
+            (
+                "an offset not starting with plus or minus",
+                "08:30:06#00:20",
+                false,
+            ),
+            ("letters", "ab:cd:ef", false),
+        ],
+    );
+}
@@ -0,0 +1,79 @@
+// With `arbitrary-precision`, numbers are kept as strings and comparing them
+// allocates.
+#![cfg(not(feature = "arbitrary-precision"))]
+
+extern crate json_schema;
+#[macro_use]
+extern crate serde_json;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use json_schema::{Schema, Session};
+
+/// Counts the allocations of the current thread while `COUNTING` is set.
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations<F: FnOnce()>(f: F) -> usize {
+    ALLOCATIONS.with(|a| a.set(0));
+    COUNTING.with(|c| c.set(true));
+    f();
+    COUNTING.with(|c| c.set(false));
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn no_allocations_for_valid_values() {
+    let schema: Schema = serde_json::from_value(json!({
+        "type": "object",
+        "required": ["id"],
+        "additionalProperties": true,
+        "properties": {
+            "id": {"type": "integer", "minimum": 1},
+            "name": {"type": "string", "minLength": 1},
+            "tags": {"type": "array", "items": {"type": "string"}},
+            "value": {"oneOf": [{"type": "string"}, {"type": "number"}]},
+            "kind": {"enum": ["a", "b"]}
+        }
+    }))
+    .unwrap();
+    let values: Vec<_> = (0..100)
+        .map(|i| json!({"id": i + 1, "name": "x", "tags": ["a"], "value": i, "kind": "b"}))
+        .collect();
+
+    let mut session = Session::new(&schema);
+    session.validate(&values[0]).unwrap();
+    let count = allocations(|| {
+        for value in &values {
+            session.validate(value).unwrap();
+        }
+    });
+    assert_eq!(count, 0);
+    // plain validation allocates for the failed `oneOf` branches
+    assert!(allocations(|| schema.validate(&values[0]).unwrap()) > 0);
+
+    let invalid = json!({"id": 0, "value": true});
+    assert_eq!(session.validate(&invalid).unwrap_err().0.len(), 2);
+    session.validate(&values[1]).unwrap();
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "test-util")]
+
+#[macro_use]
+extern crate json_schema;
+#[macro_use]
+extern crate serde_json;
+
+use json_schema::errors::ErrorKind;
+use json_schema::Schema;
+
+#[test]
+fn assert_invalid() {
+    let schema: Schema =
+        serde_json::from_value(json!({"type": "object", "required": ["name"]})).unwrap();
+    assert_invalid!(schema, json!({}), ErrorKind::MissingProperties(_));
+}
+
+#[test]
+#[should_panic(expected = "no error matches")]
+fn assert_invalid_other_kind() {
+    let schema: Schema = serde_json::from_value(json!({"type": "string"})).unwrap();
+    assert_invalid!(schema, json!(1), ErrorKind::MissingProperties(_));
+}